@@ -0,0 +1,80 @@
+/*
+ * C bindings for the polling crate.
+ *
+ * Build the crate with the `capi` feature as a static or dynamic library to link against these
+ * functions. Functions returning `int` return 0 on success, the raw OS error code if the
+ * operating system reported an error, or -1 otherwise.
+ */
+
+#ifndef POLLING_H
+#define POLLING_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The raw file descriptor or socket of a source. */
+#ifdef _WIN32
+typedef uint64_t polling_source_t;
+#else
+typedef int polling_source_t;
+#endif
+
+/* Interest and event flags. */
+#define POLLING_READABLE (1u << 0)
+#define POLLING_WRITABLE (1u << 1)
+#define POLLING_PRIORITY (1u << 2)
+#define POLLING_HANGUP (1u << 3)
+#define POLLING_ERROR (1u << 4)
+
+/* Polling modes. */
+#define POLLING_MODE_ONESHOT 0
+#define POLLING_MODE_LEVEL 1
+#define POLLING_MODE_EDGE 2
+#define POLLING_MODE_EDGE_ONESHOT 3
+
+/* An opaque poller handle. */
+typedef struct polling_t polling_t;
+
+/* An I/O event. */
+typedef struct polling_event_t {
+    size_t key;
+    uint32_t flags;
+} polling_event_t;
+
+/* Create a new poller and store its handle in `out`. */
+int polling_new(polling_t **out);
+
+/* Release a poller handle. Registered sources are not closed. */
+void polling_free(polling_t *poller);
+
+/* Add a source with the interest `flags` under `key`. Delete it before closing it. */
+int polling_add(const polling_t *poller, polling_source_t source, size_t key, uint32_t flags,
+                int mode);
+
+/* Change the interest of a source. */
+int polling_modify(const polling_t *poller, polling_source_t source, size_t key, uint32_t flags,
+                   int mode);
+
+/* Remove a source from the poller. */
+int polling_delete(const polling_t *poller, polling_source_t source);
+
+/*
+ * Wait for at most `capacity` events and store them in `events`. A negative `timeout_ms` waits
+ * forever. The number of events is stored in `count`, which may be NULL. Events that don't fit
+ * are returned by the next call.
+ */
+int polling_wait(const polling_t *poller, polling_event_t *events, size_t capacity,
+                 int64_t timeout_ms, size_t *count);
+
+/* Wake up the current or the next call to polling_wait(). */
+int polling_notify(const polling_t *poller);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* POLLING_H */
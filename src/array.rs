@@ -0,0 +1,118 @@
+//! A fixed-size container for events that doesn't allocate.
+
+use std::fmt;
+
+use crate::Event;
+
+/// A container for up to `N` events, stored inline.
+///
+/// Unlike [`Events`], this never allocates, so it can live on the stack or in a `static`.
+/// Events are delivered into it by [`Poller::wait_array()`], which collects them in the buffer
+/// owned by the poller first, like [`Poller::wait_with()`]. That buffer is allocated once and
+/// reused, so in the steady state waiting doesn't allocate either.
+///
+/// [`Events`]: crate::Events
+/// [`Poller::wait_array()`]: crate::Poller::wait_array
+/// [`Poller::wait_with()`]: crate::Poller::wait_with
+///
+/// # Examples
+///
+/// ```
+/// use polling::{ArrayEvents, Event, Poller};
+/// use std::net::UdpSocket;
+/// use std::time::Duration;
+///
+/// let socket = UdpSocket::bind("127.0.0.1:0")?;
+/// let poller = Poller::new()?;
+/// unsafe {
+///     poller.add(&socket, Event::writable(1))?;
+/// }
+///
+/// let mut events = ArrayEvents::<16>::new();
+/// poller.wait_array(&mut events, Some(Duration::from_secs(1)))?;
+/// assert_eq!(events.iter().next().unwrap().key, 1);
+/// poller.delete(&socket)?;
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Clone)]
+pub struct ArrayEvents<const N: usize> {
+    list: [Event; N],
+    len: usize,
+}
+
+impl<const N: usize> ArrayEvents<N> {
+    /// Create an empty container.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            list: [Event::none(0); N],
+            len: 0,
+        }
+    }
+
+    /// The number of events the container can hold, `N`.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// The number of events in the container.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the container holds no events.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether the container holds `N` events, in which case a wait doesn't deliver any more.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// The events in the container.
+    #[inline]
+    pub fn as_slice(&self) -> &[Event] {
+        &self.list[..self.len]
+    }
+
+    /// Iterate over the events in the container.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = Event> + '_ {
+        self.as_slice().iter().copied()
+    }
+
+    /// Remove all events from the container.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// The number of events that still fit.
+    pub(crate) fn room(&self) -> usize {
+        N - self.len
+    }
+
+    /// Append an event, which must fit.
+    pub(crate) fn push(&mut self, event: Event) {
+        self.list[self.len] = event;
+        self.len += 1;
+    }
+}
+
+impl<const N: usize> Default for ArrayEvents<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Debug for ArrayEvents<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
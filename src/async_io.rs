@@ -0,0 +1,420 @@
+//! A minimal adapter for using sources from async code.
+//!
+//! A [`Driver`] owns a [`Poller`] and the wakers of the tasks that wait for its sources. An
+//! [`Async`] wraps a source that is registered in a driver, and provides futures that complete
+//! once the source is readable or writable. The driver is either turned by the caller with
+//! [`Driver::block_on()`], which runs a future to completion on the current thread, or by a
+//! background thread that calls [`Driver::run()`]. This is enough for small async programs that
+//! don't want to pull in a full runtime.
+//!
+//! Sources have to be in nonblocking mode. They are registered in oneshot mode, and interest is
+//! enabled again whenever a task waits for them.
+//!
+//! # Examples
+//!
+//! ```
+//! use polling::async_io::{Async, Driver};
+//! use std::io::{Read, Write};
+//! use std::net::{TcpListener, TcpStream};
+//! use std::sync::Arc;
+//!
+//! let listener = TcpListener::bind("127.0.0.1:0")?;
+//! let mut writer = TcpStream::connect(listener.local_addr()?)?;
+//! let (reader, _) = listener.accept()?;
+//! reader.set_nonblocking(true)?;
+//!
+//! let driver = Arc::new(Driver::new()?);
+//! let reader = Async::new(&driver, reader)?;
+//! writer.write_all(b"hello")?;
+//!
+//! let mut buf = [0; 5];
+//! let len = driver.block_on(reader.read_with(|mut reader| reader.read(&mut buf)))?;
+//! assert_eq!(&buf[..len], b"hello");
+//! # std::io::Result::Ok(())
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::{poll_fn, Future};
+use std::io;
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::task::{Context, Poll, Wake, Waker};
+use std::time::Duration;
+
+#[cfg(unix)]
+use std::os::unix::io::BorrowedFd as BorrowedSource;
+#[cfg(windows)]
+use std::os::windows::io::BorrowedSocket as BorrowedSource;
+
+use crate::{AsRawSource, AsSource, Event, Events, PollMode, Poller, RawSource};
+
+/// The index of the readers in the state of a source.
+const READ: usize = 0;
+
+/// The index of the writers in the state of a source.
+const WRITE: usize = 1;
+
+/// A source registered in a driver.
+struct Source {
+    raw: RawSource,
+    key: usize,
+    state: Mutex<State>,
+}
+
+/// The readiness of a source and the tasks waiting for it, for reading and writing.
+#[derive(Default)]
+struct State {
+    /// Incremented whenever an event is delivered.
+    ticks: [u64; 2],
+
+    /// The wakers of the tasks waiting for the next event.
+    wakers: [Vec<Waker>; 2],
+}
+
+impl Source {
+    /// Lock the state of the source, ignoring poisoning.
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Enable interest in the directions that tasks are waiting for.
+    fn arm(&self, poller: &Poller, state: &State) -> io::Result<()> {
+        let interest = Event::new(
+            self.key,
+            !state.wakers[READ].is_empty(),
+            !state.wakers[WRITE].is_empty(),
+        );
+
+        // SAFETY: The source stays open until it is deregistered by `Async`.
+        let source = unsafe { BorrowedSource::borrow_raw(self.raw) };
+        poller.modify_with_mode(source, interest, PollMode::Oneshot)
+    }
+
+    /// Complete once an event is delivered in `dir` after the first poll.
+    fn poll_ready(
+        &self,
+        poller: &Poller,
+        dir: usize,
+        ticks: &mut Option<u64>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        let mut state = self.state();
+        match *ticks {
+            Some(ticks) if ticks != state.ticks[dir] => return Poll::Ready(Ok(())),
+            Some(_) => {}
+            None => *ticks = Some(state.ticks[dir]),
+        }
+
+        if !state.wakers[dir].iter().any(|w| w.will_wake(cx.waker())) {
+            state.wakers[dir].push(cx.waker().clone());
+        }
+        self.arm(poller, &state)?;
+        Poll::Pending
+    }
+}
+
+/// A poller that wakes up the tasks waiting for its sources.
+///
+/// See the [module-level documentation](self) for more information.
+pub struct Driver {
+    poller: Poller,
+    sources: Mutex<HashMap<usize, Arc<Source>>>,
+    next_key: AtomicUsize,
+    events: Mutex<Events>,
+    stopped: AtomicBool,
+}
+
+impl fmt::Debug for Driver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Driver")
+            .field("poller", &self.poller)
+            .field("sources", &self.sources().len())
+            .finish()
+    }
+}
+
+impl Driver {
+    /// Create a new driver.
+    pub fn new() -> io::Result<Driver> {
+        Ok(Driver::with_poller(Poller::new()?))
+    }
+
+    /// Create a new driver on top of an existing poller.
+    ///
+    /// The poller must not have sources registered under keys that the driver hands out.
+    pub fn with_poller(poller: Poller) -> Driver {
+        Driver {
+            events: Mutex::new(poller.new_events()),
+            poller,
+            sources: Mutex::new(HashMap::new()),
+            next_key: AtomicUsize::new(0),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    /// The poller the sources are registered in.
+    #[inline]
+    pub fn poller(&self) -> &Poller {
+        &self.poller
+    }
+
+    /// Wait for events once and wake up the tasks waiting for the sources that received them.
+    ///
+    /// Returns the number of tasks that were woken up. Only one thread can turn the driver at a
+    /// time; concurrent calls return `Ok(0)` immediately.
+    pub fn turn(&self, timeout: Option<Duration>) -> io::Result<usize> {
+        let mut events = match self.events.try_lock() {
+            Ok(events) => events,
+            Err(_) => return Ok(0),
+        };
+
+        events.clear();
+        self.poller.wait(&mut events, timeout)?;
+
+        let mut woken = Vec::new();
+        for event in events.iter() {
+            let source = match self.sources().get(&event.key) {
+                Some(source) => source.clone(),
+                None => continue,
+            };
+
+            let mut state = source.state();
+            for (dir, ready) in [(READ, event.readable), (WRITE, event.writable)] {
+                if ready {
+                    state.ticks[dir] = state.ticks[dir].wrapping_add(1);
+                    woken.append(&mut state.wakers[dir]);
+                }
+            }
+
+            // The oneshot event disabled interest in the other direction as well.
+            if !state.wakers[READ].is_empty() || !state.wakers[WRITE].is_empty() {
+                source.arm(&self.poller, &state)?;
+            }
+        }
+
+        let len = woken.len();
+        for waker in woken {
+            waker.wake();
+        }
+        Ok(len)
+    }
+
+    /// Turn the driver until [`Driver::stop()`] is called.
+    ///
+    /// This is meant to be called on a background thread, while tasks on other threads wait
+    /// for their sources.
+    pub fn run(&self) -> io::Result<()> {
+        while !self.stopped.swap(false, Ordering::SeqCst) {
+            self.turn(None)?;
+        }
+        Ok(())
+    }
+
+    /// Make [`Driver::run()`] return after the current turn.
+    ///
+    /// If the driver is not running, the next call to [`Driver::run()`] returns immediately.
+    pub fn stop(&self) -> io::Result<()> {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.poller.notify()
+    }
+
+    /// Run `future` to completion on the current thread, turning the driver while it waits.
+    ///
+    /// The future is woken up by the sources of this driver, and by any other waker, which
+    /// interrupts the turn with a [notification][`Poller::notify()`]. The driver must not be
+    /// turned by another thread at the same time, since its events would not reach this one.
+    pub fn block_on<T>(self: &Arc<Self>, future: impl Future<Output = T>) -> T {
+        let mut future = pin!(future);
+        let waker = Waker::from(Arc::new(BlockOnWaker {
+            driver: Arc::downgrade(self),
+        }));
+        let mut cx = Context::from_waker(&waker);
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+
+            // Errors of the driver are seen by the future through its sources.
+            if let Err(_err) = self.turn(None) {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("block_on: failed to turn the driver: {}", _err);
+            }
+        }
+    }
+
+    /// Register `source` under a new key.
+    fn register(&self, source: RawSource) -> io::Result<Arc<Source>> {
+        let key = self.next_key.fetch_add(1, Ordering::Relaxed);
+        self.poller.check_key(key)?;
+        let source = Arc::new(Source {
+            raw: source,
+            key,
+            state: Mutex::new(State::default()),
+        });
+
+        // SAFETY: `Async` owns the source and deregisters it before dropping it.
+        unsafe {
+            self.poller
+                .add_with_mode(source.raw, Event::none(key), PollMode::Oneshot)?;
+        }
+        self.sources().insert(key, source.clone());
+        Ok(source)
+    }
+
+    /// Deregister `source`.
+    fn deregister(&self, source: &Source) -> io::Result<()> {
+        self.sources().remove(&source.key);
+
+        // SAFETY: The source is still open.
+        let raw = unsafe { BorrowedSource::borrow_raw(source.raw) };
+        self.poller.delete(raw)
+    }
+
+    /// Lock the source table, ignoring poisoning.
+    fn sources(&self) -> MutexGuard<'_, HashMap<usize, Arc<Source>>> {
+        self.sources.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// The waker of the future run by `block_on()`.
+struct BlockOnWaker {
+    driver: Weak<Driver>,
+}
+
+impl Wake for BlockOnWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        if let Some(driver) = self.driver.upgrade() {
+            driver.poller.notify().ok();
+        }
+    }
+}
+
+/// A source registered in a [`Driver`], with futures that wait for its readiness.
+///
+/// The source is deregistered when this is dropped, or when it is taken out with
+/// [`into_inner()`][`Async::into_inner()`].
+///
+/// See the [module-level documentation](self) for more information.
+pub struct Async<T: AsSource> {
+    driver: Arc<Driver>,
+    source: Arc<Source>,
+
+    /// The wrapped source, which is only `None` while it is taken out by `into_inner()`.
+    io: Option<T>,
+}
+
+impl<T: AsSource> Async<T> {
+    /// Register `io` in `driver`.
+    ///
+    /// The source has to be in nonblocking mode.
+    pub fn new(driver: &Arc<Driver>, io: T) -> io::Result<Async<T>> {
+        let source = driver.register((&io.source()).raw())?;
+        Ok(Async {
+            driver: driver.clone(),
+            source,
+            io: Some(io),
+        })
+    }
+
+    /// Get a reference to the wrapped source.
+    #[inline]
+    pub fn get_ref(&self) -> &T {
+        self.io.as_ref().unwrap()
+    }
+
+    /// Get a mutable reference to the wrapped source.
+    ///
+    /// # Safety
+    ///
+    /// The underlying file descriptor or socket must not be closed or replaced through this
+    /// reference, since it would then be dropped while still registered in the poller.
+    #[inline]
+    pub unsafe fn get_mut(&mut self) -> &mut T {
+        self.io.as_mut().unwrap()
+    }
+
+    /// Deregister the source and return it.
+    pub fn into_inner(mut self) -> io::Result<T> {
+        let io = self.io.take().unwrap();
+        self.driver.deregister(&self.source).map(|()| io)
+    }
+
+    /// Wait until the source is readable.
+    ///
+    /// Readiness can be spurious, so the operation that follows can still fail with
+    /// [`WouldBlock`][`io::ErrorKind::WouldBlock`]. [`read_with()`][`Async::read_with()`] handles
+    /// this.
+    pub async fn readable(&self) -> io::Result<()> {
+        let mut ticks = None;
+        poll_fn(|cx| {
+            self.source
+                .poll_ready(&self.driver.poller, READ, &mut ticks, cx)
+        })
+        .await
+    }
+
+    /// Wait until the source is writable.
+    ///
+    /// Readiness can be spurious, so the operation that follows can still fail with
+    /// [`WouldBlock`][`io::ErrorKind::WouldBlock`]. [`write_with()`][`Async::write_with()`]
+    /// handles this.
+    pub async fn writable(&self) -> io::Result<()> {
+        let mut ticks = None;
+        poll_fn(|cx| {
+            self.source
+                .poll_ready(&self.driver.poller, WRITE, &mut ticks, cx)
+        })
+        .await
+    }
+
+    /// Run a nonblocking operation on the source until it doesn't fail with
+    /// [`WouldBlock`][`io::ErrorKind::WouldBlock`], waiting for readability in between.
+    pub async fn read_with<R>(&self, mut op: impl FnMut(&T) -> io::Result<R>) -> io::Result<R> {
+        loop {
+            match op(self.get_ref()) {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                res => return res,
+            }
+            self.readable().await?;
+        }
+    }
+
+    /// Run a nonblocking operation on the source until it doesn't fail with
+    /// [`WouldBlock`][`io::ErrorKind::WouldBlock`], waiting for writability in between.
+    pub async fn write_with<R>(&self, mut op: impl FnMut(&T) -> io::Result<R>) -> io::Result<R> {
+        loop {
+            match op(self.get_ref()) {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                res => return res,
+            }
+            self.writable().await?;
+        }
+    }
+}
+
+impl<T: AsSource> Drop for Async<T> {
+    fn drop(&mut self) {
+        if self.io.is_some() {
+            if let Err(_err) = self.driver.deregister(&self.source) {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("failed to deregister source on drop: {}", _err);
+            }
+        }
+    }
+}
+
+impl<T: AsSource + fmt::Debug> fmt::Debug for Async<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Async")
+            .field("io", &self.io)
+            .field("key", &self.source.key)
+            .finish()
+    }
+}
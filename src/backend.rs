@@ -0,0 +1,118 @@
+//! The system interfaces that a `Poller` can be built on.
+
+use std::fmt;
+
+/// A system interface used to implement a [`Poller`].
+///
+/// Each platform has a default backend, [`Backend::current()`], which [`Poller::new()`] uses.
+/// Linux and Android also have the poll backend, so [`Backend::available()`] lists both there and
+/// a poller can be switched to poll at runtime, for instance with a configuration flag, using
+/// [`PollerBuilder::require_backend()`]. Requesting a backend that isn't available fails loudly
+/// rather than silently running on a different backend.
+///
+/// [`Poller`]: crate::Poller
+/// [`Poller::new()`]: crate::Poller::new
+/// [`PollerBuilder::require_backend()`]: crate::PollerBuilder::require_backend
+///
+/// # Examples
+///
+/// ```
+/// use polling::{Backend, Poller};
+///
+/// let backend = Backend::current();
+/// let poller = Poller::builder().require_backend(backend).build()?;
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Backend {
+    /// [epoll](https://man7.org/linux/man-pages/man7/epoll.7.html), used on Linux, Android and
+    /// Redox.
+    Epoll,
+
+    /// [kqueue](https://man.freebsd.org/cgi/man.cgi?kqueue), used on Apple platforms and the
+    /// BSDs.
+    Kqueue,
+
+    /// [Event ports](https://illumos.org/man/port_create), used on illumos and Solaris.
+    EventPorts,
+
+    /// [poll](https://man7.org/linux/man-pages/man2/poll.2.html), used on other Unix systems,
+    /// and available on Linux and Android.
+    Poll,
+
+    /// [I/O completion ports](https://learn.microsoft.com/en-us/windows/win32/fileio/i-o-completion-ports),
+    /// used on Windows.
+    Iocp,
+
+    /// A [`PollerBackend`] implemented outside of this crate, see
+    /// [`PollerBuilder::build_custom()`].
+    ///
+    /// This is never [available][`Backend::available()`], since it can't be built without the
+    /// implementation.
+    ///
+    /// [`PollerBackend`]: crate::PollerBackend
+    /// [`PollerBuilder::build_custom()`]: crate::PollerBuilder::build_custom
+    Custom,
+}
+
+impl Backend {
+    /// The default backend of this platform.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::Backend;
+    ///
+    /// println!("polling with {}", Backend::current());
+    /// ```
+    #[inline]
+    pub const fn current() -> Backend {
+        crate::sys::BACKEND
+    }
+
+    /// All backends that a [`Poller`] can be built with on this platform.
+    ///
+    /// [`Poller`]: crate::Poller
+    #[inline]
+    pub fn available() -> &'static [Backend] {
+        #[cfg(all(
+            any(target_os = "linux", target_os = "android"),
+            not(polling_test_poll_backend),
+        ))]
+        const AVAILABLE: &[Backend] = &[Backend::Epoll, Backend::Poll];
+        #[cfg(not(all(
+            any(target_os = "linux", target_os = "android"),
+            not(polling_test_poll_backend),
+        )))]
+        const AVAILABLE: &[Backend] = &[Backend::current()];
+        AVAILABLE
+    }
+
+    /// Whether a [`Poller`] can be built with this backend on this platform.
+    ///
+    /// [`Poller`]: crate::Poller
+    #[inline]
+    pub fn is_available(self) -> bool {
+        Self::available().contains(&self)
+    }
+
+    /// The name of the backend.
+    #[inline]
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Epoll => "epoll",
+            Backend::Kqueue => "kqueue",
+            Backend::EventPorts => "event ports",
+            Backend::Poll => "poll",
+            Backend::Iocp => "iocp",
+            Backend::Custom => "custom",
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
@@ -0,0 +1,900 @@
+//! Builder for configuring a `Poller`.
+
+use std::io;
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use crate::custom::Driver;
+use crate::hooks::HookSet;
+use crate::{sys, Backend, Clock, Hooks, PollError, PollMode, Poller, PollerBackend};
+
+/// A builder for creating a [`Poller`] with custom options.
+///
+/// [`Poller::new()`] is equivalent to `PollerBuilder::new().build()`.
+///
+/// # Examples
+///
+/// ```
+/// use polling::{PollMode, PollerBuilder};
+/// use std::num::NonZeroUsize;
+///
+/// let poller = PollerBuilder::new()
+///     .notify_key(0)
+///     .capacity(NonZeroUsize::new(64).unwrap())
+///     .mode(PollMode::Level)
+///     .build()?;
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct PollerBuilder {
+    /// The key reserved for notifications.
+    pub(crate) notify_key: usize,
+
+    /// The capacity of the `Events` created by `Poller::new_events()`.
+    pub(crate) capacity: NonZeroUsize,
+
+    /// The mode used by `add()`, `modify()` and `register()`.
+    pub(crate) mode: PollMode,
+
+    /// The backend that was explicitly requested, if any.
+    pub(crate) backend: Option<Backend>,
+
+    /// Whether several threads may wait at the same time.
+    pub(crate) concurrent_waits: bool,
+
+    /// How long to poll for events without blocking before waiting.
+    pub(crate) busy_poll: Option<Duration>,
+
+    /// The largest budget for busy-polling that adapts to the events.
+    pub(crate) adaptive_spin: Option<Duration>,
+
+    /// Whether the poller's file descriptor is closed on `exec`.
+    pub(crate) cloexec: bool,
+
+    /// Whether sources that are already ready when they are added are reported right away.
+    pub(crate) initial_readiness: bool,
+
+    /// Whether a notification stays pending until it is cleared.
+    pub(crate) sticky_notify: bool,
+
+    /// Whether the poller can be notified.
+    pub(crate) notifications: bool,
+
+    /// The clock that measures wait timeouts.
+    pub(crate) clock: Clock,
+
+    /// How much later than their deadline waits may wake up, to coalesce wakeups.
+    pub(crate) timer_slack: Option<Duration>,
+
+    /// Whether a wait keeps collecting events while they fill the buffer.
+    pub(crate) drain: bool,
+
+    /// Whether changes of interest are submitted together with the next wait.
+    pub(crate) batch_changes: bool,
+
+    /// Whether a wait that wakes up without events or a notification waits again.
+    pub(crate) retry_spurious: bool,
+
+    /// Whether changes of interest report the errors that are ignored otherwise.
+    pub(crate) strict_errors: bool,
+
+    /// Whether misuse of the poller panics in debug builds.
+    pub(crate) misuse_checks: bool,
+
+    /// How long repeated events of a key are held back, if they are limited at all.
+    pub(crate) rate_limit: Option<Duration>,
+
+    /// The key and interval of the periodic tick event, if any.
+    pub(crate) tick: Option<(usize, Duration)>,
+
+    /// The label that tells the poller apart in diagnostics.
+    pub(crate) label: Option<String>,
+
+    /// How long a wait may block past its deadline or notification before it is reported.
+    #[cfg(feature = "stall-diagnostics")]
+    pub(crate) stall_threshold: Duration,
+
+    /// The callbacks that observe the operations of the poller.
+    pub(crate) hooks: HookSet,
+}
+
+impl Default for PollerBuilder {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PollerBuilder {
+    /// Create a new builder with the default options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::PollerBuilder;
+    ///
+    /// let poller = PollerBuilder::new().build()?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            notify_key: crate::NOTIFY_KEY,
+            capacity: crate::Events::DEFAULT_CAPACITY,
+            mode: PollMode::Oneshot,
+            backend: None,
+            concurrent_waits: false,
+            busy_poll: None,
+            adaptive_spin: None,
+            cloexec: true,
+            initial_readiness: false,
+            sticky_notify: false,
+            notifications: true,
+            clock: Clock::Monotonic,
+            timer_slack: None,
+            drain: false,
+            batch_changes: false,
+            retry_spurious: false,
+            strict_errors: false,
+            misuse_checks: false,
+            rate_limit: None,
+            tick: None,
+            label: None,
+            #[cfg(feature = "stall-diagnostics")]
+            stall_threshold: Duration::from_secs(1),
+            hooks: HookSet::default(),
+        }
+    }
+
+    /// Set the key that is reserved for notifications.
+    ///
+    /// Internally, the poller registers its own notification mechanism under a key that can't be
+    /// used for other sources. By default this is `usize::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::PollerBuilder;
+    ///
+    /// let poller = PollerBuilder::new().notify_key(0).build()?;
+    /// assert_eq!(poller.notify_key(), 0);
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn notify_key(mut self, key: usize) -> Self {
+        self.notify_key = key;
+        self
+    }
+
+    /// Set the capacity of the [`Events`] created by [`Poller::new_events()`].
+    ///
+    /// This is the maximum number of events delivered by a single call to [`Poller::wait()`]
+    /// with such a container. By default this is the same as for [`Events::new()`].
+    ///
+    /// [`Events`]: crate::Events
+    /// [`Events::new()`]: crate::Events::new
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::PollerBuilder;
+    /// use std::num::NonZeroUsize;
+    ///
+    /// let capacity = NonZeroUsize::new(16).unwrap();
+    /// let poller = PollerBuilder::new().capacity(capacity).build()?;
+    /// assert_eq!(poller.new_events().capacity(), capacity);
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn capacity(mut self, capacity: NonZeroUsize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Set the default polling mode.
+    ///
+    /// This mode is used by [`Poller::add()`], [`Poller::modify()`] and [`Poller::register()`].
+    /// The `*_with_mode()` variants of these methods are unaffected. By default this is
+    /// [`PollMode::Oneshot`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{PollMode, PollerBuilder};
+    ///
+    /// let poller = PollerBuilder::new().mode(PollMode::Level).build()?;
+    /// assert_eq!(poller.mode(), PollMode::Level);
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn mode(mut self, mode: PollMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Require the poller to use a specific backend.
+    ///
+    /// By default, the poller uses [`Backend::current()`]. [`build()`][`Self::build()`] creates
+    /// the poller on the requested backend instead, if it is one of [`Backend::available()`],
+    /// such as poll rather than epoll on Linux. Otherwise it returns an error of kind
+    /// [`Unsupported`][`io::ErrorKind::Unsupported`] instead of silently running on another
+    /// backend.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Backend, PollerBuilder};
+    ///
+    /// let result = PollerBuilder::new().require_backend(Backend::Poll).build();
+    /// assert_eq!(result.is_ok(), Backend::Poll.is_available());
+    /// ```
+    #[inline]
+    pub fn require_backend(mut self, backend: Backend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Allow several threads to wait on the poller at the same time.
+    ///
+    /// By default only one thread can wait on I/O, and concurrent calls to [`Poller::wait()`]
+    /// return immediately with no new events. With concurrent waits enabled, every call waits
+    /// on the operating system with its own [`Events`], and each event is delivered to only one
+    /// of the waiting threads. [`Poller::notify()`] wakes up at least one of them.
+    ///
+    /// With the poll backend, concurrent waits are still serialized internally.
+    ///
+    /// [`Events`]: crate::Events
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, PollerBuilder};
+    /// use std::time::Duration;
+    ///
+    /// let poller = PollerBuilder::new().concurrent_waits(true).build()?;
+    ///
+    /// std::thread::scope(|s| {
+    ///     for _ in 0..4 {
+    ///         s.spawn(|| {
+    ///             let mut events = Events::new();
+    ///             poller.wait(&mut events, Some(Duration::from_millis(10))).unwrap();
+    ///         });
+    ///     }
+    /// });
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn concurrent_waits(mut self, enabled: bool) -> Self {
+        self.concurrent_waits = enabled;
+        self
+    }
+
+    /// Busy-poll for events for up to `duration` before blocking.
+    ///
+    /// Every call to [`Poller::wait()`] first checks for events without blocking, over and over,
+    /// until events are delivered, the poller is notified, the timeout elapses or `duration` has
+    /// passed. Only then does it block in the operating system. This trades CPU time for lower
+    /// latency. By default the poller never busy-polls.
+    ///
+    /// This is independent of socket-level busy polling such as `SO_BUSY_POLL` on Linux, which can
+    /// be enabled on the sources themselves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::PollerBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let poller = PollerBuilder::new()
+    ///     .busy_poll(Duration::from_micros(50))
+    ///     .build()?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn busy_poll(mut self, duration: Duration) -> Self {
+        self.busy_poll = Some(duration).filter(|duration| !duration.is_zero());
+        self
+    }
+
+    /// Busy-poll for events before blocking, for up to `max` and for as long as it pays off.
+    ///
+    /// Like with [`busy_poll()`][`PollerBuilder::busy_poll()`], [`Poller::wait()`] first checks
+    /// for events without blocking, but the duration adapts to how soon events arrive. It doubles,
+    /// up to `max`, whenever events or a notification arrive while busy-polling, and halves
+    /// whenever the wait had to block anyway, down to a 64th of `max`. Busy event loops thus
+    /// avoid the blocking system call, and idle ones spend little time spinning.
+    /// [`Poller::spin_budget()`] returns the current duration.
+    ///
+    /// This overrides [`busy_poll()`][`PollerBuilder::busy_poll()`]. A zero `max` disables it.
+    ///
+    /// [`Poller::wait()`]: crate::Poller::wait
+    /// [`Poller::spin_budget()`]: crate::Poller::spin_budget
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::PollerBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let poller = PollerBuilder::new()
+    ///     .adaptive_spin(Duration::from_micros(200))
+    ///     .build()?;
+    /// assert_eq!(poller.spin_budget(), Some(Duration::from_micros(200)));
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn adaptive_spin(mut self, max: Duration) -> Self {
+        self.adaptive_spin = Some(max).filter(|max| !max.is_zero());
+        self
+    }
+
+    /// Set whether the poller's file descriptor is closed when the process calls `exec`.
+    ///
+    /// By default the epoll, kqueue or event port file descriptor is created with `CLOEXEC`.
+    /// Disabling this lets the descriptor be inherited across `exec`, for graceful restarts that
+    /// re-execute the program in place. The new program can take the poller over with
+    /// `Poller::from_fd()`. With epoll and event ports, registrations of sources that are
+    /// inherited as well remain in place. kqueues are never inherited by child processes created
+    /// with `fork`.
+    ///
+    /// This has no effect on the poll and IOCP backends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::PollerBuilder;
+    ///
+    /// let poller = PollerBuilder::new().cloexec(false).build()?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn cloexec(mut self, enabled: bool) -> Self {
+        self.cloexec = enabled;
+        self
+    }
+
+    /// Set whether sources that are already ready when they are added are always reported.
+    ///
+    /// Whether a source that is already readable or writable when it is
+    /// [added][`Poller::add()`] is reported by the next wait differs between backends and
+    /// modes. With this option, the poller checks the readiness of every added source with a
+    /// quick, non-blocking `poll()` and delivers the event with the next call to
+    /// [`Poller::wait()`], merged with the event of the backend if it reports one too. In the
+    /// oneshot modes, the registration is then disabled as if the backend had delivered the
+    /// event. If the poller is paused, nothing is checked.
+    ///
+    /// This costs a system call for every added source, so it is disabled by default. It has no
+    /// effect on platforms other than Unix and Windows, or on sources added with separate keys
+    /// for reading and writing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, PollerBuilder};
+    /// use std::net::UdpSocket;
+    /// use std::time::Duration;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// let poller = PollerBuilder::new().initial_readiness(true).build()?;
+    /// unsafe {
+    ///     poller.add(&socket, Event::writable(1))?;
+    /// }
+    ///
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// assert_eq!(events.len(), 1);
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn initial_readiness(mut self, enabled: bool) -> Self {
+        self.initial_readiness = enabled;
+        self
+    }
+
+    /// Set whether a notification keeps the poller notified until it is cleared.
+    ///
+    /// By default, a call to [`Poller::notify()`] wakes up the current or the next call to
+    /// [`Poller::wait()`] once. With this option, the poller stays notified instead: every wait
+    /// returns right away and reports the notification, until
+    /// [`Poller::clear_notification()`] is called. This suits signaling that there is queued
+    /// work, where the waiting thread clears the notification before it takes the work.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, PollerBuilder};
+    ///
+    /// let poller = PollerBuilder::new().sticky_notify(true).build()?;
+    /// poller.notify()?;
+    ///
+    /// let mut events = Events::new();
+    /// assert!(poller.wait_result(&mut events, None)?.is_notified());
+    /// assert!(poller.wait_result(&mut events, None)?.is_notified());
+    ///
+    /// poller.clear_notification()?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn sticky_notify(mut self, enabled: bool) -> Self {
+        self.sticky_notify = enabled;
+        self
+    }
+
+    /// Set whether the poller can be notified.
+    ///
+    /// Notifications are enabled by default. Programs that wait on a single thread and never
+    /// call [`Poller::notify()`] can disable them to save the eventfd or pipe that `epoll` uses
+    /// to wake up, and the work of clearing it after a wakeup. Other backends wake up without
+    /// an extra file descriptor, or need it internally, and keep it.
+    ///
+    /// Without notifications, [`Poller::notify()`] fails with
+    /// [`io::ErrorKind::Unsupported`]. Nothing can wake up a wait before it returns for an event
+    /// or its timeout, including [wakers][`Poller::waker()`], [`Poller::close()`] and timers
+    /// set by other threads.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::PollerBuilder;
+    ///
+    /// let poller = PollerBuilder::new().notifications(false).build()?;
+    /// assert!(poller.notify().is_err());
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn notifications(mut self, enabled: bool) -> Self {
+        self.notifications = enabled;
+        self
+    }
+
+    /// Set the clock that measures the timeouts of waits.
+    ///
+    /// By default, timeouts are measured with a monotonic clock that stops while the system is
+    /// suspended. [`Clock::Boottime`] counts the time spent suspended as well, which daemons on
+    /// laptops and phones need for timeouts that mean real time. See [`Clock`] for the caveats.
+    ///
+    /// Building the poller fails if the backend doesn't support the clock, see
+    /// [`Clock::is_supported()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Clock, PollerBuilder};
+    ///
+    /// let poller = PollerBuilder::new().clock(Clock::Monotonic).build()?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Allow waits to wake up to `slack` later than their deadline, so that wakeups coalesce.
+    ///
+    /// With a slack, the deadline of every wait and [timer][`Poller::set_timer()`] is delayed to
+    /// the next multiple of `slack`, counted from a point in time that all pollers in the
+    /// process share. Wakeups that are due within the same interval then happen at once, which
+    /// lets the system sleep longer and saves power. Waits that don't block, such as with a zero
+    /// timeout, are never delayed.
+    ///
+    /// By default there is no slack, for the lowest latency. The operating system may still add
+    /// a small slack of its own, such as the timer slack of the thread on Linux.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::PollerBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let poller = PollerBuilder::new()
+    ///     .timer_slack(Duration::from_millis(50))
+    ///     .build()?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn timer_slack(mut self, slack: Duration) -> Self {
+        self.timer_slack = Some(slack).filter(|slack| !slack.is_zero());
+        self
+    }
+
+    /// Set whether a wait collects all events that are ready, even more than the buffer holds.
+    ///
+    /// By default, a wait delivers at most as many events as the backend buffer of the
+    /// [`Events`] holds, and [`WaitResult::may_have_more()`] tells whether more may be pending.
+    /// With this option, a wait that fills the buffer checks for more events without blocking,
+    /// and appends them as well, until a check doesn't fill the buffer. A single wait then
+    /// delivers everything that is ready, which suits dispatchers that handle events in one
+    /// pass. Level-triggered sources are reported again by every check, so collecting stops
+    /// once a source is reported twice. The limits of [`Poller::wait_limit()`] and
+    /// [`Poller::wait_array()`] still apply.
+    ///
+    /// [`Events`]: crate::Events
+    /// [`WaitResult::may_have_more()`]: crate::WaitResult::may_have_more
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, PollerBuilder};
+    /// use std::net::UdpSocket;
+    /// use std::num::NonZeroUsize;
+    /// use std::time::Duration;
+    ///
+    /// let poller = PollerBuilder::new().drain(true).build()?;
+    /// let a = poller.register(UdpSocket::bind("127.0.0.1:0")?, Event::writable(1))?;
+    /// let b = poller.register(UdpSocket::bind("127.0.0.1:0")?, Event::writable(2))?;
+    ///
+    /// // Both events are delivered, although the buffer only holds one.
+    /// let mut events = Events::with_capacity(NonZeroUsize::new(1).unwrap());
+    /// poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// assert_eq!(events.len(), 2);
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn drain(mut self, enabled: bool) -> Self {
+        self.drain = enabled;
+        self
+    }
+
+    /// Submit changes of interest together with the next wait.
+    ///
+    /// By default, every [`Poller::modify()`] is a system call of its own. With this option, the
+    /// kqueue backend queues the changes instead, and the next wait submits all of them with a
+    /// single `kevent()` call before waiting, which saves system calls when many sources are
+    /// re-armed in a loop. Changes made while another thread is waiting are still submitted right
+    /// away, so that they apply to that wait, and adding and deleting sources is never batched.
+    ///
+    /// A queued change that fails is reported as the error of the next wait, instead of by
+    /// `modify()`. That wait doesn't block, and still delivers the events that are ready. Other
+    /// backends ignore this option.
+    ///
+    /// [`Poller::modify()`]: crate::Poller::modify
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, PollerBuilder};
+    /// use std::net::UdpSocket;
+    /// use std::time::Duration;
+    ///
+    /// let poller = PollerBuilder::new().batch_changes(true).build()?;
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// unsafe {
+    ///     poller.add(&socket, Event::none(1))?;
+    /// }
+    ///
+    /// // The new interest is submitted by the wait.
+    /// poller.modify(&socket, Event::writable(1))?;
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// assert_eq!(events.len(), 1);
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn batch_changes(mut self, enabled: bool) -> Self {
+        self.batch_changes = enabled;
+        self
+    }
+
+    /// Set whether a wait that wakes up without events or a notification waits again.
+    ///
+    /// The backends wake up a wait for their own reasons as well, for instance when a
+    /// notification arrives after an earlier wait already returned for it, or when the interest
+    /// in a source changes while another thread is waiting. By default such a wait returns
+    /// without events, and [`WaitResult`] reports that it was neither notified nor timed out.
+    /// With this option, the wait goes back to sleep for the rest of its timeout instead, so it
+    /// only returns for events, a notification, or the end of the timeout.
+    ///
+    /// [`WaitResult`]: crate::WaitResult
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, PollerBuilder};
+    /// use std::time::Duration;
+    ///
+    /// let poller = PollerBuilder::new().retry_spurious(true).build()?;
+    ///
+    /// let mut events = Events::new();
+    /// let result = poller.wait_result(&mut events, Some(Duration::from_millis(10)))?;
+    /// assert!(result.is_timed_out());
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn retry_spurious(mut self, enabled: bool) -> Self {
+        self.retry_spurious = enabled;
+        self
+    }
+
+    /// Set whether changes of interest report the errors that are ignored otherwise.
+    ///
+    /// The kqueue backend ignores two errors when it changes the filters of a file descriptor:
+    /// a filter that doesn't exist when it is deleted, and a pipe whose other end is closed. The
+    /// first happens when a oneshot filter already fired, and the second is reported by the
+    /// events of the pipe anyway. They can also hide bookkeeping bugs however, such as a file
+    /// descriptor that was closed while it was registered. With this option, these errors are
+    /// returned by [`Poller::modify()`] and [`Poller::delete()`], with a message that names the
+    /// filter that failed, unless the filter may have been deleted because it fired. Changes of
+    /// interest are then never [batched](PollerBuilder::batch_changes), so that the call that
+    /// caused an error reports it. Other backends ignore this option.
+    ///
+    /// [`Poller::modify()`]: crate::Poller::modify
+    /// [`Poller::delete()`]: crate::Poller::delete
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::PollerBuilder;
+    ///
+    /// let poller = PollerBuilder::new().strict_errors(true).build()?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn strict_errors(mut self, enabled: bool) -> Self {
+        self.strict_errors = enabled;
+        self
+    }
+
+    /// Set whether misuse of the poller panics in debug builds.
+    ///
+    /// A poller keeps a table of the sources that are registered in it. With this option, it
+    /// checks the calls against that table, and panics in debug builds when a source that isn't
+    /// registered is modified or deleted, a source is added twice, the notification key is
+    /// used for a source, or the poller is dropped while sources are still registered in it.
+    /// These calls return errors as well, or do nothing at all on some backends, which is easy
+    /// to miss. In release builds, the misuse is logged with the `tracing` feature instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, PollerBuilder};
+    /// use std::net::UdpSocket;
+    ///
+    /// let poller = PollerBuilder::new().misuse_checks(true).build()?;
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// unsafe {
+    ///     poller.add(&socket, Event::readable(1))?;
+    /// }
+    ///
+    /// // Dropping the poller before this would panic in a debug build.
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn misuse_checks(mut self, enabled: bool) -> Self {
+        self.misuse_checks = enabled;
+        self
+    }
+
+    /// Limit how often the events of a key are delivered.
+    ///
+    /// A single source that is always ready can flood the loop with events, in particular in
+    /// level-triggered or edge-triggered mode. With this option, the events of a key within one
+    /// wait are merged into one. With an interval longer than zero, a key is also delivered at
+    /// most once per interval: its later events are held back, merged into one, and delivered
+    /// by the first wait after the interval elapsed. This applies to the events of timers and
+    /// wakers as well. `None` disables the limit, which is the default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::PollerBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let poller = PollerBuilder::new()
+    ///     .rate_limit(Some(Duration::from_millis(10)))
+    ///     .build()?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn rate_limit(mut self, interval: Option<Duration>) -> Self {
+        self.rate_limit = interval;
+        self
+    }
+
+    /// Deliver a readable event with `key` every `interval`, even when no I/O happens.
+    ///
+    /// This is a periodic [timer][`Poller::set_timer()`] that the poller sets again whenever it
+    /// expires, so that housekeeping like flushing metrics or sweeping timeouts can run from the
+    /// event loop without a timer of its own or a short timeout for every wait. Like other
+    /// timers, the tick shares the timeout of the wait with the backend, and it is measured with
+    /// the [clock][`PollerBuilder::clock()`] of the poller. If a wait returns late, the tick is
+    /// delivered once and the next one is due `interval` later, so ticks don't pile up. The key
+    /// doesn't need to belong to a registered source. `None` or a zero interval disables the
+    /// tick, which is the default.
+    ///
+    /// [`build()`][`PollerBuilder::build()`] returns an error if `key` is the notification key.
+    ///
+    /// [`Poller::set_timer()`]: crate::Poller::set_timer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, PollerBuilder};
+    /// use std::time::Duration;
+    ///
+    /// let poller = PollerBuilder::new()
+    ///     .tick(7, Some(Duration::from_millis(10)))
+    ///     .build()?;
+    ///
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, None)?;
+    /// assert_eq!(events.iter().next().unwrap().key, 7);
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn tick(mut self, key: usize, interval: Option<Duration>) -> Self {
+        self.tick = interval
+            .filter(|interval| !interval.is_zero())
+            .map(|interval| (key, interval));
+        self
+    }
+
+    /// Give the poller a label that tells it apart from others in diagnostics.
+    ///
+    /// The label is shown in the [`Debug`] output of the poller and recorded in its `tracing`
+    /// spans, which helps when a process runs many pollers, such as one per core. It doesn't
+    /// change how the poller works. Pollers have no label by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::PollerBuilder;
+    ///
+    /// let poller = PollerBuilder::new().label("worker-3").build()?;
+    /// assert_eq!(poller.label(), Some("worker-3"));
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Set how long a wait may block past its deadline or a notification before it is reported
+    /// as a [`Stall`].
+    ///
+    /// A wait stalls when it returns more than `threshold` after its deadline, on top of the
+    /// [timer slack][`PollerBuilder::timer_slack()`], or when a notification wakes it up more
+    /// than `threshold` after it was sent. A notification that doesn't wake up the waiting thread
+    /// at all is reported when the poller is notified again. Stalls are logged with the
+    /// `tracing` feature and passed to [`Hooks::on_stall()`]. Threads that are descheduled or a
+    /// system that is suspended can cause stalls too, so the threshold should be well above the
+    /// expected scheduling latency. The default is one second.
+    ///
+    /// [`Stall`]: crate::Stall
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::PollerBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let poller = PollerBuilder::new()
+    ///     .stall_threshold(Duration::from_millis(100))
+    ///     .build()?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[cfg(feature = "stall-diagnostics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stall-diagnostics")))]
+    #[inline]
+    pub fn stall_threshold(mut self, threshold: Duration) -> Self {
+        self.stall_threshold = threshold;
+        self
+    }
+
+    /// Install callbacks that observe the operations of the poller.
+    ///
+    /// The [`Hooks`] are invoked when sources are added, modified or deleted, when the poller is
+    /// notified, and when a wait returns. This replaces hooks that were installed before.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Hooks, PollerBuilder, WaitResult};
+    /// use std::io;
+    ///
+    /// struct LogWaits;
+    ///
+    /// impl Hooks for LogWaits {
+    ///     fn on_wait(&self, result: Result<WaitResult, &io::Error>) {
+    ///         println!("wait returned: {:?}", result);
+    ///     }
+    /// }
+    ///
+    /// let poller = PollerBuilder::new().hooks(LogWaits).build()?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn hooks(mut self, hooks: impl Hooks) -> Self {
+        self.hooks = HookSet::new(hooks);
+        self
+    }
+
+    /// Create the [`Poller`].
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if the requested backend or the default polling mode isn't supported
+    /// by the operating system, or if the poller could not be created.
+    pub fn build(self) -> io::Result<Poller> {
+        self.build_with(|builder| sys::Poller::new(builder).map(Driver::Native))
+    }
+
+    /// Create the [`Poller`] on top of a custom backend.
+    ///
+    /// The backend gets the registrations, waits and notifications of the poller, while the
+    /// poller still keeps its registry, timers and the other options of this builder on top of
+    /// it. See [`PollerBackend`] for what doesn't work without a system backend.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if a backend other than [`Backend::Custom`] was required with
+    /// [`require_backend()`][`PollerBuilder::require_backend()`], or if the default polling mode
+    /// isn't supported by the custom backend.
+    ///
+    /// # Examples
+    ///
+    /// See [`PollerBackend`].
+    pub fn build_custom(mut self, backend: impl PollerBackend + 'static) -> io::Result<Poller> {
+        match self.backend.take() {
+            None | Some(Backend::Custom) => {}
+            Some(backend) => {
+                return Err(crate::unsupported_error(format!(
+                    "a custom backend can't be built as the {} backend",
+                    backend
+                )));
+            }
+        }
+        self.build_with(|_| Ok(Driver::Custom(Box::new(backend))))
+    }
+
+    /// Create the [`Poller`] on top of the backend poller returned by `f`.
+    pub(crate) fn build_with(
+        self,
+        f: impl FnOnce(&Self) -> io::Result<Driver>,
+    ) -> io::Result<Poller> {
+        if let Some(backend) = self.backend {
+            if !backend.is_available() {
+                return Err(crate::unsupported_error(format!(
+                    "the {} backend is not available on this platform",
+                    backend
+                )));
+            }
+        }
+        // Only the default backend measures timeouts with other clocks.
+        let backend = self.backend.unwrap_or(Backend::current());
+        if !self.clock.is_supported()
+            || (self.clock != Clock::Monotonic && backend != Backend::current())
+        {
+            return Err(crate::unsupported_error(format!(
+                "the {} clock is not supported by the {} backend",
+                self.clock, backend
+            )));
+        }
+
+        if let Some((key, _)) = self.tick {
+            if key == self.notify_key {
+                return Err(PollError::ReservedKey.into());
+            }
+        }
+
+        let sys = f(&self)?;
+        let poller = Poller::from_sys(self, sys);
+
+        let supported = match poller.mode() {
+            PollMode::Oneshot => true,
+            PollMode::Level => poller.supports_level(),
+            PollMode::Edge | PollMode::EdgeOneshot => poller.supports_edge(),
+        };
+        if !supported {
+            return Err(crate::unsupported_error(
+                "the default polling mode is not supported on this platform",
+            ));
+        }
+
+        Ok(poller)
+    }
+}
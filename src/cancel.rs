@@ -0,0 +1,135 @@
+//! Tokens that cancel waits from other threads.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::Poller;
+
+/// A handle that cancels waits on a [`Poller`].
+///
+/// Cancelling the token wakes up every [`wait_cancelable()`] it is passed to, and makes it
+/// return with [`WaitResult::is_cancelled()`]. Unlike [`notify()`], this doesn't touch the
+/// notification of the poller, so a shutdown doesn't swallow a wakeup meant for the event loop,
+/// nor is it mistaken for one.
+///
+/// Cancellation is permanent. A wait that is passed a cancelled token only collects the events
+/// that are ready and returns right away, so a new token is needed to wait again. Clones share
+/// the cancellation, and the same token can be passed to waits on several pollers, for instance
+/// to stop every event loop of a program at once.
+///
+/// [`wait_cancelable()`]: crate::Poller::wait_cancelable
+/// [`WaitResult::is_cancelled()`]: crate::WaitResult::is_cancelled
+/// [`notify()`]: crate::Poller::notify
+///
+/// # Examples
+///
+/// ```
+/// use polling::{CancelToken, Events, Poller};
+/// use std::thread;
+///
+/// let poller = Poller::new()?;
+/// let token = CancelToken::new();
+///
+/// let handle = token.clone();
+/// thread::spawn(move || handle.cancel());
+///
+/// let mut events = Events::new();
+/// loop {
+///     let result = poller.wait_cancelable(&mut events, None, &token)?;
+///     if result.is_cancelled() {
+///         break;
+///     }
+///     events.clear();
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    cancelled: AtomicBool,
+
+    /// The pollers that a wait with this token is blocked on.
+    waiting: Mutex<Vec<Waiting>>,
+}
+
+/// A poller that a wait with the token is blocked on.
+struct Waiting(*const Poller);
+
+// SAFETY: The poller is `Sync`, and the pointer is only used while the wait runs.
+unsafe impl Send for Waiting {}
+
+impl CancelToken {
+    /// Creates a token that isn't cancelled.
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    /// Cancels the waits that are passed this token, now and in the future.
+    ///
+    /// Cancelling a token that is already cancelled does nothing.
+    pub fn cancel(&self) {
+        if self.inner.cancelled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        for waiting in self.lock().iter() {
+            // SAFETY: A wait removes its poller before it returns, under the same lock.
+            let poller = unsafe { &*waiting.0 };
+
+            // Wake up the backend without notifying the poller.
+            if let Err(_err) = poller.poller.notify() {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(err = %_err, "cancel: failed to wake up the poller");
+            }
+        }
+    }
+
+    /// Returns `true` if the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Record that a wait on `poller` runs until the returned guard is dropped.
+    pub(crate) fn register<'a>(&'a self, poller: &'a Poller) -> Registered<'a> {
+        self.lock().push(Waiting(poller));
+        Registered {
+            token: self,
+            poller,
+        }
+    }
+
+    /// Lock the waiting pollers, ignoring poisoning.
+    fn lock(&self) -> MutexGuard<'_, Vec<Waiting>> {
+        self.inner.waiting.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for CancelToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancelToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// A wait that a token wakes up when it is cancelled.
+pub(crate) struct Registered<'a> {
+    token: &'a CancelToken,
+    poller: &'a Poller,
+}
+
+impl Drop for Registered<'_> {
+    fn drop(&mut self) {
+        let mut waiting = self.token.lock();
+        if let Some(i) = waiting
+            .iter()
+            .position(|waiting| std::ptr::eq(waiting.0, self.poller))
+        {
+            waiting.swap_remove(i);
+        }
+    }
+}
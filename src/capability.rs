@@ -0,0 +1,70 @@
+//! Features that only some backends provide.
+
+/// A feature that may not be supported by every backend.
+///
+/// Use [`Poller::supports()`] to check for a capability at runtime, instead of keeping a table
+/// of platforms and backends that may go out of date as the backends change.
+///
+/// [`Poller::supports()`]: crate::Poller::supports
+///
+/// # Examples
+///
+/// ```
+/// use polling::{Capability, PollMode, Poller};
+///
+/// let poller = Poller::new()?;
+/// let mode = if poller.supports(Capability::EdgeTrigger) {
+///     PollMode::Edge
+/// } else {
+///     PollMode::Level
+/// };
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Capability {
+    /// Sources can be registered with [`PollMode::Level`].
+    ///
+    /// [`PollMode::Level`]: crate::PollMode::Level
+    LevelTrigger,
+
+    /// Sources can be registered with [`PollMode::Edge`] and [`PollMode::EdgeOneshot`].
+    ///
+    /// [`PollMode::Edge`]: crate::PollMode::Edge
+    /// [`PollMode::EdgeOneshot`]: crate::PollMode::EdgeOneshot
+    EdgeTrigger,
+
+    /// Events report urgent data, see [`Event::with_priority()`].
+    ///
+    /// [`Event::with_priority()`]: crate::Event::with_priority
+    Priority,
+
+    /// Events report hangups, see [`Event::with_interrupt()`].
+    ///
+    /// [`Event::with_interrupt()`]: crate::Event::with_interrupt
+    Hangup,
+
+    /// Only one of several pollers is woken up for a source, see [`Interest::EXCLUSIVE`].
+    ///
+    /// [`Interest::EXCLUSIVE`]: crate::Interest::EXCLUSIVE
+    Exclusive,
+
+    /// The system is kept awake while events are handled, see [`Interest::WAKEUP`].
+    ///
+    /// [`Interest::WAKEUP`]: crate::Interest::WAKEUP
+    Wakeup,
+
+    /// Timers are delivered as events, see [`Poller::set_timer()`].
+    ///
+    /// [`Poller::set_timer()`]: crate::Poller::set_timer
+    Timers,
+
+    /// The exit and other events of processes can be watched.
+    ProcessEvents,
+
+    /// The delivery of signals can be watched.
+    Signals,
+
+    /// Waitable handles such as events, processes and threads can be registered.
+    Waitables,
+}
@@ -0,0 +1,304 @@
+//! C bindings for embedding the poller in C and C++ projects.
+//!
+//! The functions in this module are exported with unmangled names and declared in
+//! `include/polling.h`. Build the crate as a `staticlib` or `cdylib` with the `capi` feature
+//! enabled to link against them, for example:
+//!
+//! ```text
+//! cargo rustc --release --features capi --crate-type staticlib
+//! ```
+//!
+//! A poller is represented by an opaque `polling_t` handle. Functions that can fail return `0` on
+//! success, the raw OS error code if the operating system reported an error, or `-1` otherwise.
+
+#![allow(non_camel_case_types)]
+
+use std::num::NonZeroUsize;
+use std::os::raw::c_int;
+use std::ptr;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::{io, slice};
+
+use crate::{Event, Events, Interest, PollMode, Poller};
+
+#[cfg(unix)]
+use std::os::unix::io::BorrowedFd as BorrowedSource;
+#[cfg(windows)]
+use std::os::windows::io::BorrowedSocket as BorrowedSource;
+
+/// The raw file descriptor or socket of a source, `polling_source_t` in C.
+pub type polling_source_t = crate::RawSource;
+
+/// The source can be read from without blocking.
+pub const POLLING_READABLE: u32 = 1 << 0;
+/// The source can be written to without blocking.
+pub const POLLING_WRITABLE: u32 = 1 << 1;
+/// There is urgent data to read.
+pub const POLLING_PRIORITY: u32 = 1 << 2;
+/// The source has been closed or hung up.
+pub const POLLING_HANGUP: u32 = 1 << 3;
+/// An error occurred on the source.
+pub const POLLING_ERROR: u32 = 1 << 4;
+
+/// Oneshot mode, see [`PollMode::Oneshot`].
+pub const POLLING_MODE_ONESHOT: c_int = 0;
+/// Level-triggered mode, see [`PollMode::Level`].
+pub const POLLING_MODE_LEVEL: c_int = 1;
+/// Edge-triggered mode, see [`PollMode::Edge`].
+pub const POLLING_MODE_EDGE: c_int = 2;
+/// Edge-triggered oneshot mode, see [`PollMode::EdgeOneshot`].
+pub const POLLING_MODE_EDGE_ONESHOT: c_int = 3;
+
+/// The mapping between the C flags and [`Interest`].
+const FLAGS: [(u32, Interest); 5] = [
+    (POLLING_READABLE, Interest::READABLE),
+    (POLLING_WRITABLE, Interest::WRITABLE),
+    (POLLING_PRIORITY, Interest::PRIORITY),
+    (POLLING_HANGUP, Interest::HANGUP),
+    (POLLING_ERROR, Interest::ERROR),
+];
+
+/// An opaque poller handle, `polling_t` in C.
+#[derive(Debug)]
+pub struct polling_t {
+    poller: Poller,
+
+    /// The buffer reused by [`polling_wait()`].
+    events: Mutex<Option<Events>>,
+}
+
+/// An I/O event, `polling_event_t` in C.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct polling_event_t {
+    /// The key of the source.
+    pub key: usize,
+
+    /// The `POLLING_*` flags of the event.
+    pub flags: u32,
+}
+
+/// Create a new poller and store its handle in `out`.
+///
+/// # Safety
+///
+/// `out` must be valid for writes. The handle must be released with [`polling_free()`].
+#[no_mangle]
+pub unsafe extern "C" fn polling_new(out: *mut *mut polling_t) -> c_int {
+    if out.is_null() {
+        return -1;
+    }
+
+    match Poller::new() {
+        Ok(poller) => {
+            *out = Box::into_raw(Box::new(polling_t {
+                poller,
+                events: Mutex::new(None),
+            }));
+            0
+        }
+        Err(err) => {
+            *out = ptr::null_mut();
+            error_code(&err)
+        }
+    }
+}
+
+/// Release a poller handle.
+///
+/// # Safety
+///
+/// `poller` must be null or a handle returned by [`polling_new()`] that has not been released
+/// yet. Sources still registered in the poller are not closed.
+#[no_mangle]
+pub unsafe extern "C" fn polling_free(poller: *mut polling_t) {
+    if !poller.is_null() {
+        drop(Box::from_raw(poller));
+    }
+}
+
+/// Add a source to the poller with the `POLLING_*` interest `flags` under `key`.
+///
+/// # Safety
+///
+/// `poller` must be a valid handle. The source must be deleted with [`polling_delete()`] before
+/// it is closed, see [`Poller::add()`].
+#[no_mangle]
+pub unsafe extern "C" fn polling_add(
+    poller: *const polling_t,
+    source: polling_source_t,
+    key: usize,
+    flags: u32,
+    mode: c_int,
+) -> c_int {
+    let (Some(poller), Some(mode)) = (poller.as_ref(), poll_mode(mode)) else {
+        return -1;
+    };
+
+    result_code(
+        poller
+            .poller
+            .add_with_mode(source, event_from_flags(key, flags), mode),
+    )
+}
+
+/// Change the interest of a source that was added with [`polling_add()`].
+///
+/// # Safety
+///
+/// `poller` must be a valid handle and `source` must be open.
+#[no_mangle]
+pub unsafe extern "C" fn polling_modify(
+    poller: *const polling_t,
+    source: polling_source_t,
+    key: usize,
+    flags: u32,
+    mode: c_int,
+) -> c_int {
+    let (Some(poller), Some(mode)) = (poller.as_ref(), poll_mode(mode)) else {
+        return -1;
+    };
+
+    result_code(poller.poller.modify_with_mode(
+        BorrowedSource::borrow_raw(source),
+        event_from_flags(key, flags),
+        mode,
+    ))
+}
+
+/// Remove a source from the poller.
+///
+/// # Safety
+///
+/// `poller` must be a valid handle and `source` must be open.
+#[no_mangle]
+pub unsafe extern "C" fn polling_delete(
+    poller: *const polling_t,
+    source: polling_source_t,
+) -> c_int {
+    let Some(poller) = poller.as_ref() else {
+        return -1;
+    };
+
+    result_code(poller.poller.delete(BorrowedSource::borrow_raw(source)))
+}
+
+/// Wait for at most `capacity` events and store them in `events`.
+///
+/// A negative `timeout_ms` waits forever. The number of events is stored in `count`, which may
+/// be null. Events that don't fit into `events` are returned by the next call.
+///
+/// # Safety
+///
+/// `poller` must be a valid handle, `events` must be valid for writing `capacity` events, and
+/// `count` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn polling_wait(
+    poller: *const polling_t,
+    events: *mut polling_event_t,
+    capacity: usize,
+    timeout_ms: i64,
+    count: *mut usize,
+) -> c_int {
+    let (Some(poller), Some(capacity)) = (poller.as_ref(), NonZeroUsize::new(capacity)) else {
+        return -1;
+    };
+    if events.is_null() {
+        return -1;
+    }
+
+    // Another thread that waits at the same time gets a buffer of its own.
+    let mut buffer = poller.events.try_lock().ok();
+    let mut temporary = None;
+    let list = match buffer.as_deref_mut() {
+        Some(buffer) => buffer.get_or_insert_with(Events::new),
+        None => temporary.insert(Events::new()),
+    };
+
+    let timeout = u64::try_from(timeout_ms).ok().map(Duration::from_millis);
+    list.clear();
+    let full = list.limit(capacity.get());
+    let res = poller.poller.wait(list, timeout);
+    list.unlimit(full);
+
+    let out = slice::from_raw_parts_mut(events, capacity.get());
+    let mut iter = list.iter();
+    let mut n = 0;
+    for (slot, event) in out.iter_mut().zip(iter.by_ref()) {
+        *slot = polling_event_t {
+            key: event.key,
+            flags: flags_from_event(&event),
+        };
+        n += 1;
+    }
+    if let Some(count) = count.as_mut() {
+        *count = n;
+    }
+
+    // Split sources and set-aside events can exceed the capacity. Keep the rest for the next
+    // call.
+    let rest = iter.collect::<Vec<_>>();
+    if !rest.is_empty() {
+        poller.poller.deferred().splice(0..0, rest);
+    }
+    list.clear();
+
+    result_code(res.map(drop))
+}
+
+/// Wake up the current or the next call to [`polling_wait()`].
+///
+/// # Safety
+///
+/// `poller` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn polling_notify(poller: *const polling_t) -> c_int {
+    let Some(poller) = poller.as_ref() else {
+        return -1;
+    };
+
+    result_code(poller.poller.notify())
+}
+
+/// Convert a C mode to a [`PollMode`].
+fn poll_mode(mode: c_int) -> Option<PollMode> {
+    match mode {
+        POLLING_MODE_ONESHOT => Some(PollMode::Oneshot),
+        POLLING_MODE_LEVEL => Some(PollMode::Level),
+        POLLING_MODE_EDGE => Some(PollMode::Edge),
+        POLLING_MODE_EDGE_ONESHOT => Some(PollMode::EdgeOneshot),
+        _ => None,
+    }
+}
+
+/// Convert C flags to an [`Event`].
+fn event_from_flags(key: usize, flags: u32) -> Event {
+    let interest = FLAGS
+        .iter()
+        .filter(|(flag, _)| flags & flag != 0)
+        .fold(Interest::NONE, |all, (_, interest)| all | *interest);
+    Event::from_interest(key, interest)
+}
+
+/// Convert a delivered [`Event`] to C flags.
+fn flags_from_event(event: &Event) -> u32 {
+    let interest = event.interest();
+    FLAGS
+        .iter()
+        .filter(|(_, flag)| interest.contains(*flag))
+        .fold(0, |all, (flag, _)| all | flag)
+}
+
+/// Convert a result to a C error code.
+fn result_code(res: io::Result<()>) -> c_int {
+    match res {
+        Ok(()) => 0,
+        Err(err) => error_code(&err),
+    }
+}
+
+/// Convert an error to a C error code.
+fn error_code(err: &io::Error) -> c_int {
+    err.raw_os_error().unwrap_or(-1)
+}
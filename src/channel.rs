@@ -0,0 +1,149 @@
+//! Channels whose messages wake up a [`Poller`].
+//!
+//! A [`channel()`] is an [`mpsc`] channel with a [virtual source][`VirtualSource`] attached to
+//! it. Sending a message marks the source as ready, so that a wait of the poller delivers a
+//! readable event with the key of the channel, and the loop that handles I/O receives the
+//! messages as well. Dropping the last sender does the same, so that the receiver learns that
+//! the channel is disconnected.
+//!
+//! Messages sent before a wait are reported by one event. The receiver should therefore receive
+//! messages until none are left, like it reads a socket until it would block.
+//!
+//! # Examples
+//!
+//! ```
+//! use polling::{channel, Events, Poller};
+//! use std::sync::Arc;
+//!
+//! let poller = Arc::new(Poller::new()?);
+//! let (sender, receiver) = channel::channel(&poller, 1)?;
+//!
+//! std::thread::spawn(move || {
+//!     sender.send("hello").unwrap();
+//!     sender.send("world").unwrap();
+//! });
+//!
+//! let mut messages = Vec::new();
+//! let mut events = Events::new();
+//! while messages.len() < 2 {
+//!     poller.wait(&mut events, None)?;
+//!     for ev in events.iter() {
+//!         assert_eq!(ev.key, receiver.key());
+//!         messages.extend(receiver.try_iter());
+//!     }
+//!     events.clear();
+//! }
+//! assert_eq!(messages, ["hello", "world"]);
+//! # std::io::Result::Ok(())
+//! ```
+
+use std::fmt;
+use std::io;
+use std::sync::mpsc::{self, SendError, TryRecvError};
+use std::sync::Arc;
+
+use crate::{Event, Interest, Poller, VirtualSource};
+
+/// Create a channel whose messages deliver readable events with `key` to `poller`.
+///
+/// # Errors
+///
+/// This returns an error if `key` is the [notification key][`Poller::notify_key()`].
+pub fn channel<T>(poller: &Arc<Poller>, key: usize) -> io::Result<(Sender<T>, Receiver<T>)> {
+    let source = poller.virtual_source(Event::readable(key))?;
+    let (sender, receiver) = mpsc::channel();
+    Ok((
+        Sender {
+            inner: sender,
+            readiness: Arc::new(Readiness(source)),
+        },
+        Receiver {
+            inner: receiver,
+            key,
+        },
+    ))
+}
+
+/// The sending half of a [`channel()`].
+///
+/// The sender can be cloned to send from several threads.
+pub struct Sender<T> {
+    inner: mpsc::Sender<T>,
+    readiness: Arc<Readiness>,
+}
+
+/// Marks the channel as ready, and does so when the last sender is dropped.
+struct Readiness(VirtualSource);
+
+impl Readiness {
+    fn wake(&self) {
+        if let Err(_err) = self.0.set_ready(Interest::READABLE) {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(err = %_err, key = self.0.key(), "channel: failed to notify the poller");
+        }
+    }
+}
+
+impl Drop for Readiness {
+    fn drop(&mut self) {
+        self.wake();
+    }
+}
+
+impl<T> Sender<T> {
+    /// Send a message, and wake up the poller.
+    ///
+    /// This returns the message back if the receiver was dropped.
+    pub fn send(&self, message: T) -> Result<(), SendError<T>> {
+        self.inner.send(message)?;
+        self.readiness.wake();
+        Ok(())
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Sender {
+            inner: self.inner.clone(),
+            readiness: self.readiness.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("key", &self.readiness.0.key())
+            .finish()
+    }
+}
+
+/// The receiving half of a [`channel()`].
+pub struct Receiver<T> {
+    inner: mpsc::Receiver<T>,
+    key: usize,
+}
+
+impl<T> Receiver<T> {
+    /// The key of the events that the channel delivers.
+    #[inline]
+    pub fn key(&self) -> usize {
+        self.key
+    }
+
+    /// Receive a message without blocking.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.inner.try_recv()
+    }
+
+    /// Iterate over the messages that can be received without blocking.
+    pub fn try_iter(&self) -> mpsc::TryIter<'_, T> {
+        self.inner.try_iter()
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").field("key", &self.key).finish()
+    }
+}
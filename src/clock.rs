@@ -0,0 +1,83 @@
+//! The clocks that wait timeouts can be measured against.
+
+use std::fmt;
+
+use crate::Backend;
+
+/// The clock that measures the timeouts of [`Poller::wait()`].
+///
+/// The timeout of a wait is relative, and the backend decides which clock it elapses on. By
+/// default it is a monotonic clock, which stops while the system is suspended, so a wait with
+/// a timeout of a minute may return long after a minute of real time has passed. Waits of a
+/// poller built with [`Clock::Boottime`] take the time spent suspended into account instead.
+///
+/// The clock measures the timeouts of waits and the timers set with [`Poller::set_timer()`].
+/// Deadlines of waits are still [`Instant`]s, which may not count the time spent suspended, so
+/// after a suspend the wait returns before the deadline as measured by [`Instant::now()`], and
+/// is not reported as [timed out][`crate::WaitResult::is_timed_out()`].
+///
+/// [`Poller::wait()`]: crate::Poller::wait
+/// [`Poller::set_timer()`]: crate::Poller::set_timer
+/// [`Instant`]: std::time::Instant
+/// [`Instant::now()`]: std::time::Instant::now
+///
+/// # Examples
+///
+/// ```
+/// use polling::{Clock, Poller};
+///
+/// let clock = if Clock::Boottime.is_supported() {
+///     Clock::Boottime
+/// } else {
+///     Clock::Monotonic
+/// };
+/// let poller = Poller::builder().clock(clock).build()?;
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Clock {
+    /// A monotonic clock that doesn't advance while the system is suspended, such as
+    /// `CLOCK_MONOTONIC`.
+    ///
+    /// This is supported by all backends.
+    #[default]
+    Monotonic,
+
+    /// `CLOCK_BOOTTIME`, which also advances while the system is suspended.
+    ///
+    /// This is only supported by the epoll backend on Linux and Android, where waits with a
+    /// timeout use a `timerfd` on this clock.
+    Boottime,
+}
+
+impl Clock {
+    /// Whether a [`Poller`] can measure wait timeouts with this clock on this platform.
+    ///
+    /// [`Poller`]: crate::Poller
+    #[inline]
+    pub fn is_supported(self) -> bool {
+        match self {
+            Clock::Monotonic => true,
+            Clock::Boottime => {
+                cfg!(any(target_os = "linux", target_os = "android"))
+                    && Backend::current() == Backend::Epoll
+            }
+        }
+    }
+
+    /// The name of the clock.
+    #[inline]
+    pub fn name(self) -> &'static str {
+        match self {
+            Clock::Monotonic => "monotonic",
+            Clock::Boottime => "boottime",
+        }
+    }
+}
+
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
@@ -0,0 +1,365 @@
+//! An experimental completion-based API on top of the poller.
+//!
+//! A [`CompletionPoller`] accepts reads and writes with owned buffers, and reports their results
+//! as [`Completion`]s once they are done, like `io_uring` or I/O completion ports. This lets code
+//! target the completion model on every platform. Currently, the model is emulated on all
+//! backends: an operation waits for its source to become ready, and is then performed with a
+//! nonblocking system call.
+//!
+//! Sources have to be in nonblocking mode. Operations on the same source and in the same
+//! direction are performed in the order they were submitted. On Windows, only sockets are
+//! supported.
+//!
+//! # Examples
+//!
+//! ```
+//! use polling::completion::CompletionPoller;
+//! use std::net::{TcpListener, TcpStream};
+//!
+//! let listener = TcpListener::bind("127.0.0.1:0")?;
+//! let writer = TcpStream::connect(listener.local_addr()?)?;
+//! let (reader, _) = listener.accept()?;
+//! reader.set_nonblocking(true)?;
+//! writer.set_nonblocking(true)?;
+//!
+//! let poller = CompletionPoller::new()?;
+//! unsafe {
+//!     poller.submit_read(&reader, vec![0; 16], 1)?;
+//!     poller.submit_write(&writer, b"hello".to_vec(), 2)?;
+//! }
+//!
+//! let mut completions = Vec::new();
+//! while completions.len() < 2 {
+//!     poller.wait(&mut completions, None)?;
+//! }
+//!
+//! let read = completions.iter().find(|c| c.user_data == 1).unwrap();
+//! let len = *read.result.as_ref().unwrap();
+//! assert_eq!(&read.buf[..len], b"hello");
+//! # std::io::Result::Ok(())
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+#[cfg(unix)]
+use std::os::unix::io::BorrowedFd as BorrowedSource;
+#[cfg(windows)]
+use std::os::windows::io::BorrowedSocket as BorrowedSource;
+
+use crate::{AsRawSource, Event, Events, KeyAllocator, PollMode, Poller, RawSource};
+
+/// The result of an operation submitted to a [`CompletionPoller`].
+#[derive(Debug)]
+pub struct Completion {
+    /// The value the operation was submitted with.
+    pub user_data: u64,
+
+    /// The number of bytes that were read or written, or the error of the operation.
+    ///
+    /// Like with `read()` and `write()`, a read of zero bytes means the end of the stream, and a
+    /// write may write only part of the buffer.
+    pub result: io::Result<usize>,
+
+    /// The buffer the operation was submitted with.
+    ///
+    /// For reads, the bytes that were read are at the start of the buffer.
+    pub buf: Vec<u8>,
+}
+
+/// A submitted operation.
+struct Op {
+    user_data: u64,
+    buf: Vec<u8>,
+}
+
+/// The operations submitted for a source, which is registered in the poller while it has any.
+struct Queue {
+    source: RawSource,
+    reads: VecDeque<Op>,
+    writes: VecDeque<Op>,
+}
+
+impl Queue {
+    /// The interest of the source in the poller.
+    fn interest(&self, key: usize) -> Event {
+        let mut interest = Event::none(key);
+        interest.readable = !self.reads.is_empty();
+        interest.writable = !self.writes.is_empty();
+        interest
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    /// The queue of each source, by the key it is registered under.
+    queues: HashMap<usize, Queue>,
+
+    /// The key each source with pending operations is registered under.
+    keys: HashMap<RawSource, usize>,
+}
+
+/// A poller for reads and writes that are reported once they are done.
+///
+/// See the [module-level documentation](self) for more information.
+pub struct CompletionPoller {
+    poller: Poller,
+    keys: KeyAllocator,
+    inner: Mutex<Inner>,
+    events: Mutex<Events>,
+}
+
+impl fmt::Debug for CompletionPoller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompletionPoller")
+            .field("poller", &self.poller)
+            .field("pending", &self.len())
+            .finish()
+    }
+}
+
+impl CompletionPoller {
+    /// Create a new completion poller.
+    pub fn new() -> io::Result<CompletionPoller> {
+        let poller = Poller::new()?;
+        Ok(CompletionPoller {
+            keys: KeyAllocator::for_poller(&poller),
+            events: Mutex::new(poller.new_events()),
+            poller,
+            inner: Mutex::new(Inner::default()),
+        })
+    }
+
+    /// Submit a read from `source` into `buf`.
+    ///
+    /// The read fills the buffer up to its length. Its result, along with the buffer, is
+    /// reported by [`wait()`][`CompletionPoller::wait()`] with `user_data`.
+    ///
+    /// # Safety
+    ///
+    /// The source must stay open until all operations on it are completed, see [`Poller::add()`].
+    pub unsafe fn submit_read(
+        &self,
+        source: impl AsRawSource,
+        buf: Vec<u8>,
+        user_data: u64,
+    ) -> io::Result<()> {
+        self.submit(source.raw(), Op { user_data, buf }, true)
+    }
+
+    /// Submit a write of `buf` to `source`.
+    ///
+    /// Its result, along with the buffer, is reported by [`wait()`][`CompletionPoller::wait()`]
+    /// with `user_data`.
+    ///
+    /// # Safety
+    ///
+    /// The source must stay open until all operations on it are completed, see [`Poller::add()`].
+    pub unsafe fn submit_write(
+        &self,
+        source: impl AsRawSource,
+        buf: Vec<u8>,
+        user_data: u64,
+    ) -> io::Result<()> {
+        self.submit(source.raw(), Op { user_data, buf }, false)
+    }
+
+    /// The number of operations that are not completed yet.
+    pub fn len(&self) -> usize {
+        self.inner()
+            .queues
+            .values()
+            .map(|queue| queue.reads.len() + queue.writes.len())
+            .sum()
+    }
+
+    /// Whether all submitted operations are completed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Wait for operations to complete, and append their completions to `completions`.
+    ///
+    /// Returns the number of completions that were appended, which may be zero if the timeout
+    /// elapsed or the wait was [notified][`CompletionPoller::notify()`].
+    pub fn wait(
+        &self,
+        completions: &mut Vec<Completion>,
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        events.clear();
+        self.poller.wait(&mut events, timeout)?;
+
+        let start = completions.len();
+        let mut inner = self.inner();
+        for ev in events.iter() {
+            let queue = match inner.queues.get_mut(&ev.key) {
+                Some(queue) => queue,
+                None => continue,
+            };
+
+            // Events without a direction, such as errors, are delivered to both directions.
+            let neither = !ev.readable && !ev.writable;
+            if ev.readable || neither {
+                perform(queue.source, &mut queue.reads, true, completions);
+            }
+            if ev.writable || neither {
+                perform(queue.source, &mut queue.writes, false, completions);
+            }
+
+            // SAFETY: The source must stay open until all operations on it are completed.
+            let source = unsafe { BorrowedSource::borrow_raw(queue.source) };
+            if queue.reads.is_empty() && queue.writes.is_empty() {
+                let raw = queue.source;
+                inner.queues.remove(&ev.key);
+                inner.keys.remove(&raw);
+                self.keys.free(ev.key);
+                self.poller.delete(source)?;
+            } else {
+                self.poller
+                    .modify_with_mode(source, queue.interest(ev.key), PollMode::Oneshot)?;
+            }
+        }
+
+        Ok(completions.len() - start)
+    }
+
+    /// Wake up the current or next call to [`wait()`][`CompletionPoller::wait()`].
+    pub fn notify(&self) -> io::Result<()> {
+        self.poller.notify()
+    }
+
+    /// Queue `op` on `source`, and register the source for the readiness it needs.
+    unsafe fn submit(&self, source: RawSource, op: Op, read: bool) -> io::Result<()> {
+        let mut inner = self.inner();
+        if let Some(&key) = inner.keys.get(&source) {
+            let queue = inner.queues.get_mut(&key).unwrap();
+            if read {
+                queue.reads.push_back(op);
+            } else {
+                queue.writes.push_back(op);
+            }
+
+            let res = self.poller.modify_with_mode(
+                BorrowedSource::borrow_raw(source),
+                queue.interest(key),
+                PollMode::Oneshot,
+            );
+            if res.is_err() {
+                if read {
+                    queue.reads.pop_back();
+                } else {
+                    queue.writes.pop_back();
+                }
+            }
+            return res;
+        }
+
+        let mut queue = Queue {
+            source,
+            reads: VecDeque::new(),
+            writes: VecDeque::new(),
+        };
+        if read {
+            queue.reads.push_back(op);
+        } else {
+            queue.writes.push_back(op);
+        }
+
+        let key = self.keys.allocate();
+        if let Err(err) = self
+            .poller
+            .add_with_mode(source, queue.interest(key), PollMode::Oneshot)
+        {
+            self.keys.free(key);
+            return Err(err);
+        }
+        inner.queues.insert(key, queue);
+        inner.keys.insert(source, key);
+        Ok(())
+    }
+
+    /// Lock the operation table, ignoring poisoning.
+    fn inner(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Perform the queued operations in one direction until one would block.
+fn perform(source: RawSource, ops: &mut VecDeque<Op>, read: bool, out: &mut Vec<Completion>) {
+    while let Some(op) = ops.front_mut() {
+        let result = loop {
+            let result = if read {
+                sys::read(source, &mut op.buf)
+            } else {
+                sys::write(source, &op.buf)
+            };
+            match result {
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result,
+            }
+        };
+        if matches!(&result, Err(err) if err.kind() == io::ErrorKind::WouldBlock) {
+            break;
+        }
+
+        let op = ops.pop_front().unwrap();
+        out.push(Completion {
+            user_data: op.user_data,
+            result,
+            buf: op.buf,
+        });
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::io;
+    use std::os::unix::io::{BorrowedFd, RawFd};
+
+    /// Read from a file descriptor without blocking.
+    pub(super) fn read(fd: RawFd, buf: &mut [u8]) -> io::Result<usize> {
+        // SAFETY: The file descriptor must stay open until all operations on it are completed.
+        let fd = unsafe { BorrowedFd::borrow_raw(fd) };
+        Ok(rustix::io::read(fd, buf)?)
+    }
+
+    /// Write to a file descriptor without blocking.
+    pub(super) fn write(fd: RawFd, buf: &[u8]) -> io::Result<usize> {
+        // SAFETY: The file descriptor must stay open until all operations on it are completed.
+        let fd = unsafe { BorrowedFd::borrow_raw(fd) };
+        Ok(rustix::io::write(fd, buf)?)
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::io;
+    use std::os::windows::io::RawSocket;
+
+    use windows_sys::Win32::Networking::WinSock::{recv, send, WSAGetLastError, SOCKET_ERROR};
+
+    /// Receive from a socket without blocking.
+    pub(super) fn read(socket: RawSocket, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(i32::MAX as usize) as i32;
+        // SAFETY: The socket must stay open until all operations on it are completed.
+        match unsafe { recv(socket as _, buf.as_mut_ptr(), len, 0) } {
+            SOCKET_ERROR => Err(io::Error::from_raw_os_error(unsafe { WSAGetLastError() })),
+            n => Ok(n as usize),
+        }
+    }
+
+    /// Send to a socket without blocking.
+    pub(super) fn write(socket: RawSocket, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(i32::MAX as usize) as i32;
+        // SAFETY: The socket must stay open until all operations on it are completed.
+        match unsafe { send(socket as _, buf.as_ptr(), len, 0) } {
+            SOCKET_ERROR => Err(io::Error::from_raw_os_error(unsafe { WSAGetLastError() })),
+            n => Ok(n as usize),
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! Backends implemented outside of this crate.
+
+use std::fmt;
+use std::io;
+use std::time::Instant;
+
+#[cfg(target_os = "hermit")]
+use std::os::hermit::io::BorrowedFd as BorrowedSource;
+#[cfg(unix)]
+use std::os::unix::io::BorrowedFd as BorrowedSource;
+#[cfg(windows)]
+use std::os::windows::io::BorrowedSocket as BorrowedSource;
+
+use crate::{sys, AsRawSource, Backend, Event, Events, PollMode, RawSource};
+
+/// A readiness backend that a [`Poller`] can be built on instead of the one of the platform.
+///
+/// This makes it possible to drive a poller from an event source the crate doesn't know about,
+/// such as a simulated network in tests, a user-space network stack or the event loop of a host
+/// application. The poller keeps doing everything on top of the backend: the registry of
+/// sources, polling modes, keys, timers, wakers, notifications and so on. The backend only has
+/// to watch the sources and report their events.
+///
+/// Use [`PollerBuilder::build_custom()`] to create a poller with a custom backend. Its
+/// [`backend()`][`Poller::backend()`] is [`Backend::Custom`].
+///
+/// Interfaces of the poller that are specific to a system backend don't work with a custom one.
+/// Re-initialization after `fork()` and the extensions in [`os`][`crate::os`] fail with
+/// [`io::ErrorKind::Unsupported`], as do split registrations on the platforms that use `kqueue`,
+/// since their halves are filters of the system backend. The [`AsFd`], [`AsRawFd`],
+/// [`AsHandle`] and [`AsRawHandle`] implementations of the poller panic, since there is no
+/// system handle to return.
+///
+/// [`Poller`]: crate::Poller
+/// [`Poller::backend()`]: crate::Poller::backend
+/// [`PollerBuilder::build_custom()`]: crate::PollerBuilder::build_custom
+/// [`Backend::Custom`]: crate::Backend::Custom
+/// [`AsFd`]: https://doc.rust-lang.org/std/os/fd/trait.AsFd.html
+/// [`AsRawFd`]: https://doc.rust-lang.org/std/os/fd/trait.AsRawFd.html
+/// [`AsHandle`]: https://doc.rust-lang.org/std/os/windows/io/trait.AsHandle.html
+/// [`AsRawHandle`]: https://doc.rust-lang.org/std/os/windows/io/trait.AsRawHandle.html
+///
+/// # Examples
+///
+/// A backend that reports every source as ready once per wait:
+///
+/// ```
+/// use polling::{Event, PollMode, Poller, PollerBackend, RawSource};
+/// use std::collections::HashMap;
+/// use std::io;
+/// use std::sync::Mutex;
+/// use std::time::Instant;
+///
+/// #[derive(Default)]
+/// struct AlwaysReady {
+///     sources: Mutex<HashMap<RawSource, Event>>,
+/// }
+///
+/// impl PollerBackend for AlwaysReady {
+///     fn add(&self, source: RawSource, interest: Event, _mode: PollMode) -> io::Result<()> {
+///         self.sources.lock().unwrap().insert(source, interest);
+///         Ok(())
+///     }
+///
+///     fn modify(&self, source: RawSource, interest: Event, _mode: PollMode) -> io::Result<()> {
+///         self.sources.lock().unwrap().insert(source, interest);
+///         Ok(())
+///     }
+///
+///     fn delete(&self, source: RawSource) -> io::Result<()> {
+///         self.sources.lock().unwrap().remove(&source);
+///         Ok(())
+///     }
+///
+///     fn wait(&self, events: &mut Vec<Event>, _deadline: Option<Instant>) -> io::Result<()> {
+///         let sources = self.sources.lock().unwrap();
+///         events.extend(sources.values().filter(|ev| ev.readable || ev.writable));
+///         Ok(())
+///     }
+///
+///     fn notify(&self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let poller = Poller::builder().build_custom(AlwaysReady::default())?;
+/// let socket = std::net::UdpSocket::bind("127.0.0.1:0")?;
+/// unsafe {
+///     poller.add(&socket, Event::readable(7))?;
+/// }
+///
+/// let mut events = poller.new_events();
+/// poller.wait(&mut events, None)?;
+/// assert_eq!(events.iter().next().unwrap().key, 7);
+/// poller.delete(&socket)?;
+/// # std::io::Result::Ok(())
+/// ```
+pub trait PollerBackend: Send + Sync {
+    /// Starts watching `source` for the events in `interest`, reported under its key.
+    ///
+    /// The poller has already checked the key and that the source isn't registered yet. The
+    /// source stays open until it is passed to [`delete()`][`PollerBackend::delete()`].
+    fn add(&self, source: RawSource, interest: Event, mode: PollMode) -> io::Result<()>;
+
+    /// Replaces the interest in a registered source.
+    ///
+    /// This also enables a source again after a oneshot event was delivered for it. An interest
+    /// without readability or writability disables the source.
+    fn modify(&self, source: RawSource, interest: Event, mode: PollMode) -> io::Result<()>;
+
+    /// Stops watching a registered source.
+    ///
+    /// This is also called for sources that were closed without being deleted first, once the
+    /// poller notices, so the backend shouldn't use the source here, only forget about it.
+    fn delete(&self, source: RawSource) -> io::Result<()>;
+
+    /// Waits until a source has an event, [`notify()`][`PollerBackend::notify()`] is called or
+    /// `deadline` passes, and appends the events to `events`.
+    ///
+    /// `None` waits without a deadline, and a deadline in the past only checks for events
+    /// without blocking. Events must only be appended: `events` may already hold events of
+    /// earlier waits. In oneshot modes, a source must not be reported again until it is
+    /// modified. Returning early without events is allowed, the poller then waits again if
+    /// needed.
+    fn wait(&self, events: &mut Vec<Event>, deadline: Option<Instant>) -> io::Result<()>;
+
+    /// Wakes up the current or the next call to [`wait()`][`PollerBackend::wait()`].
+    ///
+    /// This is also called by [`Poller::notify_from_signal()`], so it must be
+    /// async-signal-safe if that is used.
+    ///
+    /// [`Poller::notify_from_signal()`]: crate::Poller::notify_from_signal
+    fn notify(&self) -> io::Result<()>;
+
+    /// Whether sources can be registered with [`PollMode::Level`].
+    ///
+    /// Returns `false` by default.
+    fn supports_level(&self) -> bool {
+        false
+    }
+
+    /// Whether sources can be registered with [`PollMode::Edge`] and
+    /// [`PollMode::EdgeOneshot`].
+    ///
+    /// Returns `false` by default.
+    fn supports_edge(&self) -> bool {
+        false
+    }
+}
+
+/// The backend that a poller runs on.
+// The system backend is the common case, so it isn't boxed.
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum Driver {
+    /// The backend of the platform.
+    Native(sys::Poller),
+
+    /// A backend implemented outside of this crate.
+    Custom(Box<dyn PollerBackend>),
+}
+
+impl Driver {
+    /// The backend of the platform, for interfaces that are specific to it.
+    pub(crate) fn native(&self) -> io::Result<&sys::Poller> {
+        match self {
+            Driver::Native(poller) => Ok(poller),
+            Driver::Custom(_) => Err(crate::unsupported_error(
+                "this is not supported by custom backends",
+            )),
+        }
+    }
+
+    /// The backend of the platform, for its file descriptor or handle.
+    ///
+    /// The poll backend has none.
+    #[allow(dead_code)]
+    pub(crate) fn handle(&self) -> io::Result<&sys::Poller> {
+        match self.native()? {
+            poller if poller.backend() == Backend::Poll => Err(crate::unsupported_error(
+                "this is not supported by the poll backend",
+            )),
+            poller => Ok(poller),
+        }
+    }
+
+    /// The backend of the platform, for trait implementations that can't return an error.
+    #[allow(dead_code)]
+    pub(crate) fn expect_native(&self) -> &sys::Poller {
+        match self {
+            Driver::Native(poller) if poller.backend() == Backend::Poll => {
+                panic!("a poller with the poll backend has no system handle")
+            }
+            Driver::Native(poller) => poller,
+            Driver::Custom(_) => panic!("a poller with a custom backend has no system handle"),
+        }
+    }
+
+    /// The backend that the poller runs on.
+    pub(crate) fn backend(&self) -> Backend {
+        match self {
+            Driver::Native(poller) => poller.backend(),
+            Driver::Custom(_) => Backend::Custom,
+        }
+    }
+
+    pub(crate) fn supports_level(&self) -> bool {
+        match self {
+            Driver::Native(poller) => poller.supports_level(),
+            Driver::Custom(backend) => backend.supports_level(),
+        }
+    }
+
+    pub(crate) fn supports_edge(&self) -> bool {
+        match self {
+            Driver::Native(poller) => poller.supports_edge(),
+            Driver::Custom(backend) => backend.supports_edge(),
+        }
+    }
+
+    /// Start watching `source`.
+    ///
+    /// # Safety
+    ///
+    /// `source` must stay open until it is deleted.
+    pub(crate) unsafe fn add(
+        &self,
+        source: RawSource,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        match self {
+            #[allow(unused_unsafe)]
+            Driver::Native(poller) => unsafe { poller.add(source, interest, mode) },
+            Driver::Custom(backend) => backend.add(source, interest, mode),
+        }
+    }
+
+    pub(crate) fn modify(
+        &self,
+        source: BorrowedSource<'_>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        match self {
+            Driver::Native(poller) => poller.modify(source, interest, mode),
+            Driver::Custom(backend) => backend.modify((&source).raw(), interest, mode),
+        }
+    }
+
+    pub(crate) fn delete(&self, source: BorrowedSource<'_>) -> io::Result<()> {
+        match self {
+            Driver::Native(poller) => poller.delete(source),
+            Driver::Custom(backend) => backend.delete((&source).raw()),
+        }
+    }
+
+    /// Forget about a source that was closed without being deleted.
+    pub(crate) fn forget(&self, source: RawSource) {
+        match self {
+            Driver::Native(poller) => poller.forget(source),
+            Driver::Custom(backend) => {
+                backend.delete(source).ok();
+            }
+        }
+    }
+
+    /// Wait for events, which are added to the list of `events` by `Events::collect_new()`.
+    pub(crate) fn wait_deadline(
+        &self,
+        events: &mut Events,
+        deadline: Option<Instant>,
+    ) -> io::Result<()> {
+        match self {
+            Driver::Native(poller) => poller.wait_deadline(&mut events.events, deadline),
+            Driver::Custom(backend) => backend.wait(&mut events.list, deadline),
+        }
+    }
+
+    pub(crate) fn notify(&self) -> io::Result<()> {
+        match self {
+            Driver::Native(poller) => poller.notify(),
+            Driver::Custom(backend) => backend.notify(),
+        }
+    }
+
+    pub(crate) fn notify_from_signal(&self) {
+        match self {
+            Driver::Native(poller) => poller.notify_from_signal(),
+            Driver::Custom(backend) => {
+                backend.notify().ok();
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Driver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Driver::Native(poller) => poller.fmt(f),
+            Driver::Custom(_) => f.write_str("CustomBackend"),
+        }
+    }
+}
@@ -1,7 +1,13 @@
 //! Bindings to epoll (Linux, Android).
 
 use std::io;
+use std::mem::ManuallyDrop;
+#[cfg(not(target_os = "redox"))]
+use std::os::unix::io::FromRawFd;
 use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::ptr;
+#[cfg(not(target_os = "redox"))]
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
 #[cfg(not(target_os = "redox"))]
@@ -18,7 +24,12 @@ use rustix::fs::{fcntl_getfl, fcntl_setfl, OFlags};
 use rustix::io::{fcntl_getfd, fcntl_setfd, read, write, FdFlags};
 use rustix::pipe::{pipe, pipe_with, PipeFlags};
 
-use crate::{Event, PollMode};
+#[cfg(not(target_os = "redox"))]
+use crate::Clock;
+use crate::{Backend, Event, PollMode, PollerBuilder};
+
+/// The backend implemented by this module.
+pub const BACKEND: Backend = Backend::Epoll;
 
 /// Interface to epoll.
 #[derive(Debug)]
@@ -26,38 +37,90 @@ pub struct Poller {
     /// File descriptor for the epoll instance.
     epoll_fd: OwnedFd,
 
-    /// Notifier used to wake up epoll.
-    notifier: Notifier,
+    /// Notifier used to wake up epoll, unless notifications are disabled.
+    notifier: Option<Notifier>,
 
     /// File descriptor for the timerfd that produces timeouts.
     ///
-    /// Redox does not support timerfd.
+    /// A single timer can't serve several concurrent waits, so this is not used if concurrent
+    /// waits are enabled, in favor of `timed_waiters`. It is not needed if `epoll_pwait2` is
+    /// available. Redox does not support timerfd.
     #[cfg(not(target_os = "redox"))]
     timer_fd: Option<OwnedFd>,
+
+    /// Per-thread timers for concurrent waits, if `epoll_pwait2` is not available.
+    #[cfg(not(target_os = "redox"))]
+    timed_waiters: Option<Mutex<Vec<TimedWaiter>>>,
+
+    /// The clock of the timerfds.
+    #[cfg(not(target_os = "redox"))]
+    clock: TimerfdClockId,
+
+    /// Whether timeouts are passed to `epoll_pwait2`, with nanosecond precision.
+    ///
+    /// The timerfd is not needed in this case.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pwait2: bool,
+
+    /// The key used to register the notifier and the timerfd.
+    notify_key: usize,
 }
 
 impl Poller {
     /// Creates a new poller.
-    pub fn new() -> io::Result<Poller> {
-        // Create an epoll instance.
-        //
-        // Use `epoll_create1` with `EPOLL_CLOEXEC`.
-        let epoll_fd = epoll::create(epoll::CreateFlags::CLOEXEC)?;
+    pub fn new(builder: &PollerBuilder) -> io::Result<Poller> {
+        let epoll_fd = create(builder.cloexec)?;
+        Self::from_fd(epoll_fd, builder)
+    }
+
+    /// Creates a poller from an existing epoll instance.
+    pub fn from_fd(epoll_fd: OwnedFd, builder: &PollerBuilder) -> io::Result<Poller> {
+        let notify_key = builder.notify_key;
+
+        // `epoll_pwait2` measures timeouts with the monotonic clock, so other clocks need a
+        // timerfd.
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let (clock, pwait2) = match builder.clock {
+            Clock::Boottime => (TimerfdClockId::Boottime, false),
+            _ => (TimerfdClockId::Monotonic, pwait2::is_available()),
+        };
 
         // Set up notifier and timerfd.
-        let notifier = Notifier::new()?;
+        let notifier = if builder.notifications {
+            Some(Notifier::new()?)
+        } else {
+            None
+        };
         #[cfg(not(target_os = "redox"))]
-        let timer_fd = timerfd_create(
-            TimerfdClockId::Monotonic,
-            TimerfdFlags::CLOEXEC | TimerfdFlags::NONBLOCK,
-        )
-        .ok();
+        let timed_waiters = if builder.concurrent_waits && !pwait2 {
+            Some(Mutex::new(Vec::new()))
+        } else {
+            None
+        };
+        #[cfg(not(target_os = "redox"))]
+        let timer_fd = if builder.concurrent_waits || pwait2 {
+            None
+        } else {
+            match timerfd_create(clock, TimerfdFlags::CLOEXEC | TimerfdFlags::NONBLOCK) {
+                Ok(timer_fd) => Some(timer_fd),
+                // Without a timerfd, `epoll_wait` measures timeouts with the monotonic clock.
+                Err(err) if builder.clock != Clock::Monotonic => return Err(err.into()),
+                Err(_) => None,
+            }
+        };
 
         let poller = Poller {
             epoll_fd,
             notifier,
             #[cfg(not(target_os = "redox"))]
             timer_fd,
+            #[cfg(not(target_os = "redox"))]
+            timed_waiters,
+            #[cfg(not(target_os = "redox"))]
+            clock,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            pwait2,
+            notify_key,
         };
 
         unsafe {
@@ -65,16 +128,18 @@ impl Poller {
             if let Some(ref timer_fd) = poller.timer_fd {
                 poller.add(
                     timer_fd.as_raw_fd(),
-                    Event::none(crate::NOTIFY_KEY),
+                    Event::none(poller.notify_key),
                     PollMode::Oneshot,
                 )?;
             }
 
-            poller.add(
-                poller.notifier.as_fd().as_raw_fd(),
-                Event::readable(crate::NOTIFY_KEY),
-                PollMode::Oneshot,
-            )?;
+            if let Some(ref notifier) = poller.notifier {
+                poller.add(
+                    notifier.as_fd().as_raw_fd(),
+                    Event::readable(poller.notify_key),
+                    PollMode::Oneshot,
+                )?;
+            }
         }
 
         #[cfg(feature = "tracing")]
@@ -86,6 +151,11 @@ impl Poller {
         Ok(poller)
     }
 
+    /// The backend of this poller.
+    pub fn backend(&self) -> Backend {
+        BACKEND
+    }
+
     /// Whether this poller supports level-triggered events.
     pub fn supports_level(&self) -> bool {
         true
@@ -117,8 +187,16 @@ impl Poller {
             &self.epoll_fd,
             unsafe { rustix::fd::BorrowedFd::borrow_raw(fd) },
             epoll::EventData::new_u64(ev.key as u64),
-            epoll_flags(&ev, mode) | ev.extra.flags,
-        )?;
+            epoll_flags(&ev, mode),
+        )
+        .map_err(|err| {
+            // epoll rejects files that don't support polling, such as regular files.
+            if err == rustix::io::Errno::PERM {
+                crate::unpollable_fd_error("epoll", fd, err.into())
+            } else {
+                err.into()
+            }
+        })?;
 
         Ok(())
     }
@@ -139,7 +217,7 @@ impl Poller {
             &self.epoll_fd,
             fd,
             epoll::EventData::new_u64(ev.key as u64),
-            epoll_flags(&ev, mode) | ev.extra.flags,
+            epoll_flags(&ev, mode),
         )?;
 
         Ok(())
@@ -163,8 +241,38 @@ impl Poller {
     }
 
     /// Waits for I/O events with an optional deadline.
-    #[allow(clippy::needless_update)]
     pub fn wait_deadline(&self, events: &mut Events, deadline: Option<Instant>) -> io::Result<()> {
+        self.wait_inner(events, deadline, None)
+    }
+
+    /// Forgets about a file descriptor that was closed while it was registered.
+    pub fn forget(&self, _fd: RawFd) {
+        // The kernel removes the registration when the last file descriptor referring to the
+        // file description is closed. Otherwise, it can't be removed without a file descriptor.
+    }
+
+    /// Waits for I/O events with an optional deadline and the given signal mask.
+    pub fn wait_sigmask(
+        &self,
+        events: &mut Events,
+        deadline: Option<Instant>,
+        sigmask: &libc::sigset_t,
+    ) -> io::Result<()> {
+        #[cfg(target_os = "redox")]
+        return crate::os::unix::with_sigmask(sigmask, || self.wait_deadline(events, deadline));
+
+        #[cfg(not(target_os = "redox"))]
+        self.wait_inner(events, deadline, Some(sigmask))
+    }
+
+    /// Waits for I/O events, replacing the signal mask while waiting if `sigmask` is set.
+    #[allow(clippy::needless_update)]
+    fn wait_inner(
+        &self,
+        events: &mut Events,
+        deadline: Option<Instant>,
+        sigmask: Option<&libc::sigset_t>,
+    ) -> io::Result<()> {
         #[cfg(feature = "tracing")]
         let span = tracing::trace_span!(
             "wait",
@@ -176,8 +284,16 @@ impl Poller {
 
         let timeout = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
 
+        // A zero timeout doesn't block, so the timerfd is not needed, which keeps busy-polling
+        // cheap.
+        #[cfg(not(target_os = "redox"))]
+        let timer_fd = self
+            .timer_fd
+            .as_ref()
+            .filter(|_| timeout != Some(Duration::ZERO));
+
         #[cfg(not(target_os = "redox"))]
-        if let Some(ref timer_fd) = self.timer_fd {
+        if let Some(timer_fd) = timer_fd {
             // Configure the timeout using timerfd.
             let new_val = Itimerspec {
                 it_interval: TS_ZERO,
@@ -198,13 +314,11 @@ impl Poller {
             // Set interest in timerfd.
             self.modify(
                 timer_fd.as_fd(),
-                Event::readable(crate::NOTIFY_KEY),
+                Event::readable(self.notify_key),
                 PollMode::Oneshot,
             )?;
         }
 
-        #[cfg(not(target_os = "redox"))]
-        let timer_fd = &self.timer_fd;
         #[cfg(target_os = "redox")]
         let timer_fd: Option<core::convert::Infallible> = None;
 
@@ -216,25 +330,79 @@ impl Poller {
         };
 
         // Wait for I/O events.
+        #[cfg(not(target_os = "redox"))]
+        let timed_waiters = match timeout {
+            Some(t) if t != Timespec::default() => self.timed_waiters.as_ref(),
+            _ => None,
+        };
+        #[cfg(not(target_os = "redox"))]
+        if let (Some(waiters), Some(timeout)) = (timed_waiters, timeout) {
+            // Borrow a private timer, so that it can't wake up other threads.
+            let waiter = waiters.lock().unwrap_or_else(|e| e.into_inner()).pop();
+            let waiter = match waiter {
+                Some(waiter) => waiter,
+                None => TimedWaiter::new(self.epoll_fd.as_fd(), self.clock)?,
+            };
+            let res = waiter.wait(self.epoll_fd.as_fd(), &mut events.list, &timeout, sigmask);
+            waiters
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(waiter);
+            res?;
+        } else if self.pwait2 {
+            pwait2::wait(
+                self.epoll_fd.as_fd(),
+                &mut events.list,
+                timeout.as_ref(),
+                sigmask,
+            )?;
+        } else if let Some(sigmask) = sigmask {
+            pwait2::pwait(
+                self.epoll_fd.as_fd(),
+                &mut events.list,
+                timeout.as_ref(),
+                sigmask,
+            )?;
+        } else {
+            epoll::wait(
+                &self.epoll_fd,
+                spare_capacity(&mut events.list),
+                timeout.as_ref(),
+            )?;
+        }
+        #[cfg(target_os = "redox")]
+        let _ = sigmask;
+        #[cfg(target_os = "redox")]
         epoll::wait(
             &self.epoll_fd,
             spare_capacity(&mut events.list),
             timeout.as_ref(),
         )?;
+
+        // Remove the events produced by the notifier and the timerfd.
+        let len = events.list.len();
+        events
+            .list
+            .retain(|ev| ev.data.u64() as usize != self.notify_key);
+        let notified = events.list.len() != len;
         #[cfg(feature = "tracing")]
         tracing::trace!(
             epoll_fd = ?self.epoll_fd.as_raw_fd(),
             res = ?events.list.len(),
+            ?notified,
             "new events",
         );
 
-        // Clear the notification (if received) and re-register interest in it.
-        self.notifier.clear();
-        self.modify(
-            self.notifier.as_fd(),
-            Event::readable(crate::NOTIFY_KEY),
-            PollMode::Oneshot,
-        )?;
+        // Clear the notification and re-register interest in it. Only the thread that received
+        // the notification does this, so that concurrent waits don't steal each other's wakeups.
+        if let Some(notifier) = self.notifier.as_ref().filter(|_| notified) {
+            notifier.clear();
+            self.modify(
+                notifier.as_fd(),
+                Event::readable(self.notify_key),
+                PollMode::Oneshot,
+            )?;
+        }
         Ok(())
     }
 
@@ -249,8 +417,68 @@ impl Poller {
         #[cfg(feature = "tracing")]
         let _enter = span.enter();
 
-        self.notifier.notify();
-        Ok(())
+        // Without a notifier, `Poller::notify()` fails before it gets here.
+        match self.notifier {
+            Some(ref notifier) => notifier.notify(),
+            None => Ok(()),
+        }
+    }
+
+    /// Sends a notification from a signal handler.
+    ///
+    /// This only writes to the eventfd or the pipe.
+    pub fn notify_from_signal(&self) {
+        if let Some(ref notifier) = self.notifier {
+            notifier.notify().ok();
+        }
+    }
+
+    /// Removes the notifier and the timerfd from the epoll instance.
+    fn remove_internal_fds(&mut self) {
+        #[cfg(not(target_os = "redox"))]
+        if let Some(timer_fd) = self.timer_fd.take() {
+            let _ = self.delete(timer_fd.as_fd());
+        }
+        if let Some(ref notifier) = self.notifier {
+            let _ = self.delete(notifier.as_fd());
+        }
+    }
+
+    /// Returns the epoll instance, removing the notifier and the timerfd from it.
+    pub fn into_fd(self) -> OwnedFd {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(epoll_fd = ?self.epoll_fd.as_raw_fd(), "into_fd");
+
+        let mut this = ManuallyDrop::new(self);
+        this.remove_internal_fds();
+
+        // SAFETY: `this` is not used or dropped after its fields are moved out.
+        unsafe {
+            ptr::drop_in_place(&mut this.notifier);
+            #[cfg(not(target_os = "redox"))]
+            ptr::drop_in_place(&mut this.timer_fd);
+            #[cfg(not(target_os = "redox"))]
+            ptr::drop_in_place(&mut this.timed_waiters);
+            ptr::read(&this.epoll_fd)
+        }
+    }
+
+    /// Closes the poller in the child process after `fork()`.
+    ///
+    /// The epoll instance is shared with the parent, so the notifier and the timerfd are not
+    /// removed from it.
+    pub fn close_after_fork(self) {
+        let mut this = ManuallyDrop::new(self);
+
+        // SAFETY: `this` is not used or dropped after its fields are dropped.
+        unsafe {
+            ptr::drop_in_place(&mut this.notifier);
+            #[cfg(not(target_os = "redox"))]
+            ptr::drop_in_place(&mut this.timer_fd);
+            #[cfg(not(target_os = "redox"))]
+            ptr::drop_in_place(&mut this.timed_waiters);
+            ptr::drop_in_place(&mut this.epoll_fd);
+        }
     }
 }
 
@@ -277,11 +505,7 @@ impl Drop for Poller {
         #[cfg(feature = "tracing")]
         let _enter = span.enter();
 
-        #[cfg(not(target_os = "redox"))]
-        if let Some(timer_fd) = self.timer_fd.take() {
-            let _ = self.delete(timer_fd.as_fd());
-        }
-        let _ = self.delete(self.notifier.as_fd());
+        self.remove_internal_fds();
     }
 }
 
@@ -303,6 +527,13 @@ fn epoll_flags(interest: &Event, mode: PollMode) -> epoll::EventFlags {
     if interest.writable {
         flags |= write_flags();
     }
+    flags |= interest.extra.flags;
+
+    // `EPOLLEXCLUSIVE` may not be combined with priority or hangup events. Oneshot mode is kept,
+    // so that the kernel rejects it.
+    if flags.contains(epoll::EventFlags::EXCLUSIVE) {
+        flags.remove(epoll::EventFlags::PRI | epoll::EventFlags::RDHUP);
+    }
     flags
 }
 
@@ -341,6 +572,10 @@ impl Events {
                 key: ev.data.u64() as usize,
                 readable: flags.intersects(read_flags()),
                 writable: flags.intersects(write_flags()),
+                read_low_watermark: None,
+                write_low_watermark: None,
+                timeout: false,
+                delete_on_hangup: false,
                 extra: EventExtra { flags },
             }
         })
@@ -355,6 +590,11 @@ impl Events {
     pub fn capacity(&self) -> usize {
         self.list.capacity()
     }
+
+    /// Whether the last wait filled the list, so that more events may be pending.
+    pub fn is_full(&self) -> bool {
+        self.list.len() >= self.list.capacity()
+    }
 }
 
 /// Extra information about this event.
@@ -372,6 +612,26 @@ impl EventExtra {
         }
     }
 
+    /// Create the data from raw `EPOLL*` flags.
+    #[inline]
+    pub(crate) fn from_raw(flags: u32) -> EventExtra {
+        EventExtra {
+            flags: epoll::EventFlags::from_bits_retain(flags),
+        }
+    }
+
+    /// The raw `EPOLL*` flags.
+    #[inline]
+    pub(crate) fn raw(&self) -> u32 {
+        self.flags.bits()
+    }
+
+    /// Set interest in errors.
+    #[inline]
+    pub fn set_err(&mut self, _value: bool) {
+        // Errors are always reported.
+    }
+
     /// Add the interrupt flag to this event.
     #[inline]
     pub fn set_hup(&mut self, active: bool) {
@@ -385,6 +645,30 @@ impl EventExtra {
         self.flags.set(epoll::EventFlags::PRI, active);
     }
 
+    /// Set the exclusive flag.
+    #[inline]
+    pub fn set_exclusive(&mut self, active: bool) {
+        self.flags.set(epoll::EventFlags::EXCLUSIVE, active);
+    }
+
+    /// Is the exclusive flag set?
+    #[inline]
+    pub fn is_exclusive(&self) -> bool {
+        self.flags.contains(epoll::EventFlags::EXCLUSIVE)
+    }
+
+    /// Set the wakeup flag.
+    #[inline]
+    pub fn set_wakeup(&mut self, active: bool) {
+        self.flags.set(epoll::EventFlags::WAKEUP, active);
+    }
+
+    /// Is the wakeup flag set?
+    #[inline]
+    pub fn is_wakeup(&self) -> bool {
+        self.flags.contains(epoll::EventFlags::WAKEUP)
+    }
+
     /// Tell if the interrupt flag is set.
     #[inline]
     pub fn is_hup(&self) -> bool {
@@ -412,6 +696,57 @@ impl EventExtra {
     }
 }
 
+/// Create an epoll instance.
+///
+/// Use `epoll_create1` with `EPOLL_CLOEXEC`, unless it's disabled.
+#[cfg(target_os = "redox")]
+fn create(cloexec: bool) -> io::Result<OwnedFd> {
+    let flags = if cloexec {
+        epoll::CreateFlags::CLOEXEC
+    } else {
+        epoll::CreateFlags::empty()
+    };
+    Ok(epoll::create(flags)?)
+}
+
+/// Create an epoll instance.
+///
+/// Use `epoll_create1` with `EPOLL_CLOEXEC`, unless it's disabled. Some old kernels and
+/// emulators don't implement `epoll_create1`, so fall back to `epoll_create` and set
+/// `FD_CLOEXEC` afterwards.
+#[cfg(not(target_os = "redox"))]
+fn create(cloexec: bool) -> io::Result<OwnedFd> {
+    // Skip `epoll_create1` for testing if necessary.
+    if !cfg!(polling_test_epoll_create) {
+        let flags = if cloexec {
+            epoll::CreateFlags::CLOEXEC
+        } else {
+            epoll::CreateFlags::empty()
+        };
+        match epoll::create(flags) {
+            Err(rustix::io::Errno::NOSYS) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    "epoll_create1() is not implemented, falling back to epoll_create()"
+                );
+            }
+            res => return Ok(res?),
+        }
+    }
+
+    // SAFETY: `epoll_create` takes no pointers, and the size is ignored as long as it's positive.
+    let fd = unsafe { libc::epoll_create(1) };
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `epoll_create` returned a new file descriptor.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+    if cloexec {
+        fcntl_setfd(&fd, fcntl_getfd(&fd)? | FdFlags::CLOEXEC)?;
+    }
+    Ok(fd)
+}
+
 /// The notifier for Linux.
 ///
 /// Certain container runtimes do not expose eventfd to the client, as it relies on the host and
@@ -487,17 +822,21 @@ impl Notifier {
     }
 
     /// Notify the poller.
-    fn notify(&self) {
-        match self {
+    ///
+    /// A full eventfd counter or pipe already wakes up the poller, so that is not an error.
+    fn notify(&self) -> io::Result<()> {
+        let res = match self {
             #[cfg(not(target_os = "redox"))]
             Self::EventFd(fd) => {
                 let buf: [u8; 8] = 1u64.to_ne_bytes();
-                let _ = write(fd, &buf);
+                write(fd, &buf)
             }
 
-            Self::Pipe { write_pipe, .. } => {
-                write(write_pipe, &[0; 1]).ok();
-            }
+            Self::Pipe { write_pipe, .. } => write(write_pipe, &[0; 1]),
+        };
+        match res {
+            Ok(_) | Err(rustix::io::Errno::AGAIN) => Ok(()),
+            Err(err) => Err(err.into()),
         }
     }
 
@@ -514,3 +853,223 @@ impl Notifier {
         }
     }
 }
+
+/// A private timer for a concurrent wait.
+///
+/// A timerfd in the shared epoll instance could wake up any of the waiting threads. Instead, the
+/// thread waits on its own epoll instance, which contains its timerfd and the shared epoll
+/// instance.
+#[cfg(not(target_os = "redox"))]
+#[derive(Debug)]
+struct TimedWaiter {
+    epoll_fd: OwnedFd,
+    timer_fd: OwnedFd,
+}
+
+#[cfg(not(target_os = "redox"))]
+impl TimedWaiter {
+    /// Key of the shared epoll instance.
+    const SHARED: u64 = 0;
+
+    /// Key of the timerfd.
+    const TIMER: u64 = 1;
+
+    /// Creates a timer on `clock` that waits on `shared`.
+    fn new(shared: BorrowedFd<'_>, clock: TimerfdClockId) -> io::Result<TimedWaiter> {
+        let epoll_fd = create(true)?;
+        let timer_fd = timerfd_create(clock, TimerfdFlags::CLOEXEC | TimerfdFlags::NONBLOCK)?;
+        epoll::add(
+            &epoll_fd,
+            shared,
+            epoll::EventData::new_u64(Self::SHARED),
+            epoll::EventFlags::IN,
+        )?;
+        epoll::add(
+            &epoll_fd,
+            &timer_fd,
+            epoll::EventData::new_u64(Self::TIMER),
+            epoll::EventFlags::IN,
+        )?;
+        Ok(TimedWaiter { epoll_fd, timer_fd })
+    }
+
+    /// Waits for events in `shared` until the timeout elapses.
+    fn wait(
+        &self,
+        shared: BorrowedFd<'_>,
+        list: &mut Vec<epoll::Event>,
+        timeout: &Timespec,
+        sigmask: Option<&libc::sigset_t>,
+    ) -> io::Result<()> {
+        self.set_timer(*timeout)?;
+        let res = self.wait_armed(shared, list, sigmask);
+        self.set_timer(Timespec::default())?;
+        res
+    }
+
+    /// Waits until the armed timer fires or events are collected from `shared`.
+    fn wait_armed(
+        &self,
+        shared: BorrowedFd<'_>,
+        list: &mut Vec<epoll::Event>,
+        sigmask: Option<&libc::sigset_t>,
+    ) -> io::Result<()> {
+        let len = list.len();
+        loop {
+            let mut ready = Vec::with_capacity(2);
+            match sigmask {
+                Some(sigmask) => pwait2::pwait(self.epoll_fd.as_fd(), &mut ready, None, sigmask)?,
+                None => {
+                    epoll::wait(&self.epoll_fd, spare_capacity(&mut ready), None)?;
+                }
+            }
+
+            if ready.iter().any(|ev| ev.data.u64() == Self::SHARED) {
+                // Another thread may have taken the events in the meantime.
+                epoll::wait(shared, spare_capacity(list), Some(&Timespec::default()))?;
+                if list.len() != len {
+                    return Ok(());
+                }
+            }
+            if ready.iter().any(|ev| ev.data.u64() == Self::TIMER) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Arms the timer, or disarms it and clears its expirations if `value` is zero.
+    #[allow(clippy::needless_update)]
+    fn set_timer(&self, value: Timespec) -> io::Result<()> {
+        let new_val = Itimerspec {
+            it_interval: TS_ZERO,
+            it_value: value,
+            ..unsafe { std::mem::zeroed() }
+        };
+        timerfd_settime(&self.timer_fd, TimerfdTimerFlags::empty(), &new_val)?;
+        Ok(())
+    }
+}
+
+/// Support for `epoll_pwait2`, which takes timeouts with nanosecond precision, and for waiting
+/// with a signal mask.
+///
+/// `epoll_wait` rounds timeouts to milliseconds. `epoll_pwait2` is available since Linux 5.11,
+/// so its availability is detected at runtime.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod pwait2 {
+    use std::io;
+    use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd};
+    use std::ptr;
+    use std::sync::OnceLock;
+
+    use rustix::event::{epoll, Timespec};
+
+    /// Whether `epoll_pwait2` is available, detected once per process.
+    pub(super) fn is_available() -> bool {
+        static AVAILABLE: OnceLock<bool> = OnceLock::new();
+
+        *AVAILABLE.get_or_init(|| {
+            // Probe on a fresh epoll instance, so no events can be consumed. Older kernels fail
+            // with ENOSYS, and seccomp filters that don't know the syscall may fail with EPERM.
+            let epoll_fd = match epoll::create(epoll::CreateFlags::CLOEXEC) {
+                Ok(fd) => fd,
+                Err(_) => return false,
+            };
+            let mut list = Vec::with_capacity(1);
+            wait(
+                epoll_fd.as_fd(),
+                &mut list,
+                Some(&Timespec::default()),
+                None,
+            )
+            .is_ok()
+        })
+    }
+
+    /// Wait for events with `epoll_pwait2`, appending them to the spare capacity of `list`.
+    pub(super) fn wait(
+        epoll_fd: BorrowedFd<'_>,
+        list: &mut Vec<epoll::Event>,
+        timeout: Option<&Timespec>,
+        sigmask: Option<&libc::sigset_t>,
+    ) -> io::Result<()> {
+        let timeout = timeout.map(|t| libc::timespec {
+            tv_sec: t.tv_sec as _,
+            tv_nsec: t.tv_nsec as _,
+        });
+        let spare = list.capacity() - list.len();
+
+        // SAFETY: `epoll::Event` has the same layout as `epoll_event`, and the kernel writes at
+        // most `spare` events into the spare capacity.
+        let res = unsafe {
+            libc::syscall(
+                libc::SYS_epoll_pwait2,
+                epoll_fd.as_raw_fd(),
+                list.as_mut_ptr()
+                    .add(list.len())
+                    .cast::<libc::epoll_event>(),
+                spare.min(libc::c_int::MAX as usize) as libc::c_int,
+                timeout
+                    .as_ref()
+                    .map_or(ptr::null(), |t| t as *const libc::timespec),
+                sigmask.map_or(ptr::null(), |s| s as *const libc::sigset_t),
+                KERNEL_SIGSET_SIZE,
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: The kernel initialized `res` events.
+        unsafe { list.set_len(list.len() + res as usize) };
+        Ok(())
+    }
+
+    /// Wait for events with `epoll_pwait` and a signal mask, appending them to the spare
+    /// capacity of `list`.
+    ///
+    /// The timeout is rounded up to milliseconds.
+    pub(super) fn pwait(
+        epoll_fd: BorrowedFd<'_>,
+        list: &mut Vec<epoll::Event>,
+        timeout: Option<&Timespec>,
+        sigmask: &libc::sigset_t,
+    ) -> io::Result<()> {
+        let timeout = match timeout {
+            None => -1,
+            Some(t) => (t.tv_sec as i64)
+                .saturating_mul(1_000)
+                .saturating_add((t.tv_nsec as i64 + 999_999) / 1_000_000)
+                .min(libc::c_int::MAX as i64) as libc::c_int,
+        };
+        let spare = list.capacity() - list.len();
+
+        // SAFETY: `epoll::Event` has the same layout as `epoll_event`, and the kernel writes at
+        // most `spare` events into the spare capacity.
+        let res = unsafe {
+            libc::epoll_pwait(
+                epoll_fd.as_raw_fd(),
+                list.as_mut_ptr()
+                    .add(list.len())
+                    .cast::<libc::epoll_event>(),
+                spare.min(libc::c_int::MAX as usize) as libc::c_int,
+                timeout,
+                sigmask,
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: The kernel initialized `res` events.
+        unsafe { list.set_len(list.len() + res as usize) };
+        Ok(())
+    }
+
+    /// The size of the kernel's signal set, which is smaller than `sigset_t`.
+    const KERNEL_SIGSET_SIZE: usize = if cfg!(any(target_arch = "mips", target_arch = "mips64")) {
+        16
+    } else {
+        8
+    };
+}
@@ -0,0 +1,126 @@
+//! A portable classification of the errors returned by a `Poller`.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// The reason an operation of a [`Poller`] failed.
+///
+/// Operations of the poller return [`io::Error`]s, and backends report the same failure with
+/// different OS error numbers. Converting the error into a `PollError` tells the common failures
+/// apart on all platforms, and any other error is kept as [`PollError::Backend`]. A `PollError`
+/// converts back into an [`io::Error`] with the matching [`io::ErrorKind`].
+///
+/// [`Poller`]: crate::Poller
+///
+/// # Examples
+///
+/// ```
+/// use polling::{Event, PollError, Poller};
+/// use std::net::TcpListener;
+///
+/// let socket = TcpListener::bind("127.0.0.1:0")?;
+/// let poller = Poller::new()?;
+/// unsafe {
+///     poller.add(&socket, Event::readable(1))?;
+///     let err = poller.add(&socket, Event::readable(1)).unwrap_err();
+///     assert!(matches!(PollError::from(err), PollError::AlreadyRegistered));
+/// }
+/// poller.delete(&socket)?;
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PollError {
+    /// The operation, mode or option is not supported by the backend.
+    Unsupported,
+
+    /// The source is already registered in the poller.
+    AlreadyRegistered,
+
+    /// The source is not registered in the poller.
+    NotRegistered,
+
+    /// The key is the notification key of the poller, which can't be used for sources.
+    ReservedKey,
+
+    /// The operation was interrupted by a signal.
+    Interrupted,
+
+    /// The poller was [closed][crate::Poller::close()].
+    Closed,
+
+    /// The poller was [sealed][crate::Poller::seal()] and doesn't accept new sources.
+    Sealed,
+
+    /// Any other error of the operating system or the backend.
+    Backend(io::Error),
+}
+
+impl PollError {
+    /// The kind of the [`io::Error`] this error converts into.
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            PollError::Unsupported => io::ErrorKind::Unsupported,
+            PollError::AlreadyRegistered => io::ErrorKind::AlreadyExists,
+            PollError::NotRegistered => io::ErrorKind::NotFound,
+            PollError::ReservedKey => io::ErrorKind::InvalidInput,
+            PollError::Interrupted => io::ErrorKind::Interrupted,
+            PollError::Closed => io::ErrorKind::BrokenPipe,
+            PollError::Sealed => io::ErrorKind::PermissionDenied,
+            PollError::Backend(err) => err.kind(),
+        }
+    }
+}
+
+impl From<io::Error> for PollError {
+    fn from(err: io::Error) -> Self {
+        // Errors created by the poller itself carry their `PollError`.
+        if err.get_ref().is_some_and(|inner| inner.is::<PollError>()) {
+            return *err.into_inner().unwrap().downcast::<PollError>().unwrap();
+        }
+
+        match err.kind() {
+            io::ErrorKind::Unsupported => PollError::Unsupported,
+            io::ErrorKind::AlreadyExists => PollError::AlreadyRegistered,
+            io::ErrorKind::NotFound => PollError::NotRegistered,
+            io::ErrorKind::Interrupted => PollError::Interrupted,
+            _ => PollError::Backend(err),
+        }
+    }
+}
+
+impl From<PollError> for io::Error {
+    fn from(err: PollError) -> Self {
+        match err {
+            PollError::Backend(err) => err,
+            err => io::Error::new(err.kind(), err),
+        }
+    }
+}
+
+impl fmt::Display for PollError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PollError::Unsupported => f.write_str("the operation is not supported by the backend"),
+            PollError::AlreadyRegistered => {
+                f.write_str("the source is already registered in this poller")
+            }
+            PollError::NotRegistered => f.write_str("the source is not registered in this poller"),
+            PollError::ReservedKey => f.write_str("the key is reserved for notifications"),
+            PollError::Interrupted => f.write_str("the operation was interrupted"),
+            PollError::Closed => f.write_str("the poller is closed"),
+            PollError::Sealed => f.write_str("the poller doesn't accept new sources"),
+            PollError::Backend(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl Error for PollError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PollError::Backend(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Callbacks that observe the operations of a `Poller`.
+
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+#[cfg(feature = "stall-diagnostics")]
+use crate::Stall;
+use crate::{Event, PollMode, RawSource, WaitResult};
+
+/// Callbacks that are invoked when a [`Poller`] registers sources, is notified, or returns from a
+/// wait.
+///
+/// Hooks are installed with [`PollerBuilder::hooks()`]. Each callback receives the result of
+/// the operation, including operations that failed, and the callbacks for registrations also
+/// receive the source and its interest with the key. This lets an embedder feed its own metrics
+/// or diagnostics without the `tracing` feature. All callbacks
+/// do nothing by default, so an implementation only overrides the ones it needs.
+///
+/// The callbacks are invoked on the thread that performed the operation, after it finished.
+/// They must not block, and must not call back into the same poller.
+///
+/// [`Poller`]: crate::Poller
+/// [`PollerBuilder::hooks()`]: crate::PollerBuilder::hooks
+///
+/// # Examples
+///
+/// ```
+/// use polling::{Event, Hooks, PollMode, PollerBuilder, RawSource};
+/// use std::io;
+/// use std::net::UdpSocket;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// #[derive(Default)]
+/// struct Counter(AtomicUsize);
+///
+/// impl Hooks for Counter {
+///     fn on_add(&self, _: RawSource, _: Event, _: PollMode, result: Result<(), &io::Error>) {
+///         if result.is_ok() {
+///             self.0.fetch_add(1, Ordering::Relaxed);
+///         }
+///     }
+/// }
+///
+/// let added = Arc::new(Counter::default());
+/// let poller = PollerBuilder::new().hooks(added.clone()).build()?;
+///
+/// let socket = UdpSocket::bind("127.0.0.1:0")?;
+/// unsafe {
+///     poller.add(&socket, Event::readable(1))?;
+/// }
+/// assert_eq!(added.0.load(Ordering::Relaxed), 1);
+/// poller.delete(&socket)?;
+/// # std::io::Result::Ok(())
+/// ```
+pub trait Hooks: Send + Sync + 'static {
+    /// Called when a source was added.
+    ///
+    /// For a source with separate keys for reading and writing, this is called for each half.
+    fn on_add(
+        &self,
+        source: RawSource,
+        interest: Event,
+        mode: PollMode,
+        result: Result<(), &io::Error>,
+    ) {
+        let _ = (source, interest, mode, result);
+    }
+
+    /// Called when the interest in a source was modified.
+    ///
+    /// For a source with separate keys for reading and writing, this is called for each half.
+    fn on_modify(
+        &self,
+        source: RawSource,
+        interest: Event,
+        mode: PollMode,
+        result: Result<(), &io::Error>,
+    ) {
+        let _ = (source, interest, mode, result);
+    }
+
+    /// Called when a source was deleted.
+    fn on_delete(&self, source: RawSource, result: Result<(), &io::Error>) {
+        let _ = (source, result);
+    }
+
+    /// Called when the poller was notified.
+    fn on_notify(&self, result: Result<(), &io::Error>) {
+        let _ = result;
+    }
+
+    /// Called when a wait returns.
+    ///
+    /// Waits that are skipped because another thread is already waiting are not reported.
+    fn on_wait(&self, result: Result<WaitResult, &io::Error>) {
+        let _ = result;
+    }
+
+    /// Called when a wait stalled.
+    ///
+    /// See [`PollerBuilder::stall_threshold()`] for when this happens.
+    ///
+    /// [`PollerBuilder::stall_threshold()`]: crate::PollerBuilder::stall_threshold
+    #[cfg(feature = "stall-diagnostics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stall-diagnostics")))]
+    fn on_stall(&self, stall: Stall) {
+        let _ = stall;
+    }
+}
+
+impl<T: Hooks + ?Sized> Hooks for Arc<T> {
+    fn on_add(
+        &self,
+        source: RawSource,
+        interest: Event,
+        mode: PollMode,
+        result: Result<(), &io::Error>,
+    ) {
+        (**self).on_add(source, interest, mode, result)
+    }
+
+    fn on_modify(
+        &self,
+        source: RawSource,
+        interest: Event,
+        mode: PollMode,
+        result: Result<(), &io::Error>,
+    ) {
+        (**self).on_modify(source, interest, mode, result)
+    }
+
+    fn on_delete(&self, source: RawSource, result: Result<(), &io::Error>) {
+        (**self).on_delete(source, result)
+    }
+
+    fn on_notify(&self, result: Result<(), &io::Error>) {
+        (**self).on_notify(result)
+    }
+
+    fn on_wait(&self, result: Result<WaitResult, &io::Error>) {
+        (**self).on_wait(result)
+    }
+
+    #[cfg(feature = "stall-diagnostics")]
+    fn on_stall(&self, stall: Stall) {
+        (**self).on_stall(stall)
+    }
+}
+
+/// The hooks installed in a poller, if any.
+#[derive(Clone, Default)]
+pub(crate) struct HookSet(Option<Arc<dyn Hooks>>);
+
+impl HookSet {
+    /// Install `hooks`.
+    pub(crate) fn new(hooks: impl Hooks) -> HookSet {
+        HookSet(Some(Arc::new(hooks)))
+    }
+
+    /// The installed hooks.
+    #[inline]
+    pub(crate) fn get(&self) -> Option<&dyn Hooks> {
+        self.0.as_deref()
+    }
+}
+
+impl fmt::Debug for HookSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_some() { "Some(..)" } else { "None" })
+    }
+}
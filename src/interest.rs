@@ -0,0 +1,223 @@
+//! Flags describing interest in I/O events.
+
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
+
+/// A set of I/O events.
+///
+/// This is a more extensible alternative to the `readable` and `writable` fields of [`Event`].
+/// It is used to set interest in events with [`Event::from_interest()`] and
+/// [`Event::set_interest()`], and to tell which events were delivered with [`Event::interest()`].
+/// Flags can be combined with `|`.
+///
+/// Flags that are not supported by the current platform are ignored when setting interest, and
+/// never reported in delivered events.
+///
+/// [`Event`]: crate::Event
+/// [`Event::from_interest()`]: crate::Event::from_interest
+/// [`Event::set_interest()`]: crate::Event::set_interest
+/// [`Event::interest()`]: crate::Event::interest
+///
+/// # Examples
+///
+/// ```
+/// use polling::{Event, Interest};
+///
+/// let interest = Interest::READABLE | Interest::HANGUP;
+/// let event = Event::from_interest(7, interest);
+/// assert!(event.readable);
+/// assert!(!event.writable);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Interest(u8);
+
+impl Interest {
+    /// No events.
+    pub const NONE: Interest = Interest(0);
+
+    /// The source can be read from without blocking.
+    pub const READABLE: Interest = Interest(1 << 0);
+
+    /// The source can be written to without blocking.
+    pub const WRITABLE: Interest = Interest(1 << 1);
+
+    /// There is urgent data to read.
+    ///
+    /// See [`Event::set_priority()`][crate::Event::set_priority] for supported platforms.
+    pub const PRIORITY: Interest = Interest(1 << 2);
+
+    /// The source has been closed or hung up.
+    ///
+    /// See [`Event::set_interrupt()`][crate::Event::set_interrupt] for supported platforms.
+    pub const HANGUP: Interest = Interest(1 << 3);
+
+    /// An error occurred on the source.
+    ///
+    /// Where supported, errors are reported regardless of interest, so this flag has no effect
+    /// on a registration and only shows up in delivered events. A source registered with no
+    /// other flags is only reported on errors and hangups, for example to watch a UDP socket for
+    /// ICMP errors without waking up for every datagram.
+    ///
+    /// With `kqueue`, a source registered with no interest is not watched at all, so this flag
+    /// registers the read filter with the largest low watermark, which reports pending socket
+    /// errors and the end of the stream.
+    pub const ERROR: Interest = Interest(1 << 4);
+
+    /// Wake up only one of several pollers that wait on the same source.
+    ///
+    /// This corresponds to `EPOLLEXCLUSIVE` and is only supported by `epoll`. It can only be used
+    /// when adding a source in level or edge-triggered mode, and can't be modified afterwards.
+    pub const EXCLUSIVE: Interest = Interest(1 << 5);
+
+    /// Prevent the system from suspending while events of the source are being handled.
+    ///
+    /// This corresponds to `EPOLLWAKEUP` and is only supported by `epoll`. The system is kept
+    /// awake from the time an event is queued until the next call to `wait()`. It requires the
+    /// `CAP_BLOCK_SUSPEND` capability, and is silently ignored by the kernel without it.
+    pub const WAKEUP: Interest = Interest(1 << 6);
+
+    /// All flags, in the order they are displayed.
+    const FLAGS: [(Interest, &'static str); 7] = [
+        (Interest::READABLE, "READABLE"),
+        (Interest::WRITABLE, "WRITABLE"),
+        (Interest::PRIORITY, "PRIORITY"),
+        (Interest::HANGUP, "HANGUP"),
+        (Interest::ERROR, "ERROR"),
+        (Interest::EXCLUSIVE, "EXCLUSIVE"),
+        (Interest::WAKEUP, "WAKEUP"),
+    ];
+
+    /// The names of the flags that are set, in the order they are displayed.
+    #[cfg(feature = "serde")]
+    pub(crate) fn names(self) -> impl Iterator<Item = &'static str> {
+        Interest::FLAGS
+            .into_iter()
+            .filter(move |(flag, _)| self.contains(*flag))
+            .map(|(_, name)| name)
+    }
+
+    /// The flag with the given name.
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_name(name: &str) -> Option<Interest> {
+        Interest::FLAGS
+            .into_iter()
+            .find(|(_, flag_name)| *flag_name == name)
+            .map(|(flag, _)| flag)
+    }
+
+    /// Whether no flags are set.
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether all flags in `other` are set.
+    #[inline]
+    pub const fn contains(self, other: Interest) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether any flag in `other` is set.
+    #[inline]
+    pub const fn intersects(self, other: Interest) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// The union of both sets of flags.
+    #[inline]
+    pub const fn union(self, other: Interest) -> Interest {
+        Interest(self.0 | other.0)
+    }
+
+    /// The flags that are set in `self` but not in `other`.
+    #[inline]
+    pub const fn difference(self, other: Interest) -> Interest {
+        Interest(self.0 & !other.0)
+    }
+
+    /// Set or clear the flags in `other`.
+    #[inline]
+    pub fn set(&mut self, other: Interest, active: bool) {
+        if active {
+            *self = self.union(other);
+        } else {
+            *self = self.difference(other);
+        }
+    }
+
+    /// Whether [`READABLE`][Interest::READABLE] is set.
+    #[inline]
+    pub const fn is_readable(self) -> bool {
+        self.contains(Interest::READABLE)
+    }
+
+    /// Whether [`WRITABLE`][Interest::WRITABLE] is set.
+    #[inline]
+    pub const fn is_writable(self) -> bool {
+        self.contains(Interest::WRITABLE)
+    }
+}
+
+impl BitOr for Interest {
+    type Output = Interest;
+
+    #[inline]
+    fn bitor(self, rhs: Interest) -> Interest {
+        self.union(rhs)
+    }
+}
+
+impl BitOrAssign for Interest {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Interest) {
+        *self = self.union(rhs);
+    }
+}
+
+impl BitAnd for Interest {
+    type Output = Interest;
+
+    #[inline]
+    fn bitand(self, rhs: Interest) -> Interest {
+        Interest(self.0 & rhs.0)
+    }
+}
+
+impl BitAndAssign for Interest {
+    #[inline]
+    fn bitand_assign(&mut self, rhs: Interest) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl Not for Interest {
+    type Output = Interest;
+
+    #[inline]
+    fn not(self) -> Interest {
+        Interest::FLAGS
+            .iter()
+            .fold(Interest::NONE, |all, (flag, _)| all | *flag)
+            .difference(self)
+    }
+}
+
+impl fmt::Debug for Interest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("NONE");
+        }
+
+        let mut first = true;
+        for (flag, name) in Interest::FLAGS {
+            if self.contains(flag) {
+                if !first {
+                    f.write_str(" | ")?;
+                }
+                f.write_str(name)?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
+}
@@ -94,6 +94,16 @@ impl AfdPollMask {
         AfdPollMask(0)
     }
 
+    /// Creates a mask from raw flags.
+    pub(crate) const fn from_bits(bits: u32) -> AfdPollMask {
+        AfdPollMask(bits)
+    }
+
+    /// The raw flags of this mask.
+    pub(crate) const fn bits(self) -> u32 {
+        self.0
+    }
+
     /// Checks if this mask contains the other mask.
     pub(crate) fn intersects(self, other: AfdPollMask) -> bool {
         (self.0 & other.0) != 0
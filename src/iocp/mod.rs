@@ -24,6 +24,11 @@
 //!
 //! Previously, this crate used the `wepoll` library for polling. `wepoll` uses a similar
 //! AFD-based strategy for polling.
+//!
+//! Readiness could also be emulated by associating sockets with the IOCP directly and starting
+//! zero-byte reads on them. We don't do that: a zero-byte read only reports readability, not
+//! writability or incoming connections, and a socket can't be dissociated from a completion
+//! port, so it couldn't be deleted from the poller or used for overlapped I/O by the caller.
 
 mod afd;
 mod port;
@@ -37,7 +42,7 @@ use windows_sys::Win32::System::Threading::{
     WT_EXECUTEONLYONCE,
 };
 
-use crate::{Event, PollMode};
+use crate::{Backend, Event, PollMode, PollerBuilder};
 
 use concurrent_queue::ConcurrentQueue;
 use pin_project_lite::pin_project;
@@ -53,10 +58,13 @@ use std::os::windows::io::{
     AsHandle, AsRawHandle, AsRawSocket, BorrowedHandle, BorrowedSocket, RawHandle, RawSocket,
 };
 use std::pin::Pin;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard, RwLock, Weak};
 use std::time::{Duration, Instant};
 
+/// The backend implemented by this module.
+pub(super) const BACKEND: Backend = Backend::Iocp;
+
 /// Macro to lock and ignore lock poisoning.
 macro_rules! lock {
     ($lock_result:expr) => {{
@@ -99,11 +107,11 @@ pub(super) struct Poller {
     /// updating them as we come. If we're waiting on the IOCP updates should be run immediately.
     pending_updates: ConcurrentQueue<Packet>,
 
-    /// Are we currently polling?
+    /// The number of threads currently polling.
     ///
     /// This indicates whether or not we are blocking on the IOCP, and is used to determine
     /// whether pending updates should be run immediately or queued.
-    polling: AtomicBool,
+    polling: AtomicUsize,
 
     /// The packet used to notify the poller.
     ///
@@ -116,7 +124,10 @@ unsafe impl Sync for Poller {}
 
 impl Poller {
     /// Creates a new poller.
-    pub(super) fn new() -> io::Result<Self> {
+    ///
+    /// The notification key is unused, since notifications are delivered through a dedicated
+    /// packet rather than through an event.
+    pub(super) fn new(_builder: &PollerBuilder) -> io::Result<Self> {
         // Make sure AFD is able to be used.
         if let Err(e) = afd::NtdllImports::force_load() {
             return Err(io::Error::new(
@@ -144,7 +155,7 @@ impl Poller {
             sources: RwLock::new(HashMap::new()),
             waitables: RwLock::new(HashMap::new()),
             pending_updates: ConcurrentQueue::bounded(1024),
-            polling: AtomicBool::new(false),
+            polling: AtomicUsize::new(0),
             notifier: Arc::pin(
                 PacketInner::Wakeup {
                     _pinned: PhantomPinned,
@@ -154,6 +165,11 @@ impl Poller {
         })
     }
 
+    /// The backend of this poller.
+    pub(super) fn backend(&self) -> Backend {
+        BACKEND
+    }
+
     /// Whether this poller supports level-triggered events.
     pub(super) fn supports_level(&self) -> bool {
         true
@@ -277,6 +293,14 @@ impl Poller {
         Ok(())
     }
 
+    /// Forgets about a socket that was closed while it was registered.
+    pub(super) fn forget(&self, socket: RawSocket) {
+        let source = lock!(self.sources.write()).remove(&socket);
+        if let Some(source) = source {
+            let _ = source.begin_delete();
+        }
+    }
+
     /// Delete a source from the poller.
     pub(super) fn delete(&self, socket: BorrowedSocket<'_>) -> io::Result<()> {
         #[cfg(feature = "tracing")]
@@ -448,13 +472,12 @@ impl Poller {
             let mut new_events = 0;
 
             // Indicate that we are now polling.
-            let was_polling = self.polling.swap(true, Ordering::SeqCst);
-            debug_assert!(!was_polling);
+            self.polling.fetch_add(1, Ordering::SeqCst);
 
             // Even if we panic, we want to make sure we indicate that polling has stopped.
             let guard = CallOnDrop(|| {
-                let was_polling = self.polling.swap(false, Ordering::SeqCst);
-                debug_assert!(was_polling);
+                let was_polling = self.polling.fetch_sub(1, Ordering::SeqCst);
+                debug_assert!(was_polling > 0);
             });
 
             // Process every entry in the queue before we start polling.
@@ -510,6 +533,11 @@ impl Poller {
         self.port.post(0, 0, self.notifier.clone())
     }
 
+    /// Notify this poller from a console control handler, which runs on a thread of its own.
+    pub(super) fn notify_from_signal(&self) {
+        self.notify().ok();
+    }
+
     /// Push an IOCP packet into the queue.
     pub(super) fn post(&self, packet: CompletionPacket) -> io::Result<()> {
         self.port.post(0, 0, packet.0)
@@ -519,7 +547,7 @@ impl Poller {
     fn update_packet(&self, mut packet: Packet) -> io::Result<()> {
         loop {
             // If we are currently polling, we need to update the packet immediately.
-            if self.polling.load(Ordering::Acquire) {
+            if self.polling.load(Ordering::Acquire) > 0 {
                 packet.update()?;
                 return Ok(());
             }
@@ -654,6 +682,11 @@ impl Events {
     pub fn capacity(&self) -> usize {
         self.packets.capacity()
     }
+
+    /// Whether the last wait filled the list, so that more events may be pending.
+    pub fn is_full(&self) -> bool {
+        self.packets.len() >= self.packets.capacity()
+    }
 }
 
 /// Extra information about an event.
@@ -672,6 +705,20 @@ impl EventExtra {
         }
     }
 
+    /// Create the data from raw `AFD_POLL_*` flags.
+    #[inline]
+    pub(crate) fn from_raw(flags: u32) -> EventExtra {
+        EventExtra {
+            flags: AfdPollMask::from_bits(flags),
+        }
+    }
+
+    /// The raw `AFD_POLL_*` flags.
+    #[inline]
+    pub(crate) fn raw(&self) -> u32 {
+        self.flags.bits()
+    }
+
     /// Is this a HUP event?
     #[inline]
     pub fn is_hup(&self) -> bool {
@@ -684,6 +731,12 @@ impl EventExtra {
         self.flags.intersects(AfdPollMask::RECEIVE_EXPEDITED)
     }
 
+    /// Set interest in errors.
+    #[inline]
+    pub fn set_err(&mut self, _value: bool) {
+        // Errors are always reported.
+    }
+
     /// Set up a listener for HUP events.
     #[inline]
     pub fn set_hup(&mut self, active: bool) {
@@ -696,6 +749,30 @@ impl EventExtra {
         self.flags.set(AfdPollMask::RECEIVE_EXPEDITED, active);
     }
 
+    /// Set the exclusive flag.
+    #[inline]
+    pub fn set_exclusive(&mut self, _value: bool) {
+        // No-op.
+    }
+
+    /// Is the exclusive flag set?
+    #[inline]
+    pub fn is_exclusive(&self) -> bool {
+        false
+    }
+
+    /// Set the wakeup flag.
+    #[inline]
+    pub fn set_wakeup(&mut self, _value: bool) {
+        // No-op.
+    }
+
+    /// Is the wakeup flag set?
+    #[inline]
+    pub fn is_wakeup(&self) -> bool {
+        false
+    }
+
     /// Check if TCP connect failed. Deprecated.
     #[inline]
     pub fn is_connect_failed(&self) -> Option<bool> {
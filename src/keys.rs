@@ -0,0 +1,244 @@
+//! Allocation of event keys.
+
+use std::fmt;
+use std::io;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::{AsSource, Event, Interest, PollMode, Poller, Registration};
+
+/// The number of low bits of a key that hold the index of its slot.
+const INDEX_BITS: u32 = usize::BITS / 2;
+
+/// The mask of the index bits of a key.
+const INDEX_MASK: usize = (1 << INDEX_BITS) - 1;
+
+/// An allocator of unique event keys.
+///
+/// Keys are allocated from a slab: a freed slot is reused by later allocations, so the index of a
+/// key, returned by [`index()`][`KeyAllocator::index()`], stays small and can be used to look up
+/// per-source state in a `Vec`. The upper bits of a key hold a generation that is bumped every
+/// time its slot is reused, so an event for a source that was removed in the meantime has a stale
+/// key instead of the key of the new source in the same slot.
+///
+/// The allocator never hands out the notification key of the poller it was created for, or
+/// `usize::MAX` when created with [`new()`][`KeyAllocator::new()`].
+///
+/// The allocator can be shared between threads. With [`register()`][`KeyAllocator::register()`],
+/// the key is freed when the [`Registration`] it returns is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use polling::{Events, Interest, KeyAllocator, Poller};
+/// use std::net::UdpSocket;
+/// use std::time::Duration;
+///
+/// let poller = Poller::new()?;
+/// let keys = KeyAllocator::for_poller(&poller);
+///
+/// let socket = UdpSocket::bind("127.0.0.1:0")?;
+/// let socket = keys.register(&poller, socket, Interest::WRITABLE)?;
+///
+/// let mut events = Events::new();
+/// poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+/// for ev in events.iter() {
+///     assert_eq!(keys.index(ev.key), Some(0));
+/// }
+///
+/// // The key is freed along with the registration.
+/// drop(socket);
+/// assert!(keys.is_empty());
+/// # std::io::Result::Ok(())
+/// ```
+pub struct KeyAllocator {
+    inner: Mutex<Inner>,
+
+    /// The key that is never allocated.
+    reserved: usize,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// The generation of every slot, and whether it is in use.
+    slots: Vec<Slot>,
+
+    /// The indices of the free slots.
+    free: Vec<usize>,
+
+    /// The number of allocated keys.
+    len: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    generation: usize,
+    occupied: bool,
+}
+
+impl Slot {
+    /// The key of this slot, which is at `index`.
+    fn key(&self, index: usize) -> usize {
+        (self.generation << INDEX_BITS) | index
+    }
+}
+
+impl KeyAllocator {
+    /// Create an allocator that never allocates `usize::MAX`, the default notification key.
+    pub fn new() -> KeyAllocator {
+        KeyAllocator::with_reserved(usize::MAX)
+    }
+
+    /// Create an allocator for keys of sources registered in `poller`.
+    ///
+    /// The allocator never allocates the [notification key][`Poller::notify_key()`] of `poller`.
+    pub fn for_poller(poller: &Poller) -> KeyAllocator {
+        KeyAllocator::with_reserved(poller.notify_key())
+    }
+
+    /// Create an allocator that never allocates `reserved`.
+    pub(crate) fn with_reserved(reserved: usize) -> KeyAllocator {
+        KeyAllocator {
+            inner: Mutex::new(Inner::default()),
+            reserved,
+        }
+    }
+
+    /// Lock the slab, ignoring poisoning.
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Allocate a new key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more keys are allocated at once than fit into the index bits, which are half of
+    /// the bits of a `usize`.
+    pub fn allocate(&self) -> usize {
+        let mut inner = self.lock();
+        let index = match inner.free.pop() {
+            Some(index) => index,
+            None => {
+                let index = inner.slots.len();
+                assert!(index <= INDEX_MASK, "too many keys are allocated");
+                inner.slots.push(Slot {
+                    generation: 0,
+                    occupied: false,
+                });
+                index
+            }
+        };
+
+        let slot = &mut inner.slots[index];
+        slot.occupied = true;
+        if slot.key(index) == self.reserved {
+            slot.generation = slot.generation.wrapping_add(1);
+        }
+        let key = slot.key(index);
+        inner.len += 1;
+        key
+    }
+
+    /// Free a key so that its slot can be reused.
+    ///
+    /// Returns `false` if the key is not allocated, for example because it was already freed.
+    pub fn free(&self, key: usize) -> bool {
+        let mut inner = self.lock();
+        let index = key & INDEX_MASK;
+        match inner.slots.get_mut(index) {
+            Some(slot) if slot.occupied && slot.key(index) == key => {
+                slot.occupied = false;
+                slot.generation = slot.generation.wrapping_add(1);
+            }
+            _ => return false,
+        }
+        inner.free.push(index);
+        inner.len -= 1;
+        true
+    }
+
+    /// The index of the slot of `key`, if the key is allocated.
+    ///
+    /// Indices are below the number of slots that were ever in use at the same time, so they can
+    /// be used to look up state in a `Vec`. A stale key, whose slot was freed and possibly reused
+    /// since, returns `None`.
+    pub fn index(&self, key: usize) -> Option<usize> {
+        let inner = self.lock();
+        let index = key & INDEX_MASK;
+        match inner.slots.get(index) {
+            Some(slot) if slot.occupied && slot.key(index) == key => Some(index),
+            _ => None,
+        }
+    }
+
+    /// Whether `key` is allocated.
+    #[inline]
+    pub fn contains(&self, key: usize) -> bool {
+        self.index(key).is_some()
+    }
+
+    /// The number of allocated keys.
+    pub fn len(&self) -> usize {
+        self.lock().len
+    }
+
+    /// Whether no keys are allocated.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Register `source` in `poller` with a newly allocated key.
+    ///
+    /// This works like [`Poller::register()`], and the key is freed again when the returned
+    /// [`Registration`] is dropped or [consumed][`Registration::into_inner()`]. The key is
+    /// available from [`Registration::key()`].
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error in the same situations as [`Poller::add()`]. The key is freed
+    /// on error.
+    pub fn register<'a, S: AsSource + 'static>(
+        &'a self,
+        poller: &'a Poller,
+        source: S,
+        interest: Interest,
+    ) -> io::Result<Registration<'a, S>> {
+        self.register_with_mode(poller, source, interest, poller.mode())
+    }
+
+    /// Register `source` in `poller` with a newly allocated key, using the specified mode.
+    ///
+    /// See [`register()`][`KeyAllocator::register()`] for more information.
+    pub fn register_with_mode<'a, S: AsSource + 'static>(
+        &'a self,
+        poller: &'a Poller,
+        source: S,
+        interest: Interest,
+        mode: PollMode,
+    ) -> io::Result<Registration<'a, S>> {
+        let key = self.allocate();
+        match Registration::new(poller, source, Event::from_interest(key, interest), mode) {
+            Ok(registration) => Ok(registration.with_key(self, key)),
+            Err(err) => {
+                self.free(key);
+                Err(err)
+            }
+        }
+    }
+}
+
+impl Default for KeyAllocator {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for KeyAllocator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyAllocator")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
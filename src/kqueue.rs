@@ -1,16 +1,23 @@
 //! Bindings to kqueue (macOS, iOS, tvOS, watchOS, visionOS, FreeBSD, NetBSD, OpenBSD, DragonFly BSD).
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::io;
+use std::mem::{self, ManuallyDrop};
 use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
-use std::sync::RwLock;
+use std::ptr;
+use std::sync::{Mutex, MutexGuard, RwLock};
 use std::time::Instant;
 
 use rustix::buffer::spare_capacity;
 use rustix::event::{kqueue, Timespec};
 use rustix::io::{fcntl_setfd, Errno, FdFlags};
 
-use crate::{Event, PollMode};
+use crate::os::kqueue::RawFilter;
+use crate::{Backend, Event, PollMode, PollerBuilder};
+
+/// The backend implemented by this module.
+pub const BACKEND: Backend = Backend::Kqueue;
 
 /// Interface to kqueue.
 #[derive(Debug)]
@@ -28,6 +35,95 @@ pub struct Poller {
     /// On platforms that support `EVFILT_USER`, this uses that to wake up the poller. Otherwise, it
     /// uses a pipe.
     notify: notify::Notify,
+
+    /// The key used to register the notification mechanism.
+    notify_key: usize,
+
+    /// File descriptors that kqueue can't poll, which are polled with `poll()` instead.
+    fallback: fallback::Fallback,
+
+    /// Whether changes of interest are submitted together with the next wait.
+    batch_changes: bool,
+
+    /// Whether changes of file descriptors report the errors that are ignored otherwise.
+    strict_errors: bool,
+
+    /// The changes of interest that are submitted with the next wait.
+    pending: Mutex<Pending>,
+
+    /// The read and write filters that were last submitted for each file descriptor.
+    ///
+    /// This is used to only submit the filters that change. A file descriptor without an entry
+    /// may have any filters, so all of its changes are submitted.
+    filters: Mutex<HashMap<RawFd, [Option<Filter>; 2]>>,
+
+    /// The pointers that are stored in the kqueue instead of the keys of file descriptors.
+    udata: RwLock<Udata>,
+}
+
+/// The pointers attached to file descriptors.
+#[derive(Debug, Default)]
+struct Udata {
+    /// The pointer attached to each file descriptor.
+    fds: HashMap<RawFd, usize>,
+
+    /// The keys of the read and write filters that each pointer stands for.
+    keys: HashMap<usize, [usize; 2]>,
+}
+
+/// A read or write filter that was added to the kqueue.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Filter {
+    key: usize,
+    flags: kqueue::EventFlags,
+    fflags: u32,
+    data: i64,
+}
+
+impl Filter {
+    /// The filter added by `change`, or `None` if it deletes the filter.
+    fn of(change: &kqueue::Event) -> Option<Filter> {
+        let flags = change.flags() - kqueue::EventFlags::RECEIPT;
+        if flags.contains(kqueue::EventFlags::DELETE) {
+            return None;
+        }
+        Some(Filter {
+            key: change.udata() as usize,
+            flags,
+            fflags: as_raw(change).fflags,
+            data: change.data(),
+        })
+    }
+}
+
+/// Changes of interest that are submitted with the next wait.
+#[derive(Default)]
+struct Pending {
+    /// The changes, with `EV_RECEIPT`.
+    changes: Vec<kqueue::Event>,
+
+    /// The number of threads that are waiting right now.
+    waiting: usize,
+
+    /// Events that were returned in place of the results of changes, to deliver with the next
+    /// wait.
+    ///
+    /// Kernels that ignore `EV_RECEIPT`, such as NetBSD before 10, only report failed changes,
+    /// and fill the rest of the event list with events that are ready.
+    received: Vec<kqueue::Event>,
+}
+
+// The user data of the changes is a key, not a pointer.
+unsafe impl Send for Pending {}
+
+impl fmt::Debug for Pending {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pending")
+            .field("changes", &self.changes.len())
+            .field("waiting", &self.waiting)
+            .field("received", &self.received.len())
+            .finish()
+    }
 }
 
 /// Identifier for a source.
@@ -45,19 +141,46 @@ pub enum SourceId {
 
     /// Timer ID.
     Timer(usize),
+
+    /// File descriptor registered with `EVFILT_EMPTY`.
+    #[cfg(target_os = "freebsd")]
+    Empty(RawFd),
+
+    /// Device registered with `EVFILT_DEVICE`.
+    #[cfg(target_os = "openbsd")]
+    Device(RawFd),
+
+    /// Raw filter and identifier.
+    Raw(i32, usize),
 }
 
 impl Poller {
     /// Creates a new poller.
-    pub fn new() -> io::Result<Poller> {
+    pub fn new(builder: &PollerBuilder) -> io::Result<Poller> {
         // Create a kqueue instance.
         let kqueue_fd = kqueue::kqueue()?;
-        fcntl_setfd(&kqueue_fd, FdFlags::CLOEXEC)?;
+        if builder.cloexec {
+            fcntl_setfd(&kqueue_fd, FdFlags::CLOEXEC)?;
+        }
+
+        Self::from_fd(kqueue_fd, builder)
+    }
+
+    /// Creates a poller from an existing kqueue instance.
+    pub fn from_fd(kqueue_fd: OwnedFd, builder: &PollerBuilder) -> io::Result<Poller> {
+        let notify_key = builder.notify_key;
 
         let poller = Poller {
             kqueue_fd,
             sources: RwLock::new(HashSet::new()),
             notify: notify::Notify::new()?,
+            notify_key,
+            fallback: fallback::Fallback::default(),
+            batch_changes: builder.batch_changes,
+            strict_errors: builder.strict_errors,
+            pending: Mutex::default(),
+            filters: Mutex::default(),
+            udata: RwLock::default(),
         };
 
         // Register the notification pipe.
@@ -71,6 +194,11 @@ impl Poller {
         Ok(poller)
     }
 
+    /// The backend of this poller.
+    pub fn backend(&self) -> Backend {
+        BACKEND
+    }
+
     /// Whether this poller supports level-triggered events.
     pub fn supports_level(&self) -> bool {
         true
@@ -89,19 +217,100 @@ impl Poller {
     pub unsafe fn add(&self, fd: RawFd, ev: Event, mode: PollMode) -> io::Result<()> {
         self.add_source(SourceId::Fd(fd))?;
 
-        // File descriptors don't need to be added explicitly, so just modify the interest.
-        self.modify(BorrowedFd::borrow_raw(fd), ev, mode)
+        // The kernel removes the filters of closed file descriptors, so a new one has none.
+        self.filters().insert(fd, [None, None]);
+
+        // File descriptors don't need to be added explicitly, so just modify the interest. This
+        // is never batched, so that file descriptors kqueue can't poll are detected right away.
+        self.modify_split(BorrowedFd::borrow_raw(fd), ev, ev, mode)
+            .map_err(|err| {
+                // kqueue rejects files without a read or write filter, such as some devices. The
+                // same errors can have other causes, so only report the kind of file if it is one
+                // of those.
+                let raw = err.raw_os_error();
+                if (raw == Some(Errno::INVAL.raw_os_error())
+                    || raw == Some(Errno::NODEV.raw_os_error()))
+                    && is_file_or_device(BorrowedFd::borrow_raw(fd))
+                {
+                    crate::unpollable_fd_error("kqueue", fd, err)
+                } else {
+                    err
+                }
+            })
     }
 
     /// Modifies an existing file descriptor.
+    ///
+    /// With batched changes, the change is queued and submitted with the next wait, unless a
+    /// wait is in progress or errors are strict, which are then reported by this call.
     pub fn modify(&self, fd: BorrowedFd<'_>, ev: Event, mode: PollMode) -> io::Result<()> {
+        if self.batch_changes && !self.strict_errors && !self.fallback.contains(fd.as_raw_fd()) {
+            self.has_source(SourceId::Fd(fd.as_raw_fd()))?;
+            let mut pending = self.pending();
+            if pending.waiting == 0 {
+                let mut changes = filter_changes(fd, ev, ev, mode, kqueue::EventFlags::RECEIPT);
+                self.attach_udata(fd.as_raw_fd(), &mut changes);
+                let (changes, len, _) = self.changed_filters(fd.as_raw_fd(), changes);
+                pending.changes.extend_from_slice(&changes[..len]);
+                return Ok(());
+            }
+        }
+
+        self.modify_split(fd, ev, ev, mode)
+    }
+
+    /// Modifies an existing file descriptor, with separate keys for the read and write filters.
+    ///
+    /// The read filter uses the key and readability of `read`, and the write filter uses the key
+    /// and writability of `write`.
+    ///
+    /// On Apple platforms, kqueue can't poll some kinds of file descriptors, such as terminals,
+    /// which `poll()` supports. These are polled with `poll()` next to the kqueue instead.
+    pub fn modify_split(
+        &self,
+        fd: BorrowedFd<'_>,
+        read: Event,
+        write: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        let raw = fd.as_raw_fd();
+        if self.fallback.contains(raw) {
+            self.fallback.modify(raw, read, write, mode)?;
+        } else {
+            match self.modify_filters(fd, read, write, mode) {
+                Err(err) if fallback::is_unpollable(&err) && (read.readable || write.writable) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(?fd, %err, "kqueue can't poll the file descriptor, using poll()");
+
+                    // Remove the filters that may have been added before the error.
+                    let _ = self.modify_filters(fd, Event::none(0), Event::none(0), mode);
+                    self.fallback.modify(raw, read, write, mode)?;
+                }
+                res => return res,
+            }
+        }
+
+        // Wake up a wait that is polling with the old interest.
+        self.notify.notify(self).ok();
+        Ok(())
+    }
+
+    /// Modifies the read and write filters of an existing file descriptor.
+    fn modify_filters(
+        &self,
+        fd: BorrowedFd<'_>,
+        read: Event,
+        write: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
         #[cfg(feature = "tracing")]
         let span = if !self.notify.has_fd(fd) {
             let span = tracing::trace_span!(
                 "add",
                 kqueue_fd = ?self.kqueue_fd.as_raw_fd(),
                 ?fd,
-                ?ev,
+                ?read,
+                ?write,
             );
             Some(span)
         } else {
@@ -112,41 +321,153 @@ impl Poller {
 
         self.has_source(SourceId::Fd(fd.as_raw_fd()))?;
 
-        let mode_flags = mode_to_flags(mode);
+        // Apply the changes of the filters that changed.
+        let mut changes = filter_changes(fd, read, write, mode, kqueue::EventFlags::RECEIPT);
+        self.attach_udata(fd.as_raw_fd(), &mut changes);
+        let (changes, len, old) = self.changed_filters(fd.as_raw_fd(), changes);
+        let res = self.submit_checked(&changes[..len], |raw| {
+            // A oneshot filter is deleted by the kernel once it fires.
+            let i = (raw.filter == libc::EVFILT_WRITE) as usize;
+            old.map_or(true, |old| {
+                old[i].map_or(true, |filter| {
+                    filter.flags.contains(kqueue::EventFlags::ONESHOT)
+                })
+            })
+        });
+        if res.is_err() {
+            self.filters().remove(&fd.as_raw_fd());
+        }
+        res
+    }
+
+    /// Attach a pointer to `fd`, or detach it, for the next changes of its filters.
+    ///
+    /// The pointer is stored in the kqueue instead of the key, and the keys are restored when
+    /// its events are delivered. A pointer can only be attached to one file descriptor.
+    pub(crate) fn set_udata(&self, fd: RawFd, udata: Option<usize>) -> io::Result<()> {
+        self.has_source(SourceId::Fd(fd))?;
+
+        let mut attached = self.udata.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(udata) = udata {
+            if attached.fds.get(&fd) != Some(&udata) && attached.keys.contains_key(&udata) {
+                return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+            }
+        }
+        if let Some(old) = attached.fds.remove(&fd) {
+            attached.keys.remove(&old);
+        }
+        if let Some(udata) = udata {
+            attached.fds.insert(fd, udata);
+            attached.keys.insert(udata, [0; 2]);
+        }
+        Ok(())
+    }
 
-        let read_flags = if ev.readable {
-            kqueue::EventFlags::ADD | mode_flags
-        } else {
-            kqueue::EventFlags::DELETE
-        };
-        let write_flags = if ev.writable {
-            kqueue::EventFlags::ADD | mode_flags
-        } else {
-            kqueue::EventFlags::DELETE
+    /// Replace the keys in the changes of the filters of `fd` with its pointer, if it has one.
+    fn attach_udata(&self, fd: RawFd, changes: &mut [kqueue::Event; 2]) {
+        if self
+            .udata
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .fds
+            .is_empty()
+        {
+            return;
+        }
+
+        let mut attached = self.udata.write().unwrap_or_else(|e| e.into_inner());
+        let udata = match attached.fds.get(&fd) {
+            Some(&udata) => udata,
+            None => return,
         };
+        let keys = [changes[0].udata() as usize, changes[1].udata() as usize];
+        attached.keys.insert(udata, keys);
+        for change in changes {
+            let mut raw = *as_raw(change);
+            raw.udata = udata as _;
+            *change = from_raw(raw);
+        }
+    }
 
-        // A list of changes for kqueue.
-        let changelist = [
-            kqueue::Event::new(
-                kqueue::EventFilter::Read(fd.as_raw_fd()),
-                read_flags | kqueue::EventFlags::RECEIPT,
-                ev.key as _,
-            ),
-            kqueue::Event::new(
-                kqueue::EventFilter::Write(fd.as_raw_fd()),
-                write_flags | kqueue::EventFlags::RECEIPT,
-                ev.key as _,
-            ),
-        ];
+    /// Put the keys back into the delivered events that carry a pointer instead.
+    fn detach_udata(&self, events: &mut Events) {
+        events.udata.clear();
+        events.udata.resize(events.list.len(), 0);
 
-        // Apply changes.
-        self.submit_changes(changelist)
+        let attached = self.udata.read().unwrap_or_else(|e| e.into_inner());
+        if attached.fds.is_empty() {
+            return;
+        }
+        for (ev, udata) in events.list.iter_mut().zip(&mut events.udata) {
+            let (fd, write) = match ev.filter() {
+                kqueue::EventFilter::Read(fd) => (fd, false),
+                kqueue::EventFilter::Write(fd) => (fd, true),
+                _ => continue,
+            };
+            let ptr = ev.udata() as usize;
+            if attached.fds.get(&fd) != Some(&ptr) {
+                continue;
+            }
+
+            let mut raw = *as_raw(ev);
+            raw.udata = attached.keys[&ptr][write as usize] as _;
+            *ev = from_raw(raw);
+            *udata = ptr;
+        }
+    }
+
+    /// Leave out the changes that don't change the filters of `fd`, and remember the new ones.
+    ///
+    /// Deleting a filter that isn't there is left out, and so is adding a level-triggered filter
+    /// again. Oneshot and edge-triggered filters are always added again, since that re-arms them.
+    /// This also returns the filters from before, if they are known.
+    #[allow(clippy::type_complexity)]
+    fn changed_filters(
+        &self,
+        fd: RawFd,
+        changes: [kqueue::Event; 2],
+    ) -> ([kqueue::Event; 2], usize, Option<[Option<Filter>; 2]>) {
+        let new = [Filter::of(&changes[0]), Filter::of(&changes[1])];
+        let old = self.filters().insert(fd, new);
+
+        let mut changed = changes;
+        let mut len = 0;
+        for (i, change) in changes.iter().enumerate() {
+            let unchanged = old.is_some_and(|old| {
+                old[i] == new[i]
+                    && new[i].map_or(true, |filter| {
+                        !filter
+                            .flags
+                            .intersects(kqueue::EventFlags::ONESHOT | kqueue::EventFlags::CLEAR)
+                    })
+            });
+            if !unchanged {
+                changed[len] = *change;
+                len += 1;
+            }
+        }
+        (changed, len, old)
     }
 
     /// Submit one or more changes to the kernel queue and check to see if they succeeded.
     pub(crate) fn submit_changes<A>(&self, changelist: A) -> io::Result<()>
     where
-        A: Copy + AsRef<[kqueue::Event]> + AsMut<[kqueue::Event]>,
+        A: AsRef<[kqueue::Event]>,
+    {
+        self.submit_checked(changelist, |_| true)
+    }
+
+    /// Submit changes, and check them like `submit_changes()`.
+    ///
+    /// With strict errors, a filter that doesn't exist is only ignored if `may_be_gone` returns
+    /// `true` for it, and a closed pipe is reported, with the filter that failed.
+    fn submit_checked<A>(
+        &self,
+        changelist: A,
+        may_be_gone: impl Fn(&libc::kevent) -> bool,
+    ) -> io::Result<()>
+    where
+        A: AsRef<[kqueue::Event]>,
     {
         let mut eventlist = Vec::with_capacity(changelist.as_ref().len());
 
@@ -164,18 +485,43 @@ impl Poller {
             }
         }
 
-        // Check for errors.
+        // Check for errors, and keep the events that are not the result of a change.
+        let received = eventlist
+            .iter()
+            .filter(|ev| !ev.flags().contains(kqueue::EventFlags::ERROR));
+        if received.clone().next().is_some() {
+            self.pending().received.extend(received);
+        }
         for &ev in &eventlist {
             let data = ev.data();
 
+            if !ev.flags().contains(kqueue::EventFlags::ERROR) || data == 0 {
+                continue;
+            }
+
             // Explanation for ignoring EPIPE: https://github.com/tokio-rs/mio/issues/582
-            if (ev.flags().contains(kqueue::EventFlags::ERROR))
-                && data != 0
-                && data != Errno::NOENT.raw_os_error() as _
-                && data != Errno::PIPE.raw_os_error() as _
-            {
+            let noent = data == Errno::NOENT.raw_os_error() as _;
+            let pipe = data == Errno::PIPE.raw_os_error() as _;
+            if !noent && !pipe {
                 return Err(io::Error::from_raw_os_error(data as _));
             }
+            if self.strict_errors && (pipe || !may_be_gone(as_raw(&ev))) {
+                let err = io::Error::from_raw_os_error(data as _);
+                let raw = as_raw(&ev);
+                let ident = raw.ident;
+                let filter = if raw.filter == libc::EVFILT_WRITE {
+                    "write"
+                } else {
+                    "read"
+                };
+                return Err(io::Error::new(
+                    err.kind(),
+                    format!(
+                        "failed to change the {} filter of file descriptor {}: {}",
+                        filter, ident, err
+                    ),
+                ));
+            }
         }
 
         Ok(())
@@ -228,14 +574,140 @@ impl Poller {
 
     /// Deletes a file descriptor.
     pub fn delete(&self, fd: BorrowedFd<'_>) -> io::Result<()> {
+        self.discard_changes(fd.as_raw_fd());
+        if self.fallback.remove(fd.as_raw_fd()) {
+            self.filters().remove(&fd.as_raw_fd());
+            let _ = self.set_udata(fd.as_raw_fd(), None);
+            return self.remove_source(SourceId::Fd(fd.as_raw_fd()));
+        }
+
         // Simply delete interest in the file descriptor.
-        self.modify(fd, Event::none(0), PollMode::Oneshot)?;
+        self.modify_split(fd, Event::none(0), Event::none(0), PollMode::Oneshot)?;
 
+        self.filters().remove(&fd.as_raw_fd());
+        let _ = self.set_udata(fd.as_raw_fd(), None);
         self.remove_source(SourceId::Fd(fd.as_raw_fd()))
     }
 
+    /// Forgets about a file descriptor that was closed while it was registered.
+    pub fn forget(&self, fd: RawFd) {
+        // The kernel removes the filters of closed file descriptors by itself.
+        self.discard_changes(fd);
+        self.filters().remove(&fd);
+        let _ = self.set_udata(fd, None);
+        self.fallback.remove(fd);
+        let _ = self.remove_source(SourceId::Fd(fd));
+    }
+
+    /// Lock the queued changes, ignoring poisoning.
+    fn pending(&self) -> MutexGuard<'_, Pending> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Lock the filters of the file descriptors, ignoring poisoning.
+    fn filters(&self) -> MutexGuard<'_, HashMap<RawFd, [Option<Filter>; 2]>> {
+        self.filters.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Drop the queued changes of `fd`, which would otherwise apply to a reused descriptor.
+    fn discard_changes(&self, fd: RawFd) {
+        if self.batch_changes {
+            self.pending()
+                .changes
+                .retain(|change| as_raw(change).ident != fd as _);
+        }
+    }
+
+    /// Waits for I/O events with an optional deadline and the given signal mask.
+    ///
+    /// There is no native support for this, so the signal mask is replaced around the wait.
+    pub fn wait_sigmask(
+        &self,
+        events: &mut Events,
+        deadline: Option<Instant>,
+        sigmask: &libc::sigset_t,
+    ) -> io::Result<()> {
+        crate::os::unix::with_sigmask(sigmask, || self.wait_deadline(events, deadline))
+    }
+
     /// Waits for I/O events with an optional deadline.
+    ///
+    /// With batched changes, the queued changes are submitted first. If one of them fails, the
+    /// wait collects the events that are ready without blocking, and then returns its error.
     pub fn wait_deadline(&self, events: &mut Events, deadline: Option<Instant>) -> io::Result<()> {
+        if !self.batch_changes {
+            return self.wait_events(events, deadline);
+        }
+
+        // Changes made while waiting are submitted right away instead.
+        let changes = {
+            let mut pending = self.pending();
+            pending.waiting += 1;
+            mem::take(&mut pending.changes)
+        };
+        let res = self.submit_queued(changes).and_then(|failed| {
+            let deadline = match failed {
+                Some(_) => Some(Instant::now()),
+                None => deadline,
+            };
+            self.wait_events(events, deadline)?;
+            failed.map_or(Ok(()), Err)
+        });
+        self.pending().waiting -= 1;
+        res
+    }
+
+    /// Submit the queued `changes`, and return the error of the first one that failed.
+    ///
+    /// Every change asks for a receipt, and the event list has room for all of them, so the
+    /// kernel applies every change and returns right away instead of waiting for events. If the
+    /// call fails as a whole, the changes are queued again for the next wait.
+    fn submit_queued(&self, changes: Vec<kqueue::Event>) -> io::Result<Option<io::Error>> {
+        if changes.is_empty() {
+            return Ok(None);
+        }
+
+        let mut receipts = Vec::with_capacity(changes.len());
+        let res = unsafe {
+            kqueue::kevent_timespec(
+                &self.kqueue_fd,
+                &changes,
+                spare_capacity(&mut receipts),
+                Some(&Timespec {
+                    tv_sec: 0,
+                    tv_nsec: 0,
+                }),
+            )
+        };
+        if let Err(err) = res {
+            self.pending().changes.splice(0..0, changes);
+            return Err(err.into());
+        }
+
+        // Like `submit_changes()`, ignore filters that were already deleted or the other end of
+        // a pipe being closed.
+        let mut failed = None;
+        for ev in receipts {
+            if !ev.flags().contains(kqueue::EventFlags::ERROR) {
+                // Kernels that ignore `EV_RECEIPT` return events that are ready instead.
+                self.pending().received.push(ev);
+                continue;
+            }
+            let data = ev.data();
+            if data != 0
+                && data != Errno::NOENT.raw_os_error() as _
+                && data != Errno::PIPE.raw_os_error() as _
+            {
+                // The filters of the file descriptor are unknown now.
+                self.filters().remove(&(as_raw(&ev).ident as RawFd));
+                failed.get_or_insert_with(|| io::Error::from_raw_os_error(data as _));
+            }
+        }
+        Ok(failed)
+    }
+
+    /// Waits for I/O events with an optional deadline.
+    fn wait_events(&self, events: &mut Events, deadline: Option<Instant>) -> io::Result<()> {
         #[cfg(feature = "tracing")]
         let span = tracing::trace_span!(
             "wait",
@@ -253,16 +725,40 @@ impl Poller {
             None => None,
         };
 
-        // Wait for I/O events.
-        let changelist = [];
+        // Events returned while submitting changes are delivered without blocking.
+        let received = mem::take(&mut self.pending().received);
+        let ready = !received.is_empty();
+        events.list.extend(received);
+
+        // Wait for I/O events. With file descriptors that kqueue can't poll, wait for them and the
+        // kqueue with `poll()`, and then only collect the events of the kqueue.
+        let polled = self.fallback.poll(
+            self.kqueue_fd.as_fd(),
+            if ready {
+                Some(Instant::now())
+            } else {
+                deadline
+            },
+        )?;
+        let timeout = if polled.is_some() || ready {
+            Some(Timespec {
+                tv_sec: 0,
+                tv_nsec: 0,
+            })
+        } else {
+            timeout
+        };
         let _res = unsafe {
             kqueue::kevent_timespec(
                 &self.kqueue_fd,
-                &changelist,
+                &[],
                 spare_capacity(&mut events.list),
                 timeout.as_ref(),
             )?
         };
+        if let Some(polled) = polled {
+            self.fallback.collect(polled, &mut events.list);
+        }
 
         #[cfg(feature = "tracing")]
         tracing::trace!(
@@ -271,8 +767,21 @@ impl Poller {
             "new events",
         );
 
-        // Clear the notification (if received) and re-register interest in it.
-        self.notify.reregister(self)?;
+        // Remove the notifications.
+        let len = events.list.len();
+        events
+            .list
+            .retain(|ev| ev.udata() as usize != self.notify_key);
+
+        // Clear the notification and re-register interest in it. Only the thread that received
+        // the notification does this, so that concurrent waits don't steal each other's wakeups.
+        if events.list.len() != len {
+            self.notify.reregister(self)?;
+        }
+
+        events.received = len;
+        self.detach_udata(events);
+        events.merge_filters();
 
         Ok(())
     }
@@ -287,8 +796,52 @@ impl Poller {
         #[cfg(feature = "tracing")]
         let _enter = span.enter();
 
-        self.notify.notify(self).ok();
-        Ok(())
+        self.notify.notify(self)
+    }
+
+    /// Sends a notification from a signal handler.
+    ///
+    /// This only triggers the user filter or writes to the pipe, without allocating.
+    pub fn notify_from_signal(&self) {
+        self.notify.notify_from_signal(self);
+    }
+
+    /// Returns the kqueue instance, removing the notification mechanism from it.
+    pub fn into_fd(self) -> OwnedFd {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(kqueue_fd = ?self.kqueue_fd.as_raw_fd(), "into_fd");
+
+        let mut this = ManuallyDrop::new(self);
+        let _ = this.notify.deregister(&this);
+
+        // SAFETY: `this` is not used or dropped after its fields are moved out.
+        unsafe {
+            ptr::drop_in_place(&mut this.sources);
+            ptr::drop_in_place(&mut this.notify);
+            ptr::drop_in_place(&mut this.fallback);
+            ptr::drop_in_place(&mut this.pending);
+            ptr::drop_in_place(&mut this.filters);
+            ptr::drop_in_place(&mut this.udata);
+            ptr::read(&this.kqueue_fd)
+        }
+    }
+
+    /// Closes the poller in the child process after `fork()`.
+    ///
+    /// The kqueue is not inherited by the child, and its file descriptor may already have been
+    /// reused, so it is leaked rather than closed.
+    pub fn close_after_fork(self) {
+        let mut this = ManuallyDrop::new(self);
+
+        // SAFETY: `this` is not used or dropped after its fields are dropped.
+        unsafe {
+            ptr::drop_in_place(&mut this.sources);
+            ptr::drop_in_place(&mut this.notify);
+            ptr::drop_in_place(&mut this.fallback);
+            ptr::drop_in_place(&mut this.pending);
+            ptr::drop_in_place(&mut this.filters);
+            ptr::drop_in_place(&mut this.udata);
+        }
     }
 }
 
@@ -321,6 +874,19 @@ impl Drop for Poller {
 /// A list of reported I/O events.
 pub struct Events {
     list: Vec<kqueue::Event>,
+
+    /// The write filter event merged into the read filter event at the same index, or the other
+    /// way around.
+    paired: Vec<Option<kqueue::Event>>,
+
+    /// The index of the first read or write filter event of each key.
+    index: HashMap<usize, usize>,
+
+    /// The number of events received by the last wait, before merging.
+    received: usize,
+
+    /// The pointer attached to the source of each event, or zero.
+    udata: Vec<usize>,
 }
 
 unsafe impl Send for Events {}
@@ -330,58 +896,161 @@ impl Events {
     pub fn with_capacity(cap: usize) -> Events {
         Events {
             list: Vec::with_capacity(cap),
+            paired: Vec::new(),
+            index: HashMap::new(),
+            received: 0,
+            udata: Vec::new(),
         }
     }
 
     /// Iterates over I/O events.
     pub fn iter(&self) -> impl Iterator<Item = Event> + '_ {
-        // On some platforms, closing the read end of a pipe wakes up writers, but the
-        // event is reported as EVFILT_READ with the EV_EOF flag.
-        //
-        // https://github.com/golang/go/commit/23aad448b1e3f7c3b4ba2af90120bde91ac865b4
-        self.list.iter().map(|ev| Event {
-            key: ev.udata() as usize,
-            readable: matches!(
-                ev.filter(),
-                kqueue::EventFilter::Read(..)
-                    | kqueue::EventFilter::Vnode { .. }
-                    | kqueue::EventFilter::Proc { .. }
-                    | kqueue::EventFilter::Signal { .. }
-                    | kqueue::EventFilter::Timer { .. }
-            ),
-            writable: matches!(ev.filter(), kqueue::EventFilter::Write(..))
-                || (matches!(ev.filter(), kqueue::EventFilter::Read(..))
-                    && (ev.flags().intersects(kqueue::EventFlags::EOF))),
-            extra: EventExtra,
-        })
+        let udata = self.udata.iter().copied().chain(std::iter::repeat(0));
+        self.list
+            .iter()
+            .zip(&self.paired)
+            .zip(udata)
+            .map(|((ev, paired), udata)| {
+                let mut event = convert(ev);
+                event.extra.udata = udata;
+                if let Some(paired) = paired {
+                    let paired = convert(paired);
+                    event.readable |= paired.readable;
+                    event.writable |= paired.writable;
+                    event.extra.flags |= paired.extra.flags;
+                    event.extra.hup |= paired.extra.hup;
+                }
+                event
+            })
+    }
+
+    /// Merge the read and write filter events of the same source into one event.
+    ///
+    /// kqueue reports each filter in its own event, while the other backends report all the
+    /// readiness of a source at once, so this makes dispatch behave the same on all of them.
+    /// The merged event keeps the raw filter of the event that came first.
+    fn merge_filters(&mut self) {
+        self.index.clear();
+        self.paired.clear();
+        self.paired.resize(self.list.len(), None);
+
+        let mut len = 0;
+        for i in 0..self.list.len() {
+            let ev = self.list[i];
+            let read = match ev.filter() {
+                kqueue::EventFilter::Read(..) => Some(true),
+                kqueue::EventFilter::Write(..) => Some(false),
+                _ => None,
+            };
+
+            if let Some(read) = read {
+                let key = ev.udata() as usize;
+                match self.index.get(&key) {
+                    Some(&j)
+                        if self.paired[j].is_none()
+                            && matches!(self.list[j].filter(), kqueue::EventFilter::Read(..))
+                                != read =>
+                    {
+                        self.paired[j] = Some(ev);
+                        continue;
+                    }
+                    _ => {
+                        self.index.insert(key, len);
+                    }
+                }
+            }
+
+            self.list[len] = ev;
+            if let Some(&udata) = self.udata.get(i) {
+                self.udata[len] = udata;
+            }
+            len += 1;
+        }
+
+        self.list.truncate(len);
+        self.paired.truncate(len);
+        self.udata.truncate(len);
     }
 
     /// Clears the list.
     pub fn clear(&mut self) {
         self.list.clear();
+        self.paired.clear();
+        self.received = 0;
+        self.udata.clear();
     }
 
     /// Get the capacity of the list.
     pub fn capacity(&self) -> usize {
         self.list.capacity()
     }
+
+    /// Whether the last wait filled the list, so that more events may be pending.
+    pub fn is_full(&self) -> bool {
+        self.received >= self.list.capacity()
+    }
 }
 
 /// Extra information associated with an event.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub struct EventExtra;
+pub struct EventExtra {
+    /// The raw event that was delivered, if any.
+    raw: Option<RawFilter>,
+
+    /// The `EV_*` flags of the delivered event.
+    flags: u32,
+
+    /// Whether the source hung up, or interest in hangups.
+    hup: bool,
+
+    /// Interest in errors.
+    err: bool,
+
+    /// The pointer attached to the source, or zero.
+    udata: usize,
+}
 
 impl EventExtra {
     /// Create a new, empty version of this struct.
     #[inline]
     pub const fn empty() -> EventExtra {
-        EventExtra
+        EventExtra {
+            raw: None,
+            flags: 0,
+            hup: false,
+            err: false,
+            udata: 0,
+        }
+    }
+
+    /// The raw event that was delivered.
+    #[inline]
+    pub(crate) fn raw(&self) -> Option<RawFilter> {
+        self.raw
+    }
+
+    /// The `EV_*` flags of the event that was delivered.
+    #[inline]
+    pub(crate) fn raw_flags(&self) -> Option<u32> {
+        self.raw.map(|_| self.flags)
+    }
+
+    /// The pointer attached to the source of the event that was delivered.
+    #[inline]
+    pub(crate) fn udata(&self) -> usize {
+        self.udata
+    }
+
+    /// Set interest in errors.
+    #[inline]
+    pub fn set_err(&mut self, value: bool) {
+        self.err = value;
     }
 
     /// Set the interrupt flag.
     #[inline]
-    pub fn set_hup(&mut self, _value: bool) {
-        // No-op.
+    pub fn set_hup(&mut self, value: bool) {
+        self.hup = value;
     }
 
     /// Set the priority flag.
@@ -390,10 +1059,34 @@ impl EventExtra {
         // No-op.
     }
 
+    /// Set the exclusive flag.
+    #[inline]
+    pub fn set_exclusive(&mut self, _value: bool) {
+        // No-op.
+    }
+
+    /// Is the exclusive flag set?
+    #[inline]
+    pub fn is_exclusive(&self) -> bool {
+        false
+    }
+
+    /// Set the wakeup flag.
+    #[inline]
+    pub fn set_wakeup(&mut self, _value: bool) {
+        // No-op.
+    }
+
+    /// Is the wakeup flag set?
+    #[inline]
+    pub fn is_wakeup(&self) -> bool {
+        false
+    }
+
     /// Is the interrupt flag set?
     #[inline]
     pub fn is_hup(&self) -> bool {
-        false
+        self.hup
     }
 
     /// Is the priority flag set?
@@ -413,6 +1106,168 @@ impl EventExtra {
     }
 }
 
+/// Convert a raw event into an [`Event`].
+fn convert(ev: &kqueue::Event) -> Event {
+    // On some platforms, closing the read end of a pipe wakes up writers, but the
+    // event is reported as EVFILT_READ with the EV_EOF flag.
+    //
+    // https://github.com/golang/go/commit/23aad448b1e3f7c3b4ba2af90120bde91ac865b4
+    Event {
+        key: ev.udata() as usize,
+        readable: matches!(
+            ev.filter(),
+            kqueue::EventFilter::Read(..)
+                | kqueue::EventFilter::Vnode { .. }
+                | kqueue::EventFilter::Proc { .. }
+                | kqueue::EventFilter::Signal { .. }
+                | kqueue::EventFilter::Timer { .. }
+        ) || is_aio(ev)
+            || is_device(ev),
+        writable: matches!(ev.filter(), kqueue::EventFilter::Write(..))
+            || (matches!(ev.filter(), kqueue::EventFilter::Read(..))
+                && (ev.flags().intersects(kqueue::EventFlags::EOF)))
+            || is_empty(ev),
+        read_low_watermark: None,
+        write_low_watermark: None,
+        timeout: false,
+        delete_on_hangup: false,
+        extra: EventExtra {
+            raw: Some(RawFilter::from_raw(as_raw(ev))),
+            flags: as_raw(ev).flags as u32,
+            hup: matches!(
+                ev.filter(),
+                kqueue::EventFilter::Read(..) | kqueue::EventFilter::Write(..)
+            ) && ev.flags().intersects(kqueue::EventFlags::EOF),
+            err: false,
+            udata: 0,
+        },
+    }
+}
+
+/// Whether this is the completion of an AIO request.
+fn is_aio(ev: &kqueue::Event) -> bool {
+    #[cfg(target_os = "freebsd")]
+    {
+        as_raw(ev).filter == libc::EVFILT_AIO
+    }
+
+    #[cfg(not(target_os = "freebsd"))]
+    {
+        let _ = ev;
+        false
+    }
+}
+
+/// Whether this is an `EVFILT_EMPTY` event.
+fn is_empty(ev: &kqueue::Event) -> bool {
+    #[cfg(target_os = "freebsd")]
+    {
+        as_raw(ev).filter == libc::EVFILT_EMPTY
+    }
+
+    #[cfg(not(target_os = "freebsd"))]
+    {
+        let _ = ev;
+        false
+    }
+}
+
+/// Whether this is an `EVFILT_DEVICE` event.
+fn is_device(ev: &kqueue::Event) -> bool {
+    #[cfg(target_os = "openbsd")]
+    {
+        as_raw(ev).filter == libc::EVFILT_DEVICE
+    }
+
+    #[cfg(not(target_os = "openbsd"))]
+    {
+        let _ = ev;
+        false
+    }
+}
+
+/// Whether the file descriptor is a regular file or a device.
+fn is_file_or_device(fd: BorrowedFd<'_>) -> bool {
+    use rustix::fs::{fstat, FileType};
+
+    matches!(
+        fstat(fd).map(|stat| FileType::from_raw_mode(stat.st_mode)),
+        Ok(FileType::RegularFile | FileType::CharacterDevice | FileType::BlockDevice)
+    )
+}
+
+/// The raw `kevent` of an event.
+///
+/// `rustix` doesn't know about every filter and flag, so these are read and written through the
+/// raw structure.
+pub(crate) fn as_raw(ev: &kqueue::Event) -> &libc::kevent {
+    // SAFETY: `kqueue::Event` is a transparent wrapper around `libc::kevent`.
+    unsafe { &*(ev as *const kqueue::Event).cast::<libc::kevent>() }
+}
+
+/// Create an event from a raw `kevent`.
+pub(crate) fn from_raw(raw: libc::kevent) -> kqueue::Event {
+    // SAFETY: `kqueue::Event` is a transparent wrapper around `libc::kevent`.
+    unsafe { mem::transmute::<libc::kevent, kqueue::Event>(raw) }
+}
+
+/// The changes of the read and write filters of a file descriptor, with `flags` added to both.
+fn filter_changes(
+    fd: BorrowedFd<'_>,
+    read: Event,
+    write: Event,
+    mode: PollMode,
+    flags: kqueue::EventFlags,
+) -> [kqueue::Event; 2] {
+    let mode_flags = mode_to_flags(mode);
+
+    // Interest in hangups or errors alone uses the read filter, which reports the end of the
+    // stream and pending socket errors even if less data than its low watermark is available.
+    let no_data = !read.readable && (read.extra.hup || read.extra.err);
+    let read_flags = if read.readable || no_data {
+        kqueue::EventFlags::ADD | mode_flags
+    } else {
+        kqueue::EventFlags::DELETE
+    };
+    let write_flags = if write.writable {
+        kqueue::EventFlags::ADD | mode_flags
+    } else {
+        kqueue::EventFlags::DELETE
+    };
+
+    let mut read_event = kqueue::Event::new(
+        kqueue::EventFilter::Read(fd.as_raw_fd()),
+        read_flags | flags,
+        read.key as _,
+    );
+    if no_data {
+        read_event = with_low_watermark(read_event, isize::MAX as usize);
+    } else if let Some(bytes) = read.read_low_watermark() {
+        read_event = with_low_watermark(read_event, bytes.get());
+    }
+
+    let mut write_event = kqueue::Event::new(
+        kqueue::EventFilter::Write(fd.as_raw_fd()),
+        write_flags | flags,
+        write.key as _,
+    );
+    if let Some(bytes) = write.write_low_watermark() {
+        write_event = with_low_watermark(write_event, bytes.get());
+    }
+
+    [read_event, write_event]
+}
+
+/// Make a read filter only trigger once at least `bytes` bytes can be read.
+fn with_low_watermark(ev: kqueue::Event, bytes: usize) -> kqueue::Event {
+    // SAFETY: `kqueue::Event` is a transparent wrapper around `libc::kevent`.
+    let mut raw = unsafe { mem::transmute::<kqueue::Event, libc::kevent>(ev) };
+    raw.fflags |= libc::NOTE_LOWAT;
+    raw.data = bytes.try_into().unwrap_or(isize::MAX as _);
+    // SAFETY: See above.
+    unsafe { mem::transmute::<libc::kevent, kqueue::Event>(raw) }
+}
+
 pub(crate) fn mode_to_flags(mode: PollMode) -> kqueue::EventFlags {
     use kqueue::EventFlags as EV;
 
@@ -424,6 +1279,186 @@ pub(crate) fn mode_to_flags(mode: PollMode) -> kqueue::EventFlags {
     }
 }
 
+/// Polling of file descriptors that kqueue can't poll.
+///
+/// On Apple platforms, kqueue rejects some kinds of file descriptors that `poll()` supports, such
+/// as terminals and other character devices. These are polled with `poll()`, together with the
+/// kqueue itself, and their events are added to the events of the kqueue.
+mod fallback {
+    use crate::{Event, PollMode};
+
+    use rustix::event::{kqueue, PollFd, PollFlags, Timespec};
+    use rustix::io::Errno;
+
+    use std::collections::HashMap;
+    use std::io;
+    use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+    use std::sync::{Mutex, MutexGuard};
+    use std::time::Instant;
+
+    /// Whether `err`, returned when registering a file descriptor, means that kqueue can't poll it.
+    pub(super) fn is_unpollable(err: &io::Error) -> bool {
+        cfg!(target_vendor = "apple") && err.raw_os_error() == Some(Errno::INVAL.raw_os_error())
+    }
+
+    /// The file descriptors that are polled with `poll()`.
+    #[derive(Debug, Default)]
+    pub(super) struct Fallback {
+        fds: Mutex<HashMap<RawFd, Interest>>,
+    }
+
+    /// The interest in a file descriptor.
+    #[derive(Debug)]
+    struct Interest {
+        /// The read half. In oneshot mode, readability is cleared once it fires.
+        read: Event,
+
+        /// The write half. In oneshot mode, writability is cleared once it fires.
+        write: Event,
+
+        oneshot: bool,
+    }
+
+    impl Fallback {
+        /// Lock the table, ignoring poisoning.
+        fn lock(&self) -> MutexGuard<'_, HashMap<RawFd, Interest>> {
+            self.fds.lock().unwrap_or_else(|e| e.into_inner())
+        }
+
+        /// Whether `fd` is polled with `poll()`.
+        pub(super) fn contains(&self, fd: RawFd) -> bool {
+            self.lock().contains_key(&fd)
+        }
+
+        /// Set the interest in `fd`, and start polling it with `poll()` if it isn't already.
+        pub(super) fn modify(
+            &self,
+            fd: RawFd,
+            read: Event,
+            write: Event,
+            mode: PollMode,
+        ) -> io::Result<()> {
+            let oneshot = match mode {
+                PollMode::Oneshot => true,
+                PollMode::Level => false,
+                PollMode::Edge | PollMode::EdgeOneshot => {
+                    return Err(crate::unsupported_error(
+                        "edge-triggered mode is not supported for file descriptors that kqueue can't poll",
+                    ))
+                }
+            };
+            self.lock().insert(
+                fd,
+                Interest {
+                    read,
+                    write,
+                    oneshot,
+                },
+            );
+            Ok(())
+        }
+
+        /// Stop polling `fd` with `poll()`.
+        ///
+        /// Returns `false` if `fd` is not polled with `poll()`.
+        pub(super) fn remove(&self, fd: RawFd) -> bool {
+            self.lock().remove(&fd).is_some()
+        }
+
+        /// Wait until the kqueue or any file descriptor is ready, or the deadline is reached.
+        ///
+        /// Returns `None` without waiting if no file descriptors are polled with `poll()`, and the
+        /// file descriptors that are ready otherwise.
+        pub(super) fn poll(
+            &self,
+            kqueue_fd: BorrowedFd<'_>,
+            deadline: Option<Instant>,
+        ) -> io::Result<Option<Vec<(RawFd, PollFlags)>>> {
+            let mut poll_fds = {
+                let fds = self.lock();
+                if fds.is_empty() {
+                    return Ok(None);
+                }
+
+                let mut poll_fds = Vec::with_capacity(fds.len() + 1);
+                poll_fds.push(PollFd::from_borrowed_fd(kqueue_fd, PollFlags::IN));
+                for (&fd, interest) in fds.iter() {
+                    let mut flags = PollFlags::empty();
+                    if interest.read.readable {
+                        flags |= PollFlags::IN;
+                    }
+                    if interest.write.writable {
+                        flags |= PollFlags::OUT;
+                    }
+
+                    // SAFETY: Registered file descriptors must stay open until they are deleted.
+                    let fd = unsafe { BorrowedFd::borrow_raw(fd) };
+                    poll_fds.push(PollFd::from_borrowed_fd(fd, flags));
+                }
+                poll_fds
+            };
+
+            // Timeout for `poll`. In case of overflow, use no timeout.
+            let timeout = deadline
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+                .and_then(|timeout| Timespec::try_from(timeout).ok());
+            rustix::event::poll(&mut poll_fds, timeout.as_ref())?;
+
+            Ok(Some(
+                poll_fds[1..]
+                    .iter()
+                    .filter(|poll_fd| !poll_fd.revents().is_empty())
+                    .map(|poll_fd| (poll_fd.as_fd().as_raw_fd(), poll_fd.revents()))
+                    .collect(),
+            ))
+        }
+
+        /// Add the events of the ready file descriptors to `list`, within its capacity.
+        pub(super) fn collect(
+            &self,
+            ready: Vec<(RawFd, PollFlags)>,
+            list: &mut Vec<kqueue::Event>,
+        ) {
+            let mut fds = self.lock();
+            for (fd, revents) in ready {
+                let interest = match fds.get_mut(&fd) {
+                    Some(interest) => interest,
+                    None => continue,
+                };
+
+                let flags = if revents.intersects(PollFlags::HUP) {
+                    kqueue::EventFlags::EOF
+                } else {
+                    kqueue::EventFlags::empty()
+                };
+                let any = PollFlags::HUP | PollFlags::ERR | PollFlags::NVAL;
+                if interest.read.readable
+                    && revents.intersects(PollFlags::IN | any)
+                    && list.len() < list.capacity()
+                {
+                    list.push(kqueue::Event::new(
+                        kqueue::EventFilter::Read(fd),
+                        flags,
+                        interest.read.key as _,
+                    ));
+                    interest.read.readable &= !interest.oneshot;
+                }
+                if interest.write.writable
+                    && revents.intersects(PollFlags::OUT | any)
+                    && list.len() < list.capacity()
+                {
+                    list.push(kqueue::Event::new(
+                        kqueue::EventFilter::Write(fd),
+                        flags,
+                        interest.write.key as _,
+                    ));
+                    interest.write.writable &= !interest.oneshot;
+                }
+            }
+        }
+    }
+}
+
 #[cfg(any(
     target_os = "freebsd",
     target_os = "dragonfly",
@@ -431,6 +1466,7 @@ pub(crate) fn mode_to_flags(mode: PollMode) -> kqueue::EventFlags {
 ))]
 mod notify {
     use super::Poller;
+    use rustix::buffer::spare_capacity;
     use rustix::event::kqueue;
     use std::io;
     #[cfg(feature = "tracing")]
@@ -458,7 +1494,7 @@ mod notify {
                     user_flags: kqueue::UserDefinedFlags::new(0),
                 },
                 kqueue::EventFlags::ADD | kqueue::EventFlags::RECEIPT | kqueue::EventFlags::CLEAR,
-                crate::NOTIFY_KEY as _,
+                poller.notify_key as _,
             )])
         }
 
@@ -478,12 +1514,37 @@ mod notify {
                     user_flags: kqueue::UserDefinedFlags::new(0),
                 },
                 kqueue::EventFlags::ADD | kqueue::EventFlags::RECEIPT,
-                crate::NOTIFY_KEY as _,
+                poller.notify_key as _,
             )])?;
 
             Ok(())
         }
 
+        /// Notifies the `Poller` from a signal handler.
+        pub(super) fn notify_from_signal(&self, poller: &Poller) {
+            // Without an event list, the kernel doesn't report the result of the change, so
+            // nothing is allocated for it.
+            let changes = [kqueue::Event::new(
+                kqueue::EventFilter::User {
+                    ident: 0,
+                    flags: kqueue::UserFlags::TRIGGER,
+                    user_flags: kqueue::UserDefinedFlags::new(0),
+                },
+                kqueue::EventFlags::ADD,
+                poller.notify_key as _,
+            )];
+            let mut eventlist: Vec<kqueue::Event> = Vec::new();
+            unsafe {
+                kqueue::kevent_timespec(
+                    &poller.kqueue_fd,
+                    &changes,
+                    spare_capacity(&mut eventlist),
+                    None,
+                )
+                .ok();
+            }
+        }
+
         /// Deregisters this notification pipe from the `Poller`.
         pub(super) fn deregister(&self, poller: &Poller) -> io::Result<()> {
             // Deregister the EVFILT_USER event.
@@ -494,7 +1555,7 @@ mod notify {
                     user_flags: kqueue::UserDefinedFlags::new(0),
                 },
                 kqueue::EventFlags::DELETE | kqueue::EventFlags::RECEIPT,
-                crate::NOTIFY_KEY as _,
+                poller.notify_key as _,
             )])
         }
 
@@ -513,7 +1574,7 @@ mod notify {
 )))]
 mod notify {
     use super::Poller;
-    use crate::{Event, PollMode, NOTIFY_KEY};
+    use crate::{Event, PollMode};
     use std::io::{self, prelude::*};
     #[cfg(feature = "tracing")]
     use std::os::unix::io::BorrowedFd;
@@ -553,7 +1614,7 @@ mod notify {
             unsafe {
                 poller.add(
                     self.read_stream.as_raw_fd(),
-                    Event::readable(NOTIFY_KEY),
+                    Event::readable(poller.notify_key),
                     PollMode::Oneshot,
                 )
             }
@@ -567,7 +1628,7 @@ mod notify {
             // Reregister the read end of this pipe.
             poller.modify(
                 self.read_stream.as_fd(),
-                Event::readable(NOTIFY_KEY),
+                Event::readable(poller.notify_key),
                 PollMode::Oneshot,
             )
         }
@@ -575,10 +1636,17 @@ mod notify {
         /// Notifies the `Poller`.
         #[allow(clippy::unused_io_amount)]
         pub(super) fn notify(&self, _poller: &Poller) -> io::Result<()> {
-            // Write to the write end of the pipe
-            (&self.write_stream).write(&[1])?;
+            // Write to the write end of the pipe. A full pipe already wakes up the poller.
+            match (&self.write_stream).write(&[1]) {
+                Err(err) if err.kind() != io::ErrorKind::WouldBlock => Err(err),
+                _ => Ok(()),
+            }
+        }
 
-            Ok(())
+        /// Notifies the `Poller` from a signal handler.
+        #[allow(clippy::unused_io_amount)]
+        pub(super) fn notify_from_signal(&self, _poller: &Poller) {
+            (&self.write_stream).write(&[1]).ok();
         }
 
         /// Deregisters this notification pipe from the `Poller`.
@@ -13,7 +13,22 @@
 //! kind. However, level and edge triggered modes are also available for certain operating
 //! systems. See the documentation of the [`PollMode`] type for more information.
 //!
-//! Only one thread can be waiting for I/O events at a time.
+//! By default, only one thread can be waiting for I/O events at a time. See
+//! [`PollerBuilder::concurrent_waits()`] for letting several threads wait on the same poller.
+//!
+//! With the `tracing` feature enabled, the poller emits [`tracing`](https://docs.rs/tracing)
+//! spans and events for every registration, wait and notification, including the backend in
+//! use, the source, the key and interest, and any errors returned by the operating system.
+//! With the `metrics` feature enabled, [`Poller::stats()`] returns counters of waits, delivered
+//! events, notifications, spurious wakeups and re-registrations. The `capi` feature exports C
+//! bindings for embedding the poller in C and C++ projects, see the [`capi`] module. The `mio`
+//! feature adds an adapter for code migrating from mio, see the [`mio`][crate::mio] module. The
+//! `reactor` feature adds a minimal event loop that dispatches events to per-source callbacks,
+//! see the [`reactor`] module. The experimental `completion` feature adds an API that reports
+//! reads and writes once they are done, see the [`completion`] module. The `mock` feature adds
+//! a backend that replays a script of events for tests, see the [`mock`] module. The `serde`
+//! feature implements `Serialize` and `Deserialize` for [`Event`], [`Interest`] and
+//! [`PollMode`], for shipping event records to other processes.
 //!
 //! # Examples
 //!
@@ -64,12 +79,15 @@
 )]
 
 use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::io;
 use std::marker::PhantomData;
+use std::mem;
 use std::num::NonZeroUsize;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::task::Waker;
 use std::time::{Duration, Instant};
 
 use cfg_if::cfg_if;
@@ -80,11 +98,12 @@ cfg_if! {
     if #[cfg(polling_test_poll_backend)] {
         mod poll;
         use poll as sys;
-    } else if #[cfg(any(
-        target_os = "linux",
-        target_os = "android",
-        target_os = "redox"
-    ))] {
+    } else if #[cfg(any(target_os = "linux", target_os = "android"))] {
+        mod epoll;
+        mod linux;
+        mod poll;
+        use linux as sys;
+    } else if #[cfg(target_os = "redox")] {
         mod epoll;
         use epoll as sys;
     } else if #[cfg(any(
@@ -119,11 +138,207 @@ cfg_if! {
     }
 }
 
+pub mod channel;
 pub mod os;
 
-/// Key associated with notifications.
+#[cfg(feature = "capi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "capi")))]
+pub mod capi;
+
+#[cfg(feature = "mio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mio")))]
+pub mod mio;
+
+#[cfg(feature = "reactor")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reactor")))]
+pub mod reactor;
+
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+pub mod async_io;
+
+#[cfg(feature = "completion")]
+#[cfg_attr(docsrs, doc(cfg(feature = "completion")))]
+pub mod completion;
+
+#[cfg(feature = "mock")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mock")))]
+pub mod mock;
+
+mod array;
+mod backend;
+mod builder;
+mod cancel;
+mod capability;
+mod clock;
+mod custom;
+mod error;
+mod hooks;
+mod interest;
+mod keys;
+mod limit;
+#[cfg(all(
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "redox",
+        target_os = "illumos",
+        target_os = "solaris",
+        target_vendor = "apple",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+    ),
+    not(polling_test_poll_backend),
+))]
+mod multi;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "illumos",
+    target_os = "solaris",
+    windows,
+))]
+mod network;
+#[cfg(any(unix, windows))]
+mod once;
+mod parker;
+#[cfg(any(target_os = "linux", target_os = "android", target_vendor = "apple"))]
+mod pressure;
+mod registration;
+mod registry;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(any(unix, windows))]
+mod shutdown;
+mod spin;
+mod split;
+#[cfg(feature = "stall-diagnostics")]
+mod stall;
+#[cfg(feature = "metrics")]
+mod stats;
+mod threads;
+mod timers;
+#[cfg(feature = "async")]
+mod wait_async;
+mod waiter;
+#[cfg(any(unix, windows))]
+mod wake_pipe;
+mod waker;
+
+pub use array::ArrayEvents;
+pub use backend::Backend;
+pub use builder::PollerBuilder;
+pub use cancel::CancelToken;
+pub use capability::Capability;
+pub use clock::Clock;
+pub use custom::PollerBackend;
+pub use error::PollError;
+pub use hooks::Hooks;
+pub use interest::Interest;
+pub use keys::KeyAllocator;
+#[cfg(all(
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "redox",
+        target_os = "illumos",
+        target_os = "solaris",
+        target_vendor = "apple",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+    ),
+    not(polling_test_poll_backend),
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "redox",
+        target_os = "illumos",
+        target_os = "solaris",
+        target_vendor = "apple",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+    )))
+)]
+pub use multi::wait_any;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "illumos",
+    target_os = "solaris",
+    windows,
+))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_vendor = "apple",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "illumos",
+        target_os = "solaris",
+        windows,
+    )))
+)]
+pub use network::NetworkChanges;
+#[cfg(any(unix, windows))]
+pub use once::poll_once;
+pub use parker::{Parker, Unparker};
+#[cfg(any(target_os = "linux", target_os = "android", target_vendor = "apple"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(target_os = "linux", target_os = "android", target_vendor = "apple")))
+)]
+pub use pressure::MemoryPressure;
+pub use registration::Registration;
+pub use registry::RegisteredSource;
+#[cfg(any(unix, windows))]
+pub use shutdown::ShutdownSignal;
+#[cfg(feature = "stall-diagnostics")]
+pub use stall::Stall;
+#[cfg(feature = "metrics")]
+pub use stats::Stats;
+pub use threads::LoopThreads;
+pub use waiter::{Waiter, WaiterNotifier};
+#[cfg(any(unix, windows))]
+pub use wake_pipe::WakePipe;
+pub use waker::VirtualSource;
+
+/// Default key associated with notifications.
 const NOTIFY_KEY: usize = usize::MAX;
 
+/// The longest time a single wait on the backend blocks for.
+///
+/// Some backends take the timeout as an `int` of milliseconds, which covers about 24 days, and
+/// others as a `u32`. Longer waits are split up, so that they neither end early nor never.
+#[cfg(not(polling_test_max_wait))]
+const MAX_WAIT: Duration = Duration::from_millis(i32::MAX as u64);
+
+/// Split waits much sooner, so that tests can check that they still last as long as requested.
+#[cfg(polling_test_max_wait)]
+const MAX_WAIT: Duration = Duration::from_millis(50);
+
 /// Indicates that a file descriptor or socket can read or write without blocking.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Event {
@@ -133,12 +348,21 @@ pub struct Event {
     pub readable: bool,
     /// Can it do a write operation without blocking?
     pub writable: bool,
+    /// Minimum number of bytes before read readiness is reported.
+    read_low_watermark: Option<NonZeroUsize>,
+    /// Minimum number of bytes of free send buffer space before write readiness is reported.
+    write_low_watermark: Option<NonZeroUsize>,
+    /// Whether the deadline of the source passed before it was ready.
+    timeout: bool,
+    /// Whether the source is deleted from the poller once a hangup is delivered for it.
+    delete_on_hangup: bool,
     /// System-specific event data.
     extra: sys::EventExtra,
 }
 
 /// The mode in which the poller waits for I/O events.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 #[non_exhaustive]
 pub enum PollMode {
     /// Poll in oneshot mode.
@@ -147,6 +371,9 @@ pub enum PollMode {
     /// Once an event has been delivered, interest in the event needs to be re-enabled
     /// by calling `Poller::modify` or `Poller::add`.
     ///
+    /// If the interest never changes, use [`PollMode::Level`] instead, which keeps the
+    /// registration armed without calling `Poller::modify` after every event.
+    ///
     /// This is the default mode.
     Oneshot,
 
@@ -155,6 +382,11 @@ pub enum PollMode {
     /// Once an event has been delivered, polling will continue to deliver that event
     /// until interest in the event is disabled by calling `Poller::modify` or `Poller::delete`.
     ///
+    /// This is the same as a oneshot registration that is re-armed with its last interest every
+    /// time an event is delivered. Event ports have no native support for this mode, so the
+    /// poller associates the source again after each of its events, without a call to
+    /// `Poller::modify`.
+    ///
     /// Not all operating system support this mode. Trying to register a file descriptor with
     /// this mode in an unsupported operating system will raise an error. You can check if
     /// the operating system supports this mode by calling `Poller::supports_level`.
@@ -188,6 +420,10 @@ impl Event {
             key,
             readable,
             writable,
+            read_low_watermark: None,
+            write_low_watermark: None,
+            timeout: false,
+            delete_on_hangup: false,
             extra: sys::EventExtra::empty(),
         }
     }
@@ -224,6 +460,111 @@ impl Event {
         Event::new(key, false, false)
     }
 
+    /// The event of a source whose deadline passed before it was ready.
+    #[inline]
+    pub(crate) const fn timeout(key: usize) -> Event {
+        Event {
+            timeout: true,
+            ..Event::none(key)
+        }
+    }
+
+    /// Convert the key of this event into a domain type.
+    ///
+    /// Keys are plain `usize`s in the poller, but a program can give them meaning with a type
+    /// that converts from and into `usize`, such as a small enum, and match on that when
+    /// dispatching events. Returns `None` if the key doesn't convert, for example because it
+    /// belongs to a source that was registered by other code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::Event;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum Key {
+    ///     Listener,
+    ///     Client(u16),
+    /// }
+    ///
+    /// impl From<Key> for usize {
+    ///     fn from(key: Key) -> usize {
+    ///         match key {
+    ///             Key::Listener => 0,
+    ///             Key::Client(id) => 1 + id as usize,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// impl TryFrom<usize> for Key {
+    ///     type Error = ();
+    ///
+    ///     fn try_from(key: usize) -> Result<Key, ()> {
+    ///         match key {
+    ///             0 => Ok(Key::Listener),
+    ///             key => u16::try_from(key - 1).map(Key::Client).map_err(drop),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let event = Event::readable(Key::Client(3).into());
+    /// assert_eq!(event.key_as::<Key>(), Some(Key::Client(3)));
+    /// ```
+    #[inline]
+    pub fn key_as<K: TryFrom<usize>>(&self) -> Option<K> {
+        K::try_from(self.key).ok()
+    }
+
+    /// Create a new event with interest in a set of events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Interest};
+    ///
+    /// let event = Event::from_interest(7, Interest::READABLE | Interest::PRIORITY);
+    /// assert_eq!(event.with_no_extra(), Event::readable(7));
+    /// ```
+    #[inline]
+    pub fn from_interest(key: usize, interest: Interest) -> Event {
+        let mut event = Event::none(key);
+        event.set_interest(interest);
+        event
+    }
+
+    /// Set the interest of this event.
+    ///
+    /// This replaces `readable`, `writable` and the extra flags.
+    #[inline]
+    pub fn set_interest(&mut self, interest: Interest) {
+        self.readable = interest.is_readable();
+        self.writable = interest.is_writable();
+        self.extra = sys::EventExtra::empty();
+        self.extra.set_pri(interest.contains(Interest::PRIORITY));
+        self.extra.set_hup(interest.contains(Interest::HANGUP));
+        self.extra.set_err(interest.contains(Interest::ERROR));
+        self.extra
+            .set_exclusive(interest.contains(Interest::EXCLUSIVE));
+        self.extra.set_wakeup(interest.contains(Interest::WAKEUP));
+    }
+
+    /// Get the set of events of this event.
+    ///
+    /// For delivered events, this tells which events occurred. Flags that are not supported by the
+    /// current platform are never set.
+    #[inline]
+    pub fn interest(&self) -> Interest {
+        let mut interest = Interest::NONE;
+        interest.set(Interest::READABLE, self.readable);
+        interest.set(Interest::WRITABLE, self.writable);
+        interest.set(Interest::PRIORITY, self.extra.is_pri());
+        interest.set(Interest::HANGUP, self.extra.is_hup());
+        interest.set(Interest::ERROR, self.extra.is_err().unwrap_or(false));
+        interest.set(Interest::EXCLUSIVE, self.extra.is_exclusive());
+        interest.set(Interest::WAKEUP, self.extra.is_wakeup());
+        interest
+    }
+
     /// Add interruption events to this interest.
     ///
     /// This usually indicates that the file descriptor or socket has been closed. It corresponds
@@ -232,11 +573,19 @@ impl Event {
     /// Interruption events are only supported on the following platforms:
     ///
     /// - `epoll`
+    /// - `kqueue`, for the end of the stream (`EV_EOF`)
     /// - `poll`
     /// - IOCP
     /// - Event Ports
     ///
     /// On other platforms, this function is a no-op.
+    ///
+    /// An interest in interruptions alone, with neither [`readable`][`Event::readable`] nor
+    /// [`writable`][`Event::writable`] set, watches a source for hangups without reporting it
+    /// when data arrives, for example to notice that an idle connection was closed. With
+    /// `kqueue`, this uses the read filter with the largest low watermark, so on some
+    /// platforms the event is also delivered once the receive buffer is full. With `poll` and
+    /// event ports, only a full hangup is reported, not a shutdown of the peer's write half.
     #[inline]
     pub fn set_interrupt(&mut self, active: bool) {
         self.extra.set_hup(active);
@@ -250,6 +599,7 @@ impl Event {
     /// Interruption events are only supported on the following platforms:
     ///
     /// - `epoll`
+    /// - `kqueue`, for the end of the stream (`EV_EOF`)
     /// - `poll`
     /// - IOCP
     /// - Event Ports
@@ -274,6 +624,9 @@ impl Event {
     /// - Event Ports
     ///
     /// On other platforms, this function is a no-op.
+    ///
+    /// Priority interest works without readability, for files that only signal `POLLPRI`, such as
+    /// the `value` files of GPIO pins in sysfs.
     #[inline]
     pub fn set_priority(&mut self, active: bool) {
         self.extra.set_pri(active);
@@ -306,6 +659,7 @@ impl Event {
     /// Interruption events are only supported on the following platforms:
     ///
     /// - `epoll`
+    /// - `kqueue`, for the end of the stream (`EV_EOF`)
     /// - `poll`
     /// - IOCP
     /// - Event Ports
@@ -316,6 +670,15 @@ impl Event {
         self.extra.is_hup()
     }
 
+    /// Tell if this event reports that the [deadline][`Poller::set_deadline()`] of the source
+    /// passed before it was ready.
+    ///
+    /// Such an event is neither readable nor writable.
+    #[inline]
+    pub fn is_timeout(&self) -> bool {
+        self.timeout
+    }
+
     /// Tell if this event is the result of a priority notification.
     ///
     /// This indicates that there is urgent data to read. It corresponds to the `EPOLLPRI` and
@@ -414,9 +777,144 @@ impl Event {
         self.extra.is_err()
     }
 
+    /// Only report read readiness once at least `bytes` bytes can be read.
+    ///
+    /// Protocol parsers that need a fixed-size header before they can make progress can use
+    /// this to avoid wakeups that only yield short reads. Passing `None` reports readiness as
+    /// soon as any data is available, which is the default.
+    ///
+    /// This uses `NOTE_LOWAT` on `kqueue`, so it applies to the registration. On other Unix
+    /// platforms this sets the `SO_RCVLOWAT` option of the source when it is added or modified,
+    /// so the source must be a socket and the option stays set on the socket when it is deleted.
+    /// On Windows, registering an event with a low watermark returns an error.
+    ///
+    /// The low watermark is extra information that is removed by
+    /// [`clear_extra()`][Event::clear_extra].
+    #[inline]
+    pub fn set_read_low_watermark(&mut self, bytes: Option<NonZeroUsize>) {
+        self.read_low_watermark = bytes;
+    }
+
+    /// Only report read readiness once at least `bytes` bytes can be read.
+    ///
+    /// See [`set_read_low_watermark()`][Event::set_read_low_watermark] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::Event;
+    /// use std::num::NonZeroUsize;
+    ///
+    /// let header = NonZeroUsize::new(16).unwrap();
+    /// let event = Event::readable(7).with_read_low_watermark(header);
+    /// assert_eq!(event.read_low_watermark(), Some(header));
+    /// ```
+    #[inline]
+    pub fn with_read_low_watermark(mut self, bytes: NonZeroUsize) -> Self {
+        self.set_read_low_watermark(Some(bytes));
+        self
+    }
+
+    /// The minimum number of bytes before read readiness is reported, if one was set.
+    #[inline]
+    pub fn read_low_watermark(&self) -> Option<NonZeroUsize> {
+        self.read_low_watermark
+    }
+
+    /// Only report write readiness once at least `bytes` bytes of send buffer space are free.
+    ///
+    /// Streaming servers that write in large fixed chunks can use this to avoid wakeups when
+    /// only a few bytes have drained. Passing `None` reports readiness with the default
+    /// threshold of the operating system.
+    ///
+    /// This uses `NOTE_LOWAT` on `kqueue`, so it applies to the registration. On other Unix
+    /// platforms this sets the `SO_SNDLOWAT` option of the source when it is added or modified,
+    /// like [`set_read_low_watermark()`][Event::set_read_low_watermark]. Linux, Android and
+    /// Windows don't support changing the send low watermark, so registering an event with one
+    /// returns an error there.
+    ///
+    /// The low watermark is extra information that is removed by
+    /// [`clear_extra()`][Event::clear_extra].
+    #[inline]
+    pub fn set_write_low_watermark(&mut self, bytes: Option<NonZeroUsize>) {
+        self.write_low_watermark = bytes;
+    }
+
+    /// Only report write readiness once at least `bytes` bytes of send buffer space are free.
+    ///
+    /// See [`set_write_low_watermark()`][Event::set_write_low_watermark] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::Event;
+    /// use std::num::NonZeroUsize;
+    ///
+    /// let chunk = NonZeroUsize::new(64 * 1024).unwrap();
+    /// let event = Event::writable(7).with_write_low_watermark(chunk);
+    /// assert_eq!(event.write_low_watermark(), Some(chunk));
+    /// ```
+    #[inline]
+    pub fn with_write_low_watermark(mut self, bytes: NonZeroUsize) -> Self {
+        self.set_write_low_watermark(Some(bytes));
+        self
+    }
+
+    /// The minimum number of bytes of free send buffer space before write readiness is
+    /// reported, if one was set.
+    #[inline]
+    pub fn write_low_watermark(&self) -> Option<NonZeroUsize> {
+        self.write_low_watermark
+    }
+
+    /// Delete the source from the poller once a hangup is delivered for it.
+    ///
+    /// A peer that closed the connection keeps a level-triggered source ready until it is
+    /// deleted, so an event loop that defers its cleanup would spin on it. With this set, the
+    /// poller deletes the source in the same wait that delivers the event, so the hangup is
+    /// reported once, and the source must not be deleted again. It is removed as if by
+    /// [`Poller::delete()`], and stays open.
+    ///
+    /// A hangup is an event for which [`is_interrupt()`][Event::is_interrupt] returns `true`.
+    /// To also catch the peer shutting down its write half, combine this with
+    /// [`set_interrupt()`][Event::set_interrupt] where it is supported.
+    ///
+    /// This is extra information that is removed by [`clear_extra()`][Event::clear_extra].
+    #[inline]
+    pub fn set_delete_on_hangup(&mut self, active: bool) {
+        self.delete_on_hangup = active;
+    }
+
+    /// Delete the source from the poller once a hangup is delivered for it.
+    ///
+    /// See [`set_delete_on_hangup()`][Event::set_delete_on_hangup] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::Event;
+    ///
+    /// let event = Event::readable(7).with_interrupt().with_delete_on_hangup();
+    /// assert!(event.deletes_on_hangup());
+    /// ```
+    #[inline]
+    pub fn with_delete_on_hangup(mut self) -> Self {
+        self.set_delete_on_hangup(true);
+        self
+    }
+
+    /// Whether the source is deleted from the poller once a hangup is delivered for it.
+    #[inline]
+    pub fn deletes_on_hangup(&self) -> bool {
+        self.delete_on_hangup
+    }
+
     /// Remove any extra information from this event.
     #[inline]
     pub fn clear_extra(&mut self) {
+        self.read_low_watermark = None;
+        self.write_low_watermark = None;
+        self.delete_on_hangup = false;
         self.extra = sys::EventExtra::empty();
     }
 
@@ -431,10 +929,105 @@ impl Event {
 }
 
 /// Waits for I/O events.
+///
+/// # Sharing between threads
+///
+/// `Poller` is [`Send`] and [`Sync`], and all of its methods take `&self`, so a single poller
+/// is shared between threads with an [`Arc`] rather than cloned. Sources can be added, modified
+/// and deleted from one thread while another thread is blocked in [`wait()`][`Poller::wait()`],
+/// and the waiting thread sees the changes.
+///
+/// ```
+/// use polling::{Event, Events, Poller};
+/// use std::net::UdpSocket;
+/// use std::sync::Arc;
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// let poller = Arc::new(Poller::new()?);
+///
+/// let waiter = thread::spawn({
+///     let poller = poller.clone();
+///     move || {
+///         let mut events = Events::new();
+///         poller.wait(&mut events, Some(Duration::from_secs(5)))?;
+///         std::io::Result::Ok(events.iter().next().map(|ev| ev.key))
+///     }
+/// });
+///
+/// // Register a source while the other thread is waiting.
+/// let socket = UdpSocket::bind("127.0.0.1:0")?;
+/// unsafe { poller.add(&socket, Event::writable(7))? };
+///
+/// assert_eq!(waiter.join().unwrap()?, Some(7));
+/// poller.delete(&socket)?;
+/// # std::io::Result::Ok(())
+/// ```
+///
+/// # Raw handle
+///
+/// The underlying epoll, kqueue or event port file descriptor is available through [`AsFd`] and
+/// [`AsRawFd`], and the I/O completion port through [`AsHandle`] and [`AsRawHandle`] on Windows.
+/// This can be used to apply backend-specific operations that this crate doesn't cover. The
+/// poller keeps its own state about registered sources, so sources added directly through the
+/// handle must use keys that don't collide with other sources or with
+/// [`notify_key()`][`Poller::notify_key()`], and must be removed the same way. The handle must not
+/// be closed.
+///
+/// With the poll backend, there is no such handle.
+///
+/// [`AsFd`]: https://doc.rust-lang.org/std/os/fd/trait.AsFd.html
+/// [`AsRawFd`]: https://doc.rust-lang.org/std/os/fd/trait.AsRawFd.html
+/// [`AsHandle`]: https://doc.rust-lang.org/std/os/windows/io/trait.AsHandle.html
+/// [`AsRawHandle`]: https://doc.rust-lang.org/std/os/windows/io/trait.AsRawHandle.html
 pub struct Poller {
-    poller: sys::Poller,
+    poller: custom::Driver,
     lock: Mutex<()>,
     notified: AtomicBool,
+    paused: AtomicBool,
+    closed: AtomicBool,
+    sealed: AtomicBool,
+    muted_groups: Mutex<HashSet<usize>>,
+    notify_key: usize,
+    capacity: NonZeroUsize,
+    mode: PollMode,
+    concurrent_waits: bool,
+    busy_poll: Option<Duration>,
+    adaptive_spin: Option<spin::AdaptiveSpin>,
+    #[cfg(unix)]
+    cloexec: bool,
+    initial_readiness: bool,
+    sticky_notify: bool,
+    notifications: bool,
+    #[cfg(unix)]
+    clock: Clock,
+    timer_slack: Option<Duration>,
+    drain: bool,
+    #[cfg(unix)]
+    batch_changes: bool,
+    retry_spurious: bool,
+    #[cfg(unix)]
+    strict_errors: bool,
+    misuse_checks: bool,
+    hooks: hooks::HookSet,
+    scratch: Mutex<Option<Events>>,
+    deferred: Mutex<Vec<Event>>,
+    registry: registry::Registry,
+    splits: split::Splits,
+    woken: waker::Woken,
+    timers: timers::Timers,
+    deadlines: timers::Timers,
+    deadline_sources: Mutex<HashMap<usize, RawSource>>,
+    priorities: Mutex<HashMap<usize, i32>>,
+    rate_limit: Option<limit::RateLimit>,
+    tick: Option<(usize, Duration)>,
+    ticks: timers::Timers,
+    label: Option<String>,
+    #[cfg(feature = "stall-diagnostics")]
+    stalls: stall::Monitor,
+    waiters: waiter::Waiters,
+    #[cfg(feature = "metrics")]
+    counters: stats::Counters,
 }
 
 impl Poller {
@@ -449,374 +1042,3813 @@ impl Poller {
     /// # std::io::Result::Ok(())
     /// ```
     pub fn new() -> io::Result<Poller> {
-        Ok(Poller {
-            poller: sys::Poller::new()?,
-            lock: Mutex::new(()),
-            notified: AtomicBool::new(false),
-        })
-    }
-
-    /// Tell whether or not this `Poller` supports level-triggered polling.
-    pub fn supports_level(&self) -> bool {
-        self.poller.supports_level()
-    }
-
-    /// Tell whether or not this `Poller` supports edge-triggered polling.
-    pub fn supports_edge(&self) -> bool {
-        self.poller.supports_edge()
+        PollerBuilder::new().build()
     }
 
-    /// Adds a file descriptor or socket to the poller.
+    /// Creates a builder for configuring a new poller.
     ///
-    /// A file descriptor or socket is considered readable or writable when a read or write
-    /// operation on it would not block. This doesn't mean the read or write operation will
-    /// succeed, it only means the operation will return immediately.
+    /// # Examples
     ///
-    /// If interest is set in both readability and writability, the two kinds of events might be
-    /// delivered either separately or together.
+    /// ```
+    /// use polling::Poller;
     ///
-    /// For example, interest in `Event { key: 7, readable: true, writable: true }` might result in
-    /// a single [`Event`] of the same form, or in two separate [`Event`]s:
-    /// - `Event { key: 7, readable: true, writable: false }`
-    /// - `Event { key: 7, readable: false, writable: true }`
+    /// let poller = Poller::builder().notify_key(0).build()?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn builder() -> PollerBuilder {
+        PollerBuilder::new()
+    }
+
+    /// Creates a new poller that reserves `notify_key` for notifications.
     ///
-    /// Note that interest in I/O events needs to be re-enabled using
-    /// [`modify()`][`Poller::modify()`] again after an event is delivered if we're interested in
-    /// the next event of the same kind.
+    /// Internally, the poller registers its own notification mechanism under a key that can't be
+    /// used for other sources. By default this is `usize::MAX`, but applications that already
+    /// use that key can pick a different one here.
     ///
-    /// It is possible to register interest in the same file descriptor or socket using multiple
-    /// separate [`Poller`] instances. When the event is delivered, one or more [`Poller`]s are
-    /// notified with that event. The exact number of [`Poller`]s notified depends on the
-    /// underlying platform. When registering multiple sources into one event, the user should
-    /// be careful to accommodate for events lost to other pollers.
+    /// # Examples
     ///
-    /// One may also register one source into other, non-`polling` event loops, like GLib's
-    /// context. While the plumbing will vary from platform to platform, in general the [`Poller`]
-    /// will act as if the source was registered with another [`Poller`], with the same caveats
-    /// as above.
+    /// ```
+    /// use polling::Poller;
     ///
-    /// # Safety
+    /// let poller = Poller::with_notify_key(0)?;
+    /// assert_eq!(poller.notify_key(), 0);
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn with_notify_key(notify_key: usize) -> io::Result<Poller> {
+        PollerBuilder::new().notify_key(notify_key).build()
+    }
+
+    /// Creates a new poller on top of `poller`, with the options in `builder`.
+    pub(crate) fn from_sys(builder: PollerBuilder, poller: custom::Driver) -> Poller {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            backend = %poller.backend(),
+            label = builder.label.as_deref(),
+            notify_key = builder.notify_key,
+            mode = ?builder.mode,
+            "Poller::new",
+        );
+
+        let ticks = timers::Timers::new(builder.clock);
+        if let Some((key, interval)) = builder.tick {
+            if let Some(first) = Instant::now().checked_add(interval) {
+                ticks.insert(key, first);
+            }
+        }
+
+        Poller {
+            poller,
+            lock: Mutex::new(()),
+            notified: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            closed: AtomicBool::new(false),
+            sealed: AtomicBool::new(false),
+            muted_groups: Mutex::new(HashSet::new()),
+            notify_key: builder.notify_key,
+            capacity: builder.capacity,
+            mode: builder.mode,
+            concurrent_waits: builder.concurrent_waits,
+            busy_poll: builder.busy_poll,
+            adaptive_spin: builder.adaptive_spin.map(spin::AdaptiveSpin::new),
+            #[cfg(unix)]
+            cloexec: builder.cloexec,
+            initial_readiness: builder.initial_readiness,
+            sticky_notify: builder.sticky_notify,
+            notifications: builder.notifications,
+            #[cfg(unix)]
+            clock: builder.clock,
+            timer_slack: builder.timer_slack,
+            drain: builder.drain,
+            #[cfg(unix)]
+            batch_changes: builder.batch_changes,
+            retry_spurious: builder.retry_spurious,
+            #[cfg(unix)]
+            strict_errors: builder.strict_errors,
+            misuse_checks: builder.misuse_checks,
+            hooks: builder.hooks,
+            scratch: Mutex::new(None),
+            deferred: Mutex::new(Vec::new()),
+            registry: registry::Registry::new(builder.notify_key, builder.misuse_checks),
+            splits: split::Splits::default(),
+            woken: waker::Woken::default(),
+            timers: timers::Timers::new(builder.clock),
+            deadlines: timers::Timers::new(builder.clock),
+            deadline_sources: Mutex::new(HashMap::new()),
+            priorities: Mutex::new(HashMap::new()),
+            rate_limit: builder.rate_limit.map(limit::RateLimit::new),
+            tick: builder.tick,
+            ticks,
+            label: builder.label,
+            #[cfg(feature = "stall-diagnostics")]
+            stalls: stall::Monitor::new(builder.stall_threshold),
+            waiters: waiter::Waiters::default(),
+            #[cfg(feature = "metrics")]
+            counters: stats::Counters::default(),
+        }
+    }
+
+    /// Returns the key that is reserved for notifications.
     ///
-    /// The source must be [`delete()`]d from this `Poller` before it is dropped.
+    /// Sources can't be registered under this key. The backends filter out the events of the
+    /// notification mechanism, so this key never shows up in [`Events`];
+    /// [`wait_result()`][`Poller::wait_result()`] reports whether a wait was notified instead.
     ///
-    /// [`delete()`]: Poller::delete
+    /// # Examples
     ///
-    /// # Errors
+    /// ```
+    /// use polling::Poller;
     ///
-    /// This method returns an error in the following situations:
-    ///
-    /// * If `key` equals `usize::MAX` because that key is reserved for internal use.
-    /// * If an error is returned by the syscall.
+    /// let poller = Poller::new()?;
+    /// assert_eq!(poller.notify_key(), usize::MAX);
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn notify_key(&self) -> usize {
+        self.notify_key
+    }
+
+    /// Returns the label of this poller, if it has one.
     ///
-    /// # Examples
+    /// See [`PollerBuilder::label()`] for more information.
+    #[inline]
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Returns the backend this poller is built on.
     ///
-    /// Set interest in all events:
+    /// This can be used to log the backend in use, or to account for backend-specific behavior,
+    /// like the granularity of timeouts.
     ///
-    /// ```no_run
-    /// use polling::{Event, Poller};
+    /// # Examples
     ///
-    /// let source = std::net::TcpListener::bind("127.0.0.1:0")?;
-    /// source.set_nonblocking(true)?;
-    /// let key = 7;
+    /// ```
+    /// use polling::{Backend, Poller};
     ///
     /// let poller = Poller::new()?;
-    /// unsafe {
-    ///     poller.add(&source, Event::all(key))?;
-    /// }
-    /// poller.delete(&source)?;
+    /// println!("polling with {}", poller.backend());
+    /// assert_eq!(poller.backend(), Backend::current());
     /// # std::io::Result::Ok(())
     /// ```
-    pub unsafe fn add(&self, source: impl AsRawSource, interest: Event) -> io::Result<()> {
-        self.add_with_mode(source, interest, PollMode::Oneshot)
+    #[inline]
+    pub fn backend(&self) -> Backend {
+        self.poller.backend()
     }
 
-    /// Adds a file descriptor or socket to the poller in the specified mode.
-    ///
-    /// This is identical to the `add()` function, but allows specifying the
-    /// polling mode to use for this socket.
-    ///
-    /// # Safety
-    ///
-    /// The source must be [`delete()`]d from this `Poller` before it is dropped.
+    /// Returns whether several threads can wait on this poller at the same time.
     ///
-    /// [`delete()`]: Poller::delete
+    /// See [`PollerBuilder::concurrent_waits()`] for more information.
+    #[inline]
+    pub fn concurrent_waits(&self) -> bool {
+        self.concurrent_waits
+    }
+
+    /// Returns the default polling mode of this poller.
     ///
-    /// # Errors
+    /// This is the mode used by [`add()`][`Poller::add()`], [`modify()`][`Poller::modify()`] and
+    /// [`register()`][`Poller::register()`]. It is [`PollMode::Oneshot`] unless configured
+    /// otherwise with [`PollerBuilder::mode()`].
+    #[inline]
+    pub fn mode(&self) -> PollMode {
+        self.mode
+    }
+
+    /// Returns how long the next call to [`wait()`][`Poller::wait()`] busy-polls before blocking.
     ///
-    /// If the operating system does not support the specified mode, this function
-    /// will return an error.
-    pub unsafe fn add_with_mode(
-        &self,
-        source: impl AsRawSource,
-        interest: Event,
-        mode: PollMode,
-    ) -> io::Result<()> {
-        if interest.key == NOTIFY_KEY {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "the key is not allowed to be `usize::MAX`",
-            ));
+    /// This is the duration set with [`PollerBuilder::busy_poll()`], or the current budget set
+    /// up with [`PollerBuilder::adaptive_spin()`]. Returns `None` if the poller doesn't
+    /// busy-poll.
+    #[inline]
+    pub fn spin_budget(&self) -> Option<Duration> {
+        match &self.adaptive_spin {
+            Some(spin) => Some(spin.budget()),
+            None => self.busy_poll,
         }
-        self.poller.add(source.raw(), interest, mode)
     }
 
-    /// Modifies the interest in a file descriptor or socket.
+    /// Creates a new container for events, with the capacity configured for this poller.
     ///
-    /// This method has the same behavior as [`add()`][`Poller::add()`] except it modifies the
-    /// interest of a previously added file descriptor or socket.
+    /// The capacity is the default capacity of [`Events::new()`] unless configured otherwise
+    /// with [`PollerBuilder::capacity()`]. Like with [`Events::new()`], the container starts out
+    /// smaller and grows up to its capacity on demand.
     ///
-    /// To use this method with a file descriptor or socket, you must first add it using
-    /// [`add()`][`Poller::add()`].
+    /// # Examples
     ///
-    /// Note that interest in I/O events needs to be re-enabled using
-    /// [`modify()`][`Poller::modify()`] again after an event is delivered if we're interested in
-    /// the next event of the same kind.
+    /// ```
+    /// use polling::Poller;
+    /// use std::time::Duration;
     ///
-    /// # Errors
+    /// let poller = Poller::new()?;
+    /// let mut events = poller.new_events();
+    /// poller.wait(&mut events, Some(Duration::from_secs(0)))?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn new_events(&self) -> Events {
+        Events::growing(self.capacity)
+    }
+
+    /// Returns the file descriptors or sockets that are currently registered in this poller.
     ///
-    /// This method returns an error in the following situations:
+    /// The poller keeps track of every source added with [`add()`][`Poller::add()`] or
+    /// [`register()`][`Poller::register()`] until it is [`delete()`][`Poller::delete()`]d, along
+    /// with its key, interest and mode. This can be used to detect leaked registrations or to
+    /// find out what is still being watched during shutdown.
     ///
-    /// * If `key` equals `usize::MAX` because that key is reserved for internal use.
-    /// * If an error is returned by the syscall.
+    /// Sources that are closed without being deleted are still listed, since the operating system
+    /// doesn't report this. Platform-specific sources, like kqueue filters or IOCP waitables, are
+    /// not listed. The order of the returned registrations is unspecified.
     ///
     /// # Examples
     ///
-    /// To enable interest in all events:
-    ///
-    /// ```no_run
-    /// # use polling::{Event, Poller};
-    /// # let source = std::net::TcpListener::bind("127.0.0.1:0")?;
-    /// # let key = 7;
-    /// # let poller = Poller::new()?;
-    /// # unsafe { poller.add(&source, Event::none(key))?; }
-    /// poller.modify(&source, Event::all(key))?;
-    /// # std::io::Result::Ok(())
     /// ```
+    /// use polling::{Event, Poller};
+    /// use std::net::TcpListener;
     ///
-    /// To enable interest in readable events and disable interest in writable events:
+    /// let socket = TcpListener::bind("127.0.0.1:0")?;
+    /// socket.set_nonblocking(true)?;
     ///
-    /// ```no_run
-    /// # use polling::{Event, Poller};
-    /// # let source = std::net::TcpListener::bind("127.0.0.1:0")?;
-    /// # let key = 7;
-    /// # let poller = Poller::new()?;
-    /// # unsafe { poller.add(&source, Event::none(key))?; }
-    /// poller.modify(&source, Event::readable(key))?;
-    /// # poller.delete(&source)?;
+    /// let poller = Poller::new()?;
+    /// unsafe { poller.add(&socket, Event::readable(7))?; }
+    ///
+    /// let registrations = poller.registrations();
+    /// assert_eq!(registrations.len(), 1);
+    /// assert_eq!(registrations[0].interest().key, 7);
+    ///
+    /// poller.delete(&socket)?;
+    /// assert!(poller.registrations().is_empty());
     /// # std::io::Result::Ok(())
     /// ```
+    pub fn registrations(&self) -> Vec<RegisteredSource> {
+        self.registry.snapshot()
+    }
+
+    /// Returns the registration of a file descriptor or socket, if it is registered in this
+    /// poller.
     ///
-    /// To disable interest in readable events and enable interest in writable events:
+    /// See [`registrations()`][`Poller::registrations()`] for more information.
+    pub fn registered(&self, source: impl AsRawSource) -> Option<RegisteredSource> {
+        self.registry.get(source.raw())
+    }
+
+    /// Returns the number of file descriptors or sockets registered in this poller.
+    ///
+    /// This is cheap to call and doesn't copy the registrations, so it can be used to pick the
+    /// least loaded of several pollers. A source registered with split keys counts once, and
+    /// the internal notification mechanism is not counted. See
+    /// [`registrations()`][`Poller::registrations()`] for more information.
+    ///
+    /// # Examples
     ///
-    /// ```no_run
-    /// # use polling::{Event, Poller};
-    /// # let poller = Poller::new()?;
-    /// # let key = 7;
-    /// # let source = std::net::TcpListener::bind("127.0.0.1:0")?;
-    /// # unsafe { poller.add(&source, Event::none(key))? };
-    /// poller.modify(&source, Event::writable(key))?;
-    /// # poller.delete(&source)?;
-    /// # std::io::Result::Ok(())
     /// ```
+    /// use polling::{Event, Poller};
+    /// use std::net::TcpListener;
     ///
-    /// To disable interest in all events:
+    /// let socket = TcpListener::bind("127.0.0.1:0")?;
+    /// let pollers = [Poller::new()?, Poller::new()?];
+    /// unsafe { pollers[0].add(&socket, Event::readable(7))? };
     ///
-    /// ```no_run
-    /// # use polling::{Event, Poller};
-    /// # let source = std::net::TcpListener::bind("127.0.0.1:0")?;
-    /// # let key = 7;
-    /// # let poller = Poller::new()?;
-    /// # unsafe { poller.add(&source, Event::none(key))?; }
-    /// poller.modify(&source, Event::none(key))?;
-    /// # poller.delete(&source)?;
+    /// // Register the next source in the poller with the fewest sources.
+    /// let least_loaded = pollers.iter().min_by_key(|p| p.registered_len()).unwrap();
+    /// assert_eq!(least_loaded.registered_len(), 0);
+    ///
+    /// pollers[0].delete(&socket)?;
     /// # std::io::Result::Ok(())
     /// ```
-    pub fn modify(&self, source: impl AsSource, interest: Event) -> io::Result<()> {
-        self.modify_with_mode(source, interest, PollMode::Oneshot)
+    pub fn registered_len(&self) -> usize {
+        self.registry.len()
     }
 
-    /// Modifies interest in a file descriptor or socket to the poller, but with the specified
-    /// mode.
+    /// Removes the registrations of sources that were closed without being deleted.
     ///
-    /// This is identical to the `modify()` function, but allows specifying the polling mode
-    /// to use for this socket.
+    /// Closing a source while it is registered is a bug, and the backends react to it
+    /// differently. `kqueue`, event ports and IOCP drop the registration silently, while `epoll`
+    /// keeps it alive and delivers events for as long as another file descriptor refers to the
+    /// same file description. This finds registered sources that are no longer open, forgets
+    /// about them, and returns their registrations, so that long-running programs don't
+    /// accumulate them.
     ///
-    /// # Performance Notes
+    /// A closed source whose file descriptor was reused for a new file looks open, so it is not
+    /// removed. [`modify()`][`Poller::modify()`] forgets about it when the operating system
+    /// reports that the new file is not registered.
     ///
-    /// This function can be used to change a source from one polling mode to another. However,
-    /// on some platforms, this switch can cause delays in the delivery of events.
+    /// # Examples
     ///
-    /// # Errors
+    /// ```
+    /// use polling::{Event, Poller};
+    /// use std::net::TcpListener;
     ///
-    /// If the operating system does not support the specified mode, this function will return
-    /// an error.
-    pub fn modify_with_mode(
-        &self,
-        source: impl AsSource,
-        interest: Event,
-        mode: PollMode,
-    ) -> io::Result<()> {
-        if interest.key == NOTIFY_KEY {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "the key is not allowed to be `usize::MAX`",
-            ));
+    /// let socket = TcpListener::bind("127.0.0.1:0")?;
+    /// let poller = Poller::new()?;
+    /// unsafe { poller.add(&socket, Event::readable(7))? };
+    ///
+    /// // Oops, the socket is closed without deleting it.
+    /// drop(socket);
+    ///
+    /// let purged = poller.purge_closed();
+    /// assert_eq!(purged.len(), 1);
+    /// assert_eq!(poller.registered_len(), 0);
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn purge_closed(&self) -> Vec<RegisteredSource> {
+        let mut purged = self.registry.snapshot();
+        purged.retain(|registered| is_closed(registered.source()));
+        for registered in &purged {
+            self.registry.remove(registered.source());
+            self.splits.remove(registered.source());
+            self.poller.forget(registered.source());
+        }
+        if !purged.is_empty() {
+            self.notify_if_drained();
+        }
+
+        #[cfg(feature = "tracing")]
+        if !purged.is_empty() {
+            tracing::debug!(count = purged.len(), "purge_closed: removed closed sources");
         }
-        self.poller.modify(source.source(), interest, mode)
+        purged
     }
 
-    /// Removes a file descriptor or socket from the poller.
+    /// Temporarily disables the delivery of events for all registered sources.
+    ///
+    /// The keys and interests of the sources are kept, and [`resume()`][`Poller::resume()`]
+    /// enables them again. Sources that are added or modified while the poller is paused are
+    /// registered without interest until then. Notifications are still delivered, and so are
+    /// events of sources that are not tracked by the poller, such as `kqueue` filters and IOCP
+    /// waitables.
+    ///
+    /// This is useful while reconfiguring an event loop, before forking, or while the program is
+    /// halted in a debugger.
+    ///
+    /// # Errors
     ///
-    /// Unlike [`add()`][`Poller::add()`], this method only removes the file descriptor or
-    /// socket from the poller without putting it back into blocking mode.
+    /// All sources are disabled even if disabling one of them fails, and the first error is
+    /// returned.
     ///
     /// # Examples
     ///
     /// ```
-    /// use polling::{Event, Poller};
-    /// use std::net::TcpListener;
-    ///
-    /// let socket = TcpListener::bind("127.0.0.1:0")?;
-    /// socket.set_nonblocking(true)?;
-    /// let key = 7;
+    /// use polling::{Event, Events, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::time::Duration;
     ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
     /// let poller = Poller::new()?;
-    /// unsafe { poller.add(&socket, Event::all(key))?; }
+    /// unsafe { poller.add(&socket, Event::writable(7))? };
+    ///
+    /// poller.pause()?;
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, Some(Duration::from_secs(0)))?;
+    /// assert!(events.is_empty());
+    ///
+    /// poller.resume()?;
+    /// poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// assert_eq!(events.iter().next().unwrap().key, 7);
+    ///
     /// poller.delete(&socket)?;
     /// # std::io::Result::Ok(())
     /// ```
-    pub fn delete(&self, source: impl AsSource) -> io::Result<()> {
-        self.poller.delete(source.source())
+    pub fn pause(&self) -> io::Result<()> {
+        self.paused.store(true, Ordering::SeqCst);
+        self.for_each_registered(|source, registered| {
+            self.splits.remove(registered.source());
+            self.poller.modify(
+                source,
+                Event::none(registered.interest().key),
+                registered.mode(),
+            )
+        })
     }
 
-    /// Waits for at least one I/O event and returns the number of new events.
+    /// Enables the delivery of events again after [`pause()`][`Poller::pause()`].
     ///
-    /// New events will be appended to `events`. If necessary, make sure to clear the
-    /// [`Events`][Events::clear()] before calling [`wait()`][`Poller::wait()`]!
+    /// Every registered source gets back the interest that was last set for it. In oneshot
+    /// mode, this also enables interest that was disabled by a delivered event, so a source may
+    /// report an event before it was re-enabled with [`modify()`][`Poller::modify()`].
     ///
-    /// This method will return with no new events if a notification is delivered by the
-    /// [`notify()`] method, or the timeout is reached. Sometimes it may even return with no events
-    /// spuriously.
+    /// # Errors
     ///
-    /// Only one thread can wait on I/O. If another thread is already in [`wait()`], concurrent
-    /// calls to this method will return immediately with no new events.
+    /// All sources are enabled even if enabling one of them fails, and the first error is
+    /// returned.
+    pub fn resume(&self) -> io::Result<()> {
+        self.paused.store(false, Ordering::SeqCst);
+        self.for_each_registered(|source, registered| self.apply(source, registered))
+    }
+
+    /// Returns `true` if the poller is paused.
     ///
-    /// If the operating system is ready to deliver a large number of events at once, this method
-    /// may decide to deliver them in smaller batches.
+    /// See [`pause()`][`Poller::pause()`] for more information.
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Puts a registered source into a group, which can be muted as a whole.
     ///
-    /// [`notify()`]: `Poller::notify()`
-    /// [`wait()`]: `Poller::wait()`
+    /// Every source is in the group `0` when it is added, and stays in its group until it is
+    /// deleted. While the group of a source is [muted][`Poller::mute_group()`], the source is
+    /// registered without interest, like all sources while the poller is
+    /// [paused][`Poller::pause()`]. This makes it possible to wait on a subset of the sources,
+    /// like the control sockets during startup or shutdown, while the others stay registered.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if `source` is not registered in this poller, or if its interest
+    /// can't be updated for the new group.
     ///
     /// # Examples
     ///
     /// ```
     /// use polling::{Event, Events, Poller};
-    /// use std::net::TcpListener;
+    /// use std::net::UdpSocket;
     /// use std::time::Duration;
     ///
-    /// let socket = TcpListener::bind("127.0.0.1:0")?;
-    /// socket.set_nonblocking(true)?;
-    /// let key = 7;
-    ///
+    /// let control = UdpSocket::bind("127.0.0.1:0")?;
+    /// let data = UdpSocket::bind("127.0.0.1:0")?;
     /// let poller = Poller::new()?;
     /// unsafe {
-    ///     poller.add(&socket, Event::all(key))?;
+    ///     poller.add(&control, Event::writable(1))?;
+    ///     poller.add(&data, Event::writable(2))?;
     /// }
     ///
+    /// // Only service the control socket.
+    /// poller.set_group(&data, 1)?;
+    /// poller.mute_group(1)?;
+    ///
     /// let mut events = Events::new();
-    /// let n = poller.wait(&mut events, Some(Duration::from_secs(1)))?;
-    /// poller.delete(&socket)?;
+    /// poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// assert_eq!(events.len(), 1);
+    /// assert_eq!(events.iter().next().unwrap().key, 1);
+    ///
+    /// poller.delete(&control)?;
+    /// poller.delete(&data)?;
     /// # std::io::Result::Ok(())
     /// ```
-    pub fn wait(&self, events: &mut Events, timeout: Option<Duration>) -> io::Result<usize> {
-        self.wait_impl(
-            events,
-            timeout.and_then(|timeout| Instant::now().checked_add(timeout)),
-        )
+    pub fn set_group(&self, source: impl AsSource, group: usize) -> io::Result<()> {
+        let source = source.source();
+        let raw = (&source).raw();
+        self.check_registered(raw)?;
+        let registered = match self.registry.get(raw) {
+            Some(registered) => registered,
+            None => return Err(PollError::NotRegistered.into()),
+        };
+
+        let was_muted = self.is_muted(raw);
+        self.registry.set_group(raw, group);
+        if self.is_muted(raw) == was_muted {
+            return Ok(());
+        }
+        self.apply(source, &registered)
     }
 
-    /// Waits for at least one I/O event and returns the number of new events, with a deadline.
+    /// Disables the delivery of events for the sources in `group`.
     ///
-    /// See [`wait()`][`Poller::wait()`] for more details.
-    pub fn wait_deadline(&self, events: &mut Events, deadline: Instant) -> io::Result<usize> {
-        self.wait_impl(events, Some(deadline))
+    /// The sources stay registered, and [`unmute_group()`][`Poller::unmute_group()`] enables
+    /// them again, like [`pause()`][`Poller::pause()`] and [`resume()`][`Poller::resume()`]
+    /// do for all sources. See [`set_group()`][`Poller::set_group()`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// All sources of the group are disabled even if disabling one of them fails, and the
+    /// first error is returned.
+    pub fn mute_group(&self, group: usize) -> io::Result<()> {
+        if !self.muted_groups().insert(group) || self.is_paused() {
+            return Ok(());
+        }
+        self.for_each_registered(|source, registered| {
+            if self.registry.group(registered.source()) != group {
+                return Ok(());
+            }
+            self.apply(source, registered)
+        })
     }
 
-    fn wait_impl(&self, events: &mut Events, deadline: Option<Instant>) -> io::Result<usize> {
-        #[cfg(feature = "tracing")]
-        let span = tracing::trace_span!("Poller::wait", ?deadline);
-        #[cfg(feature = "tracing")]
-        let _enter = span.enter();
+    /// Enables the delivery of events again for the sources in `group`.
+    ///
+    /// As with [`resume()`][`Poller::resume()`], every source of the group gets back the
+    /// interest that was last set for it, even a oneshot source that already had an event
+    /// delivered. See [`mute_group()`][`Poller::mute_group()`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// All sources of the group are enabled even if enabling one of them fails, and the first
+    /// error is returned.
+    pub fn unmute_group(&self, group: usize) -> io::Result<()> {
+        if !self.muted_groups().remove(&group) || self.is_paused() {
+            return Ok(());
+        }
+        self.for_each_registered(|source, registered| {
+            if self.registry.group(registered.source()) != group {
+                return Ok(());
+            }
+            self.apply(source, registered)
+        })
+    }
 
-        if let Ok(_lock) = self.lock.try_lock() {
-            loop {
-                // Wait for I/O events.
-                if let Err(e) = self.poller.wait_deadline(&mut events.events, deadline) {
-                    // If the wait was interrupted by a signal, clear events and try again.
-                    if e.kind() == io::ErrorKind::Interrupted {
-                        events.clear();
-                        continue;
-                    } else {
-                        return Err(e);
-                    }
-                }
+    /// Returns `true` if `group` is muted.
+    ///
+    /// See [`mute_group()`][`Poller::mute_group()`] for more information.
+    pub fn is_group_muted(&self, group: usize) -> bool {
+        self.muted_groups().contains(&group)
+    }
+
+    /// Lock the muted groups, ignoring poisoning.
+    fn muted_groups(&self) -> MutexGuard<'_, HashSet<usize>> {
+        self.muted_groups.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Sets the interest of a registered source in the backend, unless it is muted.
+    fn apply(&self, source: BorrowedSource<'_>, registered: &RegisteredSource) -> io::Result<()> {
+        let interest = registered.interest();
+        match registered.write_key() {
+            Some(key) => self.modify_split_sys(
+                source,
+                interest,
+                Event { key, ..interest },
+                registered.mode(),
+            ),
+            None => self.poller.modify(
+                source,
+                self.unless_muted((&source).raw(), interest),
+                registered.mode(),
+            ),
+        }
+    }
+
+    /// Replaces the backend with a new one in the child process after `fork()`, and registers
+    /// the sources in it again.
+    #[cfg(unix)]
+    pub(crate) fn reinit(&mut self) -> io::Result<()> {
+        let builder = PollerBuilder {
+            notify_key: self.notify_key,
+            capacity: self.capacity,
+            mode: self.mode,
+            backend: Some(self.backend()),
+            concurrent_waits: self.concurrent_waits,
+            busy_poll: self.busy_poll,
+            adaptive_spin: self.adaptive_spin.as_ref().map(|spin| spin.max),
+            cloexec: self.cloexec,
+            initial_readiness: self.initial_readiness,
+            sticky_notify: self.sticky_notify,
+            notifications: self.notifications,
+            clock: self.clock,
+            timer_slack: self.timer_slack,
+            drain: self.drain,
+            batch_changes: self.batch_changes,
+            retry_spurious: self.retry_spurious,
+            strict_errors: self.strict_errors,
+            misuse_checks: self.misuse_checks,
+            rate_limit: self.rate_limit.as_ref().map(|limit| limit.interval),
+            tick: self.tick,
+            label: self.label.clone(),
+            #[cfg(feature = "stall-diagnostics")]
+            stall_threshold: self.stalls.threshold(),
+            hooks: self.hooks.clone(),
+        };
+        self.poller.native()?;
+        let poller = custom::Driver::Native(sys::Poller::new(&builder)?);
+        if let custom::Driver::Native(old) = mem::replace(&mut self.poller, poller) {
+            old.close_after_fork();
+        }
+
+        // A thread of the parent may have been waiting while forking.
+        self.lock = Mutex::new(());
+        self.splits = split::Splits::default();
+
+        let res = self.for_each_registered(|source, registered| {
+            // SAFETY: Registered sources must stay open until they are deleted.
+            #[allow(unused_unsafe)]
+            let res = unsafe {
+                self.poller.add(
+                    registered.source(),
+                    Event::none(registered.interest().key),
+                    registered.mode(),
+                )
+            };
+            let res = res.and_then(|()| self.apply(source, registered));
+            if res.is_err() {
+                self.registry.remove(registered.source());
+            }
+            res
+        });
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            sources = self.registry.len(),
+            "reinit: registered the sources in a new poller",
+        );
+        res
+    }
+
+    /// Calls `f` for every registered source, and returns the first error.
+    fn for_each_registered(
+        &self,
+        mut f: impl FnMut(BorrowedSource<'_>, &RegisteredSource) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let mut res = Ok(());
+        for registered in self.registry.snapshot() {
+            // SAFETY: Registered sources must stay open until they are deleted.
+            let source = unsafe { BorrowedSource::borrow_raw(registered.source()) };
+            if let Err(err) = f(source, &registered) {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(%err, source = ?registered.source(), "failed to update a source");
+                if res.is_ok() {
+                    res = Err(err);
+                }
+            }
+        }
+        res
+    }
+
+    /// Returns a snapshot of the runtime statistics of this poller.
+    ///
+    /// This is only available with the `metrics` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, Poller};
+    ///
+    /// let poller = Poller::new()?;
+    /// poller.notify()?;
+    /// poller.wait(&mut Events::new(), None)?;
+    ///
+    /// let stats = poller.stats();
+    /// assert_eq!(stats.waits, 1);
+    /// assert_eq!(stats.notifications, 1);
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+    pub fn stats(&self) -> Stats {
+        self.counters.snapshot()
+    }
+
+    /// Returns an error if `key` is reserved for notifications.
+    #[inline]
+    pub(crate) fn check_key(&self, key: usize) -> io::Result<()> {
+        if key == self.notify_key {
+            self.misuse(format_args!("{}", PollError::ReservedKey));
+            return Err(PollError::ReservedKey.into());
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the poller is [closed][`Poller::close()`].
+    #[inline]
+    fn check_open(&self) -> io::Result<()> {
+        if self.is_closed() {
+            return Err(PollError::Closed.into());
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the poller is closed or [sealed][`Poller::seal()`].
+    #[inline]
+    fn check_accepting(&self) -> io::Result<()> {
+        self.check_open()?;
+        if self.is_sealed() {
+            return Err(PollError::Sealed.into());
+        }
+        Ok(())
+    }
+
+    /// Wakes up a wait once the last source of a sealed poller is gone.
+    fn notify_if_drained(&self) {
+        if self.is_drained() {
+            self.notify().ok();
+        }
+    }
+
+    /// Returns an error if `source` is already registered in this poller.
+    fn check_unregistered(&self, source: RawSource) -> io::Result<()> {
+        if self.registry.get(source).is_some() {
+            self.misuse(format_args!(
+                "{}: {:?}",
+                PollError::AlreadyRegistered,
+                source
+            ));
+            return Err(PollError::AlreadyRegistered.into());
+        }
+        Ok(())
+    }
+
+    /// Returns an error if `source` is not registered in this poller.
+    ///
+    /// Backends disagree about modifying sources they don't know about, so this is checked
+    /// against the registry instead.
+    fn check_registered(&self, source: RawSource) -> io::Result<()> {
+        if self.registry.get(source).is_none() {
+            self.misuse(format_args!("{}: {:?}", PollError::NotRegistered, source));
+            return Err(PollError::NotRegistered.into());
+        }
+        Ok(())
+    }
+
+    /// Report a wait that stalled.
+    #[cfg(feature = "stall-diagnostics")]
+    fn report_stall(&self, stall: Stall) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(label = self.label(), ?stall, "a wait of the poller stalled");
+        if let Some(hooks) = self.hooks.get() {
+            hooks.on_stall(stall);
+        }
+    }
+
+    /// Report a misuse of the poller, if misuse checks are enabled.
+    #[track_caller]
+    fn misuse(&self, what: fmt::Arguments<'_>) {
+        if self.misuse_checks {
+            report_misuse(what);
+        }
+    }
+
+    /// Whether `source` is disabled, because the poller is paused or its group is muted.
+    fn is_muted(&self, source: RawSource) -> bool {
+        if self.is_paused() {
+            return true;
+        }
+        let muted = self.muted_groups();
+        !muted.is_empty() && muted.contains(&self.registry.group(source))
+    }
+
+    /// The interest to register in the backend for `source`, which is none while it is muted.
+    fn unless_muted(&self, source: RawSource, interest: Event) -> Event {
+        if self.is_muted(source) {
+            Event::none(interest.key)
+        } else {
+            interest
+        }
+    }
+
+    /// Set the halves of a split source in the backend, unless it is muted.
+    fn modify_split_sys(
+        &self,
+        source: BorrowedSource<'_>,
+        read: Event,
+        write: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        if self.is_muted((&source).raw()) {
+            // The halves are set again when the source is enabled.
+            self.splits.remove((&source).raw());
+            self.poller.modify(source, Event::none(read.key), mode)
+        } else {
+            self.splits.modify(&self.poller, source, read, write, mode)
+        }
+    }
+
+    /// Apply the low watermarks of `interest` to `source`, where they aren't part of the
+    /// registration.
+    fn set_low_watermarks(&self, source: RawSource, interest: &Event) -> io::Result<()> {
+        if sys::BACKEND == Backend::Kqueue {
+            return Ok(());
+        }
+
+        let previous = self
+            .registry
+            .get(source)
+            .map(|registered| registered.interest());
+        let (read, write) = match previous {
+            Some(previous) => (previous.read_low_watermark, previous.write_low_watermark),
+            None => (None, None),
+        };
+        if interest.read_low_watermark != read {
+            set_low_watermark(source, interest.read_low_watermark, false)?;
+        }
+        if interest.write_low_watermark != write {
+            set_low_watermark(source, interest.write_low_watermark, true)?;
+        }
+        Ok(())
+    }
+
+    /// Tell whether or not this `Poller` supports level-triggered polling.
+    pub fn supports_level(&self) -> bool {
+        self.poller.supports_level()
+    }
+
+    /// Tell whether or not this `Poller` supports edge-triggered polling.
+    pub fn supports_edge(&self) -> bool {
+        self.poller.supports_edge()
+    }
+
+    /// Tell whether or not this `Poller` supports a capability.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Capability, Poller};
+    ///
+    /// let poller = Poller::new()?;
+    /// assert!(poller.supports(Capability::Timers));
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn supports(&self, capability: Capability) -> bool {
+        let backend = self.backend();
+        match capability {
+            Capability::LevelTrigger => self.supports_level(),
+            Capability::EdgeTrigger => self.supports_edge(),
+            Capability::Priority => !matches!(backend, Backend::Kqueue | Backend::Custom),
+            Capability::Hangup | Capability::Timers => true,
+            Capability::Exclusive | Capability::Wakeup => backend == Backend::Epoll,
+            Capability::ProcessEvents | Capability::Signals => backend == Backend::Kqueue,
+            Capability::Waitables => backend == Backend::Iocp,
+        }
+    }
+
+    /// Adds a file descriptor or socket to the poller.
+    ///
+    /// A file descriptor or socket is considered readable or writable when a read or write
+    /// operation on it would not block. This doesn't mean the read or write operation will
+    /// succeed, it only means the operation will return immediately.
+    ///
+    /// The source is registered as is. This doesn't put it into non-blocking mode or change its
+    /// file status flags in any other way, so a source that is shared with other code, such as
+    /// standard input, can stay in blocking mode. Such a source can still block even when it is
+    /// reported as ready, for instance if another reader drains it first, so sources that are
+    /// only used through the poller are usually put into non-blocking mode by the caller.
+    ///
+    /// If interest is set in both readability and writability, the two kinds of events might be
+    /// delivered either separately or together. A source that is readable and writable by the
+    /// time of a wait is reported in a single event on all backends, but readiness that comes
+    /// later may be delivered in an event of its own.
+    ///
+    /// For example, interest in `Event { key: 7, readable: true, writable: true }` might result in
+    /// a single [`Event`] of the same form, or in two separate [`Event`]s:
+    /// - `Event { key: 7, readable: true, writable: false }`
+    /// - `Event { key: 7, readable: false, writable: true }`
+    ///
+    /// The source is registered in this poller's default [`mode()`][`Poller::mode()`], which is
+    /// oneshot mode unless configured otherwise. In oneshot mode, interest in I/O events needs to
+    /// be re-enabled using [`modify()`][`Poller::modify()`] again after an event is delivered if
+    /// we're interested in the next event of the same kind.
+    ///
+    /// The same file descriptor or socket can be registered in several [`Poller`] instances at
+    /// once, for instance in the poller of an event loop and in a separate one that checks its
+    /// health. The registrations are independent on every backend: each poller has its own key,
+    /// interest and mode for the source, delivers an event whenever the source is ready for its
+    /// own interest, and re-enables or deletes its registration without affecting the others.
+    /// This also holds for a oneshot registration that fired, which is gone from the kqueue or
+    /// event port of its poller only, and for sockets on Windows, which every poller polls
+    /// through its own AFD handle. The exceptions are [`Interest::EXCLUSIVE`], which wakes up
+    /// only one of the pollers, and the low watermarks of [`Event`], which are options of the
+    /// socket itself and thus shared. Reading from the source in one event loop can still make it
+    /// unready before another one gets to it, so the others should expect `WouldBlock`.
+    ///
+    /// One may also register one source into other, non-`polling` event loops, like GLib's
+    /// context. While the plumbing will vary from platform to platform, in general the [`Poller`]
+    /// will act as if the source was registered with another [`Poller`].
+    ///
+    /// # Safety
+    ///
+    /// The source must be [`delete()`]d from this `Poller` before it is dropped.
+    ///
+    /// [`delete()`]: Poller::delete
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error in the following situations:
+    ///
+    /// * If `key` equals [`notify_key()`][`Poller::notify_key()`] (`usize::MAX` by default),
+    ///   because that key is reserved for internal use.
+    /// * If the source is already registered, with [`io::ErrorKind::AlreadyExists`].
+    /// * If the backend can't poll this kind of source, such as regular files with epoll or some
+    ///   devices with kqueue, with [`io::ErrorKind::Unsupported`]. The message names the backend
+    ///   and the type of the file, and such sources can be handed to a blocking thread instead.
+    /// * If an error is returned by the syscall.
+    ///
+    /// These can be told apart on all platforms by converting the error into a [`PollError`].
+    ///
+    /// # Examples
+    ///
+    /// Set interest in all events:
+    ///
+    /// ```no_run
+    /// use polling::{Event, Poller};
+    ///
+    /// let source = std::net::TcpListener::bind("127.0.0.1:0")?;
+    /// source.set_nonblocking(true)?;
+    /// let key = 7;
+    ///
+    /// let poller = Poller::new()?;
+    /// unsafe {
+    ///     poller.add(&source, Event::all(key))?;
+    /// }
+    /// poller.delete(&source)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub unsafe fn add(&self, source: impl AsRawSource, interest: Event) -> io::Result<()> {
+        self.add_with_mode(source, interest, self.mode)
+    }
+
+    /// Adds a file descriptor or socket to the poller in the specified mode.
+    ///
+    /// This is identical to the `add()` function, but allows specifying the
+    /// polling mode to use for this socket.
+    ///
+    /// # Safety
+    ///
+    /// The source must be [`delete()`]d from this `Poller` before it is dropped.
+    ///
+    /// [`delete()`]: Poller::delete
+    ///
+    /// # Errors
+    ///
+    /// If the operating system does not support the specified mode, this function
+    /// will return an error.
+    pub unsafe fn add_with_mode(
+        &self,
+        source: impl AsRawSource,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        let source = source.raw();
+        let res = self.add_inner(source, interest, mode);
+        if let Some(hooks) = self.hooks.get() {
+            hooks.on_add(source, interest, mode, res.as_ref().copied());
+        }
+        res
+    }
+
+    /// Adds a source, see `add_with_mode()`.
+    unsafe fn add_inner(
+        &self,
+        source: RawSource,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!(
+            "Poller::add",
+            backend = %self.backend(),
+            label = self.label(),
+            ?source,
+            ?interest,
+            ?mode,
+        );
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
+        self.check_accepting()?;
+        self.check_key(interest.key)?;
+        self.check_unregistered(source)?;
+        self.set_low_watermarks(source, &interest)?;
+        let res = self
+            .poller
+            .add(source, self.unless_muted(source, interest), mode);
+        if res.is_ok() {
+            self.registry.insert(source, interest, mode);
+            #[cfg(any(unix, windows))]
+            if self.initial_readiness && !self.is_muted(source) {
+                self.report_initial_readiness(source, interest, mode);
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        if let Err(ref err) = res {
+            tracing::debug!(%err, "add: failed to register the source");
+        }
+        res
+    }
+
+    /// Check whether a source that was just added is already ready, and if so, deliver its
+    /// event with the next wait.
+    ///
+    /// The source must stay open until it is deleted, as for `add()`.
+    #[cfg(any(unix, windows))]
+    unsafe fn report_initial_readiness(&self, source: RawSource, interest: Event, mode: PollMode) {
+        let borrowed = BorrowedSource::borrow_raw(source);
+        let ready = match poll_once(borrowed, interest.interest(), Some(Duration::ZERO)) {
+            Ok(Some(ready)) => ready,
+            // The backend reports the readiness later.
+            _ => return,
+        };
+        let mut event = Event::none(interest.key);
+        event.readable = interest.readable && ready.readable;
+        event.writable = interest.writable && ready.writable;
+        if !event.readable && !event.writable {
+            return;
+        }
+
+        // Disable oneshot registrations, so the backend doesn't deliver the event again later.
+        if matches!(mode, PollMode::Oneshot | PollMode::EdgeOneshot) {
+            if self
+                .poller
+                .modify(borrowed, Event::none(event.key), mode)
+                .is_err()
+            {
+                return;
+            }
+            self.registry.disarm(&[event]);
+        }
+
+        let mut deferred = self.deferred();
+        if !deferred.iter().any(|ev| ev.key == event.key) {
+            deferred.push(event);
+        }
+        drop(deferred);
+
+        // A wait that is already blocking only checks for the event when it is called again.
+        self.notify().ok();
+    }
+
+    /// Adds a file descriptor or socket to the poller under a key picked by the poller, and
+    /// returns the key.
+    ///
+    /// This is a shortcut for small programs that don't want to keep a table of keys. The key is
+    /// not used by any other source that was added this way, and is never the
+    /// [notification key][`Poller::notify_key()`]. It is freed for reuse when the source is
+    /// [`delete()`]d. Keys picked by the caller for other sources may collide with it, so don't
+    /// mix both ways of choosing keys in one poller; use a [`KeyAllocator`] for that.
+    ///
+    /// The source is added in the default mode of the poller, and its interest can be changed
+    /// with [`modify()`][`Poller::modify()`] and [`Event::from_interest()`] with the returned key.
+    ///
+    /// # Safety
+    ///
+    /// The source must be [`delete()`]d from this `Poller` before it is dropped.
+    ///
+    /// [`delete()`]: Poller::delete
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error in the same situations as [`add()`][`Poller::add()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, Interest, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::time::Duration;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// let poller = Poller::new()?;
+    /// let key = unsafe { poller.add_auto(&socket, Interest::WRITABLE)? };
+    ///
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// assert_eq!(events.iter().next().unwrap().key, key);
+    ///
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub unsafe fn add_auto(
+        &self,
+        source: impl AsRawSource,
+        interest: Interest,
+    ) -> io::Result<usize> {
+        let source = source.raw();
+        let key = self.registry.allocate_key();
+        match self.add(source, Event::from_interest(key, interest)) {
+            Ok(()) => {
+                self.registry.assign_key(source, key);
+                Ok(key)
+            }
+            Err(err) => {
+                self.registry.free_key(key);
+                Err(err)
+            }
+        }
+    }
+
+    /// Adds a file descriptor or socket to the poller, with separate keys for reading and
+    /// writing.
+    ///
+    /// Readability is delivered under the key of `read`, and writability under the key of
+    /// `write`, so that the read and write halves of a socket can be handled independently. Only
+    /// [`Event::readable`] of `read` and [`Event::writable`] of `write` are used, and the other
+    /// settings of the interest, such as [`Event::set_priority()`], are taken from `read`.
+    /// Events that are neither readable nor writable, such as hangups, are delivered to both
+    /// halves.
+    ///
+    /// Each half is enabled and disabled on its own. In oneshot mode, an event for one half
+    /// doesn't disable the other, and [`modify_split()`][`Poller::modify_split()`] re-enables
+    /// the halves. With `kqueue`, the halves are separate filters. The other backends register
+    /// the source under the key of `read` and split up its events, so that key must not be used
+    /// by other sources, and re-enabling the other half after a oneshot event takes an extra
+    /// system call.
+    ///
+    /// The source is deleted with [`delete()`][`Poller::delete()`], and
+    /// [`modify()`][`Poller::modify()`] turns it back into a source with a single key.
+    ///
+    /// # Safety
+    ///
+    /// The source must be [`delete()`]d from this `Poller` before it is dropped.
+    ///
+    /// [`delete()`]: Poller::delete
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error in the same situations as [`add()`][`Poller::add()`], for
+    /// either key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, Poller};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::time::Duration;
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0")?;
+    /// let stream = TcpStream::connect(listener.local_addr()?)?;
+    /// stream.set_nonblocking(true)?;
+    ///
+    /// let poller = Poller::new()?;
+    /// unsafe {
+    ///     poller.add_split(&stream, Event::readable(1), Event::writable(2))?;
+    /// }
+    ///
+    /// // The stream is writable, but there is nothing to read yet.
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// assert_eq!(events.iter().next().unwrap().key, 2);
+    ///
+    /// poller.delete(&stream)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub unsafe fn add_split(
+        &self,
+        source: impl AsRawSource,
+        read: Event,
+        write: Event,
+    ) -> io::Result<()> {
+        self.add_split_with_mode(source, read, write, self.mode)
+    }
+
+    /// Adds a file descriptor or socket to the poller in the specified mode, with separate keys
+    /// for reading and writing.
+    ///
+    /// This is identical to the `add_split()` function, but allows specifying the polling mode
+    /// to use for this socket.
+    ///
+    /// # Safety
+    ///
+    /// The source must be [`delete()`]d from this `Poller` before it is dropped.
+    ///
+    /// [`delete()`]: Poller::delete
+    ///
+    /// # Errors
+    ///
+    /// If the operating system does not support the specified mode, this function
+    /// will return an error.
+    pub unsafe fn add_split_with_mode(
+        &self,
+        source: impl AsRawSource,
+        read: Event,
+        write: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        let source = source.raw();
+        let res = self.add_split_inner(source, read, write, mode);
+        if let Some(hooks) = self.hooks.get() {
+            hooks.on_add(source, read, mode, res.as_ref().copied());
+            hooks.on_add(source, write, mode, res.as_ref().copied());
+        }
+        res
+    }
+
+    /// Adds a source with separate keys, see `add_split_with_mode()`.
+    unsafe fn add_split_inner(
+        &self,
+        source: RawSource,
+        read: Event,
+        write: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!(
+            "Poller::add_split",
+            backend = %self.backend(),
+            label = self.label(),
+            ?source,
+            ?read,
+            ?write,
+            ?mode,
+        );
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
+        self.check_accepting()?;
+        self.check_key(read.key)?;
+        self.check_key(write.key)?;
+        self.check_unregistered(source)?;
+        self.set_low_watermarks(source, &split::combine(read, write))?;
+
+        // Add the source without interest, then set the halves.
+        self.poller.add(source, Event::none(read.key), mode)?;
+        let borrowed = BorrowedSource::borrow_raw(source);
+        let res = self.modify_split_sys(borrowed, read, write, mode);
+        match res {
+            Ok(()) => self.registry.insert_split(source, read, write, mode),
+            Err(_) => {
+                self.poller.delete(borrowed).ok();
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        if let Err(ref err) = res {
+            tracing::debug!(%err, "add_split: failed to register the source");
+        }
+        res
+    }
+
+    /// Adds a file descriptor or socket to the poller, returning a guard that removes it again.
+    ///
+    /// This is a safe alternative to [`add()`][`Poller::add()`]. The returned [`Registration`]
+    /// takes ownership of the source, and deletes it from the poller when dropped. This makes it
+    /// impossible to forget to call [`delete()`][`Poller::delete()`], for instance on an early
+    /// return.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error in the same situations as [`add()`][`Poller::add()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, Poller};
+    /// use std::net::TcpListener;
+    /// use std::time::Duration;
+    ///
+    /// let socket = TcpListener::bind("127.0.0.1:0")?;
+    /// socket.set_nonblocking(true)?;
+    /// let key = 7;
+    ///
+    /// let poller = Poller::new()?;
+    /// let socket = poller.register(socket, Event::readable(key))?;
+    ///
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, Some(Duration::from_secs(0)))?;
+    ///
+    /// // Take the socket back out of the poller.
+    /// let socket = socket.into_inner()?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn register<S: AsSource + 'static>(
+        &self,
+        source: S,
+        interest: Event,
+    ) -> io::Result<Registration<'_, S>> {
+        self.register_with_mode(source, interest, self.mode)
+    }
+
+    /// Adds a file descriptor or socket to the poller in the specified mode, returning a guard
+    /// that removes it again.
+    ///
+    /// This is identical to the `register()` function, but allows specifying the polling mode
+    /// to use for this socket.
+    ///
+    /// # Errors
+    ///
+    /// If the operating system does not support the specified mode, this function
+    /// will return an error.
+    pub fn register_with_mode<S: AsSource + 'static>(
+        &self,
+        source: S,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<Registration<'_, S>> {
+        Registration::new(self, source, interest, mode)
+    }
+
+    /// Adds a file descriptor or socket to the poller for the duration of a closure.
+    ///
+    /// The source is added with `interest` in this poller's default
+    /// [`mode()`][`Poller::mode()`], `f` is called, which can [`wait()`][`Poller::wait()`] for
+    /// its events, and the source is deleted again, even if `f` panics. The source is only
+    /// borrowed, which makes this a quick way to wait for a temporary file descriptor once,
+    /// without keeping a [`Registration`] around.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error in the same situations as [`add()`][`Poller::add()`], in
+    /// which case `f` is not called. An error of deleting the source after `f` returns is
+    /// reported too, and the result of `f` is dropped with it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::time::Duration;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// let poller = Poller::new()?;
+    ///
+    /// let writable = poller.with_registered(&socket, Event::writable(7), |poller| {
+    ///     let mut events = Events::new();
+    ///     poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    ///     Ok::<_, std::io::Error>(events.iter().any(|ev| ev.key == 7))
+    /// })??;
+    /// assert!(writable);
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn with_registered<S: AsSource, T>(
+        &self,
+        source: S,
+        interest: Event,
+        f: impl FnOnce(&Poller) -> T,
+    ) -> io::Result<T> {
+        /// Deletes the source when `f` panics.
+        struct Guard<'a, S: AsSource> {
+            poller: &'a Poller,
+            source: Option<S>,
+        }
+
+        impl<S: AsSource> Drop for Guard<'_, S> {
+            fn drop(&mut self) {
+                if let Some(source) = self.source.take() {
+                    let _ = self.poller.delete(&source);
+                }
+            }
+        }
+
+        // SAFETY: The guard can't be leaked, and deletes the source before it is dropped.
+        unsafe {
+            self.add(&source.source(), interest)?;
+        }
+        let mut guard = Guard {
+            poller: self,
+            source: Some(source),
+        };
+
+        let res = f(self);
+        let source = guard.source.take().unwrap();
+        self.delete(&source)?;
+        Ok(res)
+    }
+
+    /// Modifies the interest in a file descriptor or socket.
+    ///
+    /// This method has the same behavior as [`add()`][`Poller::add()`] except it modifies the
+    /// interest of a previously added file descriptor or socket.
+    ///
+    /// To use this method with a file descriptor or socket, you must first add it using
+    /// [`add()`][`Poller::add()`].
+    ///
+    /// Note that interest in I/O events needs to be re-enabled using
+    /// [`modify()`][`Poller::modify()`] again after an event is delivered if we're interested in
+    /// the next event of the same kind.
+    ///
+    /// If the interest and mode are the same as the ones that were last set, and no event has
+    /// been delivered for the source since then, this returns without a syscall, so it is cheap
+    /// to assert the same interest repeatedly.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error in the following situations:
+    ///
+    /// * If `key` equals [`notify_key()`][`Poller::notify_key()`] (`usize::MAX` by default),
+    ///   because that key is reserved for internal use.
+    /// * If the source is not registered, with [`io::ErrorKind::NotFound`].
+    /// * If an error is returned by the syscall.
+    ///
+    /// These can be told apart on all platforms by converting the error into a [`PollError`].
+    ///
+    /// # Examples
+    ///
+    /// To enable interest in all events:
+    ///
+    /// ```no_run
+    /// # use polling::{Event, Poller};
+    /// # let source = std::net::TcpListener::bind("127.0.0.1:0")?;
+    /// # let key = 7;
+    /// # let poller = Poller::new()?;
+    /// # unsafe { poller.add(&source, Event::none(key))?; }
+    /// poller.modify(&source, Event::all(key))?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    ///
+    /// To enable interest in readable events and disable interest in writable events:
+    ///
+    /// ```no_run
+    /// # use polling::{Event, Poller};
+    /// # let source = std::net::TcpListener::bind("127.0.0.1:0")?;
+    /// # let key = 7;
+    /// # let poller = Poller::new()?;
+    /// # unsafe { poller.add(&source, Event::none(key))?; }
+    /// poller.modify(&source, Event::readable(key))?;
+    /// # poller.delete(&source)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    ///
+    /// To disable interest in readable events and enable interest in writable events:
+    ///
+    /// ```no_run
+    /// # use polling::{Event, Poller};
+    /// # let poller = Poller::new()?;
+    /// # let key = 7;
+    /// # let source = std::net::TcpListener::bind("127.0.0.1:0")?;
+    /// # unsafe { poller.add(&source, Event::none(key))? };
+    /// poller.modify(&source, Event::writable(key))?;
+    /// # poller.delete(&source)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    ///
+    /// To disable interest in all events:
+    ///
+    /// ```no_run
+    /// # use polling::{Event, Poller};
+    /// # let source = std::net::TcpListener::bind("127.0.0.1:0")?;
+    /// # let key = 7;
+    /// # let poller = Poller::new()?;
+    /// # unsafe { poller.add(&source, Event::none(key))?; }
+    /// poller.modify(&source, Event::none(key))?;
+    /// # poller.delete(&source)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn modify(&self, source: impl AsSource, interest: Event) -> io::Result<()> {
+        self.modify_with_mode(source, interest, self.mode)
+    }
+
+    /// Modifies interest in a file descriptor or socket to the poller, but with the specified
+    /// mode.
+    ///
+    /// This is identical to the `modify()` function, but allows specifying the polling mode
+    /// to use for this socket.
+    ///
+    /// # Performance Notes
+    ///
+    /// This function can be used to change a source from one polling mode to another. However,
+    /// on some platforms, this switch can cause delays in the delivery of events.
+    ///
+    /// # Errors
+    ///
+    /// If the operating system does not support the specified mode, this function will return
+    /// an error.
+    pub fn modify_with_mode(
+        &self,
+        source: impl AsSource,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        let source = source.source();
+        let res = self.modify_inner(source, interest, mode);
+        if let Some(hooks) = self.hooks.get() {
+            hooks.on_modify((&source).raw(), interest, mode, res.as_ref().copied());
+        }
+        res
+    }
+
+    /// Modifies the interest in a source, see `modify_with_mode()`.
+    fn modify_inner(
+        &self,
+        source: BorrowedSource<'_>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!(
+            "Poller::modify",
+            backend = %self.backend(),
+            label = self.label(),
+            ?source,
+            ?interest,
+            ?mode,
+        );
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
+        self.check_key(interest.key)?;
+        self.check_registered((&source).raw())?;
+        if self.registry.is_unchanged((&source).raw(), &interest, mode) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!("modify: the interest is unchanged");
+            return Ok(());
+        }
+        self.set_low_watermarks((&source).raw(), &interest)?;
+        let res = self
+            .poller
+            .modify(source, self.unless_muted((&source).raw(), interest), mode);
+        match res {
+            Ok(()) => {
+                self.registry.insert((&source).raw(), interest, mode);
+                self.splits.remove((&source).raw());
+                #[cfg(feature = "metrics")]
+                self.counters.modification();
+            }
+
+            // A registered source was closed and its file descriptor reused.
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                self.registry.remove((&source).raw());
+                self.splits.remove((&source).raw());
+            }
+            Err(_) => {}
+        }
+
+        #[cfg(feature = "tracing")]
+        if let Err(ref err) = res {
+            tracing::debug!(%err, "modify: failed to update the interest");
+        }
+        res
+    }
+
+    /// Changes the key of a registered file descriptor or socket, keeping its interest and mode.
+    ///
+    /// This takes a single call into the backend, so unlike deleting the source and adding it
+    /// again, no readiness is lost in between. A oneshot registration that already had an event
+    /// delivered stays disabled until it is [modified][`Poller::modify()`]. Events that were
+    /// delivered before this call still carry the old key.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error in the following situations:
+    ///
+    /// * If `key` equals [`notify_key()`][`Poller::notify_key()`].
+    /// * If the source is not registered, with [`io::ErrorKind::NotFound`].
+    /// * If the source was added with separate keys for reading and writing, which are changed
+    ///   with [`modify_split()`][`Poller::modify_split()`] instead.
+    /// * If an error is returned by the syscall.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::time::Duration;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// let poller = Poller::new()?;
+    /// unsafe {
+    ///     poller.add(&socket, Event::writable(1))?;
+    /// }
+    /// poller.rekey(&socket, 2)?;
+    ///
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// assert_eq!(events.iter().next().unwrap().key, 2);
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn rekey(&self, source: impl AsSource, key: usize) -> io::Result<()> {
+        let source = source.source();
+        let raw = (&source).raw();
+        self.check_key(key)?;
+        let registered = self
+            .registry
+            .get(raw)
+            .ok_or_else(|| io::Error::from(PollError::NotRegistered))?;
+        if registered.write_key().is_some() {
+            return Err(unsupported_error(
+                "the keys of split sources are changed with `modify_split()`",
+            ));
+        }
+        if registered.interest().key == key {
+            return Ok(());
+        }
+
+        // A oneshot registration that already fired stays disabled.
+        let interest = if self.registry.is_armed(raw) {
+            Event {
+                key,
+                ..registered.interest()
+            }
+        } else {
+            Event::none(key)
+        };
+        self.poller
+            .modify(source, self.unless_muted(raw, interest), registered.mode())?;
+        self.registry.rekey(raw, key);
+        #[cfg(feature = "metrics")]
+        self.counters.modification();
+        Ok(())
+    }
+
+    /// Modifies the interest in a file descriptor or socket, with separate keys for reading and
+    /// writing.
+    ///
+    /// This sets the halves of a source that was added with [`add()`][`Poller::add()`] or
+    /// [`add_split()`][`Poller::add_split()`], see the latter for more information.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an error in the same situations as
+    /// [`modify()`][`Poller::modify()`], for either key.
+    pub fn modify_split(&self, source: impl AsSource, read: Event, write: Event) -> io::Result<()> {
+        self.modify_split_with_mode(source, read, write, self.mode)
+    }
+
+    /// Modifies the interest in a file descriptor or socket in the specified mode, with separate
+    /// keys for reading and writing.
+    ///
+    /// This is identical to the `modify_split()` function, but allows specifying the polling
+    /// mode to use for this socket.
+    ///
+    /// # Errors
+    ///
+    /// If the operating system does not support the specified mode, this function will return
+    /// an error.
+    pub fn modify_split_with_mode(
+        &self,
+        source: impl AsSource,
+        read: Event,
+        write: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        let source = source.source();
+        let res = self.modify_split_inner(source, read, write, mode);
+        if let Some(hooks) = self.hooks.get() {
+            hooks.on_modify((&source).raw(), read, mode, res.as_ref().copied());
+            hooks.on_modify((&source).raw(), write, mode, res.as_ref().copied());
+        }
+        res
+    }
+
+    /// Modifies the interest in a source with separate keys, see `modify_split_with_mode()`.
+    fn modify_split_inner(
+        &self,
+        source: BorrowedSource<'_>,
+        read: Event,
+        write: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!(
+            "Poller::modify_split",
+            backend = %self.backend(),
+            label = self.label(),
+            ?source,
+            ?read,
+            ?write,
+            ?mode,
+        );
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
+        self.check_key(read.key)?;
+        self.check_key(write.key)?;
+        self.check_registered((&source).raw())?;
+        self.set_low_watermarks((&source).raw(), &split::combine(read, write))?;
+        let res = self.modify_split_sys(source, read, write, mode);
+        match res {
+            Ok(()) => {
+                self.registry
+                    .insert_split((&source).raw(), read, write, mode);
+                #[cfg(feature = "metrics")]
+                self.counters.modification();
+            }
+
+            // A registered source was closed and its file descriptor reused.
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                self.registry.remove((&source).raw());
+                self.splits.remove((&source).raw());
+            }
+            Err(_) => {}
+        }
+
+        #[cfg(feature = "tracing")]
+        if let Err(ref err) = res {
+            tracing::debug!(%err, "modify_split: failed to update the interest");
+        }
+        res
+    }
+
+    /// Removes a file descriptor or socket from the poller.
+    ///
+    /// Like [`add()`][`Poller::add()`], this method doesn't change the blocking mode of the file
+    /// descriptor or socket.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Poller};
+    /// use std::net::TcpListener;
+    ///
+    /// let socket = TcpListener::bind("127.0.0.1:0")?;
+    /// socket.set_nonblocking(true)?;
+    /// let key = 7;
+    ///
+    /// let poller = Poller::new()?;
+    /// unsafe { poller.add(&socket, Event::all(key))?; }
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn delete(&self, source: impl AsSource) -> io::Result<()> {
+        let source = source.source();
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!(
+            "Poller::delete",
+            backend = %self.backend(),
+            label = self.label(),
+            ?source,
+        );
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
+        // Some backends don't report deleting a source twice.
+        if self.registry.get((&source).raw()).is_none() {
+            let source = (&source).raw();
+            self.misuse(format_args!("{}: {:?}", PollError::NotRegistered, source));
+        }
+
+        let res = self.poller.delete(source);
+        if res.is_ok() {
+            self.registry.remove((&source).raw());
+            self.splits.remove((&source).raw());
+            self.notify_if_drained();
+        }
+
+        #[cfg(feature = "tracing")]
+        if let Err(ref err) = res {
+            tracing::debug!(%err, "delete: failed to remove the source");
+        }
+        if let Some(hooks) = self.hooks.get() {
+            hooks.on_delete((&source).raw(), res.as_ref().copied());
+        }
+        res
+    }
+
+    /// Removes every file descriptor and socket that is registered in the poller.
+    ///
+    /// This deletes the sources listed in [`registrations()`][`Poller::registrations()`], which
+    /// leaves the poller as if it was just created, for instance to restart an event loop that
+    /// reuses the same `Poller`. The internal notification mechanism stays registered, and so do
+    /// sources that are not tracked by the poller, such as `kqueue` filters and IOCP waitables.
+    ///
+    /// # Errors
+    ///
+    /// The poller forgets about every source even if deleting it fails, for instance because it
+    /// was closed while it was registered, and the first error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Poller};
+    /// use std::net::{TcpListener, UdpSocket};
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0")?;
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    ///
+    /// let poller = Poller::new()?;
+    /// unsafe {
+    ///     poller.add(&listener, Event::readable(1))?;
+    ///     poller.add(&socket, Event::readable(2))?;
+    /// }
+    ///
+    /// poller.delete_all()?;
+    /// assert_eq!(poller.registered_len(), 0);
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn delete_all(&self) -> io::Result<()> {
+        let res = self.for_each_registered(|source, registered| {
+            let res = self.poller.delete(source);
+            self.registry.remove(registered.source());
+            self.splits.remove(registered.source());
+            if res.is_err() {
+                self.poller.forget(registered.source());
+            }
+            res
+        });
+        self.notify_if_drained();
+        res
+    }
+
+    /// Moves every file descriptor and socket that is registered in the poller to `other`.
+    ///
+    /// Each source in [`registrations()`][`Poller::registrations()`] is registered in `other`
+    /// with the same interest, mode, keys and group, and then removed from this poller. This
+    /// hands the sources of an event loop over to another one, for instance to drain a worker
+    /// thread before stopping it, or to switch to a poller with another backend or configuration
+    /// while the program runs.
+    ///
+    /// The sources are moved one at a time, so a wait on either poller may run while some of
+    /// them are moved and others aren't yet, but every event is reported by one of the pollers.
+    /// A oneshot source whose event was already delivered stays disabled in `other` until it is
+    /// modified there, and the events this poller set aside for its next wait, such as the
+    /// [initial readiness][`PollerBuilder::initial_readiness()`] of the sources, are delivered
+    /// by `other` instead. Keys picked by [`add_auto()`][`Poller::add_auto()`] keep their value,
+    /// but they aren't reserved in `other`. Sources that are not tracked by the poller, such as
+    /// `kqueue` filters and IOCP waitables, are not moved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with [`io::ErrorKind::InvalidInput`] if `other` is this poller. A source
+    /// that can't be registered in `other`, for instance because it is already registered there
+    /// or `other` is sealed, stays in this poller. The other sources are still moved, and the
+    /// first error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Poller};
+    /// use std::net::TcpListener;
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0")?;
+    /// let old = Poller::new()?;
+    /// unsafe {
+    ///     old.add(&listener, Event::readable(1))?;
+    /// }
+    ///
+    /// let new = Poller::new()?;
+    /// old.migrate_to(&new)?;
+    /// assert_eq!(old.registered_len(), 0);
+    /// assert_eq!(new.registered(&listener).unwrap().interest(), Event::readable(1));
+    /// new.delete(&listener)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn migrate_to(&self, other: &Poller) -> io::Result<()> {
+        if std::ptr::eq(self, other) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot migrate the sources of a poller to itself",
+            ));
+        }
+
+        let mut sources = 0;
+        let mut keys = HashSet::new();
+        let res = self.for_each_registered(|source, registered| {
+            let raw = registered.source();
+
+            // Split registrations don't keep track of their delivered events.
+            let armed = registered.write_key().is_some() || self.registry.is_armed(raw);
+
+            // SAFETY: Registered sources must stay open until they are deleted, which is now
+            // done from `other`.
+            unsafe { other.adopt(source, registered, armed, self.registry.group(raw)) }?;
+            sources += 1;
+            keys.insert(registered.interest().key);
+            keys.extend(registered.write_key());
+
+            let res = self.poller.delete(source);
+            self.registry.remove(raw);
+            self.splits.remove(raw);
+            if res.is_err() {
+                self.poller.forget(raw);
+            }
+            if let Some(hooks) = self.hooks.get() {
+                hooks.on_delete(raw, res.as_ref().copied());
+            }
+            Ok(())
+        });
+
+        // Hand over the events set aside for the moved sources.
+        let moved: Vec<Event> = {
+            let mut deferred = self.deferred();
+            let (moved, kept) = deferred.drain(..).partition(|ev| keys.contains(&ev.key));
+            *deferred = kept;
+            moved
+        };
+        if !moved.is_empty() {
+            other.deferred().extend(moved);
+            other.notify().ok();
+        }
+        self.notify_if_drained();
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            sources,
+            remaining = self.registry.len(),
+            "migrate_to: moved the sources to another poller",
+        );
+        res
+    }
+
+    /// Registers a source that is moved from another poller, see `migrate_to()`.
+    ///
+    /// The source must stay open until it is deleted, as for `add()`.
+    unsafe fn adopt(
+        &self,
+        source: BorrowedSource<'_>,
+        registered: &RegisteredSource,
+        armed: bool,
+        group: usize,
+    ) -> io::Result<()> {
+        let raw = registered.source();
+        let interest = registered.interest();
+        let mode = registered.mode();
+        let res = (|| {
+            self.check_accepting()?;
+            self.check_key(interest.key)?;
+            if let Some(key) = registered.write_key() {
+                self.check_key(key)?;
+            }
+            self.check_unregistered(raw)?;
+
+            // Add the source without interest, and enable it in its group unless it stays
+            // disabled until it is modified.
+            self.poller.add(raw, Event::none(interest.key), mode)?;
+            match registered.write_key() {
+                Some(key) => {
+                    self.registry
+                        .insert_split(raw, interest, Event { key, ..interest }, mode)
+                }
+                None => {
+                    self.registry.insert(raw, interest, mode);
+                    if !armed {
+                        self.registry.disarm(&[interest]);
+                    }
+                }
+            }
+            self.registry.set_group(raw, group);
+            if armed {
+                if let Err(err) = self.apply(source, registered) {
+                    self.poller.delete(source).ok();
+                    self.registry.remove(raw);
+                    self.splits.remove(raw);
+                    return Err(err);
+                }
+            }
+            Ok(())
+        })();
+
+        if let Some(hooks) = self.hooks.get() {
+            hooks.on_add(raw, interest, mode, res.as_ref().copied());
+        }
+        res
+    }
+
+    /// Fetches and clears the pending error of a socket.
+    ///
+    /// When an event is reported with [`Event::is_err()`], such as when a non-blocking
+    /// `connect()` fails or a UDP socket receives an ICMP error, the error is stored in the
+    /// socket. This reads it with the `SO_ERROR` socket option, which also clears it, and returns
+    /// `None` if there was no pending error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::Poller;
+    /// use std::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// let poller = Poller::new()?;
+    /// assert!(poller.take_error(&socket)?.is_none());
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn take_error(&self, source: impl AsSource) -> io::Result<Option<io::Error>> {
+        let source = source.source();
+        let mut error: std::os::raw::c_int = 0;
+
+        #[cfg(unix)]
+        let res = {
+            use std::os::unix::io::AsRawFd;
+
+            let mut len = std::mem::size_of_val(&error) as libc::socklen_t;
+            // SAFETY: `error` and `len` are valid for writes, and the socket is borrowed.
+            unsafe {
+                libc::getsockopt(
+                    source.as_raw_fd(),
+                    libc::SOL_SOCKET,
+                    libc::SO_ERROR,
+                    (&mut error as *mut std::os::raw::c_int).cast(),
+                    &mut len,
+                )
+            }
+        };
+
+        #[cfg(windows)]
+        let res = {
+            use std::os::windows::io::AsRawSocket;
+            use windows_sys::Win32::Networking::WinSock;
+
+            let mut len = std::mem::size_of_val(&error) as i32;
+            // SAFETY: `error` and `len` are valid for writes, and the socket is borrowed.
+            unsafe {
+                WinSock::getsockopt(
+                    source.as_raw_socket() as _,
+                    WinSock::SOL_SOCKET,
+                    WinSock::SO_ERROR,
+                    (&mut error as *mut std::os::raw::c_int).cast(),
+                    &mut len,
+                )
+            }
+        };
+
+        if res == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok((error != 0).then(|| io::Error::from_raw_os_error(error)))
+    }
+
+    /// Adds a socket with a non-blocking `connect()` in progress to the poller.
+    ///
+    /// The socket is registered in oneshot mode for writability, which every backend reports
+    /// once the connection is established or has failed. When the event with `key` is delivered,
+    /// call [`finish_connect()`][`Poller::finish_connect()`] to find out which.
+    ///
+    /// # Safety
+    ///
+    /// The source must be [`delete()`]d from this `Poller` before it is dropped.
+    ///
+    /// [`delete()`]: Poller::delete
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, Poller};
+    /// use socket2::{Domain, Socket, Type};
+    /// use std::net::TcpListener;
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0")?;
+    /// let socket = Socket::new(Domain::IPV4, Type::STREAM, None)?;
+    /// socket.set_nonblocking(true)?;
+    /// let _ = socket.connect(&listener.local_addr()?.into());
+    ///
+    /// let poller = Poller::new()?;
+    /// unsafe {
+    ///     poller.add_connecting(&socket, 1)?;
+    /// }
+    ///
+    /// let mut events = Events::new();
+    /// loop {
+    ///     events.clear();
+    ///     poller.wait(&mut events, None)?;
+    ///     if events.with_key(1).next().is_some() {
+    ///         match poller.finish_connect(&socket) {
+    ///             Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => continue,
+    ///             res => break res?,
+    ///         }
+    ///     }
+    /// }
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub unsafe fn add_connecting(&self, source: impl AsRawSource, key: usize) -> io::Result<()> {
+        self.add_with_mode(source, Event::writable(key), PollMode::Oneshot)
+    }
+
+    /// Checks whether the non-blocking `connect()` of a socket has completed.
+    ///
+    /// Call this when an event is delivered for a socket added with
+    /// [`add_connecting()`][`Poller::add_connecting()`]. It returns `Ok(())` if the connection
+    /// is established, and the error of the connection attempt, read with `SO_ERROR`, if it
+    /// failed. If the event was spurious and the connection is still in progress, the socket is
+    /// registered again with its interest and mode, and an error of kind
+    /// [`io::ErrorKind::WouldBlock`] is returned.
+    ///
+    /// The socket stays registered after the connection completes, so it can be
+    /// [modified][`Poller::modify()`] with the interest of the established connection.
+    pub fn finish_connect(&self, source: impl AsSource) -> io::Result<()> {
+        let source = source.source();
+        if let Some(err) = self.take_error(source)? {
+            return Err(err);
+        }
+        if is_connected(source)? {
+            return Ok(());
+        }
+
+        if let Some(registered) = self.registered(&source) {
+            self.modify_with_mode(source, registered.interest(), registered.mode())?;
+        }
+        Err(io::ErrorKind::WouldBlock.into())
+    }
+
+    /// Waits for at least one I/O event and returns the number of new events.
+    ///
+    /// New events will be appended to `events`. If necessary, make sure to clear the
+    /// [`Events`][Events::clear()] before calling [`wait()`][`Poller::wait()`]! This allows
+    /// accumulating the events of several waits, even on different pollers, before they are
+    /// dispatched.
+    ///
+    /// This method will return with no new events if a notification is delivered by the
+    /// [`notify()`] method, or the timeout is reached. Sometimes it may even return with no events
+    /// spuriously.
+    ///
+    /// Only one thread can wait on I/O. If another thread is already in [`wait()`], concurrent
+    /// calls to this method will return immediately with no new events. This can be changed with
+    /// [`PollerBuilder::concurrent_waits()`], in which case every thread waits with its own
+    /// [`Events`] and each event is delivered to only one of them.
+    ///
+    /// If the operating system is ready to deliver a large number of events at once, this method
+    /// may decide to deliver them in smaller batches.
+    ///
+    /// If the underlying system call is interrupted by a signal (`EINTR`), the wait is restarted
+    /// with the time remaining until the original timeout. The interruption itself is never
+    /// reported to the caller as [`ErrorKind::Interrupted`], nor does it cut the timeout short.
+    ///
+    /// Backends that take the timeout in milliseconds round it up, so a non-zero timeout
+    /// shorter than a millisecond still blocks instead of turning the wait into a busy loop.
+    /// Only a zero timeout checks for events without blocking.
+    ///
+    /// [`notify()`]: `Poller::notify()`
+    /// [`wait()`]: `Poller::wait()`
+    /// [`ErrorKind::Interrupted`]: `io::ErrorKind::Interrupted`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, Poller};
+    /// use std::net::TcpListener;
+    /// use std::time::Duration;
+    ///
+    /// let socket = TcpListener::bind("127.0.0.1:0")?;
+    /// socket.set_nonblocking(true)?;
+    /// let key = 7;
+    ///
+    /// let poller = Poller::new()?;
+    /// unsafe {
+    ///     poller.add(&socket, Event::all(key))?;
+    /// }
+    ///
+    /// let mut events = Events::new();
+    /// let n = poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn wait(&self, events: &mut Events, timeout: Option<Duration>) -> io::Result<usize> {
+        self.wait_impl(
+            events,
+            timeout.and_then(|timeout| Instant::now().checked_add(timeout)),
+        )
+    }
+
+    /// Waits for at least one I/O event and returns the number of new events, with a deadline.
+    ///
+    /// See [`wait()`][`Poller::wait()`] for more details.
+    pub fn wait_deadline(&self, events: &mut Events, deadline: Instant) -> io::Result<usize> {
+        self.wait_impl(events, Some(deadline))
+    }
+
+    /// Waits for at least one I/O event and returns the number of new events, taking at most
+    /// `limit` events from the operating system.
+    ///
+    /// This works like [`wait()`][`Poller::wait()`], but bounds the work done per call, for
+    /// instance to bound the latency of each iteration of a dispatcher, without changing the
+    /// capacity of `events`. Further events stay queued for the following calls. Events of
+    /// [`Waker`]s are delivered in addition to the limit, and an event of a source that was
+    /// added with [`add_split()`][`Poller::add_split()`] may be delivered to both halves.
+    ///
+    /// A limit above the capacity of `events` has no effect. A lower limit uses a smaller buffer,
+    /// which is kept in `events` and reused by later calls with the same limit.
+    ///
+    /// Sources that stay ready in level-triggered mode are reported in turn, so each of them is
+    /// eventually delivered even when more of them are ready than fit into the limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::num::NonZeroUsize;
+    /// use std::time::Duration;
+    ///
+    /// let a = UdpSocket::bind("127.0.0.1:0")?;
+    /// let b = UdpSocket::bind("127.0.0.1:0")?;
+    ///
+    /// let poller = Poller::new()?;
+    /// unsafe {
+    ///     poller.add(&a, Event::writable(1))?;
+    ///     poller.add(&b, Event::writable(2))?;
+    /// }
+    ///
+    /// let mut events = Events::new();
+    /// let limit = NonZeroUsize::new(1).unwrap();
+    /// poller.wait_limit(&mut events, Some(Duration::from_secs(1)), limit)?;
+    /// assert_eq!(events.len(), 1);
+    ///
+    /// poller.delete(&a)?;
+    /// poller.delete(&b)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn wait_limit(
+        &self,
+        events: &mut Events,
+        timeout: Option<Duration>,
+        limit: NonZeroUsize,
+    ) -> io::Result<usize> {
+        let full = events.limit(limit.get());
+        let res = self.wait(events, timeout);
+        events.unlimit(full);
+        res
+    }
+
+    /// Waits for at least one I/O event, and reports why the wait returned.
+    ///
+    /// This works like [`wait()`][`Poller::wait()`], but returns a [`WaitResult`] that tells
+    /// apart a wait that delivered events, one that was woken by [`notify()`][`Poller::notify()`]
+    /// and one whose timeout expired. It also tells whether the wait filled the buffer, in which
+    /// case more events may be pending.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, Poller};
+    /// use std::time::Duration;
+    ///
+    /// let poller = Poller::new()?;
+    /// let mut events = Events::new();
+    ///
+    /// let result = poller.wait_result(&mut events, Some(Duration::from_millis(10)))?;
+    /// assert!(result.is_timed_out());
+    ///
+    /// poller.notify()?;
+    /// let result = poller.wait_result(&mut events, None)?;
+    /// assert!(result.is_notified());
+    /// assert_eq!(result.events(), 0);
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn wait_result(
+        &self,
+        events: &mut Events,
+        timeout: Option<Duration>,
+    ) -> io::Result<WaitResult> {
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        let result =
+            self.wait_sys_result(events, deadline, true, None, |poller, events, deadline| {
+                poller.wait_deadline(events, deadline)
+            });
+        self.waiters.forward(self);
+        Ok(result?.unwrap_or_default())
+    }
+
+    /// Waits for at least one I/O event, until `token` is cancelled.
+    ///
+    /// This works like [`wait_result()`][`Poller::wait_result()`], but cancelling `token` from
+    /// another thread wakes up the wait, and makes it return with
+    /// [`WaitResult::is_cancelled()`]. The wait returns right away if the token is already
+    /// cancelled, with the events that are ready. This stops an event loop without going through
+    /// [`notify()`][`Poller::notify()`], whose wakeups are meant for the loop itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{CancelToken, Events, Poller};
+    /// use std::time::Duration;
+    ///
+    /// let poller = Poller::new()?;
+    /// let token = CancelToken::new();
+    /// let mut events = Events::new();
+    ///
+    /// let result = poller.wait_cancelable(&mut events, Some(Duration::from_millis(10)), &token)?;
+    /// assert!(result.is_timed_out());
+    ///
+    /// token.cancel();
+    /// let result = poller.wait_cancelable(&mut events, None, &token)?;
+    /// assert!(result.is_cancelled());
+    /// assert!(!result.is_notified());
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn wait_cancelable(
+        &self,
+        events: &mut Events,
+        timeout: Option<Duration>,
+        token: &CancelToken,
+    ) -> io::Result<WaitResult> {
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        let result = {
+            let _registered = token.register(self);
+            self.wait_sys_result(
+                events,
+                deadline,
+                true,
+                Some(token),
+                |poller, events, deadline| poller.wait_deadline(events, deadline),
+            )
+        };
+        self.waiters.forward(self);
+        Ok(result?.unwrap_or_else(|| WaitResult {
+            cancelled: token.is_cancelled(),
+            ..WaitResult::default()
+        }))
+    }
+
+    /// Waits for at least one I/O event from async code, and returns the number of new events.
+    ///
+    /// The returned future appends the events to `events` like [`wait()`][`Poller::wait()`]
+    /// without a timeout, and completes when there are events or the poller is notified. The
+    /// events that are ready when it is first polled are collected right away. Otherwise a helper
+    /// thread blocks on the poller and wakes up the task once it returns, so each pending future
+    /// takes up a thread. This bridges a loop that waits for readiness into an async program
+    /// without an async runtime that knows about the poller. Timeouts are left to the timers of
+    /// the runtime.
+    ///
+    /// Dropping the future before it completes wakes up its helper thread, and the events that
+    /// it collected are delivered by the next wait instead.
+    ///
+    /// This is only available with the `async` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::async_io::Driver;
+    /// use polling::{Events, Poller};
+    /// use std::sync::Arc;
+    ///
+    /// let poller = Arc::new(Poller::new()?);
+    /// let waker = poller.waker(7)?;
+    /// std::thread::spawn(move || waker.wake());
+    ///
+    /// let driver = Arc::new(Driver::new()?);
+    /// let mut events = Events::new();
+    /// while events.is_empty() {
+    ///     driver.block_on(poller.wait_async(&mut events))?;
+    /// }
+    /// assert_eq!(events.iter().next().unwrap().key, 7);
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub fn wait_async<'a>(
+        self: &'a Arc<Self>,
+        events: &'a mut Events,
+    ) -> impl std::future::Future<Output = io::Result<usize>> + 'a {
+        wait_async::WaitAsync::new(self, events)
+    }
+
+    /// Collects the events that are ready right now, without blocking.
+    ///
+    /// This is meant to be called right after a wait that filled the buffer, as reported by
+    /// [`WaitResult::may_have_more()`], to collect the events that didn't fit. Unlike a wait with
+    /// a zero timeout, it leaves a pending [notification][`Poller::notify()`] for the next wait,
+    /// so it can't swallow a wakeup. The new events are appended to `events`, along with those
+    /// of wakers and expired timers.
+    ///
+    /// Returns the number of new events, which is zero if another thread is waiting right now
+    /// and concurrent waits are not enabled.
+    ///
+    /// After the poller is [closed][`Poller::close()`], this only returns the events that were
+    /// set aside for the next wait, such as those of [`wait_for_key()`][`Poller::wait_for_key()`],
+    /// and an error that converts into [`PollError::Closed`] once there are none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::num::NonZeroUsize;
+    /// use std::time::Duration;
+    ///
+    /// let poller = Poller::new()?;
+    /// let a = poller.register(UdpSocket::bind("127.0.0.1:0")?, Event::writable(1))?;
+    /// let b = poller.register(UdpSocket::bind("127.0.0.1:0")?, Event::writable(2))?;
+    ///
+    /// let mut events = Events::with_capacity(NonZeroUsize::new(1).unwrap());
+    /// let result = poller.wait_result(&mut events, Some(Duration::from_secs(1)))?;
+    /// if result.may_have_more() {
+    ///     poller.drain(&mut events)?;
+    /// }
+    /// assert_eq!(events.len(), 2);
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn drain(&self, events: &mut Events) -> io::Result<usize> {
+        // A closed poller only hands out the events that were already set aside.
+        if self.is_closed() {
+            let mut deferred = self.deferred();
+            if deferred.is_empty() {
+                return Err(PollError::Closed.into());
+            }
+            let new = deferred.len();
+            events.list.append(&mut deferred);
+            return Ok(new);
+        }
+
+        let _lock = if self.concurrent_waits {
+            None
+        } else {
+            match self.lock.try_lock() {
+                Ok(lock) => Some(lock),
+                Err(_) => return Ok(0),
+            }
+        };
+
+        let before = events.len();
+        events.list.append(&mut self.deferred());
+        let deferred = before..events.len();
+
+        let mut retried = false;
+        loop {
+            let collected = events.len();
+            let res = self.poller.wait_deadline(events, Some(Instant::now()));
+            events.collect_new();
+            self.registry.disarm(&events.list[collected..]);
+            self.splits
+                .split_events(&self.poller, &mut events.list, collected);
+            if !deferred.is_empty() {
+                merge_deferred(&mut events.list, deferred.clone(), collected);
+            }
+            match res {
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                res => res?,
+            }
+
+            // Some backends return for a pending notification before checking the sources.
+            if events.len() == collected && self.notified.load(Ordering::SeqCst) && !retried {
+                retried = true;
+                continue;
+            }
+            break;
+        }
+
+        self.cancel_deadlines(&events.list[before..]);
+        self.delete_hung_up(&events.list[before..]);
+        self.woken.drain_into(&mut events.list);
+        self.timers.expire_into(&mut events.list, Event::readable);
+        self.expire_tick(&mut events.list);
+        self.expire_deadlines(&mut events.list);
+        if let Some(limit) = &self.rate_limit {
+            limit.apply(&mut events.list, before);
+        }
+        self.order_by_priority(&mut events.list[before..]);
+
+        // The check may have consumed the wakeup of a pending notification, which the next wait
+        // still has to see.
+        if self.notified.load(Ordering::SeqCst) {
+            self.poller.notify()?;
+        }
+
+        Ok(events.len() - before)
+    }
+
+    /// Waits for at least one I/O event and calls `f` for each new event.
+    ///
+    /// This works like [`wait()`][`Poller::wait()`], except that the events are collected into a
+    /// buffer owned by the poller instead of an [`Events`] owned by the caller. The buffer is
+    /// allocated on first use with the capacity of [`new_events()`][`Poller::new_events()`] and
+    /// reused afterwards. If it's already in use by another thread, or by a call to this method
+    /// from within `f`, a temporary buffer is used instead.
+    ///
+    /// Returns the number of events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Poller};
+    /// use std::net::TcpListener;
+    /// use std::time::Duration;
+    ///
+    /// let socket = TcpListener::bind("127.0.0.1:0")?;
+    /// socket.set_nonblocking(true)?;
+    /// let key = 7;
+    ///
+    /// let poller = Poller::new()?;
+    /// unsafe {
+    ///     poller.add(&socket, Event::all(key))?;
+    /// }
+    ///
+    /// poller.wait_with(Some(Duration::from_secs(1)), |event| {
+    ///     println!("event: {:?}", event);
+    /// })?;
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn wait_with(&self, timeout: Option<Duration>, f: impl FnMut(Event)) -> io::Result<usize> {
+        self.wait_buffered(timeout, None, |events| events.iter().for_each(f))
+    }
+
+    /// Waits for at least one I/O event and appends the new events to `events`.
+    ///
+    /// This works like [`wait()`][`Poller::wait()`], except that `events` can be any collection
+    /// that implements [`Extend`], such as a `Vec<Event>`. The events are collected into a buffer
+    /// owned by the poller first, like with [`wait_with()`][`Poller::wait_with()`].
+    ///
+    /// Returns the number of new events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Poller};
+    /// use std::net::TcpListener;
+    /// use std::time::Duration;
+    ///
+    /// let socket = TcpListener::bind("127.0.0.1:0")?;
+    /// socket.set_nonblocking(true)?;
+    /// let key = 7;
+    ///
+    /// let poller = Poller::new()?;
+    /// unsafe {
+    ///     poller.add(&socket, Event::all(key))?;
+    /// }
+    ///
+    /// let mut events = Vec::new();
+    /// poller.wait_into(&mut events, Some(Duration::from_secs(1)))?;
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn wait_into(
+        &self,
+        events: &mut impl Extend<Event>,
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        self.wait_buffered(timeout, None, |buffer| events.extend(buffer.iter()))
+    }
+
+    /// Waits for at least one I/O event and appends the new events to `events`, without
+    /// allocating.
+    ///
+    /// This works like [`wait_into()`][`Poller::wait_into()`], but delivers at most as many
+    /// events as still fit into the [`ArrayEvents`]. The buffer owned by the poller is allocated
+    /// on first use and reused afterwards, so in the steady state the wait doesn't allocate. If
+    /// more events are ready, they are delivered by the next wait. If `events` is already full,
+    /// this returns right away.
+    ///
+    /// Returns the number of new events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{ArrayEvents, Event, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::time::Duration;
+    ///
+    /// let a = UdpSocket::bind("127.0.0.1:0")?;
+    /// let b = UdpSocket::bind("127.0.0.1:0")?;
+    /// let poller = Poller::new()?;
+    /// unsafe {
+    ///     poller.add(&a, Event::writable(1))?;
+    ///     poller.add(&b, Event::writable(2))?;
+    /// }
+    ///
+    /// // Only one event fits, the other one is delivered by the next wait.
+    /// let mut events = ArrayEvents::<1>::new();
+    /// poller.wait_array(&mut events, Some(Duration::from_secs(1)))?;
+    /// assert!(events.is_full());
+    ///
+    /// events.clear();
+    /// poller.wait_array(&mut events, Some(Duration::from_secs(1)))?;
+    /// assert_eq!(events.len(), 1);
+    /// # poller.delete(&a)?;
+    /// # poller.delete(&b)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn wait_array<const N: usize>(
+        &self,
+        events: &mut ArrayEvents<N>,
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        let room = match NonZeroUsize::new(events.room()) {
+            Some(room) => room,
+            None => return Ok(0),
+        };
+
+        let before = events.len();
+        self.wait_buffered(timeout, Some(room), |buffer| {
+            let mut iter = buffer.iter();
+            iter.by_ref()
+                .take(room.get())
+                .for_each(|ev| events.push(ev));
+
+            // Split sources and set-aside events can exceed the limit. Keep the rest for the
+            // next wait.
+            let rest = iter.collect::<Vec<_>>();
+            if !rest.is_empty() {
+                self.deferred().splice(0..0, rest);
+            }
+        })?;
+        Ok(events.len() - before)
+    }
+
+    /// Waits for at most `limit` events in the buffer owned by the poller, and passes them to
+    /// `f`.
+    fn wait_buffered(
+        &self,
+        timeout: Option<Duration>,
+        limit: Option<NonZeroUsize>,
+        f: impl FnOnce(&Events),
+    ) -> io::Result<usize> {
+        let mut scratch = self.scratch.try_lock().ok();
+        let mut temporary = None;
+        let events = match scratch.as_deref_mut() {
+            Some(scratch) => scratch.get_or_insert_with(|| self.new_events()),
+            None => temporary.insert(self.new_events()),
+        };
+
+        events.clear();
+        let full = limit.and_then(|limit| events.limit(limit.get()));
+        let res = self.wait(events, timeout);
+        events.unlimit(full);
+        f(events);
+        events.clear();
+        res
+    }
+
+    /// Waits until the source with the given key reports readiness, and returns its event.
+    ///
+    /// Events of other keys that arrive in the meantime are set aside, and delivered by the next
+    /// wait. This is handy during startup, to wait until a particular source is ready before
+    /// entering the event loop, and in tests.
+    ///
+    /// Returns `None` if the timeout elapses, the poller is [notified][`Poller::notify()`], or
+    /// another thread is already waiting and concurrent waits are not enabled. If the source
+    /// is reported more than once by the same wait, the other events are set aside as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::time::Duration;
+    ///
+    /// let poller = Poller::new()?;
+    /// let a = poller.register(UdpSocket::bind("127.0.0.1:0")?, Event::writable(1))?;
+    /// let b = poller.register(UdpSocket::bind("127.0.0.1:0")?, Event::writable(2))?;
+    ///
+    /// let event = poller.wait_for_key(2, Some(Duration::from_secs(1)))?;
+    /// assert_eq!(event.unwrap().key, 2);
+    ///
+    /// // The event of the other socket is delivered by the next wait.
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, Some(Duration::ZERO))?;
+    /// assert_eq!(events.iter().next().unwrap().key, 1);
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn wait_for_key(&self, key: usize, timeout: Option<Duration>) -> io::Result<Option<Event>> {
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+
+        // Take the events that are already set aside, so that waiting doesn't deliver them again.
+        let mut deferred = mem::take(&mut *self.deferred());
+        let mut result = Ok(take_key(&mut deferred, key));
+
+        let mut events = self.new_events();
+        while let Ok(None) = result {
+            events.clear();
+            let res = self.wait_sys_result(
+                &mut events,
+                deadline,
+                true,
+                None,
+                |poller, events, deadline| poller.wait_deadline(events, deadline),
+            );
+            self.waiters.forward(self);
+
+            let res = match res {
+                Ok(Some(res)) => res,
+                Ok(None) => break,
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            };
+            deferred.append(&mut events.list);
+            result = Ok(take_key(&mut deferred, key));
+            if res.is_notified()
+                || res.is_timed_out()
+                || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                break;
+            }
+        }
+
+        // Put the events back in front of the ones set aside by other threads in the meantime.
+        let mut guard = self.deferred();
+        deferred.append(&mut guard);
+        *guard = deferred;
+        result
+    }
+
+    /// Lock the events set aside by `wait_for_key()`, ignoring poisoning.
+    fn deferred(&self) -> std::sync::MutexGuard<'_, Vec<Event>> {
+        self.deferred.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn wait_impl(&self, events: &mut Events, deadline: Option<Instant>) -> io::Result<usize> {
+        self.wait_sys(events, deadline, true, |poller, events, deadline| {
+            poller.wait_deadline(events, deadline)
+        })
+    }
+
+    /// Wait for events with `wait`, which waits on the backend until the deadline it is given.
+    ///
+    /// If `retry_interrupted` is not set, interruptions by signals are returned as errors.
+    pub(crate) fn wait_sys(
+        &self,
+        events: &mut Events,
+        deadline: Option<Instant>,
+        retry_interrupted: bool,
+        wait: impl FnMut(&sys::Poller, &mut sys::Events, Option<Instant>) -> io::Result<()>,
+    ) -> io::Result<usize> {
+        let result = self.wait_sys_result(events, deadline, retry_interrupted, None, wait);
+        self.waiters.forward(self);
+        match result? {
+            Some(_) => Ok(events.len()),
+            None => Ok(0),
+        }
+    }
+
+    /// Delay `deadline` to the next multiple of the timer slack, unless it is already due.
+    fn coalesce(&self, deadline: Instant, now: Instant) -> Instant {
+        static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+        let slack = match self.timer_slack {
+            Some(slack) if deadline > now => slack.as_nanos(),
+            _ => return deadline,
+        };
+        let epoch = *EPOCH.get_or_init(|| now);
+        let offset = match deadline.checked_duration_since(epoch) {
+            Some(offset) => offset.as_nanos(),
+            None => return deadline,
+        };
+        let delay = (slack - offset % slack) % slack;
+        deadline
+            .checked_add(Duration::from_nanos(delay as u64))
+            .unwrap_or(deadline)
+    }
+
+    /// Like `wait_sys()`, but reports why the wait returned, or `None` if it was skipped
+    /// because another thread is already waiting.
+    ///
+    /// The wait returns early if `cancel` is cancelled.
+    pub(crate) fn wait_sys_result(
+        &self,
+        events: &mut Events,
+        deadline: Option<Instant>,
+        retry_interrupted: bool,
+        cancel: Option<&CancelToken>,
+        wait: impl FnMut(&sys::Poller, &mut sys::Events, Option<Instant>) -> io::Result<()>,
+    ) -> io::Result<Option<WaitResult>> {
+        let res = self.wait_sys_inner(events, deadline, retry_interrupted, cancel, wait);
+        #[cfg(feature = "stall-diagnostics")]
+        if !matches!(res, Ok(None)) {
+            let notified = matches!(res, Ok(Some(result)) if result.is_notified());
+            if let Some(stall) = self
+                .stalls
+                .wait_finished(deadline, self.timer_slack, notified)
+            {
+                self.report_stall(stall);
+            }
+        }
+        if let Some(hooks) = self.hooks.get() {
+            match res {
+                Ok(Some(result)) => hooks.on_wait(Ok(result)),
+                Ok(None) => {}
+                Err(ref err) => hooks.on_wait(Err(err)),
+            }
+        }
+        res
+    }
+
+    /// Waits for events, see `wait_sys_result()`.
+    fn wait_sys_inner(
+        &self,
+        events: &mut Events,
+        deadline: Option<Instant>,
+        retry_interrupted: bool,
+        cancel: Option<&CancelToken>,
+        mut wait: impl FnMut(&sys::Poller, &mut sys::Events, Option<Instant>) -> io::Result<()>,
+    ) -> io::Result<Option<WaitResult>> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!(
+            "Poller::wait",
+            backend = %self.backend(),
+            label = self.label(),
+            ?deadline,
+        );
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
+        self.check_open()?;
+
+        // Unless concurrent waits are enabled, only one thread may wait on I/O.
+        let _lock = if self.concurrent_waits {
+            None
+        } else {
+            match self.lock.try_lock() {
+                Ok(lock) => Some(lock),
+                Err(_) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(
+                        "wait: skipping because another thread is already waiting on I/O"
+                    );
+                    return Ok(None);
+                }
+            }
+        };
+        #[cfg(feature = "stall-diagnostics")]
+        self.stalls.wait_started();
+
+        let before = events.len();
+
+        // Deliver the events set aside by `wait_for_key()` and the ready sources reported when
+        // they were added, and only check for new ones.
+        let deadline = {
+            let mut deferred = self.deferred();
+            if deferred.is_empty() {
+                deadline
+            } else {
+                events.list.append(&mut deferred);
+                Some(Instant::now())
+            }
+        };
+        let deferred = before..events.len();
+
+        // A sticky notification makes every wait return right away until it is cleared.
+        let deadline = if self.sticky_notify && self.notified.load(Ordering::SeqCst) {
+            Some(Instant::now())
+        } else {
+            deadline
+        };
+
+        // A cancelled wait only collects the events that are ready.
+        let is_cancelled = || cancel.is_some_and(CancelToken::is_cancelled);
+        let deadline = if is_cancelled() {
+            Some(Instant::now())
+        } else {
+            deadline
+        };
+
+        // Busy-poll until this instant before blocking.
+        let spin_until = self
+            .spin_budget()
+            .and_then(|spin| Instant::now().checked_add(spin))
+            .map(|until| deadline.map_or(until, |deadline| deadline.min(until)));
+
+        // Whether more events are being collected after a wait filled the buffer.
+        let mut draining = false;
+
+        // Whether this wait busy-polled, and whether it then blocked, to adapt the budget.
+        let mut spun = false;
+        let mut blocked = false;
+
+        loop {
+            // Wait for I/O events. While busy-polling or draining, only check for events without
+            // blocking.
+            let now = Instant::now();
+            let spinning = draining || spin_until.is_some_and(|until| now < until);
+            let collected = events.len();
+
+            // Wake up for the earliest timer, if it is due before the deadline, and at least once
+            // every `MAX_WAIT`.
+            let timer = self.next_timer();
+            let wake_at = match (deadline, timer) {
+                (Some(deadline), Some(timer)) => Some(deadline.min(timer)),
+                (deadline, timer) => deadline.or(timer),
+            };
+            let wake_at = match (wake_at, now.checked_add(MAX_WAIT)) {
+                (Some(wake_at), Some(max)) => Some(wake_at.min(max)),
+                (wake_at, _) => wake_at,
+            };
+            let until = if spinning {
+                spun |= !draining;
+                Some(now)
+            } else {
+                wake_at.map(|wake_at| self.coalesce(wake_at, now))
+            };
+            blocked |= until.map_or(true, |until| until > now);
+            let res = match &self.poller {
+                custom::Driver::Native(poller) => wait(poller, &mut events.events, until),
+                custom::Driver::Custom(backend) => backend.wait(&mut events.list, until),
+            };
+            if self.is_closed() {
+                // Pass the wakeup on to the next thread that is blocked in the backend.
+                #[cfg(feature = "tracing")]
+                tracing::trace!("wait: the poller was closed");
+                self.poller.notify().ok();
+
+                // Keep the events that were set aside, so that `drain()` can still collect them.
+                if !deferred.is_empty() {
+                    self.deferred()
+                        .splice(0..0, events.list.drain(deferred.clone()));
+                }
+                events.list.truncate(before);
+                return Err(PollError::Closed.into());
+            }
+            events.collect_new();
+            self.registry.disarm(&events.list[collected..]);
+            self.splits
+                .split_events(&self.poller, &mut events.list, collected);
+            if !deferred.is_empty() {
+                merge_deferred(&mut events.list, deferred.clone(), collected);
+            }
+            if let Err(e) = res {
+                // If the wait was interrupted by a signal, try again. The backend computes
+                // the remaining timeout from the deadline, so the total wait time is kept.
+                //
+                // Don't clear `events` here: the backends don't add anything on failure,
+                // and the caller may have accumulated events from earlier calls.
+                if e.kind() == io::ErrorKind::Interrupted && retry_interrupted {
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!("wait: interrupted by a signal, retrying");
+                    continue;
+                } else {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(err = %e, "wait: failed to wait for events");
+                    return Err(e);
+                }
+            }
+
+            if spinning
+                && !draining
+                && events.len() == before
+                && !self.notified.load(Ordering::SeqCst)
+                && !is_cancelled()
+            {
+                std::hint::spin_loop();
+                continue;
+            }
+
+            // Keep collecting while the checks fill the buffer, until a source shows up twice.
+            draining = self.drain
+                && events.filled
+                && !events.limiting
+                && !repeats_key(&events.list[before..]);
+            if draining {
+                continue;
+            }
+
+            // Deliver the keys woken by wakers, and the expired timers and deadlines.
+            self.cancel_deadlines(&events.list[before..]);
+            self.delete_hung_up(&events.list[before..]);
+            self.woken.drain_into(&mut events.list);
+            self.timers.expire_into(&mut events.list, Event::readable);
+            self.expire_tick(&mut events.list);
+            self.expire_deadlines(&mut events.list);
+            if let Some(limit) = &self.rate_limit {
+                limit.apply(&mut events.list, before);
+            }
+            self.order_by_priority(&mut events.list[before..]);
+
+            // A timer that was cancelled or moved during the wait may have cut it short, as does
+            // the limit of a single wait on the backend for long timeouts. With
+            // `retry_spurious`, so may any other wakeup that delivered nothing.
+            if (wake_at != deadline || self.retry_spurious)
+                && events.len() == before
+                && !self.notified.load(Ordering::SeqCst)
+                && !is_cancelled()
+                && deadline.map_or(true, |deadline| Instant::now() < deadline)
+            {
+                continue;
+            }
+
+            // Clear the notification, if any, unless it stays until `clear_notification()`.
+            let notified = if self.sticky_notify {
+                self.notified.load(Ordering::SeqCst)
+            } else {
+                self.notified.swap(false, Ordering::SeqCst)
+            };
+
+            #[cfg(feature = "tracing")]
+            tracing::trace!(events = events.len(), notified, "wait: done");
+
+            let new = events.len() - before;
+            let cancelled = is_cancelled();
+            let timed_out = new == 0
+                && !notified
+                && !cancelled
+                && deadline.is_some_and(|deadline| Instant::now() >= deadline);
+
+            // Spin for longer after busy-polling caught the events, and for less after it had to
+            // block anyway.
+            if let Some(spin) = &self.adaptive_spin {
+                if blocked {
+                    spin.miss();
+                } else if spun && (new > 0 || notified) {
+                    spin.hit();
+                }
+            }
+
+            #[cfg(feature = "metrics")]
+            self.counters
+                .wait(new, new == 0 && !notified && !timed_out && !cancelled);
+
+            return Ok(Some(WaitResult {
+                events: new,
+                notified,
+                timed_out,
+                cancelled,
+                filled: events.filled,
+            }));
+        }
+    }
+
+    /// Wakes up the current or the following invocation of [`wait()`].
+    ///
+    /// If no thread is calling [`wait()`] right now, this method will cause the following call
+    /// to wake up immediately. If several threads are waiting because concurrent waits are
+    /// enabled, at least one of them wakes up.
+    ///
+    /// This method may allocate and calls the [hooks] of the poller, so it must not be called
+    /// from a signal handler. Use [`notify_from_signal()`][`Poller::notify_from_signal()`]
+    /// there instead.
+    ///
+    /// [`wait()`]: `Poller::wait()`
+    /// [hooks]: `PollerBuilder::hooks()`
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if the wakeup can't be sent, for example because the notification
+    /// mechanism of the backend was closed. The notification is then not pending, so the next
+    /// call tries again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, Poller};
+    ///
+    /// let poller = Poller::new()?;
+    ///
+    /// // Notify the poller.
+    /// poller.notify()?;
+    ///
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, None)?; // wakes up immediately
+    /// assert!(events.is_empty());
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn notify(&self) -> io::Result<()> {
+        self.try_notify().map(drop)
+    }
+
+    /// Wakes up the current or the following invocation of [`wait()`], and returns whether this
+    /// call sent the wakeup.
+    ///
+    /// This is like [`notify()`][`Poller::notify()`], but returns `true` if this call woke up
+    /// the poller, and `false` if a notification was already pending, in which case nothing is
+    /// sent to the operating system. Callers that hand work to the waiting thread can skip
+    /// further wakeups until it has run.
+    ///
+    /// [`wait()`]: `Poller::wait()`
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if the wakeup can't be sent, like [`notify()`][`Poller::notify()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, Poller};
+    ///
+    /// let poller = Poller::new()?;
+    ///
+    /// // The second notification is redundant.
+    /// assert!(poller.try_notify()?);
+    /// assert!(!poller.try_notify()?);
+    ///
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, None)?; // wakes up immediately
+    /// assert!(events.is_empty());
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn try_notify(&self) -> io::Result<bool> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!(
+            "Poller::notify",
+            backend = %self.backend(),
+            label = self.label(),
+        );
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
+        if !self.notifications {
+            return Err(unsupported_error(
+                "notifications are disabled for this poller",
+            ));
+        }
+
+        if self
+            .notified
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            #[cfg(feature = "stall-diagnostics")]
+            self.stalls.notified();
+            let res = self.poller.notify();
+            #[cfg(feature = "metrics")]
+            if res.is_ok() {
+                self.counters.notification();
+            }
+
+            if let Err(ref _err) = res {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(err = %_err, "notify: failed to wake up the poller");
+                self.notified.store(false, Ordering::SeqCst);
+            }
+            if let Some(hooks) = self.hooks.get() {
+                hooks.on_notify(res.as_ref().copied());
+            }
+            res.map(|()| true)
+        } else {
+            #[cfg(feature = "tracing")]
+            tracing::trace!("notify: a notification is already pending");
+            #[cfg(feature = "stall-diagnostics")]
+            if let Some(stall) = self.stalls.notified_again() {
+                self.report_stall(stall);
+            }
+            if let Some(hooks) = self.hooks.get() {
+                hooks.on_notify(Ok(()));
+            }
+            Ok(false)
+        }
+    }
+
+    /// Wakes up the current or the following invocation of [`wait()`] from a signal handler.
+    ///
+    /// This is like [`notify()`][`Poller::notify()`], but it is async-signal-safe: it doesn't
+    /// allocate, take locks, call the [hooks] of the poller or record statistics, and makes
+    /// at most one system call, which writes to an eventfd or a pipe, triggers a kqueue user
+    /// filter, or sends to an event port. On Windows, where console control handlers run on a
+    /// thread of their own, it posts a completion packet like [`notify()`][`Poller::notify()`].
+    ///
+    /// Errors are ignored, since a signal handler can't report them. The system call may change
+    /// `errno`, which the signal handler should save and restore if the interrupted code relies
+    /// on it. To reach the poller from the handler, it is usually kept in a `static`.
+    ///
+    /// [`wait()`]: `Poller::wait()`
+    /// [hooks]: `PollerBuilder::hooks()`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, Poller};
+    /// use std::sync::OnceLock;
+    ///
+    /// static POLLER: OnceLock<Poller> = OnceLock::new();
+    ///
+    /// // Installed as the handler of a signal.
+    /// extern "C" fn handle(_signal: std::os::raw::c_int) {
+    ///     if let Some(poller) = POLLER.get() {
+    ///         poller.notify_from_signal();
+    ///     }
+    /// }
+    ///
+    /// let poller = POLLER.get_or_init(|| Poller::new().unwrap());
+    /// handle(0);
+    ///
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, None)?; // wakes up immediately
+    /// assert!(events.is_empty());
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn notify_from_signal(&self) {
+        if self.notifications
+            && self
+                .notified
+                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+        {
+            self.poller.notify_from_signal();
+        }
+    }
+
+    /// Returns `true` if a notification is pending.
+    ///
+    /// This is set by [`notify()`][`Poller::notify()`] and cleared when a wait returns for the
+    /// notification, or by [`clear_notification()`][`Poller::clear_notification()`]. It only
+    /// reads a flag, so a scheduler can check it before going to sleep: if it is set, the next
+    /// wait returns right away, and handling more local work first saves a round trip through
+    /// the operating system. Another thread may notify the poller right after this returns
+    /// `false`, which the next wait sees as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, Poller};
+    ///
+    /// let poller = Poller::new()?;
+    /// assert!(!poller.is_notified());
+    ///
+    /// poller.notify()?;
+    /// assert!(poller.is_notified());
+    ///
+    /// poller.wait(&mut Events::new(), None)?;
+    /// assert!(!poller.is_notified());
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn is_notified(&self) -> bool {
+        self.notified.load(Ordering::SeqCst)
+    }
+
+    /// Shuts the poller down and wakes up every thread that is waiting on it.
+    ///
+    /// Every current and future call to [`wait()`][`Poller::wait()`] and the other waiting
+    /// methods returns an error that converts into [`PollError::Closed`], and so does every
+    /// attempt to add a source. This lets threads that share a poller stop together, without a
+    /// flag of their own and a notification for each of them. Sources that are still registered
+    /// can be modified and deleted as usual, so they can be cleaned up afterwards.
+    ///
+    /// Closing is permanent and closing again has no effect. The operating system resources are
+    /// released when the poller is dropped. Events that were set aside for the next wait can
+    /// still be collected with [`drain()`][`Poller::drain()`].
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if the waiting threads can't be woken up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, PollError, Poller};
+    /// use std::sync::Arc;
+    /// use std::thread;
+    ///
+    /// let poller = Arc::new(Poller::new()?);
+    /// let waiter = thread::spawn({
+    ///     let poller = poller.clone();
+    ///     move || poller.wait(&mut Events::new(), None)
+    /// });
+    ///
+    /// poller.close()?;
+    /// let err = waiter.join().unwrap().unwrap_err();
+    /// assert!(matches!(PollError::from(err), PollError::Closed));
+    /// assert!(poller.is_closed());
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn close(&self) -> io::Result<()> {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(backend = %self.backend(), label = self.label(), "Poller::close");
+
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        // Each thread that wakes up passes the wakeup on, until all of them have returned.
+        self.poller.notify()
+    }
+
+    /// Returns `true` if the poller was [closed][`Poller::close()`].
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Refuses new sources, while the registered ones keep delivering events.
+    ///
+    /// This is meant for draining connections before a restart: after sealing, adding a source
+    /// returns an error that converts into [`PollError::Sealed`], while the sources that are
+    /// already registered can be modified, waited on and deleted as usual. Once the last of them
+    /// is deleted, [`is_drained()`][`Poller::is_drained()`] returns `true` and the current or next
+    /// [`wait()`][`Poller::wait()`] is [notified][`Poller::notify()`], so an event loop can check
+    /// for it after every wait.
+    ///
+    /// Sealing is permanent and sealing again has no effect. [Timers][`Poller::set_timer()`] and
+    /// [wakers][`Poller::waker()`] keep working, since they are not registered sources.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, PollError, Poller};
+    /// use std::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// let poller = Poller::new()?;
+    /// unsafe { poller.add(&socket, Event::writable(1))? };
+    ///
+    /// poller.seal();
+    /// let other = UdpSocket::bind("127.0.0.1:0")?;
+    /// let err = unsafe { poller.add(&other, Event::writable(2)) }.unwrap_err();
+    /// assert!(matches!(PollError::from(err), PollError::Sealed));
+    ///
+    /// let mut events = Events::new();
+    /// while !poller.is_drained() {
+    ///     events.clear();
+    ///     poller.wait(&mut events, None)?;
+    ///     for ev in events.iter() {
+    ///         // Finish the work of the source, then delete it.
+    ///         assert_eq!(ev.key, 1);
+    ///         poller.delete(&socket)?;
+    ///     }
+    /// }
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn seal(&self) {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(backend = %self.backend(), label = self.label(), "Poller::seal");
+
+        if !self.sealed.swap(true, Ordering::SeqCst) {
+            self.notify_if_drained();
+        }
+    }
+
+    /// Returns `true` if the poller was [sealed][`Poller::seal()`].
+    #[inline]
+    pub fn is_sealed(&self) -> bool {
+        self.sealed.load(Ordering::SeqCst)
+    }
+
+    /// Returns `true` if the poller was [sealed][`Poller::seal()`] and has no registered sources
+    /// left.
+    #[inline]
+    pub fn is_drained(&self) -> bool {
+        self.is_sealed() && self.registered_len() == 0
+    }
+
+    /// Clears a pending notification without waiting.
+    ///
+    /// This discards a notification that no wait has returned for yet, so that the next call to
+    /// [`wait()`][`Poller::wait()`] blocks as usual. The wakeup that is pending in the backend is
+    /// consumed with a non-blocking check for events, and the events found by it are kept for the
+    /// next wait. If another thread is waiting right now, it may still return early once. With
+    /// [sticky notifications][`PollerBuilder::sticky_notify()`], this ends the notification.
+    ///
+    /// Returns `true` if the poller was notified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, Poller};
+    /// use std::time::Duration;
+    ///
+    /// let poller = Poller::new()?;
+    /// poller.notify()?;
+    /// assert!(poller.clear_notification()?);
+    /// assert!(!poller.clear_notification()?);
+    ///
+    /// // The stale notification doesn't wake up the wait.
+    /// let mut events = Events::new();
+    /// let result = poller.wait_result(&mut events, Some(Duration::from_millis(10)))?;
+    /// assert!(result.is_timed_out());
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn clear_notification(&self) -> io::Result<bool> {
+        if !self.notified.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+
+        // A thread that is waiting right now consumes the wakeup itself.
+        if let Ok(_lock) = self.lock.try_lock() {
+            let mut scratch = self.scratch.try_lock().ok();
+            let mut temporary = None;
+            let events = match scratch.as_deref_mut() {
+                Some(scratch) => scratch.get_or_insert_with(|| self.new_events()),
+                None => temporary.insert(self.new_events()),
+            };
+
+            events.clear();
+            let res = self.poller.wait_deadline(events, Some(Instant::now()));
+            events.collect_new();
+            self.registry.disarm(&events.list);
+            self.splits.split_events(&self.poller, &mut events.list, 0);
+            self.deferred().append(&mut events.list);
+            match res {
+                Err(err) if err.kind() != io::ErrorKind::Interrupted => return Err(err),
+                _ => {}
+            }
+        }
+
+        Ok(self.notified.swap(false, Ordering::SeqCst))
+    }
+
+    /// Create a [`Waker`] that delivers an event for `key`.
+    ///
+    /// Waking it marks `key` as ready and [notifies][`Poller::notify()`] the poller. The current
+    /// or the following call to [`wait()`][`Poller::wait()`] then delivers a readable event with
+    /// this key, once no matter how many times the waker was woken before. The key doesn't need
+    /// to belong to a registered source, which makes it possible to drive futures from the same
+    /// loop as I/O events.
+    ///
+    /// The waker doesn't keep the poller alive. Waking it after the poller is dropped does
+    /// nothing.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if `key` is the [notification key][`Poller::notify_key()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, Poller};
+    /// use std::sync::Arc;
+    ///
+    /// let poller = Arc::new(Poller::new()?);
+    /// let waker = poller.waker(7)?;
+    ///
+    /// std::thread::spawn(move || waker.wake());
+    ///
+    /// let mut events = Events::new();
+    /// while events.is_empty() {
+    ///     poller.wait(&mut events, None)?;
+    /// }
+    /// assert_eq!(events.iter().next().unwrap().key, 7);
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn waker(self: &Arc<Self>, key: usize) -> io::Result<Waker> {
+        self.check_key(key)?;
+        Ok(Waker::from(Arc::new(waker::KeyWaker {
+            poller: Arc::downgrade(self),
+            key,
+        })))
+    }
+
+    /// Create a virtual source with `interest`, which user code marks as ready.
+    ///
+    /// The source is not backed by a file descriptor or socket. Its events are delivered by
+    /// [`wait()`][`Poller::wait()`] with the key of `interest` once they are marked with
+    /// [`VirtualSource::set_ready()`], which wakes up the poller. The key doesn't need to be
+    /// distinct from the keys of registered sources, but is usually chosen to be.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if the key of `interest` is the
+    /// [notification key][`Poller::notify_key()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, Interest, Poller};
+    /// use std::sync::Arc;
+    ///
+    /// let poller = Arc::new(Poller::new()?);
+    /// let queue = poller.virtual_source(Event::readable(3))?;
+    ///
+    /// std::thread::spawn(move || queue.set_ready(Interest::READABLE));
+    ///
+    /// let mut events = Events::new();
+    /// while events.is_empty() {
+    ///     poller.wait(&mut events, None)?;
+    /// }
+    /// assert_eq!(events.iter().next().unwrap().with_no_extra(), Event::readable(3));
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn virtual_source(self: &Arc<Self>, interest: Event) -> io::Result<VirtualSource> {
+        self.check_key(interest.key)?;
+        Ok(VirtualSource::new(Arc::downgrade(self), interest))
+    }
+
+    /// Create a [`Waiter`] that can be notified without waking up other threads.
+    ///
+    /// Each thread that waits on a shared poller creates its own waiter and waits with
+    /// [`Waiter::wait()`]. A [`WaiterNotifier`] then wakes up that thread in particular, while
+    /// [`notify()`][`Poller::notify()`] still wakes up any of them. See [`Waiter`] for an example.
+    pub fn waiter(self: &Arc<Self>) -> Waiter {
+        Waiter::new(self.clone())
+    }
+
+    /// Set a timer that delivers a readable event with `key` at `deadline`.
+    ///
+    /// Timers are kept by the poller: any number of them share the timeout of
+    /// [`wait()`][`Poller::wait()`], which wakes up for the earliest one and delivers an event for
+    /// every timer that expired. A timer fires once and is then removed. Each key has at most one
+    /// timer, so setting the timer of a key again moves its deadline. The key doesn't need to
+    /// belong to a registered source.
+    ///
+    /// If the new timer expires before all other timers, a thread that is waiting right now is
+    /// [notified][`Poller::notify()`], so that it wakes up in time.
+    ///
+    /// The time left until `deadline` is measured with the [clock][`PollerBuilder::clock()`] of
+    /// the poller, from the moment the timer is set. With [`Clock::Boottime`], the time spent
+    /// suspended counts towards it, so a watchdog timer fires on time after the system resumes
+    /// rather than being extended by the suspend.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if `key` is the [notification key][`Poller::notify_key()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, Poller};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let poller = Poller::new()?;
+    /// poller.set_timer(1, Instant::now() + Duration::from_millis(10))?;
+    ///
+    /// let mut events = Events::new();
+    /// while events.is_empty() {
+    ///     poller.wait(&mut events, None)?;
+    /// }
+    /// assert_eq!(events.iter().next().unwrap().key, 1);
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn set_timer(&self, key: usize, deadline: Instant) -> io::Result<()> {
+        self.check_key(key)?;
+        if self.timers.insert(key, deadline) {
+            self.notify()?;
+        }
+        Ok(())
+    }
+
+    /// Cancel the timer of `key`.
+    ///
+    /// Returns `false` if `key` has no pending timer, for example because it already fired.
+    pub fn cancel_timer(&self, key: usize) -> bool {
+        self.timers.remove(key)
+    }
+
+    /// The number of timers that haven't fired yet.
+    pub fn pending_timers(&self) -> usize {
+        self.timers.len()
+    }
+
+    /// Set a deadline by which a registered source has to be ready.
+    ///
+    /// If the source doesn't report an event by `deadline`, a wait delivers an event with its
+    /// key that is neither readable nor writable, and for which [`Event::is_timeout()`] returns
+    /// `true`. An event of the source before then cancels the deadline, so an idle timeout of
+    /// a connection is set again after every event. Each source has at most one deadline:
+    /// setting it again moves it, and `None` cancels it. Deadlines are kept apart from the
+    /// [timers][`Poller::set_timer()`] of the same key.
+    ///
+    /// The deadline belongs to the key of the source when it is set. It is dropped if the
+    /// source is deleted or changes its key before then.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if `source` is not registered in this poller.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// let poller = Poller::new()?;
+    /// unsafe {
+    ///     poller.add(&socket, Event::readable(1))?;
+    /// }
+    /// poller.set_deadline(&socket, Some(Instant::now() + Duration::from_millis(10)))?;
+    ///
+    /// // Nothing is sent to the socket.
+    /// let mut events = Events::new();
+    /// while events.is_empty() {
+    ///     poller.wait(&mut events, None)?;
+    /// }
+    /// let event = events.iter().next().unwrap();
+    /// assert_eq!(event.key, 1);
+    /// assert!(event.is_timeout());
+    ///
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn set_deadline(&self, source: impl AsSource, deadline: Option<Instant>) -> io::Result<()> {
+        let raw = (&source.source()).raw();
+        self.check_registered(raw)?;
+        let key = match self.registry.get(raw) {
+            Some(registered) => registered.interest().key,
+            None => return Err(PollError::NotRegistered.into()),
+        };
+
+        match deadline {
+            Some(deadline) => {
+                self.deadline_sources().insert(key, raw);
+                if self.deadlines.insert(key, deadline) {
+                    self.notify()?;
+                }
+            }
+            None => {
+                self.deadlines.remove(key);
+                self.deadline_sources().remove(&key);
+            }
+        }
+        Ok(())
+    }
+
+    /// The instant of the earliest timer, deadline or event held back by the rate limit.
+    fn next_timer(&self) -> Option<Instant> {
+        [
+            self.timers.next_deadline(),
+            self.deadlines.next_deadline(),
+            self.ticks.next_deadline(),
+            self.rate_limit
+                .as_ref()
+                .and_then(|limit| limit.next_deadline()),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    /// Deliver the tick into `list` if it is due, and set it again for the next interval.
+    fn expire_tick(&self, list: &mut Vec<Event>) {
+        if let Some((key, interval)) = self.tick {
+            let len = list.len();
+            self.ticks.expire_into(list, Event::readable);
+            if list.len() > len {
+                if let Some(next) = Instant::now().checked_add(interval) {
+                    self.ticks.insert(key, next);
+                }
+            }
+        }
+    }
+
+    /// Lock the sources that the deadlines belong to, ignoring poisoning.
+    fn deadline_sources(&self) -> MutexGuard<'_, HashMap<usize, RawSource>> {
+        self.deadline_sources
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Delete the sources that asked for it and reported a hangup in `list`.
+    fn delete_hung_up(&self, list: &[Event]) {
+        for source in self.registry.hung_up(list) {
+            // SAFETY: Registered sources must stay open until they are deleted.
+            let source = unsafe { BorrowedSource::borrow_raw(source) };
+            if let Err(_err) = self.delete(source) {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(err = %_err, "wait: failed to delete a hung up source");
+            }
+        }
+    }
+
+    /// Cancel the deadlines of the sources that reported an event in `list`.
+    fn cancel_deadlines(&self, list: &[Event]) {
+        if self.deadlines.len() == 0 {
+            return;
+        }
+        let mut sources = self.deadline_sources();
+        for ev in list {
+            if self.deadlines.remove(ev.key) {
+                sources.remove(&ev.key);
+            }
+        }
+    }
+
+    /// Move the deadlines that passed by now into `list` as timeout events.
+    ///
+    /// Deadlines of sources that were deleted, or that changed their key, are dropped.
+    fn expire_deadlines(&self, list: &mut Vec<Event>) {
+        let start = list.len();
+        self.deadlines.expire_into(list, Event::timeout);
+        if list.len() == start {
+            return;
+        }
+
+        let mut sources = self.deadline_sources();
+        let expired = list.split_off(start);
+        for ev in expired {
+            let registered = sources
+                .remove(&ev.key)
+                .and_then(|source| self.registry.get(source));
+            if registered.is_some_and(|registered| {
+                registered.interest().key == ev.key || registered.write_key() == Some(ev.key)
+            }) {
+                list.push(ev);
+            }
+        }
+    }
+
+    /// Set the priority of the events with `key`.
+    ///
+    /// The new events of every wait are ordered by the priority of their keys, highest first,
+    /// so that control sources like shutdown channels or health checks are handled before a
+    /// flood of other events. Events with the same priority keep the order the backend
+    /// reported them in. Keys have the priority `0` by default, and setting it to `0` again
+    /// forgets the key. Priorities belong to keys rather than sources, so they are kept when a
+    /// source is deleted, and apply to timers and wakers with the key as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::time::Duration;
+    ///
+    /// let poller = Poller::new()?;
+    /// let _data = poller.register(UdpSocket::bind("127.0.0.1:0")?, Event::writable(1))?;
+    /// let _control = poller.register(UdpSocket::bind("127.0.0.1:0")?, Event::writable(2))?;
+    /// poller.set_key_priority(2, 10);
+    ///
+    /// let mut events = Events::new();
+    /// while events.len() < 2 {
+    ///     poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// }
+    /// assert_eq!(events.iter().next().unwrap().key, 2);
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn set_key_priority(&self, key: usize, priority: i32) {
+        let mut priorities = self.priorities();
+        if priority == 0 {
+            priorities.remove(&key);
+        } else {
+            priorities.insert(key, priority);
+        }
+    }
+
+    /// Lock the priorities of the keys, ignoring poisoning.
+    fn priorities(&self) -> MutexGuard<'_, HashMap<usize, i32>> {
+        self.priorities.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Order the events in `list` by the priority of their keys.
+    fn order_by_priority(&self, list: &mut [Event]) {
+        let priorities = self.priorities();
+        if priorities.is_empty() || list.len() < 2 {
+            return;
+        }
+        list.sort_by_key(|ev| std::cmp::Reverse(priorities.get(&ev.key).copied().unwrap_or(0)));
+    }
+}
+
+/// Remove and return the first event with `key` from `list`.
+fn take_key(list: &mut Vec<Event>, key: usize) -> Option<Event> {
+    let index = list.iter().position(|ev| ev.key == key)?;
+    Some(list.remove(index))
+}
+
+/// Set the `SO_RCVLOWAT` or, if `write` is set, the `SO_SNDLOWAT` option of a socket.
+///
+/// `None` goes back to the default watermark of one byte for reading. The send low watermark
+/// can't be changed on Linux, Android and Windows.
+fn set_low_watermark(
+    source: RawSource,
+    bytes: Option<NonZeroUsize>,
+    write: bool,
+) -> io::Result<()> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    if write && bytes.is_some() {
+        return Err(unsupported_error(
+            "write low watermarks are not supported on this platform",
+        ));
+    }
+
+    #[cfg(unix)]
+    {
+        let option = if write {
+            libc::SO_SNDLOWAT
+        } else {
+            libc::SO_RCVLOWAT
+        };
+        let bytes = match bytes {
+            Some(bytes) => bytes.get(),
+            // There's no portable default for the send low watermark, so it stays as it is.
+            None if write => return Ok(()),
+            None => 1,
+        };
+        let bytes = std::os::raw::c_int::try_from(bytes).unwrap_or(std::os::raw::c_int::MAX);
+
+        // SAFETY: `bytes` is valid for reads of its size, and the caller ensures that `source` is
+        // open.
+        let res = unsafe {
+            libc::setsockopt(
+                source,
+                libc::SOL_SOCKET,
+                option,
+                (&bytes as *const std::os::raw::c_int).cast(),
+                std::mem::size_of_val(&bytes) as libc::socklen_t,
+            )
+        };
+        if res == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = (source, bytes, write);
+        Err(unsupported_error(
+            "low watermarks are not supported on Windows",
+        ))
+    }
+}
+
+/// Why a call to [`Poller::wait_result()`] or [`Poller::wait_cancelable()`] returned.
+///
+/// A wait can deliver events, be notified and be cancelled at the same time. If none of these
+/// happened and the timeout didn't expire either, the wait returned spuriously, or it was skipped
+/// because another thread is already waiting on I/O.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WaitResult {
+    events: usize,
+    notified: bool,
+    timed_out: bool,
+    cancelled: bool,
+    filled: bool,
+}
+
+impl WaitResult {
+    /// The number of new events.
+    #[inline]
+    pub fn events(&self) -> usize {
+        self.events
+    }
 
-                // Clear the notification, if any.
-                self.notified.swap(false, Ordering::SeqCst);
+    /// Whether the wait was woken by [`Poller::notify()`].
+    #[inline]
+    pub fn is_notified(&self) -> bool {
+        self.notified
+    }
 
-                // Indicate number of events.
-                return Ok(events.len());
-            }
-        } else {
-            #[cfg(feature = "tracing")]
-            tracing::trace!("wait: skipping because another thread is already waiting on I/O");
-            Ok(0)
-        }
+    /// Whether the timeout expired without events or a notification.
+    #[inline]
+    pub fn is_timed_out(&self) -> bool {
+        self.timed_out
     }
 
-    /// Wakes up the current or the following invocation of [`wait()`].
-    ///
-    /// If no thread is calling [`wait()`] right now, this method will cause the following call
-    /// to wake up immediately.
-    ///
-    /// [`wait()`]: `Poller::wait()`
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use polling::{Events, Poller};
-    ///
-    /// let poller = Poller::new()?;
-    ///
-    /// // Notify the poller.
-    /// poller.notify()?;
-    ///
-    /// let mut events = Events::new();
-    /// poller.wait(&mut events, None)?; // wakes up immediately
-    /// assert!(events.is_empty());
-    /// # std::io::Result::Ok(())
-    /// ```
-    pub fn notify(&self) -> io::Result<()> {
-        #[cfg(feature = "tracing")]
-        let span = tracing::trace_span!("Poller::notify");
-        #[cfg(feature = "tracing")]
-        let _enter = span.enter();
+    /// Whether the [`CancelToken`] passed to [`Poller::wait_cancelable()`] was cancelled.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
 
-        if self
-            .notified
-            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
-            .is_ok()
-        {
-            self.poller.notify()?;
-        }
-        Ok(())
+    /// Whether the wait filled the buffer of the [`Events`], so that more events may be pending.
+    ///
+    /// If so, another wait with a zero timeout delivers the rest before the caller goes on to
+    /// block or sleep elsewhere.
+    #[inline]
+    pub fn may_have_more(&self) -> bool {
+        self.filled
     }
 }
 
 /// A container for I/O events.
 pub struct Events {
+    /// The buffer the backend writes events into during a wait.
     events: sys::Events,
 
+    /// The events delivered so far.
+    list: Vec<Event>,
+
+    /// A smaller backend buffer used by [`Poller::wait_limit()`], kept for reuse.
+    limited: Option<sys::Events>,
+
+    /// Whether the smaller buffer is in use.
+    limiting: bool,
+
+    /// The capacity the backend buffer grows up to.
+    capacity: NonZeroUsize,
+
+    /// The capacity the container was created with, restored by `shrink_to_fit()`.
+    base: NonZeroUsize,
+
+    /// Whether the last wait filled the backend buffer.
+    filled: bool,
+
+    /// When the last wait on the backend returned.
+    returned_at: Option<Instant>,
+
     /// This is intended to be used from &mut, thread locally, so we should make it !Sync
     /// for consistency with the rest of the API.
     _not_sync: PhantomData<Cell<()>>,
@@ -830,9 +4862,30 @@ impl Default for Events {
 }
 
 impl Events {
+    /// The default capacity.
+    // ESP-IDF has a low amount of RAM, so we use a smaller default capacity.
+    #[cfg(target_os = "espidf")]
+    pub(crate) const DEFAULT_CAPACITY: NonZeroUsize = match NonZeroUsize::new(32) {
+        Some(capacity) => capacity,
+        None => unreachable!(),
+    };
+
+    /// The default capacity.
+    #[cfg(not(target_os = "espidf"))]
+    pub(crate) const DEFAULT_CAPACITY: NonZeroUsize = match NonZeroUsize::new(1024) {
+        Some(capacity) => capacity,
+        None => unreachable!(),
+    };
+
+    /// The capacity of the backend buffer of a container that grows on demand, when it is
+    /// created.
+    const INITIAL_CAPACITY: usize = 32;
+
     /// Create a new container for events, using the default capacity.
     ///
-    /// The default capacity is 1024.
+    /// The default capacity is 1024. The container starts out with room for fewer events, and
+    /// grows up to its capacity when a wait fills it, so containers that only ever see a few
+    /// events at a time stay small.
     ///
     /// # Examples
     ///
@@ -843,18 +4896,28 @@ impl Events {
     /// ```
     #[inline]
     pub fn new() -> Self {
-        // ESP-IDF has a low amount of RAM, so we use a smaller default capacity.
-        #[cfg(target_os = "espidf")]
-        const DEFAULT_CAPACITY: usize = 32;
-
-        #[cfg(not(target_os = "espidf"))]
-        const DEFAULT_CAPACITY: usize = 1024;
+        Self::growing(Self::DEFAULT_CAPACITY)
+    }
 
-        Self::with_capacity(NonZeroUsize::new(DEFAULT_CAPACITY).unwrap())
+    /// Create a new container that grows on demand up to `capacity`.
+    pub(crate) fn growing(capacity: NonZeroUsize) -> Self {
+        Self {
+            events: sys::Events::with_capacity(capacity.get().min(Self::INITIAL_CAPACITY)),
+            list: Vec::new(),
+            limited: None,
+            limiting: false,
+            capacity,
+            base: capacity,
+            filled: false,
+            returned_at: None,
+            _not_sync: PhantomData,
+        }
     }
 
     /// Create a new container with the provided capacity.
     ///
+    /// Unlike [`Events::new()`], this allocates room for `capacity` events right away.
+    ///
     /// # Examples
     ///
     /// ```
@@ -868,6 +4931,13 @@ impl Events {
     pub fn with_capacity(capacity: NonZeroUsize) -> Self {
         Self {
             events: sys::Events::with_capacity(capacity.get()),
+            list: Vec::with_capacity(capacity.get()),
+            limited: None,
+            limiting: false,
+            capacity,
+            base: capacity,
+            filled: false,
+            returned_at: None,
             _not_sync: PhantomData,
         }
     }
@@ -891,8 +4961,52 @@ impl Events {
     /// # Ok(()) }
     /// ```
     #[inline]
-    pub fn iter(&self) -> impl Iterator<Item = Event> + '_ {
-        self.events.iter().filter(|ev| ev.key != NOTIFY_KEY)
+    pub fn iter(&self) -> EventsIter<'_> {
+        EventsIter {
+            inner: self.list.iter(),
+        }
+    }
+
+    /// Iterate over the readable events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::time::Duration;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// let poller = Poller::new()?;
+    /// let _socket = poller.register(socket, Event::writable(1))?;
+    ///
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// assert_eq!(events.readable().count(), 0);
+    /// assert_eq!(events.writable().count(), 1);
+    /// assert_eq!(events.with_key(1).count(), 1);
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn readable(&self) -> impl Iterator<Item = Event> + '_ {
+        self.iter().filter(|ev| ev.readable)
+    }
+
+    /// Iterate over the writable events.
+    ///
+    /// See [`readable()`][`Events::readable()`] for an example.
+    #[inline]
+    pub fn writable(&self) -> impl Iterator<Item = Event> + '_ {
+        self.iter().filter(|ev| ev.writable)
+    }
+
+    /// Iterate over the events with the given key.
+    ///
+    /// A source can be reported more than once by a wait, for example once for each filter with
+    /// `kqueue`. See [`readable()`][`Events::readable()`] for an example.
+    #[inline]
+    pub fn with_key(&self, key: usize) -> impl Iterator<Item = Event> + '_ {
+        self.iter().filter(move |ev| ev.key == key)
     }
 
     /// Delete all of the events in the container.
@@ -915,7 +5029,41 @@ impl Events {
     /// ```
     #[inline]
     pub fn clear(&mut self) {
-        self.events.clear();
+        self.list.clear();
+    }
+
+    /// Keep only the events for which `f` returns `true`, and delete the others.
+    ///
+    /// The remaining events keep their order. This lets a dispatcher handle some of the events,
+    /// such as those of urgent keys, and leave the rest for a later pass or another component.
+    /// Events of the next waits are appended after the remaining ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::time::Duration;
+    ///
+    /// let a = UdpSocket::bind("127.0.0.1:0")?;
+    /// let b = UdpSocket::bind("127.0.0.1:0")?;
+    /// let poller = Poller::new()?;
+    /// let _a = poller.register(a, Event::writable(1))?;
+    /// let _b = poller.register(b, Event::writable(2))?;
+    ///
+    /// let mut events = Events::new();
+    /// while events.len() < 2 {
+    ///     poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// }
+    ///
+    /// // Handle the urgent key now, and the others later.
+    /// events.retain(|ev| ev.key != 1);
+    /// assert_eq!(events.iter().map(|ev| ev.key).collect::<Vec<_>>(), [2]);
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn retain(&mut self, f: impl FnMut(&Event) -> bool) {
+        self.list.retain(f);
     }
 
     /// Returns the number of events in the container.
@@ -930,7 +5078,42 @@ impl Events {
     /// ```
     #[inline]
     pub fn len(&self) -> usize {
-        self.iter().count()
+        self.list.len()
+    }
+
+    /// Returns the number of distinct keys among the events in the container.
+    ///
+    /// This can be lower than [`len()`][`Events::len()`], because a source can be reported more
+    /// than once, for example by separate waits appending to the same container, or in one event
+    /// for each filter on some backends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Events, PollMode, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::time::Duration;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// let poller = Poller::new()?;
+    /// unsafe {
+    ///     poller.add_with_mode(&socket, Event::writable(1), PollMode::Level)?;
+    /// }
+    ///
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// assert_eq!(events.len(), 2);
+    /// assert_eq!(events.key_count(), 1);
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn key_count(&self) -> usize {
+        self.list
+            .iter()
+            .map(|ev| ev.key)
+            .collect::<HashSet<_>>()
+            .len()
     }
 
     /// Returns `true` if the container contains no events.
@@ -945,11 +5128,42 @@ impl Events {
     /// ```
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.len() == 0
+        self.list.is_empty()
+    }
+
+    /// Returns the instant the last wait that used this container returned from the backend.
+    ///
+    /// This is taken once for the whole batch of events, right after the operating system
+    /// reported them, so the delay between readiness and handling an event can be measured
+    /// without reading the clock for every event. It is `None` until the first wait, and isn't
+    /// reset by [`clear()`][`Events::clear()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Events, Poller};
+    /// use std::time::Duration;
+    ///
+    /// let poller = Poller::new()?;
+    /// let mut events = Events::new();
+    /// assert_eq!(events.timestamp(), None);
+    ///
+    /// poller.wait(&mut events, Some(Duration::from_millis(1)))?;
+    /// let queued = events.timestamp().unwrap().elapsed();
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[inline]
+    pub fn timestamp(&self) -> Option<Instant> {
+        self.returned_at
     }
 
     /// Get the total capacity of the list.
     ///
+    /// This is the number of events the backend buffer holds for a single wait on the operating
+    /// system. It doesn't bound the number of events a call to [`Poller::wait()`] delivers, which
+    /// also include the notification, the events set aside by earlier waits, those of a wait that
+    /// is split into several, and anything [`Poller::drain()`] adds.
+    ///
     /// # Examples
     ///
     /// ```
@@ -962,13 +5176,158 @@ impl Events {
     /// ```
     #[inline]
     pub fn capacity(&self) -> NonZeroUsize {
-        NonZeroUsize::new(self.events.capacity()).unwrap()
+        self.capacity
+    }
+
+    /// Raise the capacity by `additional` events, and allocate room for all of them right away.
+    ///
+    /// Every later wait collects up to the new [`capacity()`][`Events::capacity()`] from the
+    /// operating system at once, which helps to get through a burst of events in fewer waits.
+    /// The raised capacity lasts until [`shrink_to_fit()`][`Events::shrink_to_fit()`] restores
+    /// the one the container was created with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::Events;
+    ///
+    /// let mut events = Events::new();
+    /// let capacity = events.capacity().get();
+    /// events.reserve(1024);
+    /// assert_eq!(events.capacity().get(), capacity + 1024);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.capacity = self.capacity.saturating_add(additional);
+        if self.events.capacity() < self.capacity.get() {
+            self.events = sys::Events::with_capacity(self.capacity.get());
+        }
+        self.list.reserve(additional);
+    }
+
+    /// Release the memory that the container doesn't need for the events it holds.
+    ///
+    /// The [`capacity()`][`Events::capacity()`] goes back to the one the container was created
+    /// with, undoing [`reserve()`][`Events::reserve()`], and the backend buffer goes back to the
+    /// small size that [`Events::new()`] starts with, and grows again when a wait fills it.
+    /// Long-running programs can call this after a burst of events, so that an idle container
+    /// doesn't keep the memory the burst needed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::Events;
+    /// use std::num::NonZeroUsize;
+    ///
+    /// let mut events = Events::with_capacity(NonZeroUsize::new(4096).unwrap());
+    /// events.reserve(1024);
+    /// events.shrink_to_fit();
+    /// assert_eq!(events.capacity().get(), 4096);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.capacity = self.base;
+        let initial = self.capacity.get().min(Self::INITIAL_CAPACITY);
+        if self.events.capacity() > initial {
+            self.events = sys::Events::with_capacity(initial);
+        }
+        self.limited = None;
+        self.list.shrink_to_fit();
+    }
+
+    /// Switch to a backend buffer for at most `limit` events, returning the full buffer.
+    fn limit(&mut self, limit: usize) -> Option<sys::Events> {
+        if limit >= self.events.capacity() {
+            return None;
+        }
+
+        let limited = match self.limited.take() {
+            Some(limited) if limited.capacity() == limit => limited,
+            _ => sys::Events::with_capacity(limit),
+        };
+        self.limiting = true;
+        Some(mem::replace(&mut self.events, limited))
+    }
+
+    /// Switch back to the full backend buffer returned by `limit()`.
+    fn unlimit(&mut self, full: Option<sys::Events>) {
+        if let Some(full) = full {
+            self.limited = Some(mem::replace(&mut self.events, full));
+            self.limiting = false;
+        }
+    }
+
+    /// Move the events from the backend buffer into the list, and record when the wait returned.
+    ///
+    /// If the wait filled the backend buffer, it grows for the next wait.
+    fn collect_new(&mut self) {
+        self.returned_at = Some(Instant::now());
+        self.list.extend(self.events.iter());
+        self.filled = self.events.is_full();
+        self.events.clear();
+
+        let capacity = self.events.capacity();
+        if self.filled && !self.limiting && capacity < self.capacity.get() {
+            let grown = capacity.saturating_mul(2).min(self.capacity.get());
+            self.events = sys::Events::with_capacity(grown);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Events {
+    type Item = Event;
+    type IntoIter = EventsIter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the events in an [`Events`].
+///
+/// This is created by [`Events::iter()`].
+#[derive(Debug, Clone)]
+pub struct EventsIter<'a> {
+    inner: std::slice::Iter<'a, Event>,
+}
+
+impl Iterator for EventsIter<'_> {
+    type Item = Event;
+
+    #[inline]
+    fn next(&mut self) -> Option<Event> {
+        self.inner.next().copied()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<Event> {
+        self.inner.nth(n).copied()
     }
 }
 
+impl DoubleEndedIterator for EventsIter<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Event> {
+        self.inner.next_back().copied()
+    }
+}
+
+impl ExactSizeIterator for EventsIter<'_> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl std::iter::FusedIterator for EventsIter<'_> {}
+
 impl fmt::Debug for Events {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str("Events { .. }")
+        f.debug_list().entries(self.iter()).finish()
     }
 }
 
@@ -1003,18 +5362,104 @@ impl fmt::Debug for Events {
     )))
 )]
 mod raw_fd_impl {
-    use crate::Poller;
-    use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+    use crate::{custom, sys, Poller, PollerBuilder};
+    use std::io;
+    use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+
+    impl Poller {
+        /// Creates a poller from an existing epoll, kqueue or event port instance.
+        ///
+        /// This makes it possible to wrap an instance created elsewhere, for example by a host
+        /// application that embeds Rust code. The poller registers its notification mechanism in
+        /// the instance, and takes ownership of it. Events of sources that were registered in the
+        /// instance before are delivered as well, using their registered data as the key, but
+        /// they are not known to the poller and can't portably be modified or deleted through it.
+        ///
+        /// This is equivalent to `PollerBuilder::new().build_from_fd(fd)`.
+        ///
+        /// # Errors
+        ///
+        /// This may return an error if `fd` is not an instance of the backend used on this
+        /// platform. Event ports can't be told apart from other file descriptors, so this is not
+        /// checked on illumos and Solaris.
+        pub fn from_fd(fd: OwnedFd) -> io::Result<Poller> {
+            PollerBuilder::new().build_from_fd(fd)
+        }
+
+        /// Creates a poller from an existing epoll, kqueue or event port file descriptor.
+        ///
+        /// See [`from_fd()`][`Poller::from_fd()`] for more information.
+        ///
+        /// # Safety
+        ///
+        /// `fd` must be an open file descriptor that is not owned by anything else.
+        pub unsafe fn from_raw_fd(fd: RawFd) -> io::Result<Poller> {
+            Self::from_fd(OwnedFd::from_raw_fd(fd))
+        }
+
+        /// Returns the file descriptor of the epoll, kqueue or event port instance.
+        ///
+        /// Returns `None` if the poller has a [custom backend][`PollerBuilder::build_custom()`]
+        /// or the [poll backend][`crate::Backend::Poll`], which have no file descriptor. The [`AsFd`]
+        /// and [`AsRawFd`] implementations panic in that case.
+        pub fn try_as_fd(&self) -> Option<BorrowedFd<'_>> {
+            self.poller.handle().ok().map(|poller| poller.as_fd())
+        }
+    }
+
+    impl PollerBuilder {
+        /// Create the [`Poller`] from an existing epoll, kqueue or event port instance.
+        ///
+        /// See [`Poller::from_fd()`] for more information.
+        pub fn build_from_fd(self, fd: OwnedFd) -> io::Result<Poller> {
+            self.build_with(|builder| sys::Poller::from_fd(fd, builder).map(custom::Driver::Native))
+        }
+    }
+
+    /// Returns the underlying instance, after removing the notification mechanism from it.
+    ///
+    /// Sources that are still registered in the poller stay registered in the instance. A poller
+    /// with a [custom backend][`PollerBuilder::build_custom()`] or the [poll
+    /// backend][`crate::Backend::Poll`] has no instance, so this fails with
+    /// [`io::ErrorKind::Unsupported`] for it.
+    impl TryFrom<Poller> for OwnedFd {
+        type Error = io::Error;
+
+        fn try_from(poller: Poller) -> io::Result<OwnedFd> {
+            poller.poller.handle()?;
+
+            // The sources stay registered in the instance.
+            for registered in poller.registry.snapshot() {
+                poller.registry.remove(registered.source());
+            }
+            match poller.poller {
+                custom::Driver::Native(poller) => Ok(poller.into_fd()),
+                custom::Driver::Custom(_) => unreachable!(),
+            }
+        }
+    }
 
     impl AsRawFd for Poller {
+        /// Returns the raw file descriptor of the epoll, kqueue or event port instance.
+        ///
+        /// # Panics
+        ///
+        /// Panics if the poller has a custom backend or the poll backend, see
+        /// [`Poller::try_as_fd()`].
         fn as_raw_fd(&self) -> RawFd {
-            self.poller.as_raw_fd()
+            self.poller.expect_native().as_raw_fd()
         }
     }
 
     impl AsFd for Poller {
+        /// Returns the file descriptor of the epoll, kqueue or event port instance.
+        ///
+        /// # Panics
+        ///
+        /// Panics if the poller has a custom backend or the poll backend, see
+        /// [`Poller::try_as_fd()`].
         fn as_fd(&self) -> BorrowedFd<'_> {
-            self.poller.as_fd()
+            self.poller.expect_native().as_fd()
         }
     }
 }
@@ -1022,25 +5467,57 @@ mod raw_fd_impl {
 #[cfg(windows)]
 #[cfg_attr(docsrs, doc(cfg(windows)))]
 mod raw_handle_impl {
-    use crate::Poller;
+    use crate::{custom, Poller};
     use std::os::windows::io::{AsHandle, AsRawHandle, BorrowedHandle, RawHandle};
 
+    impl Poller {
+        /// Returns the handle of the I/O completion port.
+        ///
+        /// Returns `None` if the poller has a [custom backend], which has no handle. The
+        /// [`AsHandle`] and [`AsRawHandle`] implementations panic in that case.
+        ///
+        /// [custom backend]: crate::PollerBuilder::build_custom
+        pub fn try_as_handle(&self) -> Option<BorrowedHandle<'_>> {
+            match &self.poller {
+                custom::Driver::Native(poller) => Some(poller.as_handle()),
+                custom::Driver::Custom(_) => None,
+            }
+        }
+    }
+
     impl AsRawHandle for Poller {
+        /// Returns the raw handle of the I/O completion port.
+        ///
+        /// # Panics
+        ///
+        /// Panics if the poller has a custom backend, see [`Poller::try_as_handle()`].
         fn as_raw_handle(&self) -> RawHandle {
-            self.poller.as_raw_handle()
+            self.poller.expect_native().as_raw_handle()
         }
     }
 
     impl AsHandle for Poller {
+        /// Returns the handle of the I/O completion port.
+        ///
+        /// # Panics
+        ///
+        /// Panics if the poller has a custom backend, see [`Poller::try_as_handle()`].
         fn as_handle(&self) -> BorrowedHandle<'_> {
-            self.poller.as_handle()
+            self.poller.expect_native().as_handle()
         }
     }
 }
 
 impl fmt::Debug for Poller {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.poller.fmt(f)
+        match &self.label {
+            Some(label) => f
+                .debug_struct("Poller")
+                .field("label", label)
+                .field("backend", &self.poller)
+                .finish(),
+            None => self.poller.fmt(f),
+        }
     }
 }
 
@@ -1051,6 +5528,12 @@ cfg_if! {
         #[cfg(target_os = "hermit")]
         use std::os::hermit::io::{AsRawFd, RawFd, AsFd, BorrowedFd};
 
+        /// The raw file descriptor of a source.
+        pub type RawSource = RawFd;
+
+        /// The borrowed file descriptor of a source.
+        type BorrowedSource<'a> = BorrowedFd<'a>;
+
         /// A resource with a raw file descriptor.
         pub trait AsRawSource {
             /// Returns the raw file descriptor.
@@ -1081,6 +5564,12 @@ cfg_if! {
     } else if #[cfg(windows)] {
         use std::os::windows::io::{AsRawSocket, RawSocket, AsSocket, BorrowedSocket};
 
+        /// The raw socket of a source.
+        pub type RawSource = RawSocket;
+
+        /// The borrowed socket of a source.
+        type BorrowedSource<'a> = BorrowedSocket<'a>;
+
         /// A resource with a raw socket.
         pub trait AsRawSource {
             /// Returns the raw socket.
@@ -1111,11 +5600,159 @@ cfg_if! {
     }
 }
 
+/// Report a misuse of a poller: panic in debug builds, and log it otherwise.
+#[track_caller]
+fn report_misuse(what: fmt::Arguments<'_>) {
+    if cfg!(debug_assertions) && !std::thread::panicking() {
+        panic!("misuse of the poller: {}", what);
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::error!("misuse of the poller: {}", what);
+}
+
+/// Whether any key is reported more than once in `list`.
+fn repeats_key(list: &[Event]) -> bool {
+    let mut keys = HashSet::with_capacity(list.len());
+    !list.iter().all(|ev| keys.insert(ev.key))
+}
+
+/// Merge the events from `new` on into the deferred events with the same key.
+///
+/// The backend may report a source whose readiness was already found when it was added, which
+/// should only be delivered once.
+fn merge_deferred(list: &mut Vec<Event>, deferred: std::ops::Range<usize>, new: usize) {
+    let mut i = new;
+    while i < list.len() {
+        let key = list[i].key;
+        match list[deferred.clone()].iter().position(|ev| ev.key == key) {
+            Some(j) => {
+                let mut ev = list.remove(i);
+                let earlier = &mut list[deferred.start + j];
+                ev.readable |= earlier.readable;
+                ev.writable |= earlier.writable;
+                *earlier = ev;
+            }
+            None => i += 1,
+        }
+    }
+}
+
+/// Whether a registered source has been closed.
+fn is_closed(source: RawSource) -> bool {
+    #[cfg(unix)]
+    {
+        // SAFETY: `F_GETFD` only checks the file descriptor.
+        let res = unsafe { libc::fcntl(source, libc::F_GETFD) };
+        res == -1 && io::Error::last_os_error().raw_os_error() == Some(libc::EBADF)
+    }
+
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::Networking::WinSock;
+
+        let mut ty: std::os::raw::c_int = 0;
+        let mut len = std::mem::size_of_val(&ty) as i32;
+        // SAFETY: `ty` and `len` are valid for writes, and `SO_TYPE` only checks the socket.
+        let res = unsafe {
+            WinSock::getsockopt(
+                source as _,
+                WinSock::SOL_SOCKET,
+                WinSock::SO_TYPE,
+                (&mut ty as *mut std::os::raw::c_int).cast(),
+                &mut len,
+            )
+        };
+        res == WinSock::SOCKET_ERROR
+            && io::Error::last_os_error().raw_os_error() == Some(WinSock::WSAENOTSOCK)
+    }
+}
+
+/// Whether a socket is connected to a peer, or is still connecting.
+fn is_connected(source: impl AsSource) -> io::Result<bool> {
+    #[cfg(unix)]
+    let connected = {
+        use std::os::unix::io::AsRawFd;
+
+        // SAFETY: `sockaddr_storage` is plain data, for which all zeroes is a valid value.
+        let mut addr: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+        let mut len = std::mem::size_of_val(&addr) as libc::socklen_t;
+        // SAFETY: `addr` and `len` are valid for writes, and the socket is borrowed.
+        let res = unsafe {
+            libc::getpeername(
+                source.source().as_raw_fd(),
+                (&mut addr as *mut libc::sockaddr_storage).cast(),
+                &mut len,
+            )
+        };
+        match res {
+            -1 => match io::Error::last_os_error() {
+                err if err.raw_os_error() == Some(libc::ENOTCONN) => false,
+                err => return Err(err),
+            },
+            _ => true,
+        }
+    };
+
+    #[cfg(windows)]
+    let connected = {
+        use std::os::windows::io::AsRawSocket;
+        use windows_sys::Win32::Networking::WinSock;
+
+        // SAFETY: `SOCKADDR_STORAGE` is plain data, for which all zeroes is a valid value.
+        let mut addr: WinSock::SOCKADDR_STORAGE = unsafe { std::mem::zeroed() };
+        let mut len = std::mem::size_of_val(&addr) as i32;
+        // SAFETY: `addr` and `len` are valid for writes, and the socket is borrowed.
+        let res = unsafe {
+            WinSock::getpeername(
+                source.source().as_raw_socket() as _,
+                (&mut addr as *mut WinSock::SOCKADDR_STORAGE).cast(),
+                &mut len,
+            )
+        };
+        match res {
+            WinSock::SOCKET_ERROR => match io::Error::last_os_error() {
+                err if err.raw_os_error() == Some(WinSock::WSAENOTCONN) => false,
+                err => return Err(err),
+            },
+            _ => true,
+        }
+    };
+
+    Ok(connected)
+}
+
 #[allow(unused)]
 fn unsupported_error(err: impl Into<String>) -> io::Error {
     io::Error::new(io::ErrorKind::Unsupported, err.into())
 }
 
+/// An error for a file descriptor that the backend rejected because it can't poll its kind.
+///
+/// The message names the backend and the type of the file, and keeps the error of the system.
+#[cfg(unix)]
+#[allow(unused)]
+fn unpollable_fd_error(backend: &str, fd: std::os::unix::io::RawFd, err: io::Error) -> io::Error {
+    use rustix::fs::{fstat, FileType};
+
+    // SAFETY: The file descriptor was just passed to the backend, so it is open.
+    let fd_ref = unsafe { std::os::unix::io::BorrowedFd::borrow_raw(fd) };
+    let kind = match fstat(fd_ref).map(|stat| FileType::from_raw_mode(stat.st_mode)) {
+        Ok(FileType::RegularFile) => "a regular file",
+        Ok(FileType::Directory) => "a directory",
+        Ok(FileType::CharacterDevice) => "a character device",
+        Ok(FileType::BlockDevice) => "a block device",
+        Ok(FileType::Fifo) => "a pipe",
+        Ok(FileType::Socket) => "a socket",
+        Ok(FileType::Symlink) => "a symbolic link",
+        _ => "this kind of file",
+    };
+    unsupported_error(format!(
+        "{} can't poll {} (fd {}): {}",
+        backend, kind, fd, err
+    ))
+}
+
 fn _assert_send_and_sync() {
     fn assert_send<T: Send>() {}
     fn assert_sync<T: Sync>() {}
@@ -0,0 +1,119 @@
+//! Limiting how often the events of a key are delivered.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::{timers::Timers, Clock, Event};
+
+/// The rate limit of the keys of a poller.
+#[derive(Debug)]
+pub(crate) struct RateLimit {
+    /// The time after a delivery during which later events of the key are held back.
+    pub(crate) interval: Duration,
+
+    /// When the held events of each key are released.
+    releases: Timers,
+
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// When each key was last delivered, if that is less than the interval ago.
+    delivered: HashMap<usize, Instant>,
+
+    /// The merged events held back for each key.
+    held: HashMap<usize, Event>,
+}
+
+impl RateLimit {
+    /// Create a limit that delivers each key at most once per `interval`.
+    pub(crate) fn new(interval: Duration) -> RateLimit {
+        RateLimit {
+            interval,
+            releases: Timers::new(Clock::Monotonic),
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Lock the state of the keys, ignoring poisoning.
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// When the earliest held event is released.
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        self.releases.next_deadline()
+    }
+
+    /// Apply the limit to the events of `list` from `start` on.
+    ///
+    /// Events of the same key are merged, the keys delivered less than the interval ago are
+    /// held back, and the held events whose interval elapsed are added.
+    pub(crate) fn apply(&self, list: &mut Vec<Event>, start: usize) {
+        if self.interval.is_zero() {
+            merge_keys(list, start);
+            return;
+        }
+
+        let mut released = Vec::new();
+        self.releases.expire_into(&mut released, Event::none);
+
+        let now = Instant::now();
+        let mut inner = self.lock();
+        let interval = self.interval;
+        inner
+            .delivered
+            .retain(|_, delivered| now.saturating_duration_since(*delivered) < interval);
+        for ev in released {
+            if let Some(ev) = inner.held.remove(&ev.key) {
+                list.push(ev);
+            }
+        }
+        merge_keys(list, start);
+
+        for ev in list.split_off(start) {
+            match inner.delivered.get(&ev.key) {
+                Some(&delivered) => match inner.held.get_mut(&ev.key) {
+                    Some(held) => merge(held, ev),
+                    None => {
+                        inner.held.insert(ev.key, ev);
+                        self.releases.insert(ev.key, delivered + interval);
+                    }
+                },
+                None => {
+                    // The interval may have elapsed before its release expired, so the held
+                    // events are delivered now instead of on their own later.
+                    let mut ev = ev;
+                    if let Some(held) = inner.held.remove(&ev.key) {
+                        self.releases.remove(ev.key);
+                        merge(&mut ev, held);
+                    }
+                    inner.delivered.insert(ev.key, now);
+                    list.push(ev);
+                }
+            }
+        }
+    }
+}
+
+/// Merge the events of `list` from `start` on that have the same key into the first of them.
+fn merge_keys(list: &mut Vec<Event>, start: usize) {
+    let mut first = HashMap::new();
+    for ev in list.split_off(start) {
+        match first.get(&ev.key) {
+            Some(&i) => merge(&mut list[i], ev),
+            None => {
+                first.insert(ev.key, list.len());
+                list.push(ev);
+            }
+        }
+    }
+}
+
+/// Merge the readiness of `ev` into `into`.
+fn merge(into: &mut Event, ev: Event) {
+    into.readable |= ev.readable;
+    into.writable |= ev.writable;
+}
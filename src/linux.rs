@@ -0,0 +1,260 @@
+//! Bindings for Linux and Android, where a poller runs on epoll or poll.
+
+use std::io;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::time::Instant;
+
+use rustix::fd::OwnedFd;
+
+use crate::{epoll, poll};
+use crate::{Backend, Event, PollMode, PollerBuilder};
+
+pub use epoll::EventExtra;
+
+/// The backend used unless another one is requested.
+pub const BACKEND: Backend = epoll::BACKEND;
+
+/// Interface to epoll or poll, as requested when the poller is built.
+#[derive(Debug)]
+pub enum Poller {
+    /// The default backend.
+    Epoll(epoll::Poller),
+
+    /// The backend requested with `Backend::Poll`.
+    Poll(poll::Poller),
+}
+
+impl Poller {
+    /// Creates a new poller on the requested backend.
+    pub fn new(builder: &PollerBuilder) -> io::Result<Poller> {
+        match builder.backend {
+            Some(Backend::Poll) => poll::Poller::new(builder).map(Poller::Poll),
+            _ => epoll::Poller::new(builder).map(Poller::Epoll),
+        }
+    }
+
+    /// Creates a poller from an existing epoll instance.
+    pub fn from_fd(epoll_fd: OwnedFd, builder: &PollerBuilder) -> io::Result<Poller> {
+        if builder.backend == Some(Backend::Poll) {
+            return Err(crate::unsupported_error(
+                "the poll backend can't be built from a file descriptor",
+            ));
+        }
+        epoll::Poller::from_fd(epoll_fd, builder).map(Poller::Epoll)
+    }
+
+    /// The backend of this poller.
+    pub fn backend(&self) -> Backend {
+        match self {
+            Poller::Epoll(poller) => poller.backend(),
+            Poller::Poll(poller) => poller.backend(),
+        }
+    }
+
+    /// Whether this poller supports level-triggered events.
+    pub fn supports_level(&self) -> bool {
+        match self {
+            Poller::Epoll(poller) => poller.supports_level(),
+            Poller::Poll(poller) => poller.supports_level(),
+        }
+    }
+
+    /// Whether the poller supports edge-triggered events.
+    pub fn supports_edge(&self) -> bool {
+        match self {
+            Poller::Epoll(poller) => poller.supports_edge(),
+            Poller::Poll(poller) => poller.supports_edge(),
+        }
+    }
+
+    /// Adds a new file descriptor.
+    ///
+    /// # Safety
+    ///
+    /// The `fd` must be a valid file descriptor, which stays open until it is deleted.
+    pub unsafe fn add(&self, fd: RawFd, ev: Event, mode: PollMode) -> io::Result<()> {
+        match self {
+            Poller::Epoll(poller) => unsafe { poller.add(fd, ev, mode) },
+            Poller::Poll(poller) => poller.add(fd, ev, mode),
+        }
+    }
+
+    /// Modifies an existing file descriptor.
+    pub fn modify(&self, fd: BorrowedFd<'_>, ev: Event, mode: PollMode) -> io::Result<()> {
+        match self {
+            Poller::Epoll(poller) => poller.modify(fd, ev, mode),
+            Poller::Poll(poller) => poller.modify(fd, ev, mode),
+        }
+    }
+
+    /// Deletes a file descriptor.
+    pub fn delete(&self, fd: BorrowedFd<'_>) -> io::Result<()> {
+        match self {
+            Poller::Epoll(poller) => poller.delete(fd),
+            Poller::Poll(poller) => poller.delete(fd),
+        }
+    }
+
+    /// Forgets about a file descriptor that was closed while it was registered.
+    pub fn forget(&self, fd: RawFd) {
+        match self {
+            Poller::Epoll(poller) => poller.forget(fd),
+            Poller::Poll(poller) => poller.forget(fd),
+        }
+    }
+
+    /// Waits for I/O events with an optional deadline.
+    pub fn wait_deadline(&self, events: &mut Events, deadline: Option<Instant>) -> io::Result<()> {
+        match self {
+            Poller::Epoll(poller) => poller.wait_deadline(events.epoll(), deadline),
+            Poller::Poll(poller) => poller.wait_deadline(events.poll(), deadline),
+        }
+    }
+
+    /// Waits for I/O events with an optional deadline and the given signal mask.
+    pub fn wait_sigmask(
+        &self,
+        events: &mut Events,
+        deadline: Option<Instant>,
+        sigmask: &libc::sigset_t,
+    ) -> io::Result<()> {
+        match self {
+            Poller::Epoll(poller) => poller.wait_sigmask(events.epoll(), deadline, sigmask),
+            Poller::Poll(poller) => poller.wait_sigmask(events.poll(), deadline, sigmask),
+        }
+    }
+
+    /// Sends a notification to wake up the current or next `wait()` call.
+    pub fn notify(&self) -> io::Result<()> {
+        match self {
+            Poller::Epoll(poller) => poller.notify(),
+            Poller::Poll(poller) => poller.notify(),
+        }
+    }
+
+    /// Sends a notification from a signal handler.
+    pub fn notify_from_signal(&self) {
+        match self {
+            Poller::Epoll(poller) => poller.notify_from_signal(),
+            Poller::Poll(poller) => poller.notify_from_signal(),
+        }
+    }
+
+    /// Returns the epoll instance, removing the notifier and the timerfd from it.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the poll backend, which has no file descriptor.
+    pub fn into_fd(self) -> OwnedFd {
+        match self {
+            Poller::Epoll(poller) => poller.into_fd(),
+            Poller::Poll(_) => panic!("a poller with the poll backend has no file descriptor"),
+        }
+    }
+
+    /// Closes the poller in the child process after `fork()`.
+    pub fn close_after_fork(self) {
+        match self {
+            Poller::Epoll(poller) => poller.close_after_fork(),
+            Poller::Poll(poller) => poller.close_after_fork(),
+        }
+    }
+}
+
+impl AsRawFd for Poller {
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
+
+impl AsFd for Poller {
+    /// Returns the file descriptor of the epoll instance.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the poll backend, which has no file descriptor.
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        match self {
+            Poller::Epoll(poller) => poller.as_fd(),
+            Poller::Poll(_) => panic!("a poller with the poll backend has no file descriptor"),
+        }
+    }
+}
+
+/// A list of reported I/O events.
+///
+/// The list is created before it is known which backend it is used with, so it starts out as a
+/// list for epoll and is replaced by one for poll of the same capacity when it is first used with
+/// the poll backend.
+pub enum Events {
+    /// A list for the epoll backend.
+    Epoll(epoll::Events),
+
+    /// A list for the poll backend.
+    Poll(poll::Events),
+}
+
+impl Events {
+    /// Creates an empty list.
+    pub fn with_capacity(cap: usize) -> Events {
+        Events::Epoll(epoll::Events::with_capacity(cap))
+    }
+
+    /// The list for the epoll backend, replacing a list for poll.
+    fn epoll(&mut self) -> &mut epoll::Events {
+        if let Events::Poll(events) = self {
+            *self = Events::Epoll(epoll::Events::with_capacity(events.capacity()));
+        }
+        match self {
+            Events::Epoll(events) => events,
+            Events::Poll(_) => unreachable!(),
+        }
+    }
+
+    /// The list for the poll backend, replacing a list for epoll.
+    fn poll(&mut self) -> &mut poll::Events {
+        if let Events::Epoll(events) = self {
+            *self = Events::Poll(poll::Events::with_capacity(events.capacity()));
+        }
+        match self {
+            Events::Poll(events) => events,
+            Events::Epoll(_) => unreachable!(),
+        }
+    }
+
+    /// Iterates over I/O events.
+    pub fn iter(&self) -> impl Iterator<Item = Event> + '_ {
+        let (epoll, poll) = match self {
+            Events::Epoll(events) => (Some(events.iter()), None),
+            Events::Poll(events) => (None, Some(events.iter())),
+        };
+        epoll
+            .into_iter()
+            .flatten()
+            .chain(poll.into_iter().flatten())
+    }
+
+    /// Clear the list.
+    pub fn clear(&mut self) {
+        match self {
+            Events::Epoll(events) => events.clear(),
+            Events::Poll(events) => events.clear(),
+        }
+    }
+
+    /// Get the capacity of the list.
+    pub fn capacity(&self) -> usize {
+        match self {
+            Events::Epoll(events) => events.capacity(),
+            Events::Poll(events) => events.capacity(),
+        }
+    }
+
+    /// Whether the last wait filled the list, so that more events may be pending.
+    pub fn is_full(&self) -> bool {
+        match self {
+            Events::Epoll(events) => events.is_full(),
+            Events::Poll(events) => events.is_full(),
+        }
+    }
+}
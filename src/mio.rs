@@ -0,0 +1,129 @@
+//! Compatibility with [`mio`](https://docs.rs/mio) for incremental migration.
+//!
+//! This module translates between mio's [`Token`] and [`Interest`] and this crate's [`Event`],
+//! and provides a [`Registry`] with the same methods as [`mio::Registry`]. The network types of
+//! mio implement `AsRawFd` on Unix and `AsRawSocket` on Windows, so they can be registered with
+//! it directly, next to sources that are already using [`Poller`].
+//!
+//! mio registrations are edge-triggered. Sources registered through [`Registry`] use
+//! [`PollMode::Edge`] where supported, and fall back to [`PollMode::Level`] otherwise, which
+//! delivers a superset of the events. On platforms that support neither, [`PollMode::Oneshot`]
+//! is used and the source must be re-registered with [`Registry::reregister()`] after every
+//! event.
+//!
+//! [`mio::Registry`]: https://docs.rs/mio/latest/mio/struct.Registry.html
+//!
+//! # Examples
+//!
+//! ```
+//! use mio::{Interest, Token};
+//! use polling::{mio::Registry, Events, Poller};
+//! use std::net::TcpListener;
+//! use std::time::Duration;
+//!
+//! let socket = TcpListener::bind("127.0.0.1:0")?;
+//! socket.set_nonblocking(true)?;
+//!
+//! let poller = Poller::new()?;
+//! let registry = Registry::new(&poller);
+//! unsafe {
+//!     registry.register(&socket, Token(7), Interest::READABLE)?;
+//! }
+//!
+//! let mut events = Events::new();
+//! poller.wait(&mut events, Some(Duration::from_millis(10)))?;
+//! for ev in &events {
+//!     assert_eq!(polling::mio::token(&ev), Token(7));
+//! }
+//!
+//! registry.deregister(&socket)?;
+//! # std::io::Result::Ok(())
+//! ```
+
+use std::io;
+
+use ::mio::{Interest, Token};
+
+use crate::{AsRawSource, AsSource, Event, PollMode, Poller};
+
+/// Create an [`Event`] with the given mio token and interest.
+///
+/// Interest in priority events is only translated where mio supports it.
+pub fn event(token: Token, interest: Interest) -> Event {
+    let mut flags = crate::Interest::NONE;
+    flags.set(crate::Interest::READABLE, interest.is_readable());
+    flags.set(crate::Interest::WRITABLE, interest.is_writable());
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    flags.set(crate::Interest::PRIORITY, interest.is_priority());
+    Event::from_interest(token.0, flags)
+}
+
+/// The mio token of an [`Event`].
+#[inline]
+pub fn token(event: &Event) -> Token {
+    Token(event.key)
+}
+
+/// A handle for registering sources with mio's signatures.
+///
+/// This is a thin wrapper around a [`Poller`], see the [module-level documentation](self).
+#[derive(Debug, Clone, Copy)]
+pub struct Registry<'a> {
+    poller: &'a Poller,
+}
+
+impl<'a> Registry<'a> {
+    /// Create a registry that registers sources in `poller`.
+    #[inline]
+    pub fn new(poller: &'a Poller) -> Self {
+        Self { poller }
+    }
+
+    /// The poller sources are registered in.
+    #[inline]
+    pub fn poller(&self) -> &'a Poller {
+        self.poller
+    }
+
+    /// Register interest in a source, like `mio::Registry::register()`.
+    ///
+    /// # Safety
+    ///
+    /// The source must be deregistered before it is dropped, see [`Poller::add()`].
+    pub unsafe fn register(
+        &self,
+        source: impl AsRawSource,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        self.poller
+            .add_with_mode(source, event(token, interests), self.mode())
+    }
+
+    /// Change the interest of a registered source, like `mio::Registry::reregister()`.
+    pub fn reregister(
+        &self,
+        source: impl AsSource,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        self.poller
+            .modify_with_mode(source, event(token, interests), self.mode())
+    }
+
+    /// Remove a registered source, like `mio::Registry::deregister()`.
+    pub fn deregister(&self, source: impl AsSource) -> io::Result<()> {
+        self.poller.delete(source)
+    }
+
+    /// The mode closest to mio's edge-triggered registrations.
+    fn mode(&self) -> PollMode {
+        if self.poller.supports_edge() {
+            PollMode::Edge
+        } else if self.poller.supports_level() {
+            PollMode::Level
+        } else {
+            PollMode::Oneshot
+        }
+    }
+}
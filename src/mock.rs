@@ -0,0 +1,252 @@
+//! A scripted backend for testing code that handles events.
+//!
+//! A [`MockBackend`] is a [`PollerBackend`] that doesn't watch anything. Instead, each wait on
+//! the poller replays the next step of a script of events, and the registrations of the poller
+//! are recorded so that tests can check them. This makes it possible to test the dispatch logic
+//! of an event loop without real sockets, and without depending on when the system reports them
+//! as ready.
+//!
+//! The poller still does its own work on top of the backend, so timers, wakers, notifications
+//! and the disarming of oneshot registrations behave like with a system backend.
+//!
+//! # Examples
+//!
+//! ```
+//! use polling::mock::{Call, MockBackend};
+//! use polling::{Event, PollMode, Poller};
+//! use std::time::Duration;
+//!
+//! let mock = MockBackend::new();
+//! mock.push(Duration::ZERO, [Event::readable(1)]);
+//! mock.push(Duration::from_millis(10), [Event::writable(2), Event::readable(3)]);
+//!
+//! let poller = Poller::builder().build_custom(mock.clone())?;
+//! let mut events = poller.new_events();
+//!
+//! poller.wait(&mut events, None)?;
+//! assert_eq!(events.iter().map(|ev| ev.key).collect::<Vec<_>>(), [1]);
+//!
+//! events.clear();
+//! poller.wait(&mut events, None)?;
+//! assert_eq!(events.iter().map(|ev| ev.key).collect::<Vec<_>>(), [2, 3]);
+//! assert!(mock.is_done());
+//!
+//! let socket = std::net::UdpSocket::bind("127.0.0.1:0")?;
+//! unsafe {
+//!     poller.add(&socket, Event::readable(7))?;
+//! }
+//! poller.delete(&socket)?;
+//! assert!(matches!(
+//!     mock.calls()[..],
+//!     [Call::Add { interest, mode: PollMode::Oneshot, .. }, Call::Delete { .. }]
+//!         if interest == Event::readable(7)
+//! ));
+//! # std::io::Result::Ok(())
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::{Event, PollMode, PollerBackend, RawSource};
+
+/// A call from the poller to its backend, as recorded by a [`MockBackend`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Call {
+    /// A source was added.
+    Add {
+        /// The source.
+        source: RawSource,
+        /// The interest, with the key of the source.
+        interest: Event,
+        /// The polling mode.
+        mode: PollMode,
+    },
+
+    /// The interest in a source was changed, or the source was enabled again.
+    Modify {
+        /// The source.
+        source: RawSource,
+        /// The interest, with the key of the source.
+        interest: Event,
+        /// The polling mode.
+        mode: PollMode,
+    },
+
+    /// A source was deleted.
+    Delete {
+        /// The source.
+        source: RawSource,
+    },
+}
+
+/// A backend that replays a script of events.
+///
+/// Steps are added to the script with [`push()`][`MockBackend::push()`]. Each wait on the poller
+/// delivers at most one step, once its delay has passed. The delay of a step starts when the
+/// previous step was delivered, or at the first wait for the first step. If the step isn't due
+/// before the deadline of the wait, the wait times out without events, and when the script is
+/// over, waits only return for their deadline or a notification.
+///
+/// The events are delivered as scripted, whether or not a source is registered under their keys,
+/// so the script decides alone what the poller reports. All polling modes are accepted.
+///
+/// This is a handle: clones share the same script and recorded calls, so a test can keep one
+/// while the poller owns another. See the [module documentation][`crate::mock`] for an example.
+#[derive(Clone, Default)]
+pub struct MockBackend {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    state: Mutex<State>,
+    cond: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    /// The steps that weren't delivered yet, with their delays.
+    script: VecDeque<(Duration, Vec<Event>)>,
+
+    /// When the delay of the next step started.
+    since: Option<Instant>,
+
+    /// The registrations so far.
+    calls: Vec<Call>,
+
+    /// Whether a notification is pending.
+    notified: bool,
+}
+
+impl MockBackend {
+    /// Creates a backend with an empty script.
+    pub fn new() -> MockBackend {
+        MockBackend::default()
+    }
+
+    /// Appends a step to the script, which delivers `events` in one wait `after` the previous
+    /// step.
+    pub fn push(&self, after: Duration, events: impl IntoIterator<Item = Event>) {
+        self.lock()
+            .script
+            .push_back((after, events.into_iter().collect()));
+        self.inner.cond.notify_all();
+    }
+
+    /// Returns `true` if every step of the script was delivered.
+    pub fn is_done(&self) -> bool {
+        self.lock().script.is_empty()
+    }
+
+    /// Returns the registrations made by the poller so far, in order.
+    pub fn calls(&self) -> Vec<Call> {
+        self.lock().calls.clone()
+    }
+
+    /// Forgets the registrations recorded so far.
+    pub fn clear_calls(&self) {
+        self.lock().calls.clear();
+    }
+
+    /// Lock the state, ignoring poisoning.
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.inner.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl PollerBackend for MockBackend {
+    fn add(&self, source: RawSource, interest: Event, mode: PollMode) -> io::Result<()> {
+        self.lock().calls.push(Call::Add {
+            source,
+            interest,
+            mode,
+        });
+        Ok(())
+    }
+
+    fn modify(&self, source: RawSource, interest: Event, mode: PollMode) -> io::Result<()> {
+        self.lock().calls.push(Call::Modify {
+            source,
+            interest,
+            mode,
+        });
+        Ok(())
+    }
+
+    fn delete(&self, source: RawSource) -> io::Result<()> {
+        self.lock().calls.push(Call::Delete { source });
+        Ok(())
+    }
+
+    fn wait(&self, events: &mut Vec<Event>, deadline: Option<Instant>) -> io::Result<()> {
+        let mut state = self.lock();
+        let since = *state.since.get_or_insert_with(Instant::now);
+        loop {
+            let now = Instant::now();
+            let due = state
+                .script
+                .front()
+                .and_then(|(after, _)| since.checked_add(*after));
+            if let Some(due) = due {
+                if due <= now {
+                    let (_, step) = state.script.pop_front().unwrap();
+                    events.extend(step);
+                    state.since = Some(now);
+                    return Ok(());
+                }
+            }
+            if state.notified || deadline.is_some_and(|deadline| deadline <= now) {
+                state.notified = false;
+                return Ok(());
+            }
+
+            // Sleep until the next step is due, the deadline passes, or the script changes.
+            let wake_at = match (due, deadline) {
+                (Some(due), Some(deadline)) => Some(due.min(deadline)),
+                (due, deadline) => due.or(deadline),
+            };
+            state = match wake_at {
+                Some(wake_at) => {
+                    self.inner
+                        .cond
+                        .wait_timeout(state, wake_at - now)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+                None => self
+                    .inner
+                    .cond
+                    .wait(state)
+                    .unwrap_or_else(|e| e.into_inner()),
+            };
+        }
+    }
+
+    fn notify(&self) -> io::Result<()> {
+        self.lock().notified = true;
+        self.inner.cond.notify_all();
+        Ok(())
+    }
+
+    fn supports_level(&self) -> bool {
+        true
+    }
+
+    fn supports_edge(&self) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for MockBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.lock();
+        f.debug_struct("MockBackend")
+            .field("steps", &state.script.len())
+            .field("calls", &state.calls.len())
+            .finish()
+    }
+}
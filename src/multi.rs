@@ -0,0 +1,99 @@
+//! Waiting on several `Poller`s at once.
+
+use std::io;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use rustix::event::{PollFd, PollFlags, Timespec};
+
+use crate::{Events, Poller};
+
+/// Wait on several pollers at once, and collect the events of those that are ready.
+///
+/// Each poller comes with the [`Events`] its events are added to. This blocks until at least one
+/// of the pollers has events or is [notified][`Poller::notify()`], or until the timeout elapses,
+/// and then waits on every poller that is ready without blocking. It returns the number of new
+/// events across all pollers, which may be zero if a poller was only notified.
+///
+/// This lets an application that embeds two event loops block on both without a thread that
+/// bridges them. The epoll, kqueue or event port instances of the pollers are polled with
+/// `poll()`, so no other poller is created. The timers, deadlines and deferred events of every
+/// poller are taken into account.
+///
+/// If another thread already waits on one of the pollers, that poller may report no events. If
+/// the wait is interrupted by a signal, it is restarted with the remaining timeout.
+///
+/// This function is only available on platforms whose backend is an epoll, kqueue or event port
+/// instance.
+///
+/// # Errors
+///
+/// This returns an error of kind [`Unsupported`][`io::ErrorKind::Unsupported`] if one of the
+/// pollers has a [custom backend][`crate::PollerBuilder::build_custom()`] or the [poll
+/// backend][`crate::Backend::Poll`], which can't be polled with `poll()`.
+pub fn wait_any(
+    pollers: &mut [(&Poller, &mut Events)],
+    timeout: Option<Duration>,
+) -> io::Result<usize> {
+    let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+    loop {
+        // Wake up for whichever comes first: the timeout or the timers of one of the pollers.
+        let due: Vec<_> = pollers
+            .iter()
+            .map(|(poller, _)| next_deadline(poller))
+            .collect();
+        let wake_at = due.iter().flatten().chain(&deadline).min().copied();
+
+        // Timeout for `poll`. In case of overflow, use no timeout.
+        let timeout = wake_at
+            .map(|wake_at| wake_at.saturating_duration_since(Instant::now()))
+            .and_then(|timeout| Timespec::try_from(timeout).ok());
+        let mut fds = pollers
+            .iter()
+            .map(|(poller, _)| {
+                let fd = poller.try_as_fd().ok_or_else(|| {
+                    crate::unsupported_error("a poller without a file descriptor can't be polled")
+                })?;
+                Ok(PollFd::from_borrowed_fd(fd, PollFlags::IN))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        match rustix::event::poll(&mut fds, timeout.as_ref()) {
+            Err(rustix::io::Errno::INTR) => continue,
+            res => res?,
+        };
+
+        let now = Instant::now();
+        let ready: Vec<_> = fds
+            .iter()
+            .zip(&due)
+            .map(|(fd, due)| !fd.revents().is_empty() || due.is_some_and(|due| due <= now))
+            .collect();
+        drop(fds);
+
+        if ready.iter().any(|&ready| ready) {
+            let mut count = 0;
+            for ((poller, events), ready) in pollers.iter_mut().zip(ready) {
+                if ready {
+                    count += poller.wait(events, Some(Duration::ZERO))?;
+                }
+            }
+            return Ok(count);
+        }
+
+        // `poll` may round the timeout down.
+        if deadline.is_some_and(|deadline| deadline <= now) {
+            return Ok(0);
+        }
+    }
+}
+
+/// The instant at which a wait on `poller` returns without any activity of its backend.
+fn next_deadline(poller: &Poller) -> Option<Instant> {
+    if !poller.deferred().is_empty()
+        || (poller.sticky_notify && poller.notified.load(Ordering::SeqCst))
+    {
+        return Some(Instant::now());
+    }
+
+    poller.next_timer()
+}
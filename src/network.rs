@@ -0,0 +1,334 @@
+//! A readable source for changes of the network interfaces.
+
+use std::fmt;
+use std::io;
+
+/// A readable source that becomes readable when a network interface or address changes.
+///
+/// This reports that a link went up or down, or that an address was added or removed, so that
+/// programs such as VPN clients and service discovery daemons can look at the interfaces again.
+/// It is registered with a [`Poller`] like any other source, and delivers an event with its key.
+/// It stays readable until [`reset()`][`NetworkChanges::reset()`] is called, and doesn't tell
+/// which interface changed.
+///
+/// On Linux and Android this is a `NETLINK_ROUTE` socket that listens to the link and address
+/// groups. On the BSDs, Apple platforms, illumos and Solaris it is a `PF_ROUTE` socket, which
+/// also reports changes of the routing table. On Windows, the notifications of
+/// `NotifyIpInterfaceChange()` and `NotifyUnicastIpAddressChange()` wake up a [`WakePipe`].
+///
+/// [`Poller`]: crate::Poller
+/// [`WakePipe`]: crate::WakePipe
+///
+/// # Examples
+///
+/// ```no_run
+/// use polling::{Event, Events, NetworkChanges, Poller};
+///
+/// let poller = Poller::new()?;
+/// let changes = NetworkChanges::new()?;
+/// unsafe {
+///     poller.add(&changes, Event::readable(0))?;
+/// }
+///
+/// let mut events = Events::new();
+/// loop {
+///     poller.wait(&mut events, None)?;
+///     if events.iter().any(|ev| ev.key == 0) {
+///         changes.reset()?;
+///         println!("the network interfaces changed");
+///         poller.modify(&changes, Event::readable(0))?;
+///     }
+///     events.clear();
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+pub struct NetworkChanges {
+    inner: sys::Monitor,
+}
+
+impl NetworkChanges {
+    /// Start listening to changes of the network interfaces.
+    pub fn new() -> io::Result<NetworkChanges> {
+        Ok(NetworkChanges {
+            inner: sys::Monitor::new()?,
+        })
+    }
+
+    /// Consume the changes reported so far, so that the source is no longer readable.
+    pub fn reset(&self) -> io::Result<()> {
+        self.inner.reset()
+    }
+}
+
+impl fmt::Debug for NetworkChanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NetworkChanges")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+#[cfg(unix)]
+mod unix_impl {
+    use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+
+    use super::NetworkChanges;
+
+    impl AsRawFd for NetworkChanges {
+        fn as_raw_fd(&self) -> RawFd {
+            self.inner.source().as_raw_fd()
+        }
+    }
+
+    impl AsFd for NetworkChanges {
+        fn as_fd(&self) -> BorrowedFd<'_> {
+            self.inner.source()
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows_impl {
+    use std::os::windows::io::{AsRawSocket, AsSocket, BorrowedSocket, RawSocket};
+
+    use super::NetworkChanges;
+
+    impl AsRawSocket for NetworkChanges {
+        fn as_raw_socket(&self) -> RawSocket {
+            self.inner.source().as_raw_socket()
+        }
+    }
+
+    impl AsSocket for NetworkChanges {
+        fn as_socket(&self) -> BorrowedSocket<'_> {
+            self.inner.source().as_socket()
+        }
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::io;
+    use std::os::unix::io::{AsFd, BorrowedFd, FromRawFd, OwnedFd};
+
+    /// A routing socket.
+    #[derive(Debug)]
+    pub(super) struct Monitor {
+        socket: OwnedFd,
+    }
+
+    impl Monitor {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        pub(super) fn new() -> io::Result<Monitor> {
+            // Android's `libc` doesn't have the names of the groups.
+            const RTMGRP_LINK: u32 = 0x1;
+            const RTMGRP_IPV4_IFADDR: u32 = 0x10;
+            const RTMGRP_IPV6_IFADDR: u32 = 0x100;
+
+            let socket = socket(libc::AF_NETLINK, libc::NETLINK_ROUTE)?;
+
+            // SAFETY: An all-zero `sockaddr_nl` is valid, and the address is valid for reads of
+            // its size.
+            let res = unsafe {
+                let mut addr: libc::sockaddr_nl = std::mem::zeroed();
+                addr.nl_family = libc::AF_NETLINK as _;
+                addr.nl_groups = RTMGRP_LINK | RTMGRP_IPV4_IFADDR | RTMGRP_IPV6_IFADDR;
+                libc::bind(
+                    std::os::unix::io::AsRawFd::as_raw_fd(&socket),
+                    (&addr as *const libc::sockaddr_nl).cast(),
+                    std::mem::size_of_val(&addr) as libc::socklen_t,
+                )
+            };
+            if res == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Monitor { socket })
+        }
+
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        pub(super) fn new() -> io::Result<Monitor> {
+            Ok(Monitor {
+                socket: socket(libc::PF_ROUTE, 0)?,
+            })
+        }
+
+        /// Read all pending messages.
+        pub(super) fn reset(&self) -> io::Result<()> {
+            let mut buf = [0u8; 4096];
+            loop {
+                // SAFETY: The buffer is valid for writes of its size.
+                let res = unsafe {
+                    libc::recv(
+                        std::os::unix::io::AsRawFd::as_raw_fd(&self.socket),
+                        buf.as_mut_ptr().cast(),
+                        buf.len(),
+                        0,
+                    )
+                };
+                if res == -1 {
+                    let err = io::Error::last_os_error();
+                    match err.kind() {
+                        io::ErrorKind::WouldBlock => return Ok(()),
+                        io::ErrorKind::Interrupted => {}
+                        // Messages were dropped because too many changes piled up, which is
+                        // reported by the source being readable all the same.
+                        _ if err.raw_os_error() == Some(libc::ENOBUFS) => {}
+                        _ => return Err(err),
+                    }
+                }
+            }
+        }
+
+        pub(super) fn source(&self) -> BorrowedFd<'_> {
+            self.socket.as_fd()
+        }
+    }
+
+    /// Create a non-blocking raw socket that is closed on `exec`.
+    fn socket(domain: libc::c_int, protocol: libc::c_int) -> io::Result<OwnedFd> {
+        // SAFETY: `socket` takes no pointers, and returns a new file descriptor on success.
+        let socket = unsafe {
+            let fd = libc::socket(domain, libc::SOCK_RAW, protocol);
+            if fd == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            OwnedFd::from_raw_fd(fd)
+        };
+        let fd = std::os::unix::io::AsRawFd::as_raw_fd(&socket);
+
+        // SAFETY: `fd` is open.
+        unsafe {
+            let flags = libc::fcntl(fd, libc::F_GETFD);
+            if flags == -1 || libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            let flags = libc::fcntl(fd, libc::F_GETFL);
+            if flags == -1 || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(socket)
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::fmt;
+    use std::io;
+    use std::ptr;
+
+    use windows_sys::Win32::Foundation::{ERROR_SUCCESS, HANDLE};
+    use windows_sys::Win32::NetworkManagement::IpHelper::{
+        CancelMibChangeNotify2, NotifyIpInterfaceChange, NotifyUnicastIpAddressChange,
+        MIB_IPINTERFACE_ROW, MIB_NOTIFICATION_TYPE, MIB_UNICASTIPADDRESS_ROW,
+    };
+    use windows_sys::Win32::Networking::WinSock::AF_UNSPEC;
+
+    use crate::WakePipe;
+
+    /// A channel that the notifications of IP Helper wake up.
+    pub(super) struct Monitor {
+        /// The channel, boxed so that its address stays the same for the callbacks.
+        pipe: Box<WakePipe>,
+
+        /// The registrations of the callbacks, cancelled when this is dropped.
+        handles: Vec<HANDLE>,
+    }
+
+    // SAFETY: The handles are only used to cancel the notifications, which can be done from any
+    // thread.
+    unsafe impl Send for Monitor {}
+    unsafe impl Sync for Monitor {}
+
+    impl Monitor {
+        pub(super) fn new() -> io::Result<Monitor> {
+            let mut monitor = Monitor {
+                pipe: Box::new(WakePipe::new()?),
+                handles: Vec::with_capacity(2),
+            };
+            let context: *const WakePipe = &*monitor.pipe;
+
+            // SAFETY: The context stays valid until the notifications are cancelled in `drop()`.
+            let mut handle = ptr::null_mut();
+            let res = unsafe {
+                NotifyIpInterfaceChange(
+                    AF_UNSPEC,
+                    Some(on_interface_change),
+                    context.cast(),
+                    false,
+                    &mut handle,
+                )
+            };
+            if res != ERROR_SUCCESS {
+                return Err(io::Error::from_raw_os_error(res as _));
+            }
+            monitor.handles.push(handle);
+
+            // SAFETY: As above.
+            let mut handle = ptr::null_mut();
+            let res = unsafe {
+                NotifyUnicastIpAddressChange(
+                    AF_UNSPEC,
+                    Some(on_address_change),
+                    context.cast(),
+                    false,
+                    &mut handle,
+                )
+            };
+            if res != ERROR_SUCCESS {
+                return Err(io::Error::from_raw_os_error(res as _));
+            }
+            monitor.handles.push(handle);
+
+            Ok(monitor)
+        }
+
+        pub(super) fn reset(&self) -> io::Result<()> {
+            self.pipe.reset()
+        }
+
+        pub(super) fn source(&self) -> &WakePipe {
+            &self.pipe
+        }
+    }
+
+    impl fmt::Debug for Monitor {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Monitor").field("pipe", &self.pipe).finish()
+        }
+    }
+
+    impl Drop for Monitor {
+        fn drop(&mut self) {
+            // This waits for callbacks that are running, so the channel can be dropped afterwards.
+            for handle in self.handles.drain(..) {
+                // SAFETY: The handle was returned by a successful registration.
+                unsafe {
+                    CancelMibChangeNotify2(handle);
+                }
+            }
+        }
+    }
+
+    unsafe extern "system" fn on_interface_change(
+        context: *const std::ffi::c_void,
+        _row: *const MIB_IPINTERFACE_ROW,
+        _notification_type: MIB_NOTIFICATION_TYPE,
+    ) {
+        wake(context);
+    }
+
+    unsafe extern "system" fn on_address_change(
+        context: *const std::ffi::c_void,
+        _row: *const MIB_UNICASTIPADDRESS_ROW,
+        _notification_type: MIB_NOTIFICATION_TYPE,
+    ) {
+        wake(context);
+    }
+
+    /// Wake up the channel that `context` points to.
+    unsafe fn wake(context: *const std::ffi::c_void) {
+        // There is nothing to report a failure to. A full channel is readable anyway.
+        (*context.cast::<WakePipe>()).wake().ok();
+    }
+}
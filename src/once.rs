@@ -0,0 +1,152 @@
+//! Waiting for a single source without a `Poller`.
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::{AsSource, Event, Interest};
+
+/// Wait until a single file descriptor or socket is ready, without creating a [`Poller`].
+///
+/// This uses `poll()`, or `WSAPoll()` on Windows, so it doesn't allocate an `epoll`, `kqueue` or
+/// completion port instance. It waits until the source is ready for any of the events in
+/// `interest`, or until the timeout elapses, in which case it returns `None`. If the wait is
+/// interrupted by a signal, it is restarted with the remaining timeout.
+///
+/// The returned event has the key `0`, and is readable or writable depending on which events
+/// occurred. Errors and hangups make it both readable and writable. [`Interest::PRIORITY`] is
+/// not supported on Windows.
+///
+/// [`Poller`]: crate::Poller
+///
+/// # Examples
+///
+/// ```
+/// use polling::Interest;
+/// use std::net::UdpSocket;
+/// use std::time::Duration;
+///
+/// let socket = UdpSocket::bind("127.0.0.1:0")?;
+/// let event = polling::poll_once(&socket, Interest::WRITABLE, Some(Duration::from_secs(1)))?;
+/// assert!(event.unwrap().writable);
+///
+/// // Nothing was sent to the socket.
+/// let event = polling::poll_once(&socket, Interest::READABLE, Some(Duration::ZERO))?;
+/// assert!(event.is_none());
+/// # std::io::Result::Ok(())
+/// ```
+pub fn poll_once(
+    source: impl AsSource,
+    interest: Interest,
+    timeout: Option<Duration>,
+) -> io::Result<Option<Event>> {
+    let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+    loop {
+        let timeout = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        match sys::poll(&source, interest, timeout) {
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Ok(Some(ready)) => return Ok(Some(Event::from_interest(0, ready))),
+            res => return res.map(|_| None),
+        }
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::io;
+    use std::time::Duration;
+
+    use rustix::event::{PollFd, PollFlags, Timespec};
+
+    use crate::{AsSource, Interest};
+
+    /// Poll `source` once, and return the events that occurred.
+    pub(super) fn poll(
+        source: &impl AsSource,
+        interest: Interest,
+        timeout: Option<Duration>,
+    ) -> io::Result<Option<Interest>> {
+        let mut flags = PollFlags::empty();
+        flags.set(PollFlags::IN, interest.is_readable());
+        flags.set(PollFlags::OUT, interest.is_writable());
+        flags.set(PollFlags::PRI, interest.contains(Interest::PRIORITY));
+
+        // Timeout for `poll`. In case of overflow, use no timeout.
+        let timeout = timeout.and_then(|timeout| Timespec::try_from(timeout).ok());
+        let mut fds = [PollFd::from_borrowed_fd(source.source(), flags)];
+        if rustix::event::poll(&mut fds, timeout.as_ref())? == 0 {
+            return Ok(None);
+        }
+
+        let revents = fds[0].revents();
+        let failed = revents.intersects(PollFlags::ERR | PollFlags::HUP | PollFlags::NVAL);
+        let mut ready = Interest::NONE;
+        ready.set(
+            Interest::READABLE,
+            failed || revents.intersects(PollFlags::IN | PollFlags::PRI),
+        );
+        ready.set(
+            Interest::WRITABLE,
+            failed || revents.intersects(PollFlags::OUT),
+        );
+        ready.set(Interest::PRIORITY, revents.intersects(PollFlags::PRI));
+        ready.set(Interest::HANGUP, revents.intersects(PollFlags::HUP));
+        ready.set(Interest::ERROR, revents.intersects(PollFlags::ERR));
+        Ok(Some(ready))
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::io;
+    use std::os::windows::io::AsRawSocket;
+    use std::time::Duration;
+
+    use windows_sys::Win32::Networking::WinSock::{
+        WSAGetLastError, WSAPoll, POLLERR, POLLHUP, POLLNVAL, POLLRDNORM, POLLWRNORM, SOCKET_ERROR,
+        WSAPOLLFD,
+    };
+
+    use crate::{AsSource, Interest};
+
+    /// Poll `source` once, and return the events that occurred.
+    pub(super) fn poll(
+        source: &impl AsSource,
+        interest: Interest,
+        timeout: Option<Duration>,
+    ) -> io::Result<Option<Interest>> {
+        let mut events = 0;
+        if interest.is_readable() {
+            events |= POLLRDNORM;
+        }
+        if interest.is_writable() {
+            events |= POLLWRNORM;
+        }
+
+        // Round the timeout up to milliseconds, so it doesn't return early.
+        let timeout = match timeout {
+            Some(timeout) => {
+                let ms = (timeout.as_nanos() + 999_999) / 1_000_000;
+                ms.min(i32::MAX as u128) as i32
+            }
+            None => -1,
+        };
+        let mut fd = WSAPOLLFD {
+            fd: source.source().as_raw_socket() as _,
+            events,
+            revents: 0,
+        };
+        match unsafe { WSAPoll(&mut fd, 1, timeout) } {
+            SOCKET_ERROR => return Err(io::Error::from_raw_os_error(unsafe { WSAGetLastError() })),
+            0 => return Ok(None),
+            _ => {}
+        }
+
+        let failed = fd.revents & (POLLERR | POLLHUP | POLLNVAL) != 0;
+        let mut ready = Interest::NONE;
+        ready.set(Interest::READABLE, failed || fd.revents & POLLRDNORM != 0);
+        ready.set(Interest::WRITABLE, failed || fd.revents & POLLWRNORM != 0);
+        ready.set(Interest::HANGUP, fd.revents & POLLHUP != 0);
+        ready.set(Interest::ERROR, fd.revents & POLLERR != 0);
+        Ok(Some(ready))
+    }
+}
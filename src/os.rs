@@ -12,13 +12,37 @@
 ))]
 pub mod kqueue;
 
+#[cfg(all(
+    any(target_os = "linux", target_os = "android", target_os = "redox"),
+    not(polling_test_poll_backend),
+))]
+pub mod epoll;
+
+#[cfg(all(target_os = "android", not(polling_test_poll_backend)))]
+pub mod android;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod futex;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod io_uring;
+
 #[cfg(target_os = "windows")]
 pub mod iocp;
 
+#[cfg(unix)]
+pub mod unix;
+
 mod __private {
     #[doc(hidden)]
     #[allow(dead_code)]
     pub trait PollerSealed {}
 
     impl PollerSealed for crate::Poller {}
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    pub trait EventSealed {}
+
+    impl EventSealed for crate::Event {}
 }
@@ -0,0 +1,194 @@
+//! Integration with the `ALooper` of Android apps.
+
+use crate::{Events, Poller};
+
+use std::fmt;
+use std::io;
+use std::os::raw::{c_int, c_void};
+use std::os::unix::io::AsRawFd;
+use std::ptr::NonNull;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The `ALooper` type of the NDK.
+#[repr(C)]
+struct ALooper {
+    _private: [u8; 0],
+}
+
+/// Allow `ALooper_pollOnce()` to be called without callbacks.
+const ALOOPER_PREPARE_ALLOW_NON_CALLBACKS: c_int = 1;
+
+/// The file descriptor is ready for reading.
+const ALOOPER_EVENT_INPUT: c_int = 1;
+
+/// The identifier of file descriptors whose events are handled by a callback.
+const ALOOPER_POLL_CALLBACK: c_int = -2;
+
+#[link(name = "android")]
+extern "C" {
+    fn ALooper_prepare(opts: c_int) -> *mut ALooper;
+    fn ALooper_acquire(looper: *mut ALooper);
+    fn ALooper_release(looper: *mut ALooper);
+    fn ALooper_addFd(
+        looper: *mut ALooper,
+        fd: c_int,
+        ident: c_int,
+        events: c_int,
+        callback: Option<unsafe extern "C" fn(c_int, c_int, *mut c_void) -> c_int>,
+        data: *mut c_void,
+    ) -> c_int;
+    fn ALooper_removeFd(looper: *mut ALooper, fd: c_int) -> c_int;
+}
+
+/// The function that handles the events of an attached poller.
+type Callback = dyn FnMut(&Poller, io::Result<&Events>);
+
+/// The state that the looper passes to `on_ready()`.
+struct Attached {
+    poller: Arc<Poller>,
+    events: Events,
+    callback: Box<Callback>,
+}
+
+/// A [`Poller`] attached to the `ALooper` of a thread.
+///
+/// The epoll instance of the poller is added to the looper, which calls back whenever the
+/// poller has events. The callback then collects them with a wait that doesn't block, and
+/// passes them on. This way an NDK app handles the I/O of the poller on the same thread as its
+/// UI callbacks, instead of blocking in [`Poller::wait()`] on a thread of its own.
+///
+/// Only sources and notifications make the looper call back. The [timers] and [deadlines] of
+/// the poller don't, since they are kept by the poller itself, so they are delivered with the
+/// next events. No other thread should wait on the poller while it is attached.
+///
+/// The poller is removed from the looper when this is dropped. This can't be sent to other
+/// threads, since it must be dropped on the thread of the looper, which the callback runs on,
+/// and it must not be dropped by its own callback.
+///
+/// [timers]: Poller::set_timer
+/// [deadlines]: Poller::set_deadline
+///
+/// # Examples
+///
+/// ```no_run
+/// use polling::os::android::LooperAttachment;
+/// use polling::Poller;
+/// use std::sync::Arc;
+///
+/// let poller = Arc::new(Poller::new()?);
+/// let attachment = LooperAttachment::new(poller, |_poller, events| match events {
+///     Ok(events) => {
+///         for ev in events.iter() {
+///             println!("{:?}", ev);
+///         }
+///     }
+///     Err(err) => eprintln!("failed to wait for events: {}", err),
+/// })?;
+/// # std::io::Result::Ok(())
+/// ```
+pub struct LooperAttachment {
+    looper: NonNull<ALooper>,
+    poller: Arc<Poller>,
+    attached: NonNull<Attached>,
+}
+
+impl LooperAttachment {
+    /// Attach `poller` to the looper of the current thread, which is created if needed.
+    ///
+    /// `callback` is called on this thread with the events of each wakeup, or with the error of
+    /// the wait that collected them.
+    pub fn new(
+        poller: Arc<Poller>,
+        callback: impl FnMut(&Poller, io::Result<&Events>) + 'static,
+    ) -> io::Result<LooperAttachment> {
+        poller.poller.handle()?;
+
+        // SAFETY: The looper is valid until it is released in `drop()`.
+        let looper = unsafe {
+            let looper = ALooper_prepare(ALOOPER_PREPARE_ALLOW_NON_CALLBACKS);
+            let looper = NonNull::new(looper).ok_or_else(io::Error::last_os_error)?;
+            ALooper_acquire(looper.as_ptr());
+            looper
+        };
+
+        let attached = Box::new(Attached {
+            poller: poller.clone(),
+            events: poller.new_events(),
+            callback: Box::new(callback),
+        });
+        let attached = NonNull::from(Box::leak(attached));
+
+        // SAFETY: `attached` stays valid until the poller is removed from the looper.
+        let res = unsafe {
+            ALooper_addFd(
+                looper.as_ptr(),
+                poller.as_raw_fd(),
+                ALOOPER_POLL_CALLBACK,
+                ALOOPER_EVENT_INPUT,
+                Some(on_ready),
+                attached.as_ptr().cast(),
+            )
+        };
+        if res != 1 {
+            // SAFETY: The poller wasn't added, so the looper never calls back.
+            unsafe {
+                drop(Box::from_raw(attached.as_ptr()));
+                ALooper_release(looper.as_ptr());
+            }
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "failed to add the poller to the looper",
+            ));
+        }
+
+        Ok(LooperAttachment {
+            looper,
+            poller,
+            attached,
+        })
+    }
+
+    /// The attached poller.
+    #[inline]
+    pub fn poller(&self) -> &Arc<Poller> {
+        &self.poller
+    }
+}
+
+impl fmt::Debug for LooperAttachment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LooperAttachment")
+            .field("looper", &self.looper)
+            .field("poller", &self.poller)
+            .finish()
+    }
+}
+
+impl Drop for LooperAttachment {
+    fn drop(&mut self) {
+        // SAFETY: This is dropped on the thread of the looper, so the callback isn't running.
+        // Once the poller is removed, it isn't called anymore.
+        unsafe {
+            ALooper_removeFd(self.looper.as_ptr(), self.poller.as_raw_fd());
+            drop(Box::from_raw(self.attached.as_ptr()));
+            ALooper_release(self.looper.as_ptr());
+        }
+    }
+}
+
+/// Collect the events of the poller when the looper reports that it is ready.
+unsafe extern "C" fn on_ready(_fd: c_int, _events: c_int, data: *mut c_void) -> c_int {
+    let attached = &mut *data.cast::<Attached>();
+    attached.events.clear();
+    match attached
+        .poller
+        .wait(&mut attached.events, Some(Duration::ZERO))
+    {
+        Ok(_) => (attached.callback)(&attached.poller, Ok(&attached.events)),
+        Err(err) => (attached.callback)(&attached.poller, Err(err)),
+    }
+
+    // Keep the callback registered.
+    1
+}
@@ -0,0 +1,87 @@
+//! Functionality that is only available for `epoll`-based platforms.
+
+use crate::{AsRawSource, AsSource, Backend, Event, PollMode, Poller};
+
+use std::io;
+
+use super::__private::{EventSealed, PollerSealed};
+
+/// Functionality that is only available for `epoll`-based platforms.
+///
+/// This is an escape hatch for registering sources with `EPOLL*` flags that the portable API
+/// doesn't model. The flags are passed to `epoll_ctl()` as they are, so the raw flags of
+/// delivered events can be read with [`EventEpollExt::raw_flags()`].
+pub trait PollerEpollExt: PollerSealed {
+    /// Add a source to the poller with raw `EPOLL*` flags.
+    ///
+    /// `EPOLLONESHOT` and `EPOLLET` select the mode of the registration, as reported by
+    /// [`Poller::registered()`].
+    ///
+    /// # Safety
+    ///
+    /// The source must be deleted from the poller before it is dropped, see [`Poller::add()`].
+    unsafe fn add_raw(&self, source: impl AsRawSource, key: usize, flags: u32) -> io::Result<()>;
+
+    /// Modify a source in the poller with raw `EPOLL*` flags.
+    ///
+    /// See [`add_raw()`](PollerEpollExt::add_raw) for more information.
+    fn modify_raw(&self, source: impl AsSource, key: usize, flags: u32) -> io::Result<()>;
+}
+
+impl PollerEpollExt for Poller {
+    unsafe fn add_raw(&self, source: impl AsRawSource, key: usize, flags: u32) -> io::Result<()> {
+        check_epoll(self)?;
+        let (event, mode) = raw_event(key, flags);
+        self.add_with_mode(source, event, mode)
+    }
+
+    fn modify_raw(&self, source: impl AsSource, key: usize, flags: u32) -> io::Result<()> {
+        check_epoll(self)?;
+        let (event, mode) = raw_event(key, flags);
+        self.modify_with_mode(source, event, mode)
+    }
+}
+
+/// Fail unless `poller` runs on epoll, which is the only backend that understands the flags.
+fn check_epoll(poller: &Poller) -> io::Result<()> {
+    match poller.backend() {
+        Backend::Epoll => Ok(()),
+        backend => Err(crate::unsupported_error(format!(
+            "raw epoll flags are not supported by the {} backend",
+            backend
+        ))),
+    }
+}
+
+/// Functionality of events that is only available for `epoll`-based platforms.
+pub trait EventEpollExt: EventSealed {
+    /// The raw `EPOLL*` flags of this event.
+    ///
+    /// For delivered events, this is the `events` mask reported by `epoll_wait()`.
+    fn raw_flags(&self) -> u32;
+}
+
+impl EventEpollExt for Event {
+    #[inline]
+    fn raw_flags(&self) -> u32 {
+        self.extra.raw()
+    }
+}
+
+/// Split raw flags into an event carrying them and the mode they select.
+fn raw_event(key: usize, flags: u32) -> (Event, PollMode) {
+    const ONESHOT: u32 = libc::EPOLLONESHOT as u32;
+    const ET: u32 = libc::EPOLLET as u32;
+
+    let mode = match (flags & ONESHOT != 0, flags & ET != 0) {
+        (false, false) => PollMode::Level,
+        (true, false) => PollMode::Oneshot,
+        (false, true) => PollMode::Edge,
+        (true, true) => PollMode::EdgeOneshot,
+    };
+
+    // The mode flags are added back when the source is registered.
+    let mut event = Event::none(key);
+    event.extra = crate::sys::EventExtra::from_raw(flags & !(ONESHOT | ET));
+    (event, mode)
+}
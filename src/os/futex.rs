@@ -0,0 +1,112 @@
+//! Waiting for timeouts and notifications without a file descriptor.
+
+use crate::WaitResult;
+
+use rustix::io::Errno;
+use rustix::thread::futex;
+use rustix::time::Timespec;
+
+use std::io;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+/// The poller wasn't notified since the last wait.
+const IDLE: u32 = 0;
+
+/// The poller was notified, and the next wait returns right away.
+const NOTIFIED: u32 = 1;
+
+/// A poller for waits that only need timeouts and notifications, built on a futex.
+///
+/// A [`Poller`] always holds an epoll instance and an eventfd, even when no sources are ever
+/// registered in it. This offers the same [`wait()`][`FutexPoller::wait()`] and
+/// [`notify()`][`FutexPoller::notify()`] without consuming any file descriptors, for processes
+/// that create a waiter per worker thread under a tight limit of open files. Like with
+/// [`Poller::notify()`], a notification wakes up the current or the next wait, once.
+///
+/// [`Poller`]: crate::Poller
+/// [`Poller::notify()`]: crate::Poller::notify
+///
+/// # Examples
+///
+/// ```
+/// use polling::os::futex::FutexPoller;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// let poller = Arc::new(FutexPoller::new());
+/// assert!(poller.wait(Some(Duration::from_millis(1)))?.is_timed_out());
+///
+/// let notifier = poller.clone();
+/// std::thread::spawn(move || notifier.notify());
+/// while !poller.wait(None)?.is_notified() {}
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug, Default)]
+pub struct FutexPoller {
+    state: AtomicU32,
+}
+
+impl FutexPoller {
+    /// Create a new poller.
+    #[inline]
+    pub const fn new() -> FutexPoller {
+        FutexPoller {
+            state: AtomicU32::new(IDLE),
+        }
+    }
+
+    /// Wait until the poller is notified or the timeout elapses.
+    ///
+    /// The result is never reported with events. If the wait is interrupted by a signal, it is
+    /// restarted with the remaining timeout.
+    pub fn wait(&self, timeout: Option<Duration>) -> io::Result<WaitResult> {
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        loop {
+            if self.state.swap(IDLE, Ordering::SeqCst) == NOTIFIED {
+                return Ok(WaitResult {
+                    events: 0,
+                    notified: true,
+                    timed_out: false,
+                    cancelled: false,
+                    filled: false,
+                });
+            }
+
+            // In case of overflow, wait without a timeout.
+            let timeout = match deadline {
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        return Ok(WaitResult {
+                            events: 0,
+                            notified: false,
+                            timed_out: true,
+                            cancelled: false,
+                            filled: false,
+                        });
+                    }
+                    Timespec::try_from(left).ok()
+                }
+                None => None,
+            };
+
+            match futex::wait(&self.state, futex::Flags::PRIVATE, IDLE, timeout.as_ref()) {
+                // The state changed before the wait, or the wait timed out, was interrupted or
+                // woken up: check the state and the deadline again.
+                Ok(()) | Err(Errno::AGAIN | Errno::TIMEDOUT | Errno::INTR) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Wake up the current or the next call to [`wait()`][`FutexPoller::wait()`].
+    ///
+    /// If several threads are waiting, only one of them is woken up.
+    pub fn notify(&self) {
+        if self.state.swap(NOTIFIED, Ordering::SeqCst) == IDLE {
+            // Waking can only fail for an invalid address, which a reference never is.
+            futex::wake(&self.state, futex::Flags::PRIVATE, 1).ok();
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Driving an `io_uring` that is managed elsewhere from a [`Poller`].
+
+use crate::{Event, Poller, Registration};
+
+use rustix::event::{eventfd, EventfdFlags};
+use rustix::io::Errno;
+
+use std::fmt;
+use std::io;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+
+/// An eventfd that an `io_uring` signals its completions on, registered in a [`Poller`].
+///
+/// Register the eventfd with the ring, for example with `Submitter::register_eventfd()` of the
+/// [`io-uring`] crate. Whenever the ring posts completions, the poller delivers a readable event
+/// with the key of the eventfd, so the ring can be driven from the same [`wait()`] loop as the
+/// other sources.
+///
+/// After the event is delivered, call [`reset()`][`RingEventfd::reset()`] before reaping the
+/// completion queue. This clears the eventfd and enables the event again, so completions that
+/// are posted while reaping deliver another event instead of being missed.
+///
+/// The eventfd is removed from the poller and closed when this is dropped.
+///
+/// [`io-uring`]: https://docs.rs/io-uring
+/// [`wait()`]: Poller::wait
+///
+/// # Examples
+///
+/// ```no_run
+/// use polling::os::io_uring::RingEventfd;
+/// use polling::{Events, Poller};
+/// use std::os::unix::io::AsRawFd;
+///
+/// let poller = Poller::new()?;
+/// let eventfd = RingEventfd::new(&poller, 7)?;
+/// // ring.submitter().register_eventfd(eventfd.as_raw_fd())?;
+///
+/// let mut events = Events::new();
+/// loop {
+///     poller.wait(&mut events, None)?;
+///     for ev in events.iter() {
+///         if ev.key == eventfd.key() {
+///             eventfd.reset()?;
+///             // Reap the completion queue of the ring.
+///         }
+///     }
+///     events.clear();
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+pub struct RingEventfd<'a> {
+    registration: Registration<'a, OwnedFd>,
+    key: usize,
+}
+
+impl<'a> RingEventfd<'a> {
+    /// Create a new eventfd and register it in `poller` under `key`.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if the eventfd can't be created, or in the same situations as
+    /// [`Poller::add()`].
+    pub fn new(poller: &'a Poller, key: usize) -> io::Result<Self> {
+        let eventfd = eventfd(0, EventfdFlags::CLOEXEC | EventfdFlags::NONBLOCK)?;
+        Self::with_eventfd(poller, eventfd, key)
+    }
+
+    /// Register an eventfd that is already registered with a ring in `poller` under `key`.
+    ///
+    /// The eventfd must be non-blocking, so that [`reset()`][`RingEventfd::reset()`] doesn't
+    /// block when the counter is already zero.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error in the same situations as [`Poller::add()`].
+    pub fn with_eventfd(poller: &'a Poller, eventfd: OwnedFd, key: usize) -> io::Result<Self> {
+        Ok(Self {
+            registration: poller.register(eventfd, Event::readable(key))?,
+            key,
+        })
+    }
+
+    /// The key that events of the eventfd are delivered with.
+    #[inline]
+    pub fn key(&self) -> usize {
+        self.key
+    }
+
+    /// Clear the eventfd and enable its event again.
+    ///
+    /// Call this after the event is delivered and before reaping the completion queue.
+    pub fn reset(&self) -> io::Result<()> {
+        match rustix::io::read(self.registration.get_ref(), &mut [0; 8]) {
+            Ok(_) | Err(Errno::AGAIN) => {}
+            Err(err) => return Err(err.into()),
+        }
+        self.registration.modify(Event::readable(self.key))
+    }
+}
+
+impl AsFd for RingEventfd<'_> {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.registration.get_ref().as_fd()
+    }
+}
+
+impl AsRawFd for RingEventfd<'_> {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.registration.get_ref().as_raw_fd()
+    }
+}
+
+impl fmt::Debug for RingEventfd<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RingEventfd")
+            .field("fd", &self.as_raw_fd())
+            .field("key", &self.key)
+            .finish()
+    }
+}
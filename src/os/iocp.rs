@@ -2,8 +2,8 @@
 
 pub use crate::sys::CompletionPacket;
 
-use super::__private::PollerSealed;
-use crate::{Event, PollMode, Poller};
+use super::__private::{EventSealed, PollerSealed};
+use crate::{AsRawSource, AsSource, Event, PollMode, Poller};
 
 use std::io;
 use std::os::windows::io::{AsRawHandle, RawHandle};
@@ -195,7 +195,8 @@ pub trait PollerIocpExt: PollerSealed {
 
 impl PollerIocpExt for Poller {
     fn post(&self, packet: CompletionPacket) -> io::Result<()> {
-        self.poller.post(packet)
+        self.check_key(packet.event().key)?;
+        self.poller.native()?.post(packet)
     }
 
     unsafe fn add_waitable(
@@ -204,7 +205,9 @@ impl PollerIocpExt for Poller {
         event: Event,
         mode: PollMode,
     ) -> io::Result<()> {
+        self.check_key(event.key)?;
         self.poller
+            .native()?
             .add_waitable(handle.as_raw_handle(), event, mode)
     }
 
@@ -214,16 +217,133 @@ impl PollerIocpExt for Poller {
         interest: Event,
         mode: PollMode,
     ) -> io::Result<()> {
+        self.check_key(interest.key)?;
         self.poller
+            .native()?
             .modify_waitable(handle.as_waitable().as_raw_handle(), interest, mode)
     }
 
     fn remove_waitable(&self, handle: impl AsWaitable) -> io::Result<()> {
         self.poller
+            .native()?
             .remove_waitable(handle.as_waitable().as_raw_handle())
     }
 }
 
+/// Data is available to read, `AFD_POLL_RECEIVE`.
+pub const AFD_POLL_RECEIVE: u32 = 0x001;
+/// Urgent data is available to read, `AFD_POLL_RECEIVE_EXPEDITED`.
+pub const AFD_POLL_RECEIVE_EXPEDITED: u32 = 0x002;
+/// Data can be written, `AFD_POLL_SEND`.
+pub const AFD_POLL_SEND: u32 = 0x004;
+/// The peer shut down its side of the connection, `AFD_POLL_DISCONNECT`.
+pub const AFD_POLL_DISCONNECT: u32 = 0x008;
+/// The connection was reset or aborted, `AFD_POLL_ABORT`.
+pub const AFD_POLL_ABORT: u32 = 0x010;
+/// The socket was closed locally, `AFD_POLL_LOCAL_CLOSE`.
+pub const AFD_POLL_LOCAL_CLOSE: u32 = 0x020;
+/// A connection can be accepted, `AFD_POLL_ACCEPT`.
+pub const AFD_POLL_ACCEPT: u32 = 0x080;
+/// A connection attempt failed, `AFD_POLL_CONNECT_FAIL`.
+pub const AFD_POLL_CONNECT_FAIL: u32 = 0x100;
+
+/// Functionality for sockets that is only available on Windows.
+///
+/// This is an escape hatch for registering sockets with `AFD_POLL_*` flags that the portable API
+/// doesn't model. The flags are passed to `IOCTL_AFD_POLL` as they are, and the flags of
+/// delivered events can be read with [`EventWindowsExt::raw_flags()`].
+pub trait PollerWindowsExt: PollerSealed {
+    /// Add a socket to the poller with raw `AFD_POLL_*` flags.
+    ///
+    /// Events are delivered when any of the flags are reported. Their `readable` and `writable`
+    /// fields are not set; use [`EventWindowsExt::raw_flags()`] instead.
+    ///
+    /// # Safety
+    ///
+    /// The socket must be deleted from the poller before it is dropped, see [`Poller::add()`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use polling::os::iocp::{PollerWindowsExt, AFD_POLL_ACCEPT};
+    /// use polling::{Events, PollMode, Poller};
+    /// use std::net::TcpListener;
+    ///
+    /// let socket = TcpListener::bind("127.0.0.1:0")?;
+    /// socket.set_nonblocking(true)?;
+    ///
+    /// let poller = Poller::new()?;
+    /// unsafe { poller.add_raw(&socket, 7, AFD_POLL_ACCEPT, PollMode::Oneshot)? };
+    ///
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, None)?;
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    unsafe fn add_raw(
+        &self,
+        source: impl AsRawSource,
+        key: usize,
+        flags: u32,
+        mode: PollMode,
+    ) -> io::Result<()>;
+
+    /// Modify a socket in the poller with raw `AFD_POLL_*` flags.
+    ///
+    /// See [`add_raw()`](PollerWindowsExt::add_raw) for more information.
+    fn modify_raw(
+        &self,
+        source: impl AsSource,
+        key: usize,
+        flags: u32,
+        mode: PollMode,
+    ) -> io::Result<()>;
+}
+
+impl PollerWindowsExt for Poller {
+    unsafe fn add_raw(
+        &self,
+        source: impl AsRawSource,
+        key: usize,
+        flags: u32,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.add_with_mode(source, raw_event(key, flags), mode)
+    }
+
+    fn modify_raw(
+        &self,
+        source: impl AsSource,
+        key: usize,
+        flags: u32,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.modify_with_mode(source, raw_event(key, flags), mode)
+    }
+}
+
+/// Functionality of events that is only available on Windows.
+pub trait EventWindowsExt: EventSealed {
+    /// The raw `AFD_POLL_*` flags of this event.
+    ///
+    /// For delivered socket events, these are the flags reported by `IOCTL_AFD_POLL`.
+    fn raw_flags(&self) -> u32;
+}
+
+impl EventWindowsExt for Event {
+    #[inline]
+    fn raw_flags(&self) -> u32 {
+        self.extra.raw()
+    }
+}
+
+/// Create an event carrying raw flags.
+fn raw_event(key: usize, flags: u32) -> Event {
+    let mut event = Event::none(key);
+    event.extra = crate::sys::EventExtra::from_raw(flags);
+    event
+}
+
 /// A type that represents a waitable handle.
 pub trait AsRawWaitable {
     /// Returns the raw handle of this waitable.
@@ -1,22 +1,33 @@
 //! Functionality that is only available for `kqueue`-based platforms.
 
 use crate::sys::{mode_to_flags, SourceId};
-use crate::{PollMode, Poller};
+#[cfg(target_vendor = "apple")]
+use crate::Events;
+use crate::{AsSource, Event, PollMode, Poller};
 
 use std::io;
 use std::marker::PhantomData;
+use std::os::raw::c_void;
+use std::os::unix::io::AsRawFd;
+#[cfg(any(target_os = "freebsd", target_os = "openbsd"))]
+use std::os::unix::io::BorrowedFd;
 use std::process::Child;
+use std::ptr::NonNull;
 use std::time::Duration;
+#[cfg(target_vendor = "apple")]
+use std::time::Instant;
+#[cfg(any(target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"))]
+use std::time::SystemTime;
 
 use rustix::event::kqueue;
 
-use super::__private::PollerSealed;
+use super::__private::{EventSealed, PollerSealed};
 use __private::FilterSealed;
 
-// TODO(notgull): We should also have EVFILT_AIO, EVFILT_VNODE and EVFILT_USER. However, the current
-// API makes it difficult to effectively express events from these filters. At the next breaking
-// change, we should change `Event` to be a struct with private fields, and encode additional
-// information in there.
+// TODO(notgull): We should also have EVFILT_VNODE and EVFILT_USER, which are only available
+// through `RawFilter` for now. However, the current API makes it difficult to effectively express
+// events from these filters. At the next breaking change, we should change `Event` to be a struct
+// with private fields, and encode additional information in there.
 
 /// Functionality that is only available for `kqueue`-based platforms.
 ///
@@ -99,18 +110,21 @@ impl<F: Filter> PollerKqueueExt<F> for Poller {
     #[inline(always)]
     fn add_filter(&self, filter: F, key: usize, mode: PollMode) -> io::Result<()> {
         // No difference between adding and modifying in kqueue.
-        self.poller.add_source(filter.source_id())?;
+        self.check_key(key)?;
+        self.poller.native()?.add_source(filter.source_id())?;
         self.modify_filter(filter, key, mode)
     }
 
     fn modify_filter(&self, filter: F, key: usize, mode: PollMode) -> io::Result<()> {
-        self.poller.has_source(filter.source_id())?;
+        self.check_key(key)?;
+        let poller = self.poller.native()?;
+        poller.has_source(filter.source_id())?;
 
         // Convert the filter into a kevent.
         let event = filter.filter(kqueue::EventFlags::ADD | mode_to_flags(mode), key);
 
         // Modify the filter.
-        self.poller.submit_changes([event])
+        poller.submit_changes([event])
     }
 
     fn delete_filter(&self, filter: F) -> io::Result<()> {
@@ -118,9 +132,501 @@ impl<F: Filter> PollerKqueueExt<F> for Poller {
         let event = filter.filter(kqueue::EventFlags::DELETE, 0);
 
         // Delete the filter.
-        self.poller.submit_changes([event])?;
+        let poller = self.poller.native()?;
+        poller.submit_changes([event])?;
 
-        self.poller.remove_source(filter.source_id())
+        poller.remove_source(filter.source_id())
+    }
+}
+
+/// Attaching pointers to file descriptors and sockets in a `kqueue`.
+///
+/// The `udata` field of a `kevent` is pointer-sized and is returned by the kernel with every
+/// event of the filter. The poller normally stores the key of the source there. With this
+/// extension, a source stores a pointer instead, such as the address of its handler, which
+/// is returned by [`EventKqueueExt::udata()`] along with the key of the event. The keys are
+/// still delivered as usual, so the rest of the program doesn't need to know about the pointer.
+pub trait PollerUdataExt: PollerSealed {
+    /// Modifies the interest in a registered source, and attaches `udata` to it.
+    ///
+    /// This is like [`Poller::modify_with_mode()`], and the pointer is kept by later
+    /// modifications of the interest until it is replaced, detached with `None`, or the source
+    /// is deleted. Only file descriptors polled by the kqueue carry the pointer; sources that
+    /// the poller polls with `poll()` deliver events without it.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error with [`io::ErrorKind::AlreadyExists`] if the pointer is already
+    /// attached to another source.
+    ///
+    /// # Safety
+    ///
+    /// The poller never dereferences the pointer, but events that carry it may be delivered
+    /// until the source is deleted or the pointer is replaced. The pointer must stay valid for
+    /// as long as code that handles these events dereferences it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::os::kqueue::{EventKqueueExt, PollerUdataExt};
+    /// use polling::{Event, Events, PollMode, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::ptr::NonNull;
+    /// use std::time::Duration;
+    ///
+    /// let handler = Box::new(String::from("writable"));
+    /// let udata = NonNull::from(&*handler).cast();
+    ///
+    /// let poller = Poller::new()?;
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// unsafe {
+    ///     poller.add(&socket, Event::none(1))?;
+    ///     poller.modify_with_udata(&socket, Event::writable(1), PollMode::Oneshot, Some(udata))?;
+    /// }
+    ///
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    /// let event = events.iter().next().unwrap();
+    /// assert_eq!(event.key, 1);
+    /// let handler = unsafe { event.udata().unwrap().cast::<String>().as_ref() };
+    /// assert_eq!(handler, "writable");
+    ///
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    unsafe fn modify_with_udata(
+        &self,
+        source: impl AsSource,
+        interest: Event,
+        mode: PollMode,
+        udata: Option<NonNull<c_void>>,
+    ) -> io::Result<()>;
+}
+
+impl PollerUdataExt for Poller {
+    unsafe fn modify_with_udata(
+        &self,
+        source: impl AsSource,
+        interest: Event,
+        mode: PollMode,
+        udata: Option<NonNull<c_void>>,
+    ) -> io::Result<()> {
+        let fd = source.source().as_raw_fd();
+        self.poller
+            .native()?
+            .set_udata(fd, udata.map(|udata| udata.as_ptr() as usize))?;
+        self.modify_with_mode(source, interest, mode)
+    }
+}
+
+/// Functionality that is only available on Apple platforms.
+///
+/// Apple platforms schedule threads by their quality-of-service class. A thread that spends
+/// most of its time waiting for events can run the wait, and the wakeup that ends it, at a
+/// lower class so the system can coalesce the work and save power. `kevent_qos()` is private
+/// API, so this is done by changing the class of the calling thread around the wait.
+#[cfg(target_vendor = "apple")]
+pub trait PollerQosExt: PollerSealed {
+    /// Waits for I/O events while the calling thread runs at the given QoS class.
+    ///
+    /// This is otherwise the same as [`Poller::wait()`]. The previous class of the thread is
+    /// restored before returning.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use polling::os::kqueue::{PollerQosExt, QosClass};
+    /// use polling::{Events, Poller};
+    /// use std::time::Duration;
+    ///
+    /// let poller = Poller::new()?;
+    /// let mut events = Events::new();
+    /// poller.wait_with_qos(&mut events, Some(Duration::from_secs(1)), QosClass::Utility)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    fn wait_with_qos(
+        &self,
+        events: &mut Events,
+        timeout: Option<Duration>,
+        qos: QosClass,
+    ) -> io::Result<usize>;
+}
+
+#[cfg(target_vendor = "apple")]
+impl PollerQosExt for Poller {
+    fn wait_with_qos(
+        &self,
+        events: &mut Events,
+        timeout: Option<Duration>,
+        qos: QosClass,
+    ) -> io::Result<usize> {
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        self.wait_sys(events, deadline, true, |poller, events, deadline| {
+            qos::with_class(qos, || poller.wait_deadline(events, deadline))
+        })
+    }
+}
+
+/// A quality-of-service class of a thread on Apple platforms.
+#[cfg(target_vendor = "apple")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum QosClass {
+    /// Work the user is interacting with, such as updating the user interface.
+    UserInteractive,
+
+    /// Work the user started and is waiting for.
+    UserInitiated,
+
+    /// The default class.
+    Default,
+
+    /// Long-running work the user doesn't wait for.
+    Utility,
+
+    /// Work that is not visible to the user, such as indexing or backups.
+    Background,
+}
+
+#[cfg(target_vendor = "apple")]
+mod qos {
+    use super::QosClass;
+    use std::io;
+    use std::os::raw::c_int;
+
+    // From <sys/qos.h>.
+    const QOS_CLASS_USER_INTERACTIVE: u32 = 0x21;
+    const QOS_CLASS_USER_INITIATED: u32 = 0x19;
+    const QOS_CLASS_DEFAULT: u32 = 0x15;
+    const QOS_CLASS_UTILITY: u32 = 0x11;
+    const QOS_CLASS_BACKGROUND: u32 = 0x09;
+
+    extern "C" {
+        fn pthread_set_qos_class_self_np(class: u32, relative_priority: c_int) -> c_int;
+        fn pthread_get_qos_class_np(
+            thread: libc::pthread_t,
+            class: *mut u32,
+            relative_priority: *mut c_int,
+        ) -> c_int;
+    }
+
+    /// The value of `qos_class_t` for `qos`.
+    pub(super) fn class(qos: QosClass) -> u32 {
+        match qos {
+            QosClass::UserInteractive => QOS_CLASS_USER_INTERACTIVE,
+            QosClass::UserInitiated => QOS_CLASS_USER_INITIATED,
+            QosClass::Default => QOS_CLASS_DEFAULT,
+            QosClass::Utility => QOS_CLASS_UTILITY,
+            QosClass::Background => QOS_CLASS_BACKGROUND,
+        }
+    }
+
+    /// Run `f` with the QoS class of the calling thread set to `qos`.
+    pub(super) fn with_class<T>(qos: QosClass, f: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+        let class = class(qos);
+
+        // SAFETY: The out pointers are valid for writes.
+        let (old_class, old_priority) = unsafe {
+            let (mut class, mut priority) = (0, 0);
+            match pthread_get_qos_class_np(libc::pthread_self(), &mut class, &mut priority) {
+                0 => (class, priority),
+                err => return Err(io::Error::from_raw_os_error(err)),
+            }
+        };
+
+        // SAFETY: This only changes the scheduling of the calling thread.
+        match unsafe { pthread_set_qos_class_self_np(class, 0) } {
+            0 => {}
+            err => return Err(io::Error::from_raw_os_error(err)),
+        }
+
+        let res = f();
+
+        // A thread whose class was never set reports `QOS_CLASS_UNSPECIFIED`, which can't be set
+        // again, so it goes back to the default class instead.
+        let old_class = if old_class == 0 {
+            QOS_CLASS_DEFAULT
+        } else {
+            old_class
+        };
+        // SAFETY: This only changes the scheduling of the calling thread.
+        unsafe {
+            pthread_set_qos_class_self_np(old_class, old_priority);
+        }
+        res
+    }
+}
+
+/// A dispatch source that drains the events of a [`Poller`] on a dispatch queue.
+///
+/// Applications on Apple platforms that service a run loop, such as the main thread of a GUI
+/// application, can't block in [`Poller::wait()`]. Instead, this watches the kqueue of the
+/// poller with a `DISPATCH_SOURCE_TYPE_READ` source. Whenever the kqueue has events, the source
+/// waits for them without blocking on its queue, and passes them to the handler. The main queue
+/// is serviced by the `CFRunLoop` of the main thread, so a source on the main queue integrates
+/// with `NSApplication` and `UIApplication` as well as with `dispatch_main()`.
+///
+/// Only the events that wake up the kqueue run the handler: registered sources and
+/// [notifications][`Poller::notify()`]. [Timers][`Poller::set_timer()`], deadlines and events
+/// held back by the rate limit of the poller are delivered with a later run, so they should be
+/// scheduled with a dispatch timer instead. The handler of a source never runs concurrently with
+/// itself, even on a concurrent queue.
+///
+/// The source is cancelled when this is dropped. The handler may still be running on its queue
+/// at that point, and is dropped together with the poller once it returns.
+///
+/// # Examples
+///
+/// ```no_run
+/// use polling::os::kqueue::DispatchSource;
+/// use polling::{Event, Poller};
+/// use std::net::UdpSocket;
+/// use std::sync::Arc;
+///
+/// let poller = Arc::new(Poller::new()?);
+/// let socket = UdpSocket::bind("127.0.0.1:0")?;
+/// unsafe {
+///     poller.add(&socket, Event::readable(7))?;
+/// }
+///
+/// let source = DispatchSource::main_queue(poller.clone(), |events| {
+///     for ev in events.unwrap().iter() {
+///         println!("event on the main thread: {:?}", ev);
+///     }
+/// })?;
+/// // Run the main loop of the application, for example `NSApplication::run()`.
+/// # drop(source);
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(target_vendor = "apple")]
+pub struct DispatchSource {
+    source: NonNull<c_void>,
+}
+
+#[cfg(target_vendor = "apple")]
+impl DispatchSource {
+    /// Drain the events of `poller` on the main queue, and pass them to `handler`.
+    ///
+    /// The handler is passed the error instead if waiting for the events fails.
+    pub fn main_queue(
+        poller: std::sync::Arc<Poller>,
+        handler: impl FnMut(io::Result<&Events>) + Send + 'static,
+    ) -> io::Result<DispatchSource> {
+        // SAFETY: The main queue exists for the life of the process.
+        let queue = unsafe { dispatch::main_queue() };
+        Self::new(poller, queue, Box::new(handler))
+    }
+
+    /// Drain the events of `poller` on the global concurrent queue of the QoS class `qos`, and
+    /// pass them to `handler`.
+    ///
+    /// The handler is passed the error instead if waiting for the events fails.
+    pub fn global_queue(
+        poller: std::sync::Arc<Poller>,
+        qos: QosClass,
+        handler: impl FnMut(io::Result<&Events>) + Send + 'static,
+    ) -> io::Result<DispatchSource> {
+        // SAFETY: Global queues exist for the life of the process.
+        let queue = unsafe { dispatch::dispatch_get_global_queue(qos::class(qos) as isize, 0) };
+        Self::new(poller, queue, Box::new(handler))
+    }
+
+    /// Create the source on `queue` and start it.
+    fn new(
+        poller: std::sync::Arc<Poller>,
+        queue: *mut c_void,
+        handler: dispatch::Handler,
+    ) -> io::Result<DispatchSource> {
+        if queue.is_null() {
+            return Err(io::Error::from(io::ErrorKind::InvalidInput));
+        }
+        poller.poller.native()?;
+
+        // SAFETY: The kqueue stays open while the context holds the poller, which lives until
+        // the cancel handler runs.
+        unsafe {
+            let source = NonNull::new(dispatch::dispatch_source_create(
+                dispatch::source_type_read(),
+                poller.as_raw_fd() as usize,
+                0,
+                queue,
+            ))
+            .ok_or_else(|| io::Error::from(io::ErrorKind::OutOfMemory))?;
+
+            let context = Box::into_raw(Box::new(dispatch::Context {
+                poller,
+                events: Events::new(),
+                handler,
+                source,
+            }));
+            dispatch::dispatch_set_context(source.as_ptr(), context.cast());
+            dispatch::dispatch_source_set_event_handler_f(source.as_ptr(), dispatch::on_event);
+            dispatch::dispatch_source_set_cancel_handler_f(source.as_ptr(), dispatch::on_cancel);
+            dispatch::dispatch_resume(source.as_ptr());
+
+            Ok(DispatchSource { source })
+        }
+    }
+}
+
+#[cfg(target_vendor = "apple")]
+impl Drop for DispatchSource {
+    fn drop(&mut self) {
+        // SAFETY: The source is released by its cancel handler, after the last event handler.
+        unsafe {
+            dispatch::dispatch_source_cancel(self.source.as_ptr());
+        }
+    }
+}
+
+#[cfg(target_vendor = "apple")]
+impl std::fmt::Debug for DispatchSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DispatchSource")
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+// SAFETY: Dispatch objects can be used from any thread, and the handler is `Send`.
+#[cfg(target_vendor = "apple")]
+unsafe impl Send for DispatchSource {}
+
+// SAFETY: Cancelling a dispatch source from several threads at once is allowed.
+#[cfg(target_vendor = "apple")]
+unsafe impl Sync for DispatchSource {}
+
+#[cfg(target_vendor = "apple")]
+mod dispatch {
+    use crate::{Events, Poller};
+    use std::io;
+    use std::os::raw::c_void;
+    use std::ptr::{addr_of, NonNull};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    extern "C" {
+        static _dispatch_main_q: c_void;
+        static _dispatch_source_type_read: c_void;
+
+        pub(super) fn dispatch_get_global_queue(identifier: isize, flags: usize) -> *mut c_void;
+        pub(super) fn dispatch_source_create(
+            kind: *const c_void,
+            handle: usize,
+            mask: usize,
+            queue: *mut c_void,
+        ) -> *mut c_void;
+        pub(super) fn dispatch_set_context(object: *mut c_void, context: *mut c_void);
+        pub(super) fn dispatch_source_set_event_handler_f(
+            source: *mut c_void,
+            handler: unsafe extern "C" fn(*mut c_void),
+        );
+        pub(super) fn dispatch_source_set_cancel_handler_f(
+            source: *mut c_void,
+            handler: unsafe extern "C" fn(*mut c_void),
+        );
+        pub(super) fn dispatch_resume(object: *mut c_void);
+        pub(super) fn dispatch_source_cancel(source: *mut c_void);
+        fn dispatch_release(object: *mut c_void);
+    }
+
+    /// `dispatch_get_main_queue()`, which is a macro in C.
+    pub(super) unsafe fn main_queue() -> *mut c_void {
+        addr_of!(_dispatch_main_q) as *mut c_void
+    }
+
+    /// `DISPATCH_SOURCE_TYPE_READ`, which is a macro in C.
+    pub(super) unsafe fn source_type_read() -> *const c_void {
+        addr_of!(_dispatch_source_type_read)
+    }
+
+    /// The handler that the events of a source are passed to.
+    pub(super) type Handler = Box<dyn FnMut(io::Result<&Events>) + Send>;
+
+    /// The state of a source that its handlers are called with.
+    pub(super) struct Context {
+        pub(super) poller: Arc<Poller>,
+        pub(super) events: Events,
+        pub(super) handler: Handler,
+        pub(super) source: NonNull<c_void>,
+    }
+
+    /// Wait for the events of the poller without blocking, and pass them to the handler.
+    pub(super) unsafe extern "C" fn on_event(context: *mut c_void) {
+        // SAFETY: The event handler never runs concurrently with itself or the cancel handler.
+        let context = &mut *context.cast::<Context>();
+        context.events.clear();
+        match context
+            .poller
+            .wait(&mut context.events, Some(Duration::ZERO))
+        {
+            Ok(_) => (context.handler)(Ok(&context.events)),
+            Err(err) => (context.handler)(Err(err)),
+        }
+    }
+
+    /// Drop the state of a source once it is cancelled, and release the source.
+    pub(super) unsafe extern "C" fn on_cancel(context: *mut c_void) {
+        // SAFETY: No handler of the source runs after the cancel handler.
+        let context = Box::from_raw(context.cast::<Context>());
+        dispatch_release(context.source.as_ptr());
+    }
+}
+
+/// Functionality for POSIX AIO that is only available on FreeBSD.
+///
+/// FreeBSD can deliver the completion of asynchronous I/O requests through a `kqueue` with the
+/// `EVFILT_AIO` filter, so that no separate thread is needed to wait for them.
+#[cfg(target_os = "freebsd")]
+pub trait PollerAioExt: PollerSealed {
+    /// Deliver the completion of an AIO request as a readable event with the given key.
+    ///
+    /// This sets the `aio_sigevent` field of `aiocb`, so it must be called before the request
+    /// is submitted with `aio_read()`, `aio_write()` or similar. The event is delivered once,
+    /// after which the result of the request can be collected with `aio_return()`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use polling::os::kqueue::PollerAioExt;
+    /// use polling::{Events, Poller};
+    /// use std::fs::File;
+    /// use std::os::unix::io::AsRawFd;
+    ///
+    /// let poller = Poller::new()?;
+    /// let file = File::open("/etc/hosts")?;
+    /// let mut buf = [0u8; 64];
+    ///
+    /// let mut aiocb: libc::aiocb = unsafe { std::mem::zeroed() };
+    /// aiocb.aio_fildes = file.as_raw_fd();
+    /// aiocb.aio_buf = buf.as_mut_ptr().cast();
+    /// aiocb.aio_nbytes = buf.len();
+    /// poller.set_aio_event(&mut aiocb, 7)?;
+    ///
+    /// unsafe { libc::aio_read(&mut aiocb) };
+    ///
+    /// let mut events = Events::new();
+    /// poller.wait(&mut events, None)?;
+    /// let read = unsafe { libc::aio_return(&mut aiocb) };
+    /// # let _ = read;
+    /// # std::io::Result::Ok(())
+    /// ```
+    fn set_aio_event(&self, aiocb: &mut libc::aiocb, key: usize) -> io::Result<()>;
+}
+
+#[cfg(target_os = "freebsd")]
+impl PollerAioExt for Poller {
+    fn set_aio_event(&self, aiocb: &mut libc::aiocb, key: usize) -> io::Result<()> {
+        self.check_key(key)?;
+
+        // `sigev_signo` doubles as `sigev_notify_kqueue`.
+        let sigevent = &mut aiocb.aio_sigevent;
+        sigevent.sigev_notify = libc::SIGEV_KEVENT;
+        sigevent.sigev_signo = self.poller.native()?.as_raw_fd();
+        sigevent.sigev_value = libc::sigval {
+            sival_ptr: key as *mut _,
+        };
+        Ok(())
     }
 }
 
@@ -287,6 +793,290 @@ unsafe impl FilterSealed for Timer {
 
 impl Filter for Timer {}
 
+/// Wait until a point in wall-clock time.
+///
+/// Unlike [`Timer`], the deadline doesn't move when the timer is registered late, so work that
+/// is scheduled at a time of day doesn't drift. It follows changes to the system clock. Timers
+/// share their identifiers with [`Timer`].
+///
+/// This uses `NOTE_ABSTIME`, which is available on FreeBSD, NetBSD and Apple platforms. A
+/// deadline in the past expires immediately.
+///
+/// # Examples
+///
+/// ```no_run
+/// use polling::os::kqueue::{AbsoluteTimer, PollerKqueueExt};
+/// use polling::{Events, PollMode, Poller};
+/// use std::time::{Duration, SystemTime};
+///
+/// let poller = Poller::new()?;
+/// let timer = AbsoluteTimer {
+///     id: 1,
+///     deadline: SystemTime::now() + Duration::from_secs(60),
+/// };
+/// poller.add_filter(timer, 7, PollMode::Oneshot)?;
+///
+/// let mut events = Events::new();
+/// poller.wait(&mut events, None)?;
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(any(target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AbsoluteTimer {
+    /// Identifier for the timer.
+    pub id: usize,
+
+    /// The time at which the timer expires.
+    pub deadline: SystemTime,
+}
+
+#[cfg(any(target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"))]
+unsafe impl FilterSealed for AbsoluteTimer {
+    fn filter(&self, flags: kqueue::EventFlags, key: usize) -> kqueue::Event {
+        #[cfg(target_vendor = "apple")]
+        use libc::NOTE_ABSOLUTE as NOTE_ABSTIME;
+        #[cfg(not(target_vendor = "apple"))]
+        use libc::NOTE_ABSTIME;
+
+        let nanos = self
+            .deadline
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+
+        // SAFETY: An all-zero `kevent` is valid.
+        let mut raw: libc::kevent = unsafe { std::mem::zeroed() };
+        raw.ident = self.id as _;
+        raw.filter = libc::EVFILT_TIMER as _;
+        raw.flags = (flags | kqueue::EventFlags::RECEIPT).bits() as _;
+        raw.fflags = libc::NOTE_NSECONDS | NOTE_ABSTIME;
+        raw.data = i64::try_from(nanos).unwrap_or(i64::MAX) as _;
+        raw.udata = key as _;
+        crate::sys::from_raw(raw)
+    }
+
+    #[inline(always)]
+    fn source_id(&self) -> SourceId {
+        SourceId::Timer(self.id)
+    }
+}
+
+#[cfg(any(target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"))]
+impl Filter for AbsoluteTimer {}
+
+/// Wait until all data written to a socket has been acknowledged or sent.
+///
+/// This uses the `EVFILT_EMPTY` filter, which is only available on FreeBSD. The event is
+/// delivered as writable once the send buffer of the socket is empty, for instance to know when
+/// it is safe to close a socket after `sendfile()`. It is registered separately from the
+/// interest in the socket itself.
+#[cfg(target_os = "freebsd")]
+#[derive(Debug, Copy, Clone)]
+pub struct Empty<'a>(pub BorrowedFd<'a>);
+
+#[cfg(target_os = "freebsd")]
+unsafe impl FilterSealed for Empty<'_> {
+    fn filter(&self, flags: kqueue::EventFlags, key: usize) -> kqueue::Event {
+        // SAFETY: An all-zero `kevent` is valid.
+        let mut raw: libc::kevent = unsafe { std::mem::zeroed() };
+        raw.ident = self.0.as_raw_fd() as _;
+        raw.filter = libc::EVFILT_EMPTY;
+        raw.flags = (flags | kqueue::EventFlags::RECEIPT).bits();
+        raw.udata = key as _;
+        crate::sys::from_raw(raw)
+    }
+
+    #[inline(always)]
+    fn source_id(&self) -> SourceId {
+        SourceId::Empty(self.0.as_raw_fd())
+    }
+}
+
+#[cfg(target_os = "freebsd")]
+impl Filter for Empty<'_> {}
+
+/// Wait for a device to change, such as a display being attached or detached.
+///
+/// This uses the `EVFILT_DEVICE` filter with `NOTE_CHANGE`, which is only available on
+/// OpenBSD, where `drm(4)` devices support it. The event is delivered as readable whenever the
+/// device reports a change, so a daemon can rescan its outputs from the same wait loop as its
+/// other sources. Which change occurred is read from the device itself.
+///
+/// # Examples
+///
+/// ```no_run
+/// use polling::os::kqueue::{Device, PollerKqueueExt};
+/// use polling::{Events, PollMode, Poller};
+/// use std::fs::File;
+/// use std::os::unix::io::AsFd;
+///
+/// let poller = Poller::new()?;
+/// let drm = File::open("/dev/drm0")?;
+/// poller.add_filter(Device(drm.as_fd()), 7, PollMode::Level)?;
+///
+/// let mut events = Events::new();
+/// poller.wait(&mut events, None)?;
+/// poller.delete_filter(Device(drm.as_fd()))?;
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(target_os = "openbsd")]
+#[derive(Debug, Copy, Clone)]
+pub struct Device<'a>(pub BorrowedFd<'a>);
+
+#[cfg(target_os = "openbsd")]
+unsafe impl FilterSealed for Device<'_> {
+    fn filter(&self, flags: kqueue::EventFlags, key: usize) -> kqueue::Event {
+        // SAFETY: An all-zero `kevent` is valid.
+        let mut raw: libc::kevent = unsafe { std::mem::zeroed() };
+        raw.ident = self.0.as_raw_fd() as _;
+        raw.filter = libc::EVFILT_DEVICE;
+        raw.flags = (flags | kqueue::EventFlags::RECEIPT).bits() as _;
+        raw.fflags = libc::NOTE_CHANGE;
+        raw.udata = key as _;
+        crate::sys::from_raw(raw)
+    }
+
+    #[inline(always)]
+    fn source_id(&self) -> SourceId {
+        SourceId::Device(self.0.as_raw_fd())
+    }
+}
+
+#[cfg(target_os = "openbsd")]
+impl Filter for Device<'_> {}
+
+/// A raw `kqueue` filter that the portable API doesn't model.
+///
+/// This is submitted as a `kevent` with these fields, the flags of the [`PollMode`] and the key
+/// as `udata`. Taken together, `filter` and `ident` identify the registration, so a raw filter
+/// must not duplicate a registration that is made through another API.
+///
+/// Events delivered by `kqueue` carry their raw fields, which can be read with
+/// [`EventKqueueExt::raw_filter()`].
+///
+/// This also covers filters that are specific to one platform and not exported by `libc`, such
+/// as `EVFILT_SOCK` on Apple platforms, which reports socket state changes like
+/// `NOTE_CONNECTED` and `NOTE_CONNRESET`. FreeBSD has no such filter. There, a connection reset
+/// is reported as `EV_EOF` on the read and write filters, with the error in `fflags`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use polling::os::kqueue::{EventKqueueExt, PollerKqueueExt, RawFilter};
+/// use polling::{Events, PollMode, Poller};
+/// use std::fs::File;
+/// use std::os::unix::io::AsRawFd;
+///
+/// let poller = Poller::new()?;
+///
+/// // Watch a file for writes, deletion and renames.
+/// let file = File::open("/tmp/watched")?;
+/// let vnode = RawFilter {
+///     ident: file.as_raw_fd() as usize,
+///     filter: libc::EVFILT_VNODE as _,
+///     fflags: libc::NOTE_WRITE | libc::NOTE_DELETE | libc::NOTE_RENAME,
+///     data: 0,
+/// };
+/// poller.add_filter(vnode, 7, PollMode::Level)?;
+///
+/// let mut events = Events::new();
+/// poller.wait(&mut events, None)?;
+/// for event in events.iter() {
+///     let raw = event.raw_filter().unwrap();
+///     if raw.fflags & libc::NOTE_DELETE != 0 {
+///         println!("the file was deleted");
+///     }
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RawFilter {
+    /// Identifier of the source, such as a file descriptor or a process ID.
+    pub ident: usize,
+
+    /// The `EVFILT_*` filter.
+    pub filter: i32,
+
+    /// Filter-specific flags, such as `NOTE_*` flags.
+    pub fflags: u32,
+
+    /// Filter-specific data.
+    pub data: i64,
+}
+
+impl RawFilter {
+    /// Read the fields of a raw `kevent`.
+    pub(crate) fn from_raw(raw: &libc::kevent) -> RawFilter {
+        RawFilter {
+            ident: raw.ident as _,
+            filter: raw.filter as _,
+            fflags: raw.fflags,
+            data: raw.data as _,
+        }
+    }
+}
+
+unsafe impl FilterSealed for RawFilter {
+    fn filter(&self, flags: kqueue::EventFlags, key: usize) -> kqueue::Event {
+        // SAFETY: An all-zero `kevent` is valid.
+        let mut raw: libc::kevent = unsafe { std::mem::zeroed() };
+        raw.ident = self.ident as _;
+        raw.filter = self.filter as _;
+        raw.flags = (flags | kqueue::EventFlags::RECEIPT).bits() as _;
+        raw.fflags = self.fflags;
+        raw.data = self.data as _;
+        raw.udata = key as _;
+        crate::sys::from_raw(raw)
+    }
+
+    #[inline(always)]
+    fn source_id(&self) -> SourceId {
+        SourceId::Raw(self.filter, self.ident)
+    }
+}
+
+impl Filter for RawFilter {}
+
+/// Functionality of events that is only available for `kqueue`-based platforms.
+pub trait EventKqueueExt: EventSealed {
+    /// The raw fields of the `kevent` that produced this event.
+    ///
+    /// Returns `None` for events that were not delivered by `kqueue`, such as events that were
+    /// created to register interest. If the read and write filters of a source were reported
+    /// together, this is the filter that came first.
+    fn raw_filter(&self) -> Option<RawFilter>;
+
+    /// The raw `EV_*` flags of the `kevent` that produced this event, such as `EV_EOF`.
+    ///
+    /// If the read and write filters of a source were reported together, these are the flags of
+    /// both. Returns `None` for events that were not delivered by `kqueue`.
+    fn raw_flags(&self) -> Option<u32>;
+
+    /// The pointer attached to the source of this event with
+    /// [`PollerUdataExt::modify_with_udata()`].
+    ///
+    /// Returns `None` if the source has no pointer attached, or if the event was not delivered
+    /// by `kqueue`.
+    fn udata(&self) -> Option<NonNull<c_void>>;
+}
+
+impl EventKqueueExt for Event {
+    #[inline]
+    fn raw_filter(&self) -> Option<RawFilter> {
+        self.extra.raw()
+    }
+
+    #[inline]
+    fn raw_flags(&self) -> Option<u32> {
+        self.extra.raw_flags()
+    }
+
+    #[inline]
+    fn udata(&self) -> Option<NonNull<c_void>> {
+        NonNull::new(self.extra.udata() as *mut c_void)
+    }
+}
+
 mod __private {
     use crate::sys::SourceId;
     use rustix::event::kqueue;
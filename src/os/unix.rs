@@ -0,0 +1,114 @@
+//! Functionality that is only available on Unix platforms.
+
+use crate::{Events, Poller};
+
+use std::io;
+use std::ptr;
+use std::time::{Duration, Instant};
+
+use super::__private::PollerSealed;
+
+/// Functionality that is only available on Unix platforms.
+pub trait PollerUnixExt: PollerSealed {
+    /// Waits for I/O events while the signal mask of the thread is replaced by `sigmask`.
+    ///
+    /// This is the equivalent of `epoll_pwait()`, `ppoll()` or `pselect()`: signals that are
+    /// blocked in the rest of the program can be unblocked only while sleeping in the poller,
+    /// so that a signal handler can't run between checking a flag it sets and going to sleep.
+    ///
+    /// Unlike [`Poller::wait()`], this doesn't retry when the wait is interrupted by a signal,
+    /// and returns an error of kind [`Interrupted`][`io::ErrorKind::Interrupted`] instead, so that
+    /// the caller can handle the signal.
+    ///
+    /// On Linux and Android, the signal mask is replaced atomically by the kernel. On other
+    /// platforms, this is emulated by replacing the signal mask with `pthread_sigmask()` around
+    /// the wait, so a pending signal is handled right before the wait starts rather than
+    /// interrupting it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::os::unix::PollerUnixExt;
+    /// use polling::{Events, Poller};
+    /// use std::time::Duration;
+    ///
+    /// let poller = Poller::new()?;
+    /// let mut events = Events::new();
+    ///
+    /// // Wait with all signals unblocked.
+    /// let sigmask = unsafe {
+    ///     let mut sigmask = std::mem::zeroed();
+    ///     libc::sigemptyset(&mut sigmask);
+    ///     sigmask
+    /// };
+    /// poller.wait_with_sigmask(&mut events, Some(Duration::from_millis(10)), &sigmask)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    fn wait_with_sigmask(
+        &self,
+        events: &mut Events,
+        timeout: Option<Duration>,
+        sigmask: &libc::sigset_t,
+    ) -> io::Result<usize>;
+
+    /// Makes the poller usable in the child process after `fork()`.
+    ///
+    /// A child process can't keep using the poller of its parent: a kqueue or event port is not
+    /// inherited at all, and an epoll instance is shared with the parent, so both processes
+    /// would steal each other's events. This creates a new poller with the same options and
+    /// registers every source of [`Poller::registrations()`] in it again, with the interest that
+    /// was last set for it, even if it was disabled by a oneshot event. The parent is not
+    /// affected.
+    ///
+    /// Call this in the child before using the poller, while no other thread uses it. Sources
+    /// that are not tracked by the poller, such as `kqueue` filters, are not registered again.
+    ///
+    /// # Errors
+    ///
+    /// If a source can't be registered again, it is removed from the poller, the other sources
+    /// are still registered, and the first error is returned.
+    fn reinit_after_fork(&mut self) -> io::Result<()>;
+}
+
+impl PollerUnixExt for Poller {
+    fn wait_with_sigmask(
+        &self,
+        events: &mut Events,
+        timeout: Option<Duration>,
+        sigmask: &libc::sigset_t,
+    ) -> io::Result<usize> {
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        self.poller.native()?;
+        self.wait_sys(events, deadline, false, |poller, events, deadline| {
+            poller.wait_sigmask(events, deadline, sigmask)
+        })
+    }
+
+    fn reinit_after_fork(&mut self) -> io::Result<()> {
+        self.reinit()
+    }
+}
+
+/// Run `f` with the signal mask of the thread replaced by `sigmask`.
+#[allow(dead_code)]
+pub(crate) fn with_sigmask<T>(
+    sigmask: &libc::sigset_t,
+    f: impl FnOnce() -> io::Result<T>,
+) -> io::Result<T> {
+    // SAFETY: The old mask is fully initialized by `pthread_sigmask()` on success.
+    let old = unsafe {
+        let mut old = std::mem::zeroed::<libc::sigset_t>();
+        match libc::pthread_sigmask(libc::SIG_SETMASK, sigmask, &mut old) {
+            0 => old,
+            err => return Err(io::Error::from_raw_os_error(err)),
+        }
+    };
+
+    let res = f();
+
+    // SAFETY: `old` is a valid signal set.
+    unsafe {
+        libc::pthread_sigmask(libc::SIG_SETMASK, &old, ptr::null_mut());
+    }
+    res
+}
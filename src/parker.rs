@@ -0,0 +1,166 @@
+//! Thread parking on top of a `Poller`.
+
+use std::cell::Cell;
+use std::fmt;
+use std::io;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::{Events, Poller};
+
+/// A thread parker that blocks on a [`Poller`].
+///
+/// This works like [`std::thread::park()`], but the parked thread sleeps in the poller, so the
+/// same thread can also wait for I/O events with [`park_events()`][`Parker::park_events()`].
+/// Parking is woken up by an [`Unparker`], which sends a [notification][`Poller::notify()`] to
+/// the poller.
+///
+/// Like with [`std::thread::park()`], a call to [`Unparker::unpark()`] before the thread parks
+/// makes the next park return immediately.
+///
+/// # Examples
+///
+/// ```
+/// use polling::Parker;
+///
+/// let parker = Parker::new()?;
+/// let unparker = parker.unparker();
+///
+/// std::thread::spawn(move || unparker.unpark().unwrap());
+/// parker.park()?;
+/// # std::io::Result::Ok(())
+/// ```
+pub struct Parker {
+    inner: Arc<Inner>,
+
+    /// Only one thread may park at a time.
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+/// A handle that wakes up a [`Parker`].
+///
+/// This is created by [`Parker::unparker()`].
+#[derive(Clone)]
+pub struct Unparker {
+    inner: Arc<Inner>,
+}
+
+/// The state shared between a parker and its unparkers.
+struct Inner {
+    poller: Poller,
+    unparked: AtomicBool,
+}
+
+impl Parker {
+    /// Create a parker with a new poller.
+    pub fn new() -> io::Result<Parker> {
+        Ok(Parker::with_poller(Poller::new()?))
+    }
+
+    /// Create a parker on top of an existing poller.
+    pub fn with_poller(poller: Poller) -> Parker {
+        Parker {
+            inner: Arc::new(Inner {
+                poller,
+                unparked: AtomicBool::new(false),
+            }),
+            _not_sync: PhantomData,
+        }
+    }
+
+    /// The poller the parker blocks on.
+    ///
+    /// Sources can be registered in it, and their events are delivered by
+    /// [`park_events()`][`Parker::park_events()`].
+    #[inline]
+    pub fn poller(&self) -> &Poller {
+        &self.inner.poller
+    }
+
+    /// Create an [`Unparker`] that wakes up this parker.
+    #[inline]
+    pub fn unparker(&self) -> Unparker {
+        Unparker {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Block until the parker is unparked.
+    ///
+    /// I/O events delivered by the poller in the meantime are discarded.
+    pub fn park(&self) -> io::Result<()> {
+        while !self.take_token() {
+            self.inner.poller.wait_with(None, |_| {})?;
+        }
+        Ok(())
+    }
+
+    /// Block until the parker is unparked or the timeout elapses.
+    ///
+    /// Returns `true` if the parker was unparked. I/O events delivered by the poller in the
+    /// meantime are discarded.
+    pub fn park_timeout(&self, timeout: Duration) -> io::Result<bool> {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            if self.take_token() {
+                return Ok(true);
+            }
+
+            let timeout = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(timeout) => Some(timeout),
+                    None => return Ok(false),
+                },
+                None => None,
+            };
+            self.inner.poller.wait_with(timeout, |_| {})?;
+        }
+    }
+
+    /// Block until the parker is unparked, I/O events are delivered or the timeout elapses.
+    ///
+    /// The I/O events are added to `events`, like with [`Poller::wait()`]. Returns `true` if the
+    /// parker was unparked.
+    pub fn park_events(&self, events: &mut Events, timeout: Option<Duration>) -> io::Result<bool> {
+        if self.take_token() {
+            return Ok(true);
+        }
+
+        self.inner.poller.wait(events, timeout)?;
+        Ok(self.take_token())
+    }
+
+    /// Consume the token left by an unpark, if any.
+    fn take_token(&self) -> bool {
+        self.inner.unparked.swap(false, Ordering::SeqCst)
+    }
+}
+
+impl Unparker {
+    /// Wake up the parker, or make its next park return immediately.
+    ///
+    /// If the parker was already unparked and has not parked since, this does nothing.
+    pub fn unpark(&self) -> io::Result<()> {
+        if !self.inner.unparked.swap(true, Ordering::SeqCst) {
+            self.inner.poller.notify()?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Parker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Parker")
+            .field("poller", &self.inner.poller)
+            .field("unparked", &self.inner.unparked.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl fmt::Debug for Unparker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Unparker { .. }")
+    }
+}
@@ -16,7 +16,17 @@ use syscall::{poll, PollFd, PollFlags};
 // std::os::unix doesn't exist on Fuchsia
 type RawFd = std::os::raw::c_int;
 
-use crate::{Event, PollMode};
+use crate::{Backend, Event, PollMode, PollerBuilder};
+
+// Next to epoll, events carry its extra information.
+#[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    not(polling_test_poll_backend),
+))]
+use crate::sys::EventExtra;
+
+/// The backend implemented by this module.
+pub const BACKEND: Backend = Backend::Poll;
 
 /// Interface to poll.
 #[derive(Debug)]
@@ -52,6 +62,9 @@ struct Fds {
     /// The map of each file descriptor to data associated with it. This does not include the file
     /// descriptors `notify_read` or `notify_write`.
     fd_data: HashMap<RawFd, FdData>,
+    /// The offset into `poll_fds`, after the notification pipe, at which the next wait starts
+    /// reporting events.
+    start: usize,
 }
 
 /// Data associated with a file descriptor in a poller.
@@ -67,7 +80,10 @@ struct FdData {
 
 impl Poller {
     /// Creates a new poller.
-    pub fn new() -> io::Result<Poller> {
+    ///
+    /// The notification key is unused, since notifications never produce events here. Concurrent
+    /// waits are serialized by the `fds` mutex.
+    pub fn new(_builder: &PollerBuilder) -> io::Result<Poller> {
         let notify = notify::Notify::new()?;
 
         #[cfg(feature = "tracing")]
@@ -81,6 +97,7 @@ impl Poller {
                     notify.poll_flags(),
                 )],
                 fd_data: HashMap::new(),
+                start: 0,
             }),
             notify,
             waiting_operations: AtomicUsize::new(0),
@@ -89,6 +106,11 @@ impl Poller {
         })
     }
 
+    /// The backend of this poller.
+    pub fn backend(&self) -> Backend {
+        BACKEND
+    }
+
     /// Whether this poller supports level-triggered events.
     pub fn supports_level(&self) -> bool {
         true
@@ -191,11 +213,26 @@ impl Poller {
         #[cfg(feature = "tracing")]
         let _enter = span.enter();
 
+        self.remove_fd(fd.as_raw_fd())
+    }
+
+    /// Forgets about a file descriptor that was closed while it was registered.
+    pub fn forget(&self, fd: RawFd) {
+        let _ = self.remove_fd(fd);
+    }
+
+    /// Closes the poller in the child process after `fork()`.
+    ///
+    /// Nothing is shared with the parent except for the notification pipe, which stays open
+    /// there.
+    pub fn close_after_fork(self) {
+        drop(self);
+    }
+
+    /// Removes a file descriptor from the list of polled file descriptors.
+    fn remove_fd(&self, fd: RawFd) -> io::Result<()> {
         self.modify_fds(|fds| {
-            let data = fds
-                .fd_data
-                .remove(&fd.as_raw_fd())
-                .ok_or(io::ErrorKind::NotFound)?;
+            let data = fds.fd_data.remove(&fd).ok_or(io::ErrorKind::NotFound)?;
             fds.poll_fds.swap_remove(data.poll_fds_index);
             if let Some(swapped_pollfd) = fds.poll_fds.get(data.poll_fds_index) {
                 fds.fd_data
@@ -208,6 +245,19 @@ impl Poller {
         })
     }
 
+    /// Waits for I/O events with an optional deadline and the given signal mask.
+    ///
+    /// There is no native support for this, so the signal mask is replaced around the wait.
+    #[cfg(unix)]
+    pub fn wait_sigmask(
+        &self,
+        events: &mut Events,
+        deadline: Option<Instant>,
+        sigmask: &libc::sigset_t,
+    ) -> io::Result<()> {
+        crate::os::unix::with_sigmask(sigmask, || self.wait_deadline(events, deadline))
+    }
+
     /// Waits for I/O events with an optional deadline.
     pub fn wait_deadline(&self, events: &mut Events, deadline: Option<Instant>) -> io::Result<()> {
         #[cfg(feature = "tracing")]
@@ -257,33 +307,49 @@ impl Poller {
                 continue;
             }
 
-            // Store the events if there were any.
+            // Store the events if there were any. Leave the rest of the events for the next
+            // wait, like the other backends.
+            let num_fd_events = num_fd_events.min(events.cap.saturating_sub(events.inner.len()));
             if num_fd_events > 0 {
                 let fds = &mut *fds;
-
                 events.inner.reserve(num_fd_events);
-                for fd_data in fds.fd_data.values_mut() {
-                    let poll_fd = &mut fds.poll_fds[fd_data.poll_fds_index];
-                    if !poll_fd.revents().is_empty() {
-                        // Store event
-                        let revents = poll_fd.revents();
-                        events.inner.push(Event {
-                            key: fd_data.key,
-                            readable: revents.intersects(read_events()),
-                            writable: revents.intersects(write_events()),
-                            extra: EventExtra { flags: revents },
-                        });
-                        // Remove interest if necessary
-                        if fd_data.remove {
-                            *poll_fd = PollFd::from_borrowed_fd(
-                                unsafe { BorrowedFd::borrow_raw(poll_fd.as_fd().as_raw_fd()) },
-                                PollFlags::empty(),
-                            );
-                        }
-
-                        if events.inner.len() == num_fd_events {
-                            break;
-                        }
+
+                // Start after the last reported file descriptor, so that file descriptors which
+                // don't fit into `events` are reported by the next wait rather than starved.
+                let len = fds.poll_fds.len() - 1;
+                let mut reported = 0;
+                for i in 0..len {
+                    let index = 1 + (fds.start + i) % len;
+                    let poll_fd = &mut fds.poll_fds[index];
+                    let revents = poll_fd.revents();
+                    if revents.is_empty() {
+                        continue;
+                    }
+                    let fd_data = &fds.fd_data[&poll_fd.as_fd().as_raw_fd()];
+
+                    // Store event
+                    events.inner.push(Event {
+                        key: fd_data.key,
+                        readable: revents.intersects(read_events()),
+                        writable: revents.intersects(write_events()),
+                        read_low_watermark: None,
+                        write_low_watermark: None,
+                        timeout: false,
+                        delete_on_hangup: false,
+                        extra: extra(revents),
+                    });
+                    // Remove interest if necessary
+                    if fd_data.remove {
+                        *poll_fd = PollFd::from_borrowed_fd(
+                            unsafe { BorrowedFd::borrow_raw(poll_fd.as_fd().as_raw_fd()) },
+                            PollFlags::empty(),
+                        );
+                    }
+
+                    reported += 1;
+                    if reported == num_fd_events {
+                        fds.start = (fds.start + i + 1) % len;
+                        break;
                     }
                 }
             }
@@ -305,13 +371,29 @@ impl Poller {
         let _enter = span.enter();
 
         if !self.notified.swap(true, Ordering::SeqCst) {
-            self.notify.notify()?;
+            if let Err(err) = self.notify.notify() {
+                // A full pipe already wakes up the poller.
+                if err.kind() != io::ErrorKind::WouldBlock {
+                    self.notified.store(false, Ordering::SeqCst);
+                    return Err(err);
+                }
+            }
             self.operations_complete.notify_one();
         }
 
         Ok(())
     }
 
+    /// Sends a notification from a signal handler.
+    ///
+    /// This only writes to the notification pipe. A wait that is blocked on pending
+    /// modifications sees the notification once they complete.
+    pub fn notify_from_signal(&self) {
+        if !self.notified.swap(true, Ordering::SeqCst) {
+            self.notify.notify().ok();
+        }
+    }
+
     /// Perform a modification on `fds`, interrupting the current caller of `wait` if it's running.
     fn modify_fds(&self, f: impl FnOnce(&mut Fds) -> io::Result<()>) -> io::Result<()> {
         self.waiting_operations.fetch_add(1, Ordering::SeqCst);
@@ -346,6 +428,14 @@ fn poll_events(ev: Event) -> PollFlags {
         PollFlags::OUT | PollFlags::WRBAND
     } else {
         PollFlags::empty()
+    }) | (if ev.extra.is_hup() {
+        PollFlags::HUP
+    } else {
+        PollFlags::empty()
+    }) | (if ev.extra.is_pri() {
+        PollFlags::PRI
+    } else {
+        PollFlags::empty()
     })
 }
 
@@ -362,6 +452,9 @@ fn write_events() -> PollFlags {
 /// A list of reported I/O events.
 pub struct Events {
     inner: Vec<Event>,
+
+    /// The maximum number of events per wait.
+    cap: usize,
 }
 
 impl Events {
@@ -369,6 +462,7 @@ impl Events {
     pub fn with_capacity(cap: usize) -> Events {
         Self {
             inner: Vec::with_capacity(cap),
+            cap,
         }
     }
 
@@ -384,17 +478,50 @@ impl Events {
 
     /// Get the capacity of the list.
     pub fn capacity(&self) -> usize {
-        self.inner.capacity()
+        self.cap
+    }
+
+    /// Whether the last wait filled the list, so that more events may be pending.
+    pub fn is_full(&self) -> bool {
+        self.inner.len() >= self.cap
     }
 }
 
+/// Extra information about an event with the given flags.
+#[cfg(not(all(
+    any(target_os = "linux", target_os = "android"),
+    not(polling_test_poll_backend),
+)))]
+fn extra(flags: PollFlags) -> EventExtra {
+    EventExtra { flags }
+}
+
+/// Extra information about an event with the given flags.
+///
+/// The flags of epoll have the same values as those of poll on Linux.
+#[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    not(polling_test_poll_backend),
+))]
+fn extra(flags: PollFlags) -> EventExtra {
+    EventExtra::from_raw(flags.bits() as u32)
+}
+
 /// Extra information associated with an event.
+#[cfg(not(all(
+    any(target_os = "linux", target_os = "android"),
+    not(polling_test_poll_backend),
+)))]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct EventExtra {
     /// Flags associated with this event.
     flags: PollFlags,
 }
 
+#[cfg(not(all(
+    any(target_os = "linux", target_os = "android"),
+    not(polling_test_poll_backend),
+)))]
 impl EventExtra {
     /// Creates an empty set of extra information.
     #[inline]
@@ -404,6 +531,12 @@ impl EventExtra {
         }
     }
 
+    /// Set interest in errors.
+    #[inline]
+    pub fn set_err(&mut self, _value: bool) {
+        // Errors are always reported.
+    }
+
     /// Set the interrupt flag.
     #[inline]
     pub fn set_hup(&mut self, value: bool) {
@@ -416,6 +549,30 @@ impl EventExtra {
         self.flags.set(PollFlags::PRI, value);
     }
 
+    /// Set the exclusive flag.
+    #[inline]
+    pub fn set_exclusive(&mut self, _value: bool) {
+        // No-op.
+    }
+
+    /// Is the exclusive flag set?
+    #[inline]
+    pub fn is_exclusive(&self) -> bool {
+        false
+    }
+
+    /// Set the wakeup flag.
+    #[inline]
+    pub fn set_wakeup(&mut self, _value: bool) {
+        // No-op.
+    }
+
+    /// Is the wakeup flag set?
+    #[inline]
+    pub fn is_wakeup(&self) -> bool {
+        false
+    }
+
     /// Is this an interrupt event?
     #[inline]
     pub fn is_hup(&self) -> bool {
@@ -516,7 +673,7 @@ mod syscall {
 
     /// Safe wrapper around the `poll` system call.
     pub(super) fn poll(fds: &mut [PollFd<'_>], timeout: Option<Duration>) -> io::Result<usize> {
-        // Timeout in milliseconds for epoll. In case of overflow, use no timeout.
+        // Timeout in milliseconds for poll. In case of overflow, use no timeout.
         let mut timeout_ms = -1;
         if let Some(t) = timeout {
             if let Ok(ms) = i32::try_from(t.as_millis()) {
@@ -1,7 +1,10 @@
 //! Bindings to event port (illumos, Solaris).
 
+use std::collections::HashMap;
 use std::io;
+use std::mem;
 use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::sync::{Mutex, MutexGuard};
 use std::time::Instant;
 
 use rustix::buffer::spare_capacity;
@@ -9,21 +12,42 @@ use rustix::event::{port, PollFlags, Timespec};
 use rustix::fd::OwnedFd;
 use rustix::io::{fcntl_getfd, fcntl_setfd, FdFlags};
 
-use crate::{Event, PollMode};
+use crate::{Backend, Event, PollMode, PollerBuilder};
+
+/// The backend implemented by this module.
+pub const BACKEND: Backend = Backend::EventPorts;
 
 /// Interface to event ports.
 #[derive(Debug)]
 pub struct Poller {
     /// File descriptor for the port instance.
     port_fd: OwnedFd,
+
+    /// The key used to send notifications to the port.
+    notify_key: usize,
+
+    /// The interest of file descriptors registered in level-triggered mode.
+    ///
+    /// Event ports only support oneshot associations, so these are associated again after each
+    /// of their events is retrieved.
+    levels: Mutex<HashMap<RawFd, (PollFlags, usize)>>,
 }
 
 impl Poller {
     /// Creates a new poller.
-    pub fn new() -> io::Result<Poller> {
+    pub fn new(builder: &PollerBuilder) -> io::Result<Poller> {
         let port_fd = port::create()?;
-        let flags = fcntl_getfd(&port_fd)?;
-        fcntl_setfd(&port_fd, flags | FdFlags::CLOEXEC)?;
+        if builder.cloexec {
+            let flags = fcntl_getfd(&port_fd)?;
+            fcntl_setfd(&port_fd, flags | FdFlags::CLOEXEC)?;
+        }
+
+        Self::from_fd(port_fd, builder)
+    }
+
+    /// Creates a poller from an existing event port.
+    pub fn from_fd(port_fd: OwnedFd, builder: &PollerBuilder) -> io::Result<Poller> {
+        let notify_key = builder.notify_key;
 
         #[cfg(feature = "tracing")]
         tracing::trace!(
@@ -31,12 +55,21 @@ impl Poller {
             "new",
         );
 
-        Ok(Poller { port_fd })
+        Ok(Poller {
+            port_fd,
+            notify_key,
+            levels: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The backend of this poller.
+    pub fn backend(&self) -> Backend {
+        BACKEND
     }
 
     /// Whether this poller supports level-triggered events.
     pub fn supports_level(&self) -> bool {
-        false
+        true
     }
 
     /// Whether this poller supports edge-triggered events.
@@ -73,16 +106,29 @@ impl Poller {
         if ev.writable {
             flags |= write_flags();
         }
-
-        if mode != PollMode::Oneshot {
-            return Err(crate::unsupported_error(
-                "this kind of event is not supported with event ports",
-            ));
+        if ev.extra.is_hup() {
+            flags |= PollFlags::HUP;
         }
 
+        let level = match mode {
+            PollMode::Oneshot => false,
+            PollMode::Level => true,
+            _ => {
+                return Err(crate::unsupported_error(
+                    "this kind of event is not supported with event ports",
+                ))
+            }
+        };
+
+        let mut levels = self.levels();
         unsafe {
             port::associate_fd(&self.port_fd, fd, flags, ev.key as _)?;
         }
+        if level {
+            levels.insert(fd.as_raw_fd(), (flags, ev.key));
+        } else {
+            levels.remove(&fd.as_raw_fd());
+        }
 
         Ok(())
     }
@@ -98,6 +144,7 @@ impl Poller {
         #[cfg(feature = "tracing")]
         let _enter = span.enter();
 
+        self.levels().remove(&fd.as_raw_fd());
         let result = unsafe { port::dissociate_fd(&self.port_fd, fd) };
         if let Err(e) = result {
             match e {
@@ -109,6 +156,24 @@ impl Poller {
         Ok(())
     }
 
+    /// Forgets about a file descriptor that was closed while it was registered.
+    pub fn forget(&self, fd: RawFd) {
+        // Closing a file descriptor dissociates it from the port.
+        self.levels().remove(&fd);
+    }
+
+    /// Waits for I/O events with an optional deadline and the given signal mask.
+    ///
+    /// There is no native support for this, so the signal mask is replaced around the wait.
+    pub fn wait_sigmask(
+        &self,
+        events: &mut Events,
+        deadline: Option<Instant>,
+        sigmask: &libc::sigset_t,
+    ) -> io::Result<()> {
+        crate::os::unix::with_sigmask(sigmask, || self.wait_deadline(events, deadline))
+    }
+
     /// Waits for I/O events with an optional deadline.
     pub fn wait_deadline(&self, events: &mut Events, deadline: Option<Instant>) -> io::Result<()> {
         #[cfg(feature = "tracing")]
@@ -152,6 +217,37 @@ impl Poller {
             }
         }
 
+        // Remove the notifications.
+        events
+            .list
+            .retain(|ev| ev.userdata() as usize != self.notify_key);
+
+        // Associate level-triggered file descriptors again, so they keep reporting readiness.
+        let levels = self.levels();
+        if !levels.is_empty() {
+            for ev in &events.list {
+                let fd = ev.object() as RawFd;
+                if let Some(&(flags, key)) = levels.get(&fd) {
+                    // SAFETY: Registered file descriptors must stay open until they are deleted.
+                    let res = unsafe {
+                        port::associate_fd(
+                            &self.port_fd,
+                            BorrowedFd::borrow_raw(fd),
+                            flags,
+                            key as _,
+                        )
+                    };
+                    if let Err(_err) = res {
+                        #[cfg(feature = "tracing")]
+                        tracing::warn!(
+                            "failed to associate a level-triggered source again: {}",
+                            _err
+                        );
+                    }
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -168,10 +264,35 @@ impl Poller {
         let _enter = span.enter();
 
         // Use port_send to send a notification to the port.
-        port::send(&self.port_fd, PORT_SOURCE_USER, crate::NOTIFY_KEY as _)?;
+        port::send(&self.port_fd, PORT_SOURCE_USER, self.notify_key as _)?;
 
         Ok(())
     }
+
+    /// Sends a notification from a signal handler.
+    ///
+    /// This is a single call of `port_send()`.
+    pub fn notify_from_signal(&self) {
+        self.notify().ok();
+    }
+
+    /// Lock the level-triggered interests, ignoring poisoning.
+    fn levels(&self) -> MutexGuard<'_, HashMap<RawFd, (PollFlags, usize)>> {
+        self.levels.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the event port.
+    pub fn into_fd(self) -> OwnedFd {
+        self.port_fd
+    }
+
+    /// Closes the poller in the child process after `fork()`.
+    ///
+    /// Event ports are not inherited by the child, and the file descriptor may already have been
+    /// reused, so it is leaked rather than closed.
+    pub fn close_after_fork(self) {
+        mem::forget(self.port_fd);
+    }
 }
 
 impl AsRawFd for Poller {
@@ -219,6 +340,10 @@ impl Events {
                 key: ev.userdata() as usize,
                 readable: flags.intersects(read_flags()),
                 writable: flags.intersects(write_flags()),
+                read_low_watermark: None,
+                write_low_watermark: None,
+                timeout: false,
+                delete_on_hangup: false,
                 extra: EventExtra { flags },
             }
         })
@@ -233,6 +358,11 @@ impl Events {
     pub fn capacity(&self) -> usize {
         self.list.capacity()
     }
+
+    /// Whether the last wait filled the list, so that more events may be pending.
+    pub fn is_full(&self) -> bool {
+        self.list.len() >= self.list.capacity()
+    }
 }
 
 /// Extra information associated with an event.
@@ -251,6 +381,12 @@ impl EventExtra {
         }
     }
 
+    /// Set interest in errors.
+    #[inline]
+    pub fn set_err(&mut self, _value: bool) {
+        // Errors are always reported.
+    }
+
     /// Set the interrupt flag.
     #[inline]
     pub fn set_hup(&mut self, value: bool) {
@@ -263,6 +399,30 @@ impl EventExtra {
         self.flags.set(PollFlags::PRI, value);
     }
 
+    /// Set the exclusive flag.
+    #[inline]
+    pub fn set_exclusive(&mut self, _value: bool) {
+        // No-op.
+    }
+
+    /// Is the exclusive flag set?
+    #[inline]
+    pub fn is_exclusive(&self) -> bool {
+        false
+    }
+
+    /// Set the wakeup flag.
+    #[inline]
+    pub fn set_wakeup(&mut self, _value: bool) {
+        // No-op.
+    }
+
+    /// Is the wakeup flag set?
+    #[inline]
+    pub fn is_wakeup(&self) -> bool {
+        false
+    }
+
     /// Is this an interrupt event?
     #[inline]
     pub fn is_hup(&self) -> bool {
@@ -0,0 +1,288 @@
+//! A source for memory pressure notifications.
+
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use crate::Event;
+
+/// A source that reports that the system is running low on memory.
+///
+/// Caches and services that allocate a lot can register this with the [`Poller`] that drives
+/// their I/O, and shed load when its event is delivered, instead of watching the memory from a
+/// thread of their own. The event doesn't tell how much memory is left.
+///
+/// On Linux and Android this is a trigger on `/proc/pressure/memory`, which fires when tasks
+/// stalled on memory for a given time within a window, see
+/// [PSI](https://docs.kernel.org/accounting/psi.html). It is reported as priority data, so
+/// register it with [`interest()`][`MemoryPressure::interest()`] rather than a readable
+/// interest. Creating one fails if the kernel was built without PSI. Since Linux 6.5,
+/// unprivileged processes can create triggers whose window is a multiple of two seconds, and
+/// other windows, or older kernels, require `CAP_SYS_RESOURCE`.
+///
+/// On Apple platforms this is a dispatch source of the type `DISPATCH_SOURCE_TYPE_MEMORYPRESSURE`
+/// that wakes up a [`WakePipe`] when the system reports the warning or critical level. The
+/// thresholds are chosen by the system.
+///
+/// [`Poller`]: crate::Poller
+/// [`WakePipe`]: crate::WakePipe
+///
+/// # Examples
+///
+/// ```no_run
+/// use polling::{Events, MemoryPressure, Poller};
+///
+/// let poller = Poller::new()?;
+/// let pressure = MemoryPressure::new()?;
+/// unsafe {
+///     poller.add(&pressure, pressure.interest(0))?;
+/// }
+///
+/// let mut events = Events::new();
+/// loop {
+///     poller.wait(&mut events, None)?;
+///     if events.iter().any(|ev| ev.key == 0) {
+///         pressure.reset()?;
+///         println!("memory is running low, dropping caches");
+///         poller.modify(&pressure, pressure.interest(0))?;
+///     }
+///     events.clear();
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+pub struct MemoryPressure {
+    inner: sys::Monitor,
+}
+
+impl MemoryPressure {
+    /// Start watching the memory pressure with the default thresholds.
+    ///
+    /// On Linux and Android this fires when some tasks stalled on memory for 300 milliseconds
+    /// within two seconds.
+    pub fn new() -> io::Result<MemoryPressure> {
+        Self::with_threshold(Duration::from_millis(300), Duration::from_secs(2))
+    }
+
+    /// Start watching the memory pressure, reported when some tasks stalled on memory for
+    /// `stall` within `window`.
+    ///
+    /// The kernel requires the window to be between 500 milliseconds and 10 seconds, and the
+    /// stall to be shorter than the window. On Apple platforms the thresholds are ignored.
+    pub fn with_threshold(stall: Duration, window: Duration) -> io::Result<MemoryPressure> {
+        Ok(MemoryPressure {
+            inner: sys::Monitor::new(stall, window)?,
+        })
+    }
+
+    /// The interest to register this source with, under `key`.
+    ///
+    /// This is a priority interest on Linux and Android, and a readable one elsewhere.
+    pub fn interest(&self, key: usize) -> Event {
+        sys::interest(key)
+    }
+
+    /// Consume the notifications reported so far, so that the source is no longer ready.
+    ///
+    /// On Linux and Android a trigger stops being ready once it is reported, so this does
+    /// nothing.
+    pub fn reset(&self) -> io::Result<()> {
+        self.inner.reset()
+    }
+}
+
+impl fmt::Debug for MemoryPressure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryPressure")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+mod unix_impl {
+    use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+
+    use super::MemoryPressure;
+
+    impl AsRawFd for MemoryPressure {
+        fn as_raw_fd(&self) -> RawFd {
+            self.inner.source().as_raw_fd()
+        }
+    }
+
+    impl AsFd for MemoryPressure {
+        fn as_fd(&self) -> BorrowedFd<'_> {
+            self.inner.source()
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod sys {
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, Write};
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::os::unix::io::{AsFd, BorrowedFd};
+    use std::time::Duration;
+
+    use crate::Event;
+
+    /// A trigger on the memory pressure file.
+    #[derive(Debug)]
+    pub(super) struct Monitor {
+        file: File,
+    }
+
+    impl Monitor {
+        pub(super) fn new(stall: Duration, window: Duration) -> io::Result<Monitor> {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+                .open("/proc/pressure/memory")?;
+
+            // The trigger is kept until the file is closed. It must be written at once, with
+            // the terminating nul byte.
+            let trigger = format!("some {} {}\0", stall.as_micros(), window.as_micros());
+            let written = file.write(trigger.as_bytes())?;
+            if written != trigger.len() {
+                return Err(io::Error::from(io::ErrorKind::WriteZero));
+            }
+            Ok(Monitor { file })
+        }
+
+        pub(super) fn reset(&self) -> io::Result<()> {
+            Ok(())
+        }
+
+        pub(super) fn source(&self) -> BorrowedFd<'_> {
+            self.file.as_fd()
+        }
+    }
+
+    pub(super) fn interest(key: usize) -> Event {
+        Event::none(key).with_priority()
+    }
+}
+
+#[cfg(target_vendor = "apple")]
+mod sys {
+    use std::fmt;
+    use std::io;
+    use std::os::raw::c_void;
+    use std::os::unix::io::{AsFd, BorrowedFd};
+    use std::ptr::{addr_of, NonNull};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::{Event, WakePipe};
+
+    /// The warning level of memory pressure.
+    const DISPATCH_MEMORYPRESSURE_WARN: usize = 0x2;
+
+    /// The critical level of memory pressure.
+    const DISPATCH_MEMORYPRESSURE_CRITICAL: usize = 0x4;
+
+    /// The default quality-of-service class, `QOS_CLASS_DEFAULT`.
+    const QOS_CLASS_DEFAULT: isize = 0x15;
+
+    extern "C" {
+        static _dispatch_source_type_memorypressure: c_void;
+
+        fn dispatch_get_global_queue(identifier: isize, flags: usize) -> *mut c_void;
+        fn dispatch_source_create(
+            kind: *const c_void,
+            handle: usize,
+            mask: usize,
+            queue: *mut c_void,
+        ) -> *mut c_void;
+        fn dispatch_set_context(object: *mut c_void, context: *mut c_void);
+        fn dispatch_source_set_event_handler_f(
+            source: *mut c_void,
+            handler: unsafe extern "C" fn(*mut c_void),
+        );
+        fn dispatch_source_set_cancel_handler_f(
+            source: *mut c_void,
+            handler: unsafe extern "C" fn(*mut c_void),
+        );
+        fn dispatch_resume(object: *mut c_void);
+        fn dispatch_source_cancel(source: *mut c_void);
+        fn dispatch_release(object: *mut c_void);
+    }
+
+    /// A dispatch source that wakes up a channel.
+    pub(super) struct Monitor {
+        pipe: Arc<WakePipe>,
+        source: NonNull<c_void>,
+    }
+
+    // SAFETY: Dispatch objects can be used from any thread.
+    unsafe impl Send for Monitor {}
+    unsafe impl Sync for Monitor {}
+
+    impl Monitor {
+        pub(super) fn new(_stall: Duration, _window: Duration) -> io::Result<Monitor> {
+            let pipe = Arc::new(WakePipe::new()?);
+
+            // SAFETY: The context holds a reference to the channel, which the cancel handler
+            // releases after the last event handler ran.
+            unsafe {
+                let queue = dispatch_get_global_queue(QOS_CLASS_DEFAULT, 0);
+                let source = NonNull::new(dispatch_source_create(
+                    addr_of!(_dispatch_source_type_memorypressure),
+                    0,
+                    DISPATCH_MEMORYPRESSURE_WARN | DISPATCH_MEMORYPRESSURE_CRITICAL,
+                    queue,
+                ))
+                .ok_or_else(|| io::Error::from(io::ErrorKind::OutOfMemory))?;
+
+                let context = Arc::into_raw(pipe.clone());
+                dispatch_set_context(source.as_ptr(), context as *mut c_void);
+                dispatch_source_set_event_handler_f(source.as_ptr(), on_event);
+                dispatch_source_set_cancel_handler_f(source.as_ptr(), on_cancel);
+                dispatch_resume(source.as_ptr());
+
+                Ok(Monitor { pipe, source })
+            }
+        }
+
+        pub(super) fn reset(&self) -> io::Result<()> {
+            self.pipe.reset()
+        }
+
+        pub(super) fn source(&self) -> BorrowedFd<'_> {
+            self.pipe.as_fd()
+        }
+    }
+
+    impl fmt::Debug for Monitor {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Monitor").field("pipe", &self.pipe).finish()
+        }
+    }
+
+    impl Drop for Monitor {
+        fn drop(&mut self) {
+            // SAFETY: The source is valid until it is released here. The context is released by
+            // the cancel handler.
+            unsafe {
+                dispatch_source_cancel(self.source.as_ptr());
+                dispatch_release(self.source.as_ptr());
+            }
+        }
+    }
+
+    /// Wake up the channel when the memory pressure changes.
+    unsafe extern "C" fn on_event(context: *mut c_void) {
+        // There is nothing to report a failure to. A full channel is readable anyway.
+        (*context.cast::<WakePipe>()).wake().ok();
+    }
+
+    /// Release the reference to the channel once the source is cancelled.
+    unsafe extern "C" fn on_cancel(context: *mut c_void) {
+        drop(Arc::from_raw(context.cast::<WakePipe>()));
+    }
+
+    pub(super) fn interest(key: usize) -> Event {
+        Event::readable(key)
+    }
+}
@@ -0,0 +1,263 @@
+//! A minimal reactor that dispatches events to per-source callbacks.
+//!
+//! A [`Reactor`] owns a [`Poller`] and a table of callbacks. Sources are registered together
+//! with the callback that handles their events, and [`Reactor::turn()`] or [`Reactor::run()`]
+//! wait for events and call the callbacks. Keys are assigned by the reactor.
+//!
+//! Sources are registered in level-triggered mode where supported. Otherwise they are
+//! registered in oneshot mode, and the reactor re-enables interest after every callback, so
+//! callbacks see the same behavior on all platforms.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use polling::reactor::Reactor;
+//! use polling::Interest;
+//! use std::net::TcpListener;
+//!
+//! let socket = TcpListener::bind("127.0.0.1:8000")?;
+//! socket.set_nonblocking(true)?;
+//!
+//! let listener = socket.try_clone()?;
+//!
+//! let reactor = Reactor::new()?;
+//! unsafe {
+//!     reactor.register(&socket, Interest::READABLE, move |_reactor, _event| {
+//!         // Accept connections until the backlog is empty.
+//!         while let Ok((_stream, addr)) = listener.accept() {
+//!             println!("connection from {}", addr);
+//!         }
+//!     })?;
+//! }
+//!
+//! reactor.run()?;
+//! # std::io::Result::Ok(())
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+#[cfg(unix)]
+use std::os::unix::io::BorrowedFd as BorrowedSource;
+#[cfg(windows)]
+use std::os::windows::io::BorrowedSocket as BorrowedSource;
+
+use crate::{AsRawSource, Event, Events, Interest, PollMode, Poller, RawSource};
+
+/// A callback handling the events of a source.
+type Callback = Box<dyn FnMut(&Reactor, Event) + Send>;
+
+/// A registered source.
+struct Handler {
+    source: RawSource,
+    interest: Interest,
+
+    /// The callback, or `None` while it is running.
+    callback: Option<Callback>,
+}
+
+/// A poller that dispatches events to callbacks.
+///
+/// See the [module-level documentation](self) for more information.
+pub struct Reactor {
+    poller: Poller,
+    mode: PollMode,
+    handlers: Mutex<HashMap<usize, Handler>>,
+    next_key: AtomicUsize,
+    events: Mutex<Events>,
+    stopped: AtomicBool,
+}
+
+impl fmt::Debug for Reactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reactor")
+            .field("poller", &self.poller)
+            .field("mode", &self.mode)
+            .field("sources", &self.handlers().len())
+            .finish()
+    }
+}
+
+impl Reactor {
+    /// Create a new reactor.
+    pub fn new() -> io::Result<Reactor> {
+        Ok(Reactor::with_poller(Poller::new()?))
+    }
+
+    /// Create a new reactor on top of an existing poller.
+    ///
+    /// The poller must not have sources registered under keys that the reactor hands out.
+    pub fn with_poller(poller: Poller) -> Reactor {
+        let mode = if poller.supports_level() {
+            PollMode::Level
+        } else {
+            PollMode::Oneshot
+        };
+
+        Reactor {
+            events: Mutex::new(poller.new_events()),
+            poller,
+            mode,
+            handlers: Mutex::new(HashMap::new()),
+            next_key: AtomicUsize::new(0),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    /// The poller the sources are registered in.
+    #[inline]
+    pub fn poller(&self) -> &Poller {
+        &self.poller
+    }
+
+    /// Register a source with the callback that handles its events.
+    ///
+    /// The callback receives the reactor, so it can register and deregister sources, including
+    /// its own. Returns the key of the source, which is used to deregister it.
+    ///
+    /// # Safety
+    ///
+    /// The source must be deregistered with [`Reactor::deregister()`] before it is closed, see
+    /// [`Poller::add()`]. If the callback owns the source, deregister it before dropping the
+    /// reactor.
+    pub unsafe fn register(
+        &self,
+        source: impl AsRawSource,
+        interest: Interest,
+        callback: impl FnMut(&Reactor, Event) + Send + 'static,
+    ) -> io::Result<usize> {
+        let source = source.raw();
+        let key = self.next_key()?;
+
+        // Insert the handler first, so events are never delivered for an unknown key.
+        self.handlers().insert(
+            key,
+            Handler {
+                source,
+                interest,
+                callback: Some(Box::new(callback)),
+            },
+        );
+
+        let res = self
+            .poller
+            .add_with_mode(source, Event::from_interest(key, interest), self.mode);
+        if res.is_err() {
+            self.handlers().remove(&key);
+        }
+        res.map(|()| key)
+    }
+
+    /// Deregister the source with the given key.
+    ///
+    /// If this is called from the source's own callback, the callback is dropped once it
+    /// returns.
+    pub fn deregister(&self, key: usize) -> io::Result<()> {
+        let handler = self.handlers().remove(&key).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "no source is registered with this key",
+            )
+        })?;
+
+        // SAFETY: `register()` requires the source to stay open until it is deregistered.
+        let source = unsafe { BorrowedSource::borrow_raw(handler.source) };
+        self.poller.delete(source)
+    }
+
+    /// Number of registered sources.
+    pub fn len(&self) -> usize {
+        self.handlers().len()
+    }
+
+    /// Whether no sources are registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Wait for events once and call the callbacks of the sources that received them.
+    ///
+    /// Returns the number of callbacks that were called. Only one thread can turn the reactor at
+    /// a time; concurrent calls return `Ok(0)` immediately.
+    pub fn turn(&self, timeout: Option<Duration>) -> io::Result<usize> {
+        let mut events = match self.events.try_lock() {
+            Ok(events) => events,
+            Err(_) => return Ok(0),
+        };
+
+        events.clear();
+        self.poller.wait(&mut events, timeout)?;
+
+        let mut called = 0;
+        for event in events.iter() {
+            if self.dispatch(event)? {
+                called += 1;
+            }
+        }
+        Ok(called)
+    }
+
+    /// Turn the reactor until [`Reactor::stop()`] is called.
+    pub fn run(&self) -> io::Result<()> {
+        while !self.stopped.swap(false, Ordering::SeqCst) {
+            self.turn(None)?;
+        }
+        Ok(())
+    }
+
+    /// Make [`Reactor::run()`] return after the current turn.
+    ///
+    /// If the reactor is not running, the next call to [`Reactor::run()`] returns immediately.
+    pub fn stop(&self) -> io::Result<()> {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.poller.notify()
+    }
+
+    /// Call the callback for `event`, if its source is still registered.
+    fn dispatch(&self, event: Event) -> io::Result<bool> {
+        let mut callback = match self.handlers().get_mut(&event.key) {
+            Some(handler) => match handler.callback.take() {
+                Some(callback) => callback,
+                None => return Ok(false),
+            },
+            None => return Ok(false),
+        };
+
+        callback(self, event);
+
+        // Put the callback back, unless the source was deregistered in the meantime.
+        let mut handlers = self.handlers();
+        let handler = match handlers.get_mut(&event.key) {
+            Some(handler) => handler,
+            None => return Ok(true),
+        };
+        handler.callback = Some(callback);
+
+        if self.mode == PollMode::Oneshot {
+            // SAFETY: `register()` requires the source to stay open until it is deregistered.
+            let source = unsafe { BorrowedSource::borrow_raw(handler.source) };
+            self.poller.modify_with_mode(
+                source,
+                Event::from_interest(event.key, handler.interest),
+                self.mode,
+            )?;
+        }
+        Ok(true)
+    }
+
+    /// Allocate a key for a new source.
+    fn next_key(&self) -> io::Result<usize> {
+        let key = self.next_key.fetch_add(1, Ordering::Relaxed);
+        self.poller.check_key(key)?;
+        Ok(key)
+    }
+
+    /// Lock the handler table, ignoring poisoning.
+    fn handlers(&self) -> MutexGuard<'_, HashMap<usize, Handler>> {
+        self.handlers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,251 @@
+//! RAII guard for registered sources.
+
+use std::fmt;
+use std::io;
+
+use crate::{AsSource, Event, KeyAllocator, PollMode, Poller};
+
+/// A source registered in a [`Poller`] that is removed from it when dropped.
+///
+/// This is created by [`Poller::register()`] and [`Poller::register_with_mode()`], or by
+/// [`KeyAllocator::register()`], which also frees the key of the source when the guard is
+/// dropped. The guard owns
+/// the source, which guarantees that the source stays alive for as long as it is registered. When
+/// the guard is dropped, the source is [`delete()`]d from the poller and then dropped.
+///
+/// [`delete()`]: Poller::delete
+///
+/// # Examples
+///
+/// ```
+/// use polling::{Event, Poller};
+/// use std::net::TcpListener;
+///
+/// let socket = TcpListener::bind("127.0.0.1:0")?;
+/// socket.set_nonblocking(true)?;
+/// let key = 7;
+///
+/// let poller = Poller::new()?;
+/// let registration = poller.register(socket, Event::readable(key))?;
+///
+/// // The socket is removed from the poller here, even on an early return.
+/// drop(registration);
+/// # std::io::Result::Ok(())
+/// ```
+pub struct Registration<'a, S: AsSource> {
+    /// The poller the source is registered in.
+    poller: &'a Poller,
+
+    /// The registered source.
+    ///
+    /// This is only `None` while the guard is being consumed by `into_inner()`.
+    source: Option<S>,
+
+    /// The allocator the key of the source is freed in, and the key.
+    key: Option<(&'a KeyAllocator, usize)>,
+}
+
+impl<'a, S: AsSource + 'static> Registration<'a, S> {
+    /// Register `source` in `poller`.
+    pub(crate) fn new(
+        poller: &'a Poller,
+        source: S,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<Self> {
+        // SAFETY: The source is owned by the guard and is deleted from the poller before it is
+        // dropped. If the guard is leaked, the source is leaked along with it.
+        unsafe {
+            poller.add_with_mode(&source.source(), interest, mode)?;
+        }
+
+        Ok(Self {
+            poller,
+            source: Some(source),
+            key: None,
+        })
+    }
+}
+
+impl<'a, S: AsSource> Registration<'a, S> {
+    /// Free `key` in `keys` once the source is removed.
+    pub(crate) fn with_key(mut self, keys: &'a KeyAllocator, key: usize) -> Self {
+        self.key = Some((keys, key));
+        self
+    }
+
+    /// Get the poller this source is registered in.
+    #[inline]
+    pub fn poller(&self) -> &'a Poller {
+        self.poller
+    }
+
+    /// Get the key that was allocated for the source, if it was registered with
+    /// [`KeyAllocator::register()`].
+    #[inline]
+    pub fn key(&self) -> Option<usize> {
+        self.key.map(|(_, key)| key)
+    }
+
+    /// Get a reference to the registered source.
+    #[inline]
+    pub fn get_ref(&self) -> &S {
+        self.source.as_ref().unwrap()
+    }
+
+    /// Get a mutable reference to the registered source.
+    ///
+    /// # Safety
+    ///
+    /// The underlying file descriptor or socket must not be closed or replaced through this
+    /// reference, since it would then be dropped while still registered in the poller.
+    #[inline]
+    pub unsafe fn get_mut(&mut self) -> &mut S {
+        self.source.as_mut().unwrap()
+    }
+
+    /// Modify the interest in the registered source.
+    ///
+    /// See [`Poller::modify()`] for more information.
+    #[inline]
+    pub fn modify(&self, interest: Event) -> io::Result<()> {
+        self.poller.modify(self.get_ref(), interest)
+    }
+
+    /// Modify the interest in the registered source, using the specified mode.
+    ///
+    /// See [`Poller::modify_with_mode()`] for more information.
+    #[inline]
+    pub fn modify_with_mode(&self, interest: Event, mode: PollMode) -> io::Result<()> {
+        self.poller.modify_with_mode(self.get_ref(), interest, mode)
+    }
+
+    /// Run a non-blocking operation on the source until it doesn't fail with
+    /// [`WouldBlock`][`io::ErrorKind::WouldBlock`], waiting for readability in between.
+    ///
+    /// This is the loop that every blocking read on a non-blocking source needs: readiness can
+    /// be spurious, so the operation is attempted first, and only a `WouldBlock` error waits
+    /// for the source. Before waiting, interest in readability is enabled again with the key
+    /// and mode of the registration, which re-arms it in oneshot mode and costs nothing in
+    /// level mode. Events of other sources that arrive while waiting are set aside for the next
+    /// wait, like with [`Poller::wait_for_key()`].
+    ///
+    /// This blocks the calling thread. Unless [concurrent waits] are enabled, no other thread
+    /// may wait on the poller at the same time.
+    ///
+    /// [concurrent waits]: crate::PollerBuilder::concurrent_waits
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Poller};
+    /// use std::net::UdpSocket;
+    ///
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// socket.set_nonblocking(true)?;
+    /// socket.send_to(b"hello", socket.local_addr()?)?;
+    ///
+    /// let poller = Poller::new()?;
+    /// let socket = poller.register(socket, Event::none(7))?;
+    ///
+    /// let mut buf = [0; 5];
+    /// let len = socket.read_with(|socket| socket.recv(&mut buf))?;
+    /// assert_eq!(&buf[..len], b"hello");
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn read_with<R>(&self, op: impl FnMut(&S) -> io::Result<R>) -> io::Result<R> {
+        self.op_with(false, op)
+    }
+
+    /// Run a non-blocking operation on the source until it doesn't fail with
+    /// [`WouldBlock`][`io::ErrorKind::WouldBlock`], waiting for writability in between.
+    ///
+    /// See [`read_with()`][`Registration::read_with()`] for more information. With split keys,
+    /// this waits for the key of the write half.
+    pub fn write_with<R>(&self, op: impl FnMut(&S) -> io::Result<R>) -> io::Result<R> {
+        self.op_with(true, op)
+    }
+
+    /// Run `op` until it doesn't block, waiting for writability if `write` is set, or for
+    /// readability otherwise.
+    fn op_with<R>(&self, write: bool, mut op: impl FnMut(&S) -> io::Result<R>) -> io::Result<R> {
+        loop {
+            match op(self.get_ref()) {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                res => return res,
+            }
+
+            let source = self.get_ref().source();
+            let registered = self
+                .poller
+                .registered(&source)
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+            let mut read = registered.interest();
+            let mode = registered.mode();
+            let key = match registered.write_key() {
+                Some(write_key) => {
+                    let mut write_half = read;
+                    write_half.key = write_key;
+                    write_half.readable = false;
+                    write_half.writable |= write;
+                    read.readable |= !write;
+                    read.writable = false;
+                    self.poller
+                        .modify_split_with_mode(source, read, write_half, mode)?;
+                    if write {
+                        write_key
+                    } else {
+                        read.key
+                    }
+                }
+                None => {
+                    read.readable |= !write;
+                    read.writable |= write;
+                    self.poller.modify_with_mode(source, read, mode)?;
+                    read.key
+                }
+            };
+
+            // Any event of the key may have made the operation possible, so it is retried.
+            self.poller.wait_for_key(key, None)?;
+        }
+    }
+
+    /// Remove the source from the poller and return it.
+    ///
+    /// Unlike dropping the guard, this reports errors that occur while deleting the source. The
+    /// allocated key, if any, is freed either way.
+    pub fn into_inner(mut self) -> io::Result<S> {
+        let source = self.source.take().unwrap();
+        let res = self.poller.delete(&source);
+        self.free_key();
+        res.map(|()| source)
+    }
+
+    /// Free the allocated key, if any.
+    fn free_key(&mut self) {
+        if let Some((keys, key)) = self.key.take() {
+            keys.free(key);
+        }
+    }
+}
+
+impl<S: AsSource> Drop for Registration<'_, S> {
+    fn drop(&mut self) {
+        if let Some(source) = self.source.take() {
+            if let Err(_err) = self.poller.delete(&source) {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("failed to delete source on drop: {}", _err);
+            }
+        }
+        self.free_key();
+    }
+}
+
+impl<S: AsSource + fmt::Debug> fmt::Debug for Registration<'_, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Registration")
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
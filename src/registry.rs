@@ -0,0 +1,328 @@
+//! Userspace table of the sources registered in a `Poller`.
+
+use std::collections::HashMap;
+use std::mem;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::{split, Event, KeyAllocator, PollMode, RawSource};
+
+/// A source that is currently registered in a [`Poller`].
+///
+/// This is returned by [`Poller::registrations()`] and [`Poller::registered()`].
+///
+/// [`Poller`]: crate::Poller
+/// [`Poller::registrations()`]: crate::Poller::registrations
+/// [`Poller::registered()`]: crate::Poller::registered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisteredSource {
+    source: RawSource,
+    interest: Event,
+    write_key: Option<usize>,
+    mode: PollMode,
+}
+
+impl RegisteredSource {
+    /// The raw file descriptor or socket of the source.
+    #[inline]
+    pub fn source(&self) -> RawSource {
+        self.source
+    }
+
+    /// The interest that was last set for the source.
+    ///
+    /// In oneshot mode the operating system disables the interest once an event is delivered,
+    /// but this still returns the interest until it is re-enabled with
+    /// [`Poller::modify()`][crate::Poller::modify].
+    #[inline]
+    pub fn interest(&self) -> Event {
+        self.interest
+    }
+
+    /// The key of the write half, if the source was registered with split keys.
+    ///
+    /// The key of the read half is the key of [`interest()`][RegisteredSource::interest]. See
+    /// [`Poller::add_split()`][crate::Poller::add_split].
+    #[inline]
+    pub fn write_key(&self) -> Option<usize> {
+        self.write_key
+    }
+
+    /// The mode the source was last registered or modified with.
+    #[inline]
+    pub fn mode(&self) -> PollMode {
+        self.mode
+    }
+}
+
+/// The table of registered sources.
+#[derive(Debug)]
+pub(crate) struct Registry {
+    inner: Mutex<Inner>,
+
+    /// The keys assigned by `Poller::add_auto()`.
+    keys: KeyAllocator,
+
+    /// Whether dropping the table while sources are registered is reported as a misuse.
+    misuse_checks: bool,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    sources: HashMap<RawSource, RegisteredSource>,
+
+    /// The oneshot sources that haven't had an event delivered since their interest was set, by
+    /// key.
+    armed: HashMap<usize, RawSource>,
+
+    /// The keys that were assigned to sources, which are freed when the source is removed.
+    assigned: HashMap<RawSource, usize>,
+
+    /// The groups of the sources that are not in the group `0`.
+    groups: HashMap<RawSource, usize>,
+
+    /// The sources that are deleted once a hangup is delivered for them, by key.
+    hangup: HashMap<usize, RawSource>,
+}
+
+impl Inner {
+    /// Forget whether the oneshot registration of `source` is still armed.
+    fn disarm_source(&mut self, source: RawSource) {
+        if let Some(old) = self.sources.get(&source) {
+            if self.armed.get(&old.interest.key) == Some(&source) {
+                self.armed.remove(&old.interest.key);
+            }
+        }
+    }
+
+    /// Forget whether the registration of `source` is deleted on a hangup.
+    fn keep_on_hangup(&mut self, source: RawSource) {
+        if let Some(old) = self.sources.get(&source) {
+            for key in [Some(old.interest.key), old.write_key]
+                .into_iter()
+                .flatten()
+            {
+                if self.hangup.get(&key) == Some(&source) {
+                    self.hangup.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+impl Registry {
+    /// Create an empty table, which never assigns the key `notify_key`.
+    pub(crate) fn new(notify_key: usize, misuse_checks: bool) -> Registry {
+        Registry {
+            inner: Mutex::new(Inner::default()),
+            keys: KeyAllocator::with_reserved(notify_key),
+            misuse_checks,
+        }
+    }
+
+    /// Lock the table, ignoring poisoning.
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record that `source` is registered with `interest` and `mode`.
+    pub(crate) fn insert(&self, source: RawSource, interest: Event, mode: PollMode) {
+        let mut inner = self.lock();
+        inner.disarm_source(source);
+        inner.keep_on_hangup(source);
+        if is_oneshot(mode) {
+            inner.armed.insert(interest.key, source);
+        }
+        if interest.delete_on_hangup {
+            inner.hangup.insert(interest.key, source);
+        }
+        inner.sources.insert(
+            source,
+            RegisteredSource {
+                source,
+                interest,
+                write_key: None,
+                mode,
+            },
+        );
+    }
+
+    /// Record that `source` is registered with the split halves `read` and `write`.
+    pub(crate) fn insert_split(
+        &self,
+        source: RawSource,
+        read: Event,
+        write: Event,
+        mode: PollMode,
+    ) {
+        let mut inner = self.lock();
+        inner.disarm_source(source);
+        inner.keep_on_hangup(source);
+        if read.delete_on_hangup || write.delete_on_hangup {
+            inner.hangup.insert(read.key, source);
+            inner.hangup.insert(write.key, source);
+        }
+        inner.sources.insert(
+            source,
+            RegisteredSource {
+                source,
+                interest: split::combine(read, write),
+                write_key: Some(write.key),
+                mode,
+            },
+        );
+    }
+
+    /// Record that `source` is no longer registered, and free the key assigned to it.
+    pub(crate) fn remove(&self, source: RawSource) {
+        let mut inner = self.lock();
+        inner.disarm_source(source);
+        inner.keep_on_hangup(source);
+        inner.sources.remove(&source);
+        inner.groups.remove(&source);
+        if let Some(key) = inner.assigned.remove(&source) {
+            self.keys.free(key);
+        }
+    }
+
+    /// Allocate a key that is not assigned to any other source.
+    pub(crate) fn allocate_key(&self) -> usize {
+        self.keys.allocate()
+    }
+
+    /// Free a key that was allocated but not assigned.
+    pub(crate) fn free_key(&self, key: usize) {
+        self.keys.free(key);
+    }
+
+    /// Record that `key` was assigned to `source`, to free it along with the registration.
+    pub(crate) fn assign_key(&self, source: RawSource, key: usize) {
+        if let Some(old) = self.lock().assigned.insert(source, key) {
+            self.keys.free(old);
+        }
+    }
+
+    /// Change the key of the registration of `source`, keeping whether it is armed.
+    ///
+    /// A key that was assigned to the source is freed.
+    pub(crate) fn rekey(&self, source: RawSource, key: usize) {
+        let mut inner = self.lock();
+        let old = match inner.sources.get_mut(&source) {
+            Some(registered) => mem::replace(&mut registered.interest.key, key),
+            None => return,
+        };
+        if inner.armed.get(&old) == Some(&source) {
+            inner.armed.remove(&old);
+            inner.armed.insert(key, source);
+        }
+        if inner.hangup.get(&old) == Some(&source) {
+            inner.hangup.remove(&old);
+            inner.hangup.insert(key, source);
+        }
+        if let Some(assigned) = inner.assigned.remove(&source) {
+            self.keys.free(assigned);
+        }
+    }
+
+    /// Put the registration of `source` into `group`.
+    pub(crate) fn set_group(&self, source: RawSource, group: usize) {
+        let mut inner = self.lock();
+        if group == 0 {
+            inner.groups.remove(&source);
+        } else if inner.sources.contains_key(&source) {
+            inner.groups.insert(source, group);
+        }
+    }
+
+    /// The group of the registration of `source`.
+    pub(crate) fn group(&self, source: RawSource) -> usize {
+        self.lock().groups.get(&source).copied().unwrap_or(0)
+    }
+
+    /// Whether the registration of `source` still reports events, which is only not the case
+    /// for a oneshot registration that already had an event delivered.
+    pub(crate) fn is_armed(&self, source: RawSource) -> bool {
+        let inner = self.lock();
+        match inner.sources.get(&source) {
+            Some(registered) => {
+                !is_oneshot(registered.mode)
+                    || inner.armed.get(&registered.interest.key) == Some(&source)
+            }
+            None => false,
+        }
+    }
+
+    /// Whether `source` is registered with `interest` and `mode` and is still armed, so that
+    /// setting them again would change nothing.
+    pub(crate) fn is_unchanged(&self, source: RawSource, interest: &Event, mode: PollMode) -> bool {
+        let inner = self.lock();
+        match inner.sources.get(&source) {
+            Some(registered) => {
+                registered.interest == *interest
+                    && registered.mode == mode
+                    && registered.write_key.is_none()
+                    && (!is_oneshot(mode) || inner.armed.get(&interest.key) == Some(&source))
+            }
+            None => false,
+        }
+    }
+
+    /// Record that `events` were delivered, which disables the oneshot sources they belong to.
+    pub(crate) fn disarm(&self, events: &[Event]) {
+        let mut inner = self.lock();
+        if inner.armed.is_empty() {
+            return;
+        }
+        for ev in events {
+            inner.armed.remove(&ev.key);
+        }
+    }
+
+    /// The sources that are deleted on a hangup and reported one in `events`.
+    pub(crate) fn hung_up(&self, events: &[Event]) -> Vec<RawSource> {
+        let inner = self.lock();
+        if inner.hangup.is_empty() {
+            return Vec::new();
+        }
+        let mut sources = Vec::new();
+        for ev in events.iter().filter(|ev| ev.is_interrupt()) {
+            if let Some(&source) = inner.hangup.get(&ev.key) {
+                if !sources.contains(&source) {
+                    sources.push(source);
+                }
+            }
+        }
+        sources
+    }
+
+    /// Get the registration of `source`.
+    pub(crate) fn get(&self, source: RawSource) -> Option<RegisteredSource> {
+        self.lock().sources.get(&source).copied()
+    }
+
+    /// Get a snapshot of all registrations.
+    pub(crate) fn snapshot(&self) -> Vec<RegisteredSource> {
+        self.lock().sources.values().copied().collect()
+    }
+
+    /// Number of registered sources.
+    pub(crate) fn len(&self) -> usize {
+        self.lock().sources.len()
+    }
+}
+
+impl Drop for Registry {
+    fn drop(&mut self) {
+        let registered = self.len();
+        if self.misuse_checks && registered > 0 {
+            crate::report_misuse(format_args!(
+                "the poller was dropped with {} registered sources",
+                registered
+            ));
+        }
+    }
+}
+
+/// Whether the operating system disables a registration in `mode` once an event is delivered.
+fn is_oneshot(mode: PollMode) -> bool {
+    matches!(mode, PollMode::Oneshot | PollMode::EdgeOneshot)
+}
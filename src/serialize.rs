@@ -0,0 +1,83 @@
+//! `serde` support for events and interest.
+//!
+//! Events are serialized with their key, the flags of their [`Event::interest()`], their low
+//! watermarks and whether they delete the source on a hangup, rather than the system-specific
+//! flags they carry. Deserializing an event sets its interest with [`Event::from_interest()`],
+//! so flags that aren't supported by the current platform are dropped. Interest is serialized
+//! as a list of flag names, such as `["READABLE", "HANGUP"]`.
+
+use std::fmt;
+use std::num::NonZeroUsize;
+
+use ::serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use ::serde::ser::{Serialize, Serializer};
+
+use crate::{Event, Interest};
+
+/// The portable representation of an event.
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+#[serde(rename = "Event")]
+struct EventRecord {
+    key: usize,
+    interest: Interest,
+    read_low_watermark: Option<NonZeroUsize>,
+    write_low_watermark: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    delete_on_hangup: bool,
+}
+
+impl Serialize for Event {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        EventRecord {
+            key: self.key,
+            interest: self.interest(),
+            read_low_watermark: self.read_low_watermark,
+            write_low_watermark: self.write_low_watermark,
+            delete_on_hangup: self.delete_on_hangup,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Event {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let record = EventRecord::deserialize(deserializer)?;
+        let mut event = Event::from_interest(record.key, record.interest);
+        event.read_low_watermark = record.read_low_watermark;
+        event.write_low_watermark = record.write_low_watermark;
+        event.delete_on_hangup = record.delete_on_hangup;
+        Ok(event)
+    }
+}
+
+impl Serialize for Interest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.names())
+    }
+}
+
+impl<'de> Deserialize<'de> for Interest {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct InterestVisitor;
+
+        impl<'de> Visitor<'de> for InterestVisitor {
+            type Value = Interest;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a list of interest flags")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Interest, A::Error> {
+                let mut interest = Interest::NONE;
+                while let Some(name) = seq.next_element::<String>()? {
+                    interest |= Interest::from_name(&name).ok_or_else(|| {
+                        de::Error::custom(format_args!("unknown interest flag `{}`", name))
+                    })?;
+                }
+                Ok(interest)
+            }
+        }
+
+        deserializer.deserialize_seq(InterestVisitor)
+    }
+}
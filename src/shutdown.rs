@@ -0,0 +1,221 @@
+//! A readable source for the signals that ask the process to terminate.
+
+use std::fmt;
+use std::io;
+use std::sync::{Mutex, OnceLock};
+
+use crate::WakePipe;
+
+/// The channel that the signal handlers wake up.
+static PIPE: OnceLock<WakePipe> = OnceLock::new();
+
+/// Whether the signal handlers were installed.
+static INSTALLED: Mutex<bool> = Mutex::new(false);
+
+/// A readable source that becomes readable when the process is asked to terminate.
+///
+/// Creating the first `ShutdownSignal` installs handlers for `SIGINT` and `SIGTERM` on Unix
+/// platforms, or a console control handler for Ctrl-C, Ctrl-Break, closing the console and
+/// shutting down the system on Windows. These replace the default behavior of terminating the
+/// process right away, so that the program can shut down gracefully once the source is
+/// readable: it is registered with a [`Poller`] like any other source, and delivers an event
+/// with its key. It stays readable until [`reset()`][`ShutdownSignal::reset()`] is called.
+///
+/// All `ShutdownSignal`s share the same channel, and the handlers stay installed for the rest of
+/// the life of the process. Handlers of the same signals that were installed before are
+/// replaced. On Windows, the system still terminates the process shortly after a console is
+/// closed or the system shuts down.
+///
+/// [`Poller`]: crate::Poller
+///
+/// # Examples
+///
+/// ```no_run
+/// use polling::{Event, Events, Poller, ShutdownSignal};
+///
+/// let poller = Poller::new()?;
+/// let signal = ShutdownSignal::new()?;
+/// unsafe {
+///     poller.add(&signal, Event::readable(0))?;
+/// }
+///
+/// let mut events = Events::new();
+/// loop {
+///     poller.wait(&mut events, None)?;
+///     if events.iter().any(|ev| ev.key == 0) {
+///         println!("shutting down");
+///         break;
+///     }
+///     events.clear();
+/// }
+/// poller.delete(&signal)?;
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Clone, Copy)]
+pub struct ShutdownSignal {
+    pipe: &'static WakePipe,
+}
+
+impl ShutdownSignal {
+    /// Install the signal handlers if necessary, and return the source they make readable.
+    pub fn new() -> io::Result<ShutdownSignal> {
+        let mut installed = INSTALLED.lock().unwrap_or_else(|e| e.into_inner());
+        if !*installed {
+            if PIPE.get().is_none() {
+                PIPE.set(WakePipe::new()?).ok();
+            }
+            sys::install()?;
+            *installed = true;
+        }
+
+        Ok(ShutdownSignal {
+            pipe: PIPE.get().unwrap(),
+        })
+    }
+
+    /// Consume the signals received so far, so that the source is no longer readable.
+    pub fn reset(&self) -> io::Result<()> {
+        self.pipe.reset()
+    }
+}
+
+impl fmt::Debug for ShutdownSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShutdownSignal")
+            .field("pipe", self.pipe)
+            .finish()
+    }
+}
+
+/// Wake up the channel from a signal handler.
+fn wake() {
+    if let Some(pipe) = PIPE.get() {
+        // There is nothing to report a failure to. A full channel is readable anyway.
+        pipe.wake().ok();
+    }
+}
+
+#[cfg(unix)]
+mod unix_impl {
+    use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+
+    use super::ShutdownSignal;
+
+    impl AsRawFd for ShutdownSignal {
+        fn as_raw_fd(&self) -> RawFd {
+            self.pipe.as_raw_fd()
+        }
+    }
+
+    impl AsFd for ShutdownSignal {
+        fn as_fd(&self) -> BorrowedFd<'_> {
+            self.pipe.as_fd()
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows_impl {
+    use std::os::windows::io::{AsRawSocket, AsSocket, BorrowedSocket, RawSocket};
+
+    use super::ShutdownSignal;
+
+    impl AsRawSocket for ShutdownSignal {
+        fn as_raw_socket(&self) -> RawSocket {
+            self.pipe.as_raw_socket()
+        }
+    }
+
+    impl AsSocket for ShutdownSignal {
+        fn as_socket(&self) -> BorrowedSocket<'_> {
+            self.pipe.as_socket()
+        }
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::io;
+    use std::mem;
+    use std::os::raw::c_int;
+
+    /// Install the handlers of `SIGINT` and `SIGTERM`.
+    pub(super) fn install() -> io::Result<()> {
+        for signal in [libc::SIGINT, libc::SIGTERM] {
+            // SAFETY: The handler only wakes up the channel, which is async-signal-safe: it
+            // swaps an atomic flag and writes to a file descriptor.
+            unsafe {
+                let mut action: libc::sigaction = mem::zeroed();
+                action.sa_sigaction = handle as extern "C" fn(c_int) as libc::sighandler_t;
+                action.sa_flags = libc::SA_RESTART;
+                libc::sigemptyset(&mut action.sa_mask);
+                if libc::sigaction(signal, &action, std::ptr::null_mut()) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    extern "C" fn handle(_signal: c_int) {
+        // Waking up may change `errno`, which the interrupted code may be about to read.
+        let errno = errno_location();
+        // SAFETY: `errno` is thread-local, so it can't be changed concurrently.
+        let saved = unsafe { *errno };
+        super::wake();
+        unsafe { *errno = saved };
+    }
+
+    /// The location of `errno` of the current thread.
+    fn errno_location() -> *mut c_int {
+        cfg_if::cfg_if! {
+            if #[cfg(any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "redox",
+                target_os = "hurd",
+                target_os = "emscripten",
+                target_os = "fuchsia",
+            ))] {
+                // SAFETY: This only returns a pointer.
+                unsafe { libc::__errno_location() }
+            } else if #[cfg(any(target_vendor = "apple", target_os = "freebsd"))] {
+                // SAFETY: This only returns a pointer.
+                unsafe { libc::__error() }
+            } else if #[cfg(any(target_os = "illumos", target_os = "solaris"))] {
+                // SAFETY: This only returns a pointer.
+                unsafe { libc::___errno() }
+            } else if #[cfg(target_os = "haiku")] {
+                // SAFETY: This only returns a pointer.
+                unsafe { libc::_errnop() }
+            } else {
+                // SAFETY: This only returns a pointer.
+                unsafe { libc::__errno() }
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::io;
+
+    use windows_sys::core::BOOL;
+    use windows_sys::Win32::System::Console::SetConsoleCtrlHandler;
+
+    /// Install the console control handler.
+    pub(super) fn install() -> io::Result<()> {
+        // SAFETY: The handler has the signature of a console control handler.
+        if unsafe { SetConsoleCtrlHandler(Some(handle), 1) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    unsafe extern "system" fn handle(_ctrl_type: u32) -> BOOL {
+        super::wake();
+
+        // The event is handled, so that the next handler doesn't terminate the process.
+        1
+    }
+}
@@ -0,0 +1,57 @@
+//! Busy-polling that adapts to how soon events arrive.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// The smallest budget, as a fraction of the largest one.
+///
+/// The budget never drops to zero, so that the poller keeps noticing when events start arriving
+/// quickly again.
+const MIN_FRACTION: u32 = 64;
+
+/// The busy-polling budget of a poller.
+#[derive(Debug)]
+pub(crate) struct AdaptiveSpin {
+    /// The largest budget.
+    pub(crate) max: Duration,
+
+    /// The current budget, in nanoseconds.
+    budget: AtomicU64,
+}
+
+impl AdaptiveSpin {
+    /// Create a budget of at most `max`, which starts out at the largest.
+    pub(crate) fn new(max: Duration) -> AdaptiveSpin {
+        AdaptiveSpin {
+            max,
+            budget: AtomicU64::new(nanos(max)),
+        }
+    }
+
+    /// How long the next wait busy-polls before blocking.
+    pub(crate) fn budget(&self) -> Duration {
+        Duration::from_nanos(self.budget.load(Ordering::Relaxed))
+    }
+
+    /// Events arrived while busy-polling, so spin for longer next time.
+    pub(crate) fn hit(&self) {
+        let max = nanos(self.max);
+        self.update(|budget| budget.saturating_mul(2).min(max));
+    }
+
+    /// Nothing arrived while busy-polling and the wait blocked, so spin for less next time.
+    pub(crate) fn miss(&self) {
+        let min = nanos(self.max / MIN_FRACTION).max(1);
+        self.update(|budget| (budget / 2).max(min));
+    }
+
+    fn update(&self, f: impl Fn(u64) -> u64) {
+        // Concurrent waits may race here, and either of their updates is fine.
+        let budget = self.budget.load(Ordering::Relaxed);
+        self.budget.store(f(budget), Ordering::Relaxed);
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
@@ -0,0 +1,208 @@
+//! Sources registered with separate keys for their read and write interest.
+//!
+//! `kqueue` has separate filters for reading and writing, so each half is registered under its
+//! own key. The other backends report both directions of a source under one key, so the source
+//! is registered under the key of the read half, and its events are split up after every wait.
+
+use std::io;
+
+#[cfg(target_os = "hermit")]
+use std::os::hermit::io::BorrowedFd as BorrowedSource;
+#[cfg(unix)]
+use std::os::unix::io::BorrowedFd as BorrowedSource;
+#[cfg(windows)]
+use std::os::windows::io::BorrowedSocket as BorrowedSource;
+
+use crate::custom::Driver;
+use crate::{Event, PollMode, RawSource};
+
+/// The interest in a split source as a single event, under the key of the read half.
+pub(crate) fn combine(read: Event, write: Event) -> Event {
+    Event {
+        writable: write.writable,
+        write_low_watermark: write.write_low_watermark,
+        ..read
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(
+        not(polling_test_poll_backend),
+        any(
+            target_vendor = "apple",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd",
+            target_os = "dragonfly",
+        ),
+    ))] {
+        /// The split sources of a poller, which need no bookkeeping with `kqueue`.
+        #[derive(Debug, Default)]
+        pub(crate) struct Splits {}
+
+        impl Splits {
+            /// Set the halves of a registered source.
+            pub(crate) fn modify(
+                &self,
+                poller: &Driver,
+                source: BorrowedSource<'_>,
+                read: Event,
+                write: Event,
+                mode: PollMode,
+            ) -> io::Result<()> {
+                poller.native()?.modify_split(source, read, write, mode)
+            }
+
+            /// Forget about the halves of `source`.
+            pub(crate) fn remove(&self, _source: RawSource) {}
+
+            /// Split up the events starting at `from`.
+            pub(crate) fn split_events(
+                &self,
+                _poller: &Driver,
+                _list: &mut Vec<Event>,
+                _from: usize,
+            ) {
+            }
+        }
+    } else {
+        use std::collections::HashMap;
+        use std::sync::{Mutex, MutexGuard};
+
+        use crate::AsRawSource;
+
+        /// The split sources of a poller.
+        #[derive(Debug, Default)]
+        pub(crate) struct Splits {
+            inner: Mutex<Inner>,
+        }
+
+        #[derive(Debug, Default)]
+        struct Inner {
+            /// The halves of each split source, by the key it is registered under.
+            halves: HashMap<usize, Halves>,
+
+            /// The key each split source is registered under.
+            keys: HashMap<RawSource, usize>,
+        }
+
+        /// The halves of a split source.
+        #[derive(Debug)]
+        struct Halves {
+            source: RawSource,
+
+            /// The read half. In oneshot mode, readability is cleared once it fires.
+            read: Event,
+
+            /// The write half. In oneshot mode, writability is cleared once it fires.
+            write: Event,
+
+            mode: PollMode,
+        }
+
+        impl Splits {
+            /// Lock the table, ignoring poisoning.
+            fn lock(&self) -> MutexGuard<'_, Inner> {
+                self.inner.lock().unwrap_or_else(|e| e.into_inner())
+            }
+
+            /// Set the halves of a registered source.
+            pub(crate) fn modify(
+                &self,
+                poller: &Driver,
+                source: BorrowedSource<'_>,
+                read: Event,
+                write: Event,
+                mode: PollMode,
+            ) -> io::Result<()> {
+                let mut inner = self.lock();
+                poller.modify(source, combine(read, write), mode)?;
+
+                let raw = (&source).raw();
+                if let Some(key) = inner.keys.insert(raw, read.key) {
+                    inner.halves.remove(&key);
+                }
+                inner.halves.insert(
+                    read.key,
+                    Halves {
+                        source: raw,
+                        read,
+                        write,
+                        mode,
+                    },
+                );
+                Ok(())
+            }
+
+            /// Forget about the halves of `source`.
+            pub(crate) fn remove(&self, source: RawSource) {
+                let mut inner = self.lock();
+                if let Some(key) = inner.keys.remove(&source) {
+                    inner.halves.remove(&key);
+                }
+            }
+
+            /// Split up the events starting at `from`.
+            ///
+            /// In oneshot mode, the backend disables the whole source when either half fires, so
+            /// the half that didn't fire is enabled again.
+            pub(crate) fn split_events(
+                &self,
+                poller: &Driver,
+                list: &mut Vec<Event>,
+                from: usize,
+            ) {
+                let mut inner = self.lock();
+                if inner.halves.is_empty() || list.len() <= from {
+                    return;
+                }
+
+                for ev in list.split_off(from) {
+                    let halves = match inner.halves.get_mut(&ev.key) {
+                        Some(halves) => halves,
+                        None => {
+                            list.push(ev);
+                            continue;
+                        }
+                    };
+
+                    // Events without a direction, such as hangups, are delivered to both halves.
+                    let neither = !ev.readable && !ev.writable;
+                    let read = halves.read.readable && (ev.readable || neither);
+                    let write = halves.write.writable && (ev.writable || neither);
+                    if read {
+                        list.push(Event {
+                            key: halves.read.key,
+                            writable: false,
+                            ..ev
+                        });
+                    }
+                    if write {
+                        list.push(Event {
+                            key: halves.write.key,
+                            readable: false,
+                            ..ev
+                        });
+                    }
+
+                    if matches!(halves.mode, PollMode::Oneshot | PollMode::EdgeOneshot) {
+                        halves.read.readable &= !read;
+                        halves.write.writable &= !write;
+                        if halves.read.readable || halves.write.writable {
+                            // SAFETY: Registered sources must stay open until they are deleted.
+                            let source = unsafe { BorrowedSource::borrow_raw(halves.source) };
+                            if let Err(_err) = poller.modify(
+                                source,
+                                combine(halves.read, halves.write),
+                                halves.mode,
+                            ) {
+                                #[cfg(feature = "tracing")]
+                                tracing::warn!("failed to re-enable a split source: {}", _err);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Detection of waits that block much longer than they should.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// A wait that blocked much longer than it should have.
+///
+/// Stalls are detected with the `stall-diagnostics` feature, logged with the `tracing` feature,
+/// and passed to [`Hooks::on_stall()`]. They point at lost wakeups, either in the poller or in
+/// the code that uses it, such as a key that is re-armed too late, and at threads that are
+/// starved or suspended. See [`PollerBuilder::stall_threshold()`] for how they are detected.
+///
+/// [`Hooks::on_stall()`]: crate::Hooks::on_stall
+/// [`PollerBuilder::stall_threshold()`]: crate::PollerBuilder::stall_threshold
+#[cfg_attr(docsrs, doc(cfg(feature = "stall-diagnostics")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Stall {
+    /// A wait returned `late` after its deadline.
+    Overslept {
+        /// How long after the deadline the wait returned.
+        late: Duration,
+    },
+
+    /// A notification took `delay` to wake up a wait, or hasn't woken up the waiting thread
+    /// after `delay` when the poller is notified again.
+    LostWakeup {
+        /// How long the notification was pending while a thread was waiting.
+        delay: Duration,
+    },
+}
+
+/// The timestamps that stalls are detected from.
+#[derive(Debug)]
+pub(crate) struct Monitor {
+    threshold: Duration,
+
+    /// The instant that the timestamps are relative to.
+    base: Instant,
+
+    /// When the pending notification was sent, or zero if there is none.
+    notified_at: AtomicU64,
+
+    /// When the current wait started, or zero if no thread is waiting.
+    waiting_since: AtomicU64,
+}
+
+impl Monitor {
+    /// Detect stalls longer than `threshold`.
+    pub(crate) fn new(threshold: Duration) -> Monitor {
+        Monitor {
+            threshold,
+            base: Instant::now(),
+            notified_at: AtomicU64::new(0),
+            waiting_since: AtomicU64::new(0),
+        }
+    }
+
+    /// The threshold that longer stalls are reported above, to set up the poller again after
+    /// `fork()`.
+    #[cfg(unix)]
+    pub(crate) fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    /// The timestamp of `instant`, which is never zero.
+    fn stamp(&self, instant: Instant) -> u64 {
+        let nanos = instant.saturating_duration_since(self.base).as_nanos();
+        u64::try_from(nanos).unwrap_or(u64::MAX - 1) + 1
+    }
+
+    /// The instant of the timestamp in `stamp`, if it is set.
+    fn instant(&self, stamp: &AtomicU64) -> Option<Instant> {
+        match stamp.load(Ordering::SeqCst) {
+            0 => None,
+            nanos => self.base.checked_add(Duration::from_nanos(nanos - 1)),
+        }
+    }
+
+    /// Record that a wait starts now.
+    pub(crate) fn wait_started(&self) {
+        self.waiting_since
+            .store(self.stamp(Instant::now()), Ordering::SeqCst);
+    }
+
+    /// Record that the current wait returned, and check whether it stalled.
+    ///
+    /// `slack` is how much later than `deadline` the wait was allowed to wake up.
+    pub(crate) fn wait_finished(
+        &self,
+        deadline: Option<Instant>,
+        slack: Option<Duration>,
+        notified: bool,
+    ) -> Option<Stall> {
+        let now = Instant::now();
+        let started = self.instant(&self.waiting_since);
+        let notified_at = self.instant(&self.notified_at);
+        self.waiting_since.store(0, Ordering::SeqCst);
+        self.notified_at.store(0, Ordering::SeqCst);
+        let started = started?;
+
+        // A notification sent before the wait started only counts from then on.
+        if let Some(notified_at) = notified_at.filter(|_| notified) {
+            let delay = now.saturating_duration_since(notified_at.max(started));
+            if delay > self.threshold {
+                return Some(Stall::LostWakeup { delay });
+            }
+        }
+
+        // A deadline that passed before the wait started only counts from then on as well.
+        let late = now.saturating_duration_since(deadline?.max(started));
+        if late > self.threshold + slack.unwrap_or_default() {
+            return Some(Stall::Overslept { late });
+        }
+        None
+    }
+
+    /// Record that the poller was notified.
+    pub(crate) fn notified(&self) {
+        self.notified_at
+            .store(self.stamp(Instant::now()), Ordering::SeqCst);
+    }
+
+    /// Check whether the notification that is still pending should have woken up the waiting
+    /// thread by now.
+    pub(crate) fn notified_again(&self) -> Option<Stall> {
+        let notified_at = self.instant(&self.notified_at)?;
+        let waiting_since = self.instant(&self.waiting_since)?;
+        let delay = notified_at.max(waiting_since).elapsed();
+        (delay > self.threshold).then_some(Stall::LostWakeup { delay })
+    }
+}
@@ -0,0 +1,78 @@
+//! Runtime statistics of a `Poller`.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A snapshot of the runtime statistics of a [`Poller`].
+///
+/// This is returned by [`Poller::stats()`]. All counters start at zero when the poller is created
+/// and wrap around on overflow.
+///
+/// [`Poller`]: crate::Poller
+/// [`Poller::stats()`]: crate::Poller::stats
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Stats {
+    /// Number of calls to `wait()` that waited on the operating system.
+    ///
+    /// Calls that return immediately because another thread is already waiting are not counted.
+    pub waits: usize,
+
+    /// Number of events delivered by `wait()`.
+    pub events: usize,
+
+    /// Number of notifications sent to the operating system by `notify()`.
+    ///
+    /// Calls to `notify()` while a notification is already pending are not counted.
+    pub notifications: usize,
+
+    /// Number of calls to `wait()` that returned without events, without a notification and
+    /// before the timeout elapsed.
+    pub spurious_wakeups: usize,
+
+    /// Number of times the interest in an already registered source was changed with
+    /// `modify()`.
+    pub modifications: usize,
+}
+
+/// Counters backing [`Stats`].
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    waits: AtomicUsize,
+    events: AtomicUsize,
+    notifications: AtomicUsize,
+    spurious_wakeups: AtomicUsize,
+    modifications: AtomicUsize,
+}
+
+impl Counters {
+    /// Record a finished wait that delivered `events` events.
+    pub(crate) fn wait(&self, events: usize, spurious: bool) {
+        self.waits.fetch_add(1, Ordering::Relaxed);
+        self.events.fetch_add(events, Ordering::Relaxed);
+        if spurious {
+            self.spurious_wakeups.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Record a notification.
+    pub(crate) fn notification(&self) {
+        self.notifications.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a modification.
+    pub(crate) fn modification(&self) {
+        self.modifications.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Take a snapshot of the counters.
+    pub(crate) fn snapshot(&self) -> Stats {
+        Stats {
+            waits: self.waits.load(Ordering::Relaxed),
+            events: self.events.load(Ordering::Relaxed),
+            notifications: self.notifications.load(Ordering::Relaxed),
+            spurious_wakeups: self.spurious_wakeups.load(Ordering::Relaxed),
+            modifications: self.modifications.load(Ordering::Relaxed),
+        }
+    }
+}
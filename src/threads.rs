@@ -0,0 +1,233 @@
+//! Running the event loop of a `Poller` on worker threads.
+
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use crate::{Event, Events, Poller};
+
+/// A handler of the events delivered to worker threads.
+type Handler = dyn Fn(&Poller, Event) + Send + Sync;
+
+/// Worker threads that wait on a [`Poller`] and handle its events.
+///
+/// This is returned by [`Poller::run_on_threads()`]. The threads run until
+/// [`stop()`][`LoopThreads::stop()`] is called, the handle is dropped, or a wait fails.
+pub struct LoopThreads {
+    shared: Arc<Shared>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+/// The state shared by the worker threads.
+struct Shared {
+    poller: Arc<Poller>,
+    handler: Box<Handler>,
+
+    /// Whether the threads were asked to stop.
+    stopped: AtomicBool,
+
+    /// The number of threads that didn't exit yet.
+    running: AtomicUsize,
+
+    /// Taken around each wait, unless the poller allows concurrent waits.
+    wait_lock: Option<Mutex<()>>,
+
+    /// The first error returned by a wait.
+    error: Mutex<Option<io::Error>>,
+}
+
+impl Poller {
+    /// Run the event loop of this poller on `threads` worker threads.
+    ///
+    /// Each thread waits for events and calls `handler` with every event it receives. Without
+    /// [concurrent waits][`crate::PollerBuilder::concurrent_waits()`], only one thread waits at a
+    /// time, and the next one takes over while the events are being handled. Otherwise all
+    /// threads wait at the same time, and each event is delivered to only one of them. Either
+    /// way an event of a oneshot source is handled once until its interest is re-enabled, while
+    /// sources in level-triggered or edge-triggered mode may be handled by several threads at
+    /// once.
+    ///
+    /// The threads are stopped with a [notification][`Poller::notify()`]. Notifications sent to
+    /// the poller while the threads run may cut a wait short, but are not passed to `handler`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `threads` is zero, or if a thread can't be spawned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polling::{Event, Poller};
+    /// use std::net::UdpSocket;
+    /// use std::sync::{mpsc, Arc};
+    ///
+    /// let poller = Arc::new(Poller::new()?);
+    /// let socket = UdpSocket::bind("127.0.0.1:0")?;
+    /// unsafe {
+    ///     poller.add(&socket, Event::writable(1))?;
+    /// }
+    ///
+    /// let (sender, receiver) = mpsc::channel();
+    /// let sender = std::sync::Mutex::new(sender);
+    /// let threads = poller.run_on_threads(2, move |_, event| {
+    ///     sender.lock().unwrap().send(event.key).unwrap();
+    /// })?;
+    ///
+    /// assert_eq!(receiver.recv().unwrap(), 1);
+    /// threads.stop()?;
+    /// poller.delete(&socket)?;
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub fn run_on_threads(
+        self: &Arc<Self>,
+        threads: usize,
+        handler: impl Fn(&Poller, Event) + Send + Sync + 'static,
+    ) -> io::Result<LoopThreads> {
+        if threads == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the event loop needs at least one thread",
+            ));
+        }
+
+        let shared = Arc::new(Shared {
+            poller: self.clone(),
+            handler: Box::new(handler),
+            stopped: AtomicBool::new(false),
+            running: AtomicUsize::new(threads),
+            wait_lock: (!self.concurrent_waits()).then(Mutex::default),
+            error: Mutex::new(None),
+        });
+
+        let mut handle = LoopThreads {
+            shared: shared.clone(),
+            threads: Vec::with_capacity(threads),
+        };
+        for i in 0..threads {
+            let worker = shared.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("polling-{}", i))
+                .spawn(move || worker.run());
+            match spawned {
+                Ok(thread) => handle.threads.push(thread),
+                Err(err) => {
+                    // The threads that weren't spawned don't run.
+                    shared.running.fetch_sub(threads - i, Ordering::SeqCst);
+                    return Err(err);
+                }
+            }
+        }
+        Ok(handle)
+    }
+}
+
+impl LoopThreads {
+    /// Stop the worker threads and wait until they exit.
+    ///
+    /// Returns the error of the wait that stopped the threads, if any. If the handler panicked
+    /// on one of the threads, the panic is propagated.
+    pub fn stop(mut self) -> io::Result<()> {
+        self.shutdown();
+        for thread in self.threads.drain(..) {
+            if let Err(panic) = thread.join() {
+                std::panic::resume_unwind(panic);
+            }
+        }
+        match self.shared.error().take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns whether the worker threads stopped or were asked to stop.
+    pub fn is_stopped(&self) -> bool {
+        self.shared.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Ask the worker threads to stop.
+    fn shutdown(&self) {
+        if !self.shared.stopped.swap(true, Ordering::SeqCst) {
+            self.shared.wake_one();
+        }
+    }
+}
+
+impl Drop for LoopThreads {
+    fn drop(&mut self) {
+        self.shutdown();
+        for thread in self.threads.drain(..) {
+            thread.join().ok();
+        }
+    }
+}
+
+impl fmt::Debug for LoopThreads {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoopThreads")
+            .field("poller", &self.shared.poller)
+            .field("threads", &self.threads.len())
+            .field("stopped", &self.is_stopped())
+            .finish()
+    }
+}
+
+impl Shared {
+    /// The loop of a worker thread.
+    fn run(&self) {
+        // Stop the other threads if this one exits early, even by a panic.
+        let _exit = Exit(self);
+
+        let mut events = Events::new();
+        while !self.stopped.load(Ordering::SeqCst) {
+            let res = {
+                let _lock = self.wait_lock.as_ref().map(|lock| lock_ignore_poison(lock));
+
+                // The threads may have been stopped while this one waited for its turn.
+                if self.stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                self.poller.wait(&mut events, None)
+            };
+
+            if let Err(err) = res {
+                self.error().get_or_insert(err);
+                break;
+            }
+            for event in events.iter() {
+                (self.handler)(&self.poller, event);
+            }
+            events.clear();
+        }
+    }
+
+    /// Wake up one of the threads that are still running.
+    fn wake_one(&self) {
+        if let Err(_err) = self.poller.notify() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(err = %_err, "run_on_threads: failed to notify the poller");
+        }
+    }
+
+    fn error(&self) -> MutexGuard<'_, Option<io::Error>> {
+        lock_ignore_poison(&self.error)
+    }
+}
+
+/// Marks a worker thread as exited when dropped.
+struct Exit<'a>(&'a Shared);
+
+impl Drop for Exit<'_> {
+    fn drop(&mut self) {
+        // Every exiting thread wakes up the next one, until all of them exited.
+        self.0.stopped.store(true, Ordering::SeqCst);
+        if self.0.running.fetch_sub(1, Ordering::SeqCst) > 1 {
+            self.0.wake_one();
+        }
+    }
+}
+
+fn lock_ignore_poison<T>(lock: &Mutex<T>) -> MutexGuard<'_, T> {
+    lock.lock().unwrap_or_else(|e| e.into_inner())
+}
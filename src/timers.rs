@@ -0,0 +1,135 @@
+//! Timers that deliver events to a `Poller`.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::{Clock, Event};
+
+/// The pending timers of a poller.
+///
+/// The timers are ordered by their deadline, so that the wait only has to sleep until the
+/// earliest one, no matter how many are pending. Deadlines are kept as readings of the clock
+/// of the poller, so that with `CLOCK_BOOTTIME` the time spent suspended counts towards them.
+#[derive(Debug)]
+pub(crate) struct Timers {
+    clock: Clock,
+
+    /// The instant that readings of the monotonic clock are relative to.
+    base: Instant,
+
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// The deadline and key of every timer, in the order they expire.
+    queue: BTreeSet<(Duration, usize)>,
+
+    /// The deadline of the timer of each key.
+    deadlines: HashMap<usize, Duration>,
+}
+
+impl Timers {
+    /// Create the timers of a poller that measures time with `clock`.
+    pub(crate) fn new(clock: Clock) -> Timers {
+        Timers {
+            clock,
+            base: Instant::now(),
+            inner: Mutex::default(),
+        }
+    }
+
+    /// The current reading of the clock.
+    fn now(&self) -> Duration {
+        match self.clock {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Clock::Boottime => {
+                use rustix::time::{clock_gettime, ClockId};
+
+                let now = clock_gettime(ClockId::Boottime);
+                Duration::new(now.tv_sec as u64, now.tv_nsec as u32)
+            }
+            _ => self.base.elapsed(),
+        }
+    }
+
+    /// The reading of the clock at `deadline`.
+    ///
+    /// With a clock other than the monotonic one, the time left until the deadline is counted
+    /// from now.
+    fn reading(&self, deadline: Instant) -> Duration {
+        if self.clock == Clock::Monotonic {
+            return deadline.saturating_duration_since(self.base);
+        }
+
+        let now = Instant::now();
+        match deadline.checked_duration_since(now) {
+            Some(left) => self.now().saturating_add(left),
+            None => self.now().saturating_sub(now - deadline),
+        }
+    }
+
+    /// The instant at which the clock shows `reading`.
+    fn instant(&self, reading: Duration) -> Option<Instant> {
+        if self.clock == Clock::Monotonic {
+            return self.base.checked_add(reading);
+        }
+
+        Instant::now().checked_add(reading.saturating_sub(self.now()))
+    }
+
+    /// Lock the timers, ignoring poisoning.
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Set the timer of `key`, replacing its previous deadline.
+    ///
+    /// Returns `true` if this is now the earliest timer.
+    pub(crate) fn insert(&self, key: usize, deadline: Instant) -> bool {
+        let deadline = self.reading(deadline);
+        let mut inner = self.lock();
+        if let Some(old) = inner.deadlines.insert(key, deadline) {
+            inner.queue.remove(&(old, key));
+        }
+        inner.queue.insert((deadline, key));
+        inner.queue.first() == Some(&(deadline, key))
+    }
+
+    /// Cancel the timer of `key`.
+    ///
+    /// Returns `false` if `key` has no pending timer.
+    pub(crate) fn remove(&self, key: usize) -> bool {
+        let mut inner = self.lock();
+        match inner.deadlines.remove(&key) {
+            Some(deadline) => inner.queue.remove(&(deadline, key)),
+            None => false,
+        }
+    }
+
+    /// The number of pending timers.
+    pub(crate) fn len(&self) -> usize {
+        self.lock().deadlines.len()
+    }
+
+    /// The deadline of the earliest timer.
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        let deadline = self.lock().queue.first().map(|&(deadline, _)| deadline)?;
+        self.instant(deadline)
+    }
+
+    /// Move the timers that expired by now into `list`, as the events created by `event`.
+    pub(crate) fn expire_into(&self, list: &mut Vec<Event>, event: fn(usize) -> Event) {
+        let now = self.now();
+        let mut inner = self.lock();
+        while let Some(&(deadline, key)) = inner.queue.first() {
+            if deadline > now {
+                break;
+            }
+            inner.queue.pop_first();
+            inner.deadlines.remove(&key);
+            list.push(event(key));
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Waiting on a poller from async code.
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+
+use crate::{CancelToken, Events, Poller, WaitResult};
+
+/// A wait on a poller that completes once the poller has events, see `Poller::wait_async()`.
+pub(crate) struct WaitAsync<'a> {
+    poller: &'a Arc<Poller>,
+    events: &'a mut Events,
+
+    /// The thread that blocks on the poller, once the events that were ready are collected.
+    helper: Option<Arc<Helper>>,
+}
+
+/// The state shared with the helper thread.
+#[derive(Default)]
+struct Helper {
+    state: Mutex<State>,
+
+    /// Wakes up the helper thread when the future is dropped.
+    cancel: CancelToken,
+}
+
+#[derive(Default)]
+struct State {
+    /// The events collected by the helper thread.
+    result: Option<io::Result<(Events, WaitResult)>>,
+
+    /// The task to wake up once the result is there.
+    waker: Option<Waker>,
+}
+
+impl Helper {
+    /// Lock the state, ignoring poisoning.
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<'a> WaitAsync<'a> {
+    pub(crate) fn new(poller: &'a Arc<Poller>, events: &'a mut Events) -> WaitAsync<'a> {
+        WaitAsync {
+            poller,
+            events,
+            helper: None,
+        }
+    }
+
+    /// Start a thread that blocks on the poller until it has events.
+    fn spawn(&self, waker: Waker) -> io::Result<Arc<Helper>> {
+        let helper = Arc::new(Helper::default());
+        helper.lock().waker = Some(waker);
+
+        let poller = self.poller.clone();
+        let shared = helper.clone();
+        thread::Builder::new()
+            .name("polling-wait".into())
+            .spawn(move || {
+                let mut events = poller.new_events();
+                let res = poller.wait_cancelable(&mut events, None, &shared.cancel);
+
+                let mut state = shared.lock();
+                if shared.cancel.is_cancelled() {
+                    // The future is gone, so leave what was collected for the next wait.
+                    if let Ok(result) = res {
+                        give_back(&poller, events, result);
+                    }
+                    return;
+                }
+                state.result = Some(res.map(|result| (events, result)));
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            })?;
+        Ok(helper)
+    }
+}
+
+impl Future for WaitAsync<'_> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let helper = match &this.helper {
+            Some(helper) => helper.clone(),
+            None => {
+                // Deliver the events that are ready without starting a thread.
+                let result = this.poller.wait_result(this.events, Some(Duration::ZERO))?;
+                if result.events() > 0 || result.is_notified() {
+                    return Poll::Ready(Ok(result.events()));
+                }
+                this.helper = Some(this.spawn(cx.waker().clone())?);
+                return Poll::Pending;
+            }
+        };
+
+        let mut state = helper.lock();
+        match state.result.take() {
+            Some(Ok((mut events, result))) => {
+                this.events.list.append(&mut events.list);
+                this.events.filled = events.filled;
+                this.events.returned_at = events.returned_at;
+                drop(state);
+                this.helper = None;
+                Poll::Ready(Ok(result.events()))
+            }
+            Some(Err(err)) => {
+                drop(state);
+                this.helper = None;
+                Poll::Ready(Err(err))
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for WaitAsync<'_> {
+    fn drop(&mut self) {
+        if let Some(helper) = self.helper.take() {
+            let mut state = helper.lock();
+            match state.result.take() {
+                Some(Ok((events, result))) => give_back(self.poller, events, result),
+                Some(Err(_)) => {}
+                None => helper.cancel.cancel(),
+            }
+        }
+    }
+}
+
+/// Leave the events and the notification that a helper thread took from `poller` for its next
+/// wait.
+fn give_back(poller: &Poller, mut events: Events, result: WaitResult) {
+    if !events.list.is_empty() {
+        poller.deferred().append(&mut events.list);
+    }
+    if result.is_notified() {
+        poller.notify().ok();
+    }
+}
@@ -0,0 +1,221 @@
+//! Waiters that can be notified individually.
+
+use std::cell::Cell;
+use std::fmt;
+use std::io;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::{Events, Poller};
+
+/// A thread waiting on a shared [`Poller`] that can be woken up on its own.
+///
+/// With [concurrent waits][`crate::PollerBuilder::concurrent_waits()`], several threads wait on
+/// the same poller, and [`Poller::notify()`] wakes up any one of them. Each thread can instead
+/// wait through its own `Waiter`, which is woken up by its [`WaiterNotifier`]s, for example to
+/// direct a wakeup at the worker thread that owns a queue.
+///
+/// A notification of a waiter makes its current or next [`wait()`][`Waiter::wait()`] return.
+/// The operating system may still wake up another thread first; that thread's wait returns
+/// without events and passes the notification on, until the waiter itself wakes up.
+///
+/// This is created by [`Poller::waiter()`].
+///
+/// # Examples
+///
+/// ```
+/// use polling::{Events, Poller};
+/// use std::sync::Arc;
+///
+/// let poller = Arc::new(Poller::builder().concurrent_waits(true).build()?);
+/// let waiter = poller.waiter();
+/// let notifier = waiter.notifier();
+///
+/// std::thread::spawn(move || notifier.notify().unwrap());
+///
+/// let mut events = Events::new();
+/// waiter.wait(&mut events, None)?;
+/// # std::io::Result::Ok(())
+/// ```
+pub struct Waiter {
+    poller: Arc<Poller>,
+    state: Arc<State>,
+
+    /// Only one thread may wait at a time.
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+/// A handle that wakes up a [`Waiter`].
+///
+/// This is created by [`Waiter::notifier()`].
+#[derive(Clone)]
+pub struct WaiterNotifier {
+    poller: Arc<Poller>,
+    state: Arc<State>,
+}
+
+/// The state shared between a waiter and its notifiers.
+#[derive(Debug, Default)]
+pub(crate) struct State {
+    /// Whether the waiter is blocked in the poller.
+    waiting: AtomicBool,
+
+    /// Whether the waiter was notified and has not returned from a wait since.
+    notified: AtomicBool,
+
+    /// Whether the waiter was dropped.
+    dropped: AtomicBool,
+}
+
+/// The waiters of a poller.
+#[derive(Debug, Default)]
+pub(crate) struct Waiters {
+    list: Mutex<Vec<Arc<State>>>,
+
+    /// The number of waiters that are notified, to skip the list when there are none.
+    notified: AtomicUsize,
+}
+
+impl Waiters {
+    /// Lock the list, ignoring poisoning.
+    fn lock(&self) -> MutexGuard<'_, Vec<Arc<State>>> {
+        self.list.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Notify the poller again if a waiter that is still blocked was notified.
+    ///
+    /// This is called after every wait, in case the notification woke up the wrong thread.
+    pub(crate) fn forward(&self, poller: &Poller) {
+        if self.notified.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+
+        let blocked = self.lock().iter().any(|state| {
+            state.waiting.load(Ordering::SeqCst) && state.notified.load(Ordering::SeqCst)
+        });
+        if blocked {
+            if let Err(_err) = poller.notify() {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(err = %_err, "waiter: failed to pass on a notification");
+            }
+        }
+    }
+
+    /// Consume the notification of `state`, if any.
+    fn take(&self, state: &State) -> bool {
+        let notified = state.notified.swap(false, Ordering::SeqCst);
+        if notified {
+            self.notified.fetch_sub(1, Ordering::SeqCst);
+        }
+        notified
+    }
+}
+
+impl Waiter {
+    /// Create a waiter for `poller`.
+    pub(crate) fn new(poller: Arc<Poller>) -> Waiter {
+        let state = Arc::new(State::default());
+        poller.waiters.lock().push(state.clone());
+        Waiter {
+            poller,
+            state,
+            _not_sync: PhantomData,
+        }
+    }
+
+    /// The poller this waiter blocks on.
+    #[inline]
+    pub fn poller(&self) -> &Arc<Poller> {
+        &self.poller
+    }
+
+    /// Create a [`WaiterNotifier`] that wakes up this waiter.
+    #[inline]
+    pub fn notifier(&self) -> WaiterNotifier {
+        WaiterNotifier {
+            poller: self.poller.clone(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Waits for I/O events like [`Poller::wait()`], until this waiter is notified.
+    ///
+    /// If the waiter was notified since its last wait, this returns immediately without waiting
+    /// for events. A notification of the whole poller also makes this return.
+    ///
+    /// Returns the number of new events.
+    pub fn wait(&self, events: &mut Events, timeout: Option<Duration>) -> io::Result<usize> {
+        let waiters = &self.poller.waiters;
+
+        // Publish that the waiter is about to block before checking for a notification, so that
+        // a notifier either sees it blocked or leaves a notification for the check.
+        self.state.waiting.store(true, Ordering::SeqCst);
+        if waiters.take(&self.state) {
+            self.state.waiting.store(false, Ordering::SeqCst);
+            return Ok(0);
+        }
+
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        let res = self.poller.wait_sys_result(
+            events,
+            deadline,
+            true,
+            None,
+            |poller, events, deadline| poller.wait_deadline(events, deadline),
+        );
+
+        self.state.waiting.store(false, Ordering::SeqCst);
+        waiters.take(&self.state);
+        waiters.forward(&self.poller);
+
+        Ok(res?.map_or(0, |result| result.events()))
+    }
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        let waiters = &self.poller.waiters;
+        self.state.dropped.store(true, Ordering::SeqCst);
+        waiters.take(&self.state);
+        waiters
+            .lock()
+            .retain(|state| !Arc::ptr_eq(state, &self.state));
+    }
+}
+
+impl WaiterNotifier {
+    /// Wake up the waiter, or make its next wait return immediately.
+    ///
+    /// If the waiter was already notified and has not waited since, this does nothing. If the
+    /// waiter was dropped, this does nothing either.
+    pub fn notify(&self) -> io::Result<()> {
+        if self.state.dropped.load(Ordering::SeqCst)
+            || self.state.notified.swap(true, Ordering::SeqCst)
+        {
+            return Ok(());
+        }
+        self.poller.waiters.notified.fetch_add(1, Ordering::SeqCst);
+
+        if self.state.waiting.load(Ordering::SeqCst) {
+            self.poller.notify()?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Waiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Waiter")
+            .field("poller", &self.poller)
+            .field("notified", &self.state.notified.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl fmt::Debug for WaiterNotifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WaiterNotifier { .. }")
+    }
+}
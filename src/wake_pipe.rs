@@ -0,0 +1,266 @@
+//! A wakeup channel that can be registered with any poller.
+
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A readable source that other threads can wake up.
+///
+/// This is the kind of channel a [`Poller`] uses for its own [notifications], made available
+/// for other uses: the readable end is registered with a poller, or any other event loop, and
+/// becomes readable when [`wake()`][`WakePipe::wake()`] is called. It stays readable until
+/// [`reset()`][`WakePipe::reset()`] is called. The channel is independent of the notifications
+/// of the poller, so that one poller can wait for several of them.
+///
+/// On Linux and Android this is an `eventfd`, on other Unix platforms a pipe, and on Windows a
+/// UDP socket that sends to itself, which can be registered like any other socket. Waking up
+/// the channel again before it is reset doesn't make a system call.
+///
+/// [`Poller`]: crate::Poller
+/// [notifications]: crate::Poller::notify()
+///
+/// # Examples
+///
+/// ```
+/// use polling::{Event, Events, Poller, WakePipe};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// let poller = Poller::new()?;
+/// let pipe = Arc::new(WakePipe::new()?);
+/// unsafe {
+///     poller.add(&*pipe, Event::readable(1))?;
+/// }
+///
+/// let waker = pipe.clone();
+/// std::thread::spawn(move || waker.wake().unwrap());
+///
+/// let mut events = Events::new();
+/// poller.wait(&mut events, Some(Duration::from_secs(5)))?;
+/// assert_eq!(events.iter().next().unwrap().key, 1);
+///
+/// pipe.reset()?;
+/// poller.delete(&*pipe)?;
+/// # std::io::Result::Ok(())
+/// ```
+pub struct WakePipe {
+    inner: sys::Pipe,
+
+    /// Whether the channel was woken up since it was last reset.
+    woken: AtomicBool,
+}
+
+impl WakePipe {
+    /// Create a new channel that is not woken up.
+    pub fn new() -> io::Result<WakePipe> {
+        Ok(WakePipe {
+            inner: sys::Pipe::new()?,
+            woken: AtomicBool::new(false),
+        })
+    }
+
+    /// Make the readable end readable, until the channel is reset.
+    pub fn wake(&self) -> io::Result<()> {
+        if self.woken.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        self.inner.wake()
+    }
+
+    /// Consume the wakeups, so that the readable end is no longer readable.
+    ///
+    /// A wakeup that arrives while the channel is being reset is kept.
+    pub fn reset(&self) -> io::Result<()> {
+        if !self.woken.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        self.inner.drain()?;
+
+        // A wakeup during the drain may have been consumed with the earlier one.
+        if self.woken.load(Ordering::SeqCst) {
+            self.inner.wake()?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for WakePipe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WakePipe")
+            .field("source", &self.inner.source())
+            .field("woken", &self.woken.load(Ordering::SeqCst))
+            .finish()
+    }
+}
+
+#[cfg(unix)]
+mod unix_impl {
+    use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+
+    use super::WakePipe;
+
+    impl AsRawFd for WakePipe {
+        fn as_raw_fd(&self) -> RawFd {
+            self.inner.source().as_raw_fd()
+        }
+    }
+
+    impl AsFd for WakePipe {
+        fn as_fd(&self) -> BorrowedFd<'_> {
+            self.inner.source()
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows_impl {
+    use std::os::windows::io::{AsRawSocket, AsSocket, BorrowedSocket, RawSocket};
+
+    use super::WakePipe;
+
+    impl AsRawSocket for WakePipe {
+        fn as_raw_socket(&self) -> RawSocket {
+            self.inner.source().as_raw_socket()
+        }
+    }
+
+    impl AsSocket for WakePipe {
+        fn as_socket(&self) -> BorrowedSocket<'_> {
+            self.inner.source()
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod sys {
+    use std::io;
+    use std::os::unix::io::{AsFd, BorrowedFd, OwnedFd};
+
+    use rustix::event::{eventfd, EventfdFlags};
+
+    /// An `eventfd` that counts the wakeups.
+    pub(super) struct Pipe {
+        event_fd: OwnedFd,
+    }
+
+    impl Pipe {
+        pub(super) fn new() -> io::Result<Pipe> {
+            let event_fd = eventfd(0, EventfdFlags::CLOEXEC | EventfdFlags::NONBLOCK)?;
+            Ok(Pipe { event_fd })
+        }
+
+        pub(super) fn source(&self) -> BorrowedFd<'_> {
+            self.event_fd.as_fd()
+        }
+
+        pub(super) fn wake(&self) -> io::Result<()> {
+            match rustix::io::write(&self.event_fd, &1u64.to_ne_bytes()) {
+                // The counter is full, so the eventfd is readable anyway.
+                Ok(_) | Err(rustix::io::Errno::AGAIN) => Ok(()),
+                Err(err) => Err(err.into()),
+            }
+        }
+
+        pub(super) fn drain(&self) -> io::Result<()> {
+            // Reading resets the counter.
+            match rustix::io::read(&self.event_fd, &mut [0; 8]) {
+                Ok(_) | Err(rustix::io::Errno::AGAIN) => Ok(()),
+                Err(err) => Err(err.into()),
+            }
+        }
+    }
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+mod sys {
+    use std::io;
+    use std::os::unix::io::{AsFd, BorrowedFd, OwnedFd};
+
+    use rustix::fs::{fcntl_getfl, fcntl_setfl, OFlags};
+    use rustix::io::{fcntl_getfd, fcntl_setfd, Errno, FdFlags};
+
+    /// A pipe with a byte in it for each wakeup.
+    pub(super) struct Pipe {
+        read_pipe: OwnedFd,
+        write_pipe: OwnedFd,
+    }
+
+    impl Pipe {
+        pub(super) fn new() -> io::Result<Pipe> {
+            let (read_pipe, write_pipe) = rustix::pipe::pipe()?;
+            for fd in [&read_pipe, &write_pipe] {
+                fcntl_setfd(fd, fcntl_getfd(fd)? | FdFlags::CLOEXEC)?;
+                fcntl_setfl(fd, fcntl_getfl(fd)? | OFlags::NONBLOCK)?;
+            }
+            Ok(Pipe {
+                read_pipe,
+                write_pipe,
+            })
+        }
+
+        pub(super) fn source(&self) -> BorrowedFd<'_> {
+            self.read_pipe.as_fd()
+        }
+
+        pub(super) fn wake(&self) -> io::Result<()> {
+            match rustix::io::write(&self.write_pipe, &[1]) {
+                // The pipe is full, so it is readable anyway.
+                Ok(_) | Err(Errno::AGAIN) => Ok(()),
+                Err(err) => Err(err.into()),
+            }
+        }
+
+        pub(super) fn drain(&self) -> io::Result<()> {
+            loop {
+                match rustix::io::read(&self.read_pipe, &mut [0; 64]) {
+                    Ok(0) | Err(Errno::AGAIN) => return Ok(()),
+                    Ok(_) | Err(Errno::INTR) => {}
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::io;
+    use std::net::UdpSocket;
+    use std::os::windows::io::{AsSocket, BorrowedSocket};
+
+    /// A UDP socket with a datagram queued to itself for each wakeup.
+    pub(super) struct Pipe {
+        socket: UdpSocket,
+    }
+
+    impl Pipe {
+        pub(super) fn new() -> io::Result<Pipe> {
+            let socket = UdpSocket::bind("127.0.0.1:0")?;
+            socket.connect(socket.local_addr()?)?;
+            socket.set_nonblocking(true)?;
+            Ok(Pipe { socket })
+        }
+
+        pub(super) fn source(&self) -> BorrowedSocket<'_> {
+            self.socket.as_socket()
+        }
+
+        pub(super) fn wake(&self) -> io::Result<()> {
+            match self.socket.send(&[1]) {
+                // The buffer is full, so the socket is readable anyway.
+                Err(err) if err.kind() != io::ErrorKind::WouldBlock => Err(err),
+                _ => Ok(()),
+            }
+        }
+
+        pub(super) fn drain(&self) -> io::Result<()> {
+            loop {
+                match self.socket.recv(&mut [0; 64]) {
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                    Err(err) => return Err(err),
+                    Ok(_) => {}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Wakers and virtual sources that deliver events to a `Poller`.
+
+use std::io;
+use std::sync::{Arc, Mutex, Weak};
+use std::task::Wake;
+
+use crate::{Event, Interest, Poller};
+
+/// The events of wakers and virtual sources since the last wait.
+#[derive(Debug, Default)]
+pub(crate) struct Woken {
+    ready: Mutex<Vec<Event>>,
+}
+
+impl Woken {
+    /// Mark `key` as ready with the readiness of `ev`, merged with the earlier readiness.
+    fn insert(&self, ev: Event) {
+        let mut ready = self.ready.lock().unwrap_or_else(|e| e.into_inner());
+        match ready.iter_mut().find(|earlier| earlier.key == ev.key) {
+            Some(earlier) => {
+                earlier.readable |= ev.readable;
+                earlier.writable |= ev.writable;
+            }
+            None => ready.push(ev),
+        }
+    }
+
+    /// Move the ready keys into `list`.
+    pub(crate) fn drain_into(&self, list: &mut Vec<Event>) {
+        let mut ready = self.ready.lock().unwrap_or_else(|e| e.into_inner());
+        list.append(&mut ready);
+    }
+}
+
+/// A waker that marks a key as ready and wakes up the poller.
+pub(crate) struct KeyWaker {
+    pub(crate) poller: Weak<Poller>,
+    pub(crate) key: usize,
+}
+
+impl Wake for KeyWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        // Wakers may outlive the poller, in which case there is nothing to wake up.
+        if let Some(poller) = self.poller.upgrade() {
+            poller.woken.insert(Event::readable(self.key));
+            if let Err(_err) = poller.notify() {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(err = %_err, key = self.key, "waker: failed to notify the poller");
+            }
+        }
+    }
+}
+
+/// A source of events that is driven by user code instead of a file descriptor or socket.
+///
+/// This is created by [`Poller::virtual_source()`]. Marking the source as ready with
+/// [`set_ready()`][`VirtualSource::set_ready()`] wakes up the poller, and the current or the
+/// following wait then delivers an event with the key of the source, like the event of a
+/// registered source. Readiness that is marked several times before a wait is delivered as one
+/// event. This lets in-memory queues be dispatched by the same loop as I/O.
+///
+/// The handle can be cloned to mark the source as ready from several places. It doesn't keep
+/// the poller alive, and marking the source as ready after the poller is dropped does nothing.
+#[derive(Debug, Clone)]
+pub struct VirtualSource {
+    poller: Weak<Poller>,
+    interest: Event,
+}
+
+impl VirtualSource {
+    /// Create a source with `interest` that delivers its events to `poller`.
+    pub(crate) fn new(poller: Weak<Poller>, interest: Event) -> VirtualSource {
+        VirtualSource { poller, interest }
+    }
+
+    /// The key of the events of this source.
+    #[inline]
+    pub fn key(&self) -> usize {
+        self.interest.key
+    }
+
+    /// The interest in this source.
+    #[inline]
+    pub fn interest(&self) -> Event {
+        self.interest
+    }
+
+    /// Mark the source as ready for the events in `ready`.
+    ///
+    /// Only the readiness in the interest of the source is delivered. Marking the source as
+    /// ready for nothing it is interested in does nothing.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if the poller can't be notified.
+    pub fn set_ready(&self, ready: Interest) -> io::Result<()> {
+        let ev = Event {
+            readable: self.interest.readable && ready.is_readable(),
+            writable: self.interest.writable && ready.is_writable(),
+            ..Event::none(self.interest.key)
+        };
+        if !ev.readable && !ev.writable {
+            return Ok(());
+        }
+
+        // Nothing waits for the events of a dropped poller.
+        match self.poller.upgrade() {
+            Some(poller) => {
+                poller.woken.insert(ev);
+                poller.notify()
+            }
+            None => Ok(()),
+        }
+    }
+}
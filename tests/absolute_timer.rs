@@ -0,0 +1,49 @@
+//! Tests for absolute timers on `kqueue`.
+
+#![cfg(all(
+    any(target_vendor = "apple", target_os = "freebsd", target_os = "netbsd"),
+    not(polling_test_poll_backend)
+))]
+
+use polling::os::kqueue::{AbsoluteTimer, PollerKqueueExt};
+use polling::{Events, PollMode, Poller};
+
+use std::time::{Duration, Instant, SystemTime};
+
+#[test]
+fn expires_at_deadline() {
+    let poller = Poller::new().unwrap();
+    let timer = AbsoluteTimer {
+        id: 1,
+        deadline: SystemTime::now() + Duration::from_millis(100),
+    };
+    poller.add_filter(timer, 2, PollMode::Oneshot).unwrap();
+
+    let start = Instant::now();
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(90));
+    assert_eq!(events.iter().next().unwrap().key, 2);
+
+    poller.delete_filter(timer).unwrap();
+}
+
+#[test]
+fn deadline_in_the_past() {
+    let poller = Poller::new().unwrap();
+    let timer = AbsoluteTimer {
+        id: 1,
+        deadline: SystemTime::UNIX_EPOCH,
+    };
+    poller.add_filter(timer, 2, PollMode::Oneshot).unwrap();
+
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+
+    poller.delete_filter(timer).unwrap();
+}
@@ -0,0 +1,44 @@
+//! Tests for the completion of POSIX AIO requests on FreeBSD.
+
+#![cfg(all(target_os = "freebsd", not(polling_test_poll_backend)))]
+
+use polling::os::kqueue::PollerAioExt;
+use polling::{Events, Poller};
+
+use std::fs;
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+#[test]
+fn aio_read() {
+    let path = std::env::temp_dir().join(format!("polling-aio-{}", std::process::id()));
+    fs::write(&path, b"hello").unwrap();
+    let file = fs::File::open(&path).unwrap();
+
+    let poller = Poller::new().unwrap();
+    let mut buf = [0u8; 16];
+
+    // SAFETY: An all-zero `aiocb` is valid.
+    let mut aiocb: libc::aiocb = unsafe { std::mem::zeroed() };
+    aiocb.aio_fildes = file.as_raw_fd();
+    aiocb.aio_buf = buf.as_mut_ptr().cast();
+    aiocb.aio_nbytes = buf.len();
+    poller.set_aio_event(&mut aiocb, 3).unwrap();
+
+    // SAFETY: `aiocb` and `buf` outlive the request.
+    assert_eq!(unsafe { libc::aio_read(&mut aiocb) }, 0);
+
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    let event = events.iter().next().unwrap();
+    assert_eq!(event.key, 3);
+    assert!(event.readable);
+
+    // SAFETY: The request has completed.
+    assert_eq!(unsafe { libc::aio_return(&mut aiocb) }, 5);
+    assert_eq!(&buf[..5], b"hello");
+
+    fs::remove_file(&path).unwrap();
+}
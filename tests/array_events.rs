@@ -0,0 +1,81 @@
+use polling::{ArrayEvents, Event, Poller};
+use std::net::UdpSocket;
+use std::time::Duration;
+
+#[test]
+fn fills_up() {
+    let poller = Poller::new().unwrap();
+    let sockets = (0..5)
+        .map(|_| UdpSocket::bind("127.0.0.1:0").unwrap())
+        .collect::<Vec<_>>();
+    for (key, socket) in sockets.iter().enumerate() {
+        unsafe {
+            poller.add(socket, Event::writable(key)).unwrap();
+        }
+    }
+
+    let mut events = ArrayEvents::<2>::new();
+    assert_eq!(events.capacity(), 2);
+    let mut keys = Vec::new();
+    while keys.len() < sockets.len() {
+        events.clear();
+        let n = poller
+            .wait_array(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert!(n <= 2);
+        assert_eq!(n, events.len());
+        keys.extend(events.iter().map(|ev| ev.key));
+    }
+    keys.sort_unstable();
+    assert_eq!(keys, [0, 1, 2, 3, 4]);
+
+    // A full container returns right away.
+    poller.modify(&sockets[0], Event::writable(0)).unwrap();
+    events.clear();
+    poller
+        .wait_array(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    poller.modify(&sockets[1], Event::writable(1)).unwrap();
+    poller
+        .wait_array(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert!(events.is_full());
+    assert_eq!(
+        poller
+            .wait_array(&mut events, Some(Duration::from_secs(10)))
+            .unwrap(),
+        0
+    );
+
+    for socket in &sockets {
+        poller.delete(socket).unwrap();
+    }
+}
+
+#[test]
+fn split_overflow() {
+    let poller = Poller::new().unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.connect(socket.local_addr().unwrap()).unwrap();
+    socket.send(&[1]).unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    unsafe {
+        poller
+            .add_split(&socket, Event::readable(1), Event::writable(2))
+            .unwrap();
+    }
+
+    // The read and write halves are reported under separate keys, one at a time.
+    let mut events = ArrayEvents::<1>::new();
+    let mut keys = Vec::new();
+    while keys.len() < 2 {
+        events.clear();
+        poller
+            .wait_array(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        keys.extend(events.iter().map(|ev| ev.key));
+    }
+    keys.sort_unstable();
+    assert_eq!(keys, [1, 2]);
+    poller.delete(&socket).unwrap();
+}
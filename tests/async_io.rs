@@ -0,0 +1,147 @@
+#![cfg(feature = "async")]
+
+use polling::async_io::{Async, Driver};
+use polling::{Event, Events, Poller};
+use std::future::Future;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Wake, Waker};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn block_on() {
+    let driver = Arc::new(Driver::new().unwrap());
+    let (read, mut write) = tcp_pair().unwrap();
+    read.set_nonblocking(true).unwrap();
+    let read = Async::new(&driver, read).unwrap();
+
+    let writer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        write.write_all(b"hello").unwrap();
+        write
+    });
+
+    let mut buf = [0; 5];
+    let len = driver
+        .block_on(read.read_with(|mut read| read.read(&mut buf)))
+        .unwrap();
+    assert_eq!(&buf[..len], b"hello");
+    writer.join().unwrap();
+
+    let writable = driver.block_on(async {
+        read.writable().await?;
+        read.get_ref().peer_addr()
+    });
+    assert!(writable.is_ok());
+}
+
+#[test]
+fn background_driver() {
+    let driver = Arc::new(Driver::new().unwrap());
+    let runner = thread::spawn({
+        let driver = driver.clone();
+        move || driver.run()
+    });
+
+    let (read, mut write) = tcp_pair().unwrap();
+    read.set_nonblocking(true).unwrap();
+    let read = Async::new(&driver, read).unwrap();
+
+    // Wait for the event on another driver's thread to check that `readable()` is woken by
+    // the background one.
+    let other = Arc::new(Driver::new().unwrap());
+    let waiter = thread::spawn(move || other.block_on(read.readable()).map(|()| read));
+
+    thread::sleep(Duration::from_millis(50));
+    write.write_all(&[1]).unwrap();
+    let read = waiter.join().unwrap().unwrap();
+
+    let mut read = read.into_inner().unwrap();
+    assert_eq!(read.read(&mut [0; 16]).unwrap(), 1);
+
+    driver.stop().unwrap();
+    runner.join().unwrap().unwrap();
+}
+
+#[test]
+fn wait_async() {
+    let driver = Arc::new(Driver::new().unwrap());
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut events = Events::new();
+
+    // Ready events are collected right away.
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    unsafe {
+        poller.add(&socket, Event::writable(1)).unwrap();
+    }
+    let n = driver.block_on(poller.wait_async(&mut events)).unwrap();
+    assert_eq!(n, 1);
+    assert_eq!(events.iter().next().unwrap().key, 1);
+    poller.delete(&socket).unwrap();
+
+    // Otherwise the future completes once the poller has events.
+    let waker = poller.waker(2).unwrap();
+    let wakeup = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        waker.wake();
+    });
+    events.clear();
+    while events.is_empty() {
+        driver.block_on(poller.wait_async(&mut events)).unwrap();
+    }
+    assert_eq!(events.iter().next().unwrap().key, 2);
+    wakeup.join().unwrap();
+
+    poller.notify().unwrap();
+    assert_eq!(driver.block_on(poller.wait_async(&mut events)).unwrap(), 0);
+}
+
+#[test]
+fn wait_async_dropped() {
+    struct Noop;
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let waker = Waker::from(Arc::new(Noop));
+    let mut cx = Context::from_waker(&waker);
+
+    // Start the helper thread, and drop the future after it collected an event.
+    let mut events = Events::new();
+    {
+        let mut future = pin!(poller.wait_async(&mut events));
+        assert!(future.as_mut().poll(&mut cx).is_pending());
+        poller.waker(3).unwrap().wake();
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    // The event is delivered by the next wait.
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 3);
+
+    // A future dropped while the helper thread is blocked doesn't swallow anything.
+    {
+        let mut future = pin!(poller.wait_async(&mut events));
+        assert!(future.as_mut().poll(&mut cx).is_pending());
+    }
+    poller.notify().unwrap();
+    events.clear();
+    let result = poller
+        .wait_result(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert!(result.is_notified());
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
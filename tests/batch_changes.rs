@@ -0,0 +1,96 @@
+//! Tests for submitting changes of interest with the wait.
+
+use std::io::{self, prelude::*};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use polling::{Event, Events, PollerBuilder};
+
+#[test]
+fn rearm_in_loop() {
+    let (mut reader, mut writer) = tcp_pair().unwrap();
+    let poller = PollerBuilder::new().batch_changes(true).build().unwrap();
+    unsafe {
+        poller.add(&reader, Event::readable(1)).unwrap();
+    }
+
+    let mut events = Events::new();
+    for _ in 0..10 {
+        writer.write_all(&[1]).unwrap();
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(10)))
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events.iter().next().unwrap().key, 1);
+        reader.read_exact(&mut [0]).unwrap();
+
+        // Nothing is pending until the source is re-armed.
+        events.clear();
+        poller.wait(&mut events, Some(Duration::ZERO)).unwrap();
+        assert!(events.is_empty());
+        poller.modify(&reader, Event::readable(1)).unwrap();
+    }
+
+    poller.delete(&reader).unwrap();
+}
+
+#[test]
+fn modify_while_waiting() {
+    let (_reader, writer) = tcp_pair().unwrap();
+    let poller = Arc::new(PollerBuilder::new().batch_changes(true).build().unwrap());
+    unsafe {
+        poller.add(&writer, Event::none(1)).unwrap();
+    }
+
+    // The change applies to the wait that is in progress.
+    let waiting = thread::spawn({
+        let poller = poller.clone();
+        move || {
+            let mut events = Events::new();
+            poller
+                .wait(&mut events, Some(Duration::from_secs(10)))
+                .unwrap();
+            events.iter().map(|ev| ev.key).collect::<Vec<_>>()
+        }
+    });
+    thread::sleep(Duration::from_millis(100));
+    poller.modify(&writer, Event::writable(1)).unwrap();
+    assert_eq!(waiting.join().unwrap(), [1]);
+
+    poller.delete(&writer).unwrap();
+}
+
+#[test]
+fn delete_discards_changes() {
+    let (_reader, writer) = tcp_pair().unwrap();
+    let poller = PollerBuilder::new().batch_changes(true).build().unwrap();
+    unsafe {
+        poller.add(&writer, Event::none(1)).unwrap();
+    }
+
+    // The queued change doesn't register the source again.
+    poller.modify(&writer, Event::writable(1)).unwrap();
+    poller.delete(&writer).unwrap();
+    let mut events = Events::new();
+    poller.wait(&mut events, Some(Duration::ZERO)).unwrap();
+    assert!(events.is_empty());
+
+    unsafe {
+        poller.add(&writer, Event::writable(2)).unwrap();
+    }
+    poller
+        .wait(&mut events, Some(Duration::from_secs(10)))
+        .unwrap();
+    assert_eq!(events.iter().map(|ev| ev.key).collect::<Vec<_>>(), [2]);
+    poller.delete(&writer).unwrap();
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
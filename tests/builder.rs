@@ -0,0 +1,307 @@
+use std::io;
+use std::net::UdpSocket;
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+use polling::{Backend, Capability, Event, Events, PollMode, Poller};
+
+#[test]
+fn defaults() {
+    let poller = Poller::builder().build().unwrap();
+    assert_eq!(poller.mode(), PollMode::Oneshot);
+    assert_eq!(poller.notify_key(), usize::MAX);
+    assert_eq!(poller.label(), None);
+    assert_eq!(
+        poller.new_events().capacity(),
+        polling::Events::new().capacity()
+    );
+}
+
+#[test]
+fn capacity() {
+    let capacity = NonZeroUsize::new(4).unwrap();
+    let poller = Poller::builder().capacity(capacity).build().unwrap();
+    let events = poller.new_events();
+    assert_eq!(events.capacity(), capacity);
+}
+
+#[test]
+fn reserve() {
+    let poller = Poller::new().unwrap();
+    let sockets = (0..16)
+        .map(|_| {
+            let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+            socket.send_to(b"x", socket.local_addr().unwrap()).unwrap();
+            socket
+        })
+        .collect::<Vec<_>>();
+    for (key, socket) in sockets.iter().enumerate() {
+        unsafe { poller.add(socket, Event::readable(key)).unwrap() };
+    }
+
+    let mut events = Events::with_capacity(NonZeroUsize::new(2).unwrap());
+    events.reserve(14);
+    assert_eq!(events.capacity().get(), 16);
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 16);
+
+    events.clear();
+    events.shrink_to_fit();
+    assert_eq!(events.capacity().get(), 2);
+
+    for socket in &sockets {
+        poller.delete(socket).unwrap();
+    }
+}
+
+#[test]
+fn label() {
+    let poller = Poller::builder().label("worker-3").build().unwrap();
+    assert_eq!(poller.label(), Some("worker-3"));
+    assert!(format!("{:?}", poller).contains("worker-3"));
+}
+
+#[test]
+fn level_by_default() {
+    let poller = match Poller::builder().mode(PollMode::Level).build() {
+        Ok(poller) => poller,
+        Err(err) if err.kind() == io::ErrorKind::Unsupported => return,
+        Err(err) => panic!("{}", err),
+    };
+    assert_eq!(poller.mode(), PollMode::Level);
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    server.set_nonblocking(true).unwrap();
+
+    let registration = poller.register(server, Event::readable(1)).unwrap();
+    io::Write::write_all(&mut client, &[1]).unwrap();
+
+    // In level mode the event keeps being delivered without calling `modify()`.
+    let mut events = poller.new_events();
+    for _ in 0..2 {
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events.iter().next().unwrap().key, 1);
+    }
+
+    drop(registration);
+}
+
+#[test]
+fn capabilities() {
+    let poller = Poller::new().unwrap();
+    assert_eq!(
+        poller.supports(Capability::LevelTrigger),
+        poller.supports_level()
+    );
+    assert_eq!(
+        poller.supports(Capability::EdgeTrigger),
+        poller.supports_edge()
+    );
+    assert!(poller.supports(Capability::Timers));
+    assert_eq!(
+        poller.supports(Capability::Exclusive),
+        poller.backend() == Backend::Epoll
+    );
+}
+
+#[test]
+fn backend() {
+    assert!(Backend::available().contains(&Backend::current()));
+    let poller = Poller::builder()
+        .require_backend(Backend::current())
+        .build()
+        .unwrap();
+    assert_eq!(poller.backend(), Backend::current());
+
+    for backend in [
+        Backend::Epoll,
+        Backend::Kqueue,
+        Backend::EventPorts,
+        Backend::Poll,
+        Backend::Iocp,
+        Backend::Custom,
+    ] {
+        match Poller::builder().require_backend(backend).build() {
+            Ok(poller) => {
+                assert!(backend.is_available());
+                assert_eq!(poller.backend(), backend);
+            }
+            Err(err) => {
+                assert!(!backend.is_available());
+                assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+            }
+        }
+    }
+}
+
+#[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    not(polling_test_poll_backend),
+))]
+#[test]
+fn poll_backend() {
+    use std::os::unix::io::OwnedFd;
+
+    assert_eq!(Backend::available(), &[Backend::Epoll, Backend::Poll]);
+    let poller = Poller::builder()
+        .require_backend(Backend::Poll)
+        .build()
+        .unwrap();
+    assert_eq!(poller.backend(), Backend::Poll);
+    assert!(poller.supports_level());
+    assert!(!poller.supports_edge());
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.send_to(b"x", socket.local_addr().unwrap()).unwrap();
+    unsafe { poller.add(&socket, Event::readable(7)).unwrap() };
+
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    let event = events.iter().next().unwrap();
+    assert_eq!(event.key, 7);
+    assert!(event.readable);
+
+    // The poll backend has no file descriptor.
+    assert!(poller.try_as_fd().is_none());
+    poller.delete(&socket).unwrap();
+    let err = OwnedFd::try_from(poller).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+
+    // Only the default backend supports other clocks.
+    let err = Poller::builder()
+        .require_backend(Backend::Poll)
+        .clock(polling::Clock::Boottime)
+        .build()
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+}
+
+#[test]
+fn busy_poll() {
+    let poller = Poller::builder()
+        .busy_poll(Duration::from_millis(20))
+        .build()
+        .unwrap();
+    let mut events = poller.new_events();
+
+    // The timeout is still respected while busy-polling, and afterwards.
+    for timeout in [Duration::from_millis(5), Duration::from_millis(50)] {
+        let start = Instant::now();
+        poller.wait(&mut events, Some(timeout)).unwrap();
+        assert!(start.elapsed() >= timeout);
+        assert!(events.is_empty());
+    }
+
+    // Notifications and events end the busy-polling early.
+    poller.notify().unwrap();
+    let start = Instant::now();
+    poller.wait(&mut events, None).unwrap();
+    assert!(start.elapsed() < Duration::from_millis(20));
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    let registration = poller.register(server, Event::readable(1)).unwrap();
+    io::Write::write_all(&mut client, &[1]).unwrap();
+    poller.wait(&mut events, None).unwrap();
+    assert_eq!(events.len(), 1);
+
+    drop(registration);
+}
+
+#[test]
+fn adaptive_spin() {
+    let max = Duration::from_millis(8);
+    let poller = Poller::builder().adaptive_spin(max).build().unwrap();
+    assert_eq!(poller.spin_budget(), Some(max));
+    let mut events = poller.new_events();
+
+    // Waits that block anyway shrink the budget, down to a floor.
+    for _ in 0..10 {
+        let start = Instant::now();
+        poller
+            .wait(&mut events, Some(Duration::from_millis(20)))
+            .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert!(events.is_empty());
+    }
+    assert_eq!(poller.spin_budget(), Some(max / 64));
+
+    // Events that arrive while busy-polling grow it again.
+    poller.notify().unwrap();
+    poller.wait(&mut events, None).unwrap();
+    assert_eq!(poller.spin_budget(), Some(max / 32));
+
+    // A zero-timeout check leaves it alone.
+    poller.wait(&mut events, Some(Duration::ZERO)).unwrap();
+    assert_eq!(poller.spin_budget(), Some(max / 32));
+
+    // Without it, the fixed duration is reported.
+    let poller = Poller::builder()
+        .busy_poll(Duration::from_millis(1))
+        .build()
+        .unwrap();
+    assert_eq!(poller.spin_budget(), Some(Duration::from_millis(1)));
+    assert_eq!(Poller::new().unwrap().spin_budget(), None);
+}
+
+#[test]
+fn rate_limit() {
+    let interval = Duration::from_millis(100);
+    let poller = Poller::builder()
+        .rate_limit(Some(interval))
+        .build()
+        .unwrap();
+    if !poller.supports_level() {
+        return;
+    }
+    let mut events = poller.new_events();
+
+    // An always writable socket is delivered once per interval.
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    unsafe {
+        poller
+            .add_with_mode(&socket, Event::writable(1), PollMode::Level)
+            .unwrap();
+    }
+    poller.wait(&mut events, None).unwrap();
+    assert_eq!(events.len(), 1);
+    let delivered = Instant::now();
+
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    while events.is_empty() {
+        poller.wait(&mut events, Some(interval * 10)).unwrap();
+    }
+    assert!(delivered.elapsed() >= interval);
+    assert_eq!(
+        events.iter().next().unwrap().with_no_extra(),
+        Event::writable(1)
+    );
+
+    // A timer of the same key is merged with the events of the socket.
+    events.clear();
+    poller.set_timer(1, Instant::now()).unwrap();
+    while events.is_empty() {
+        poller.wait(&mut events, Some(interval * 10)).unwrap();
+    }
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().with_no_extra(), Event::all(1));
+
+    poller.delete(&socket).unwrap();
+}
@@ -0,0 +1,81 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use polling::{CancelToken, Event, Events, Poller};
+
+#[test]
+fn cancel_from_thread() {
+    let poller = Poller::new().unwrap();
+    let token = CancelToken::new();
+    let mut events = Events::new();
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(Duration::from_millis(50));
+            token.cancel();
+        });
+
+        let start = Instant::now();
+        let result = poller
+            .wait_cancelable(&mut events, Some(Duration::from_secs(10)), &token)
+            .unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(result.is_cancelled());
+        assert!(!result.is_notified());
+        assert!(!result.is_timed_out());
+    });
+
+    // The token stays cancelled, and the notification of the poller is left alone.
+    assert!(token.clone().is_cancelled());
+    poller.notify().unwrap();
+    let result = poller.wait_cancelable(&mut events, None, &token).unwrap();
+    assert!(result.is_cancelled());
+    assert!(result.is_notified());
+    let result = poller
+        .wait_result(&mut events, Some(Duration::ZERO))
+        .unwrap();
+    assert!(!result.is_notified());
+}
+
+#[test]
+fn cancelled_wait_collects_events() {
+    let poller = Poller::new().unwrap();
+    let token = CancelToken::new();
+    token.cancel();
+
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    unsafe {
+        poller.add(&socket, Event::writable(1)).unwrap();
+    }
+    let mut events = Events::new();
+    let result = poller.wait_cancelable(&mut events, None, &token).unwrap();
+    assert!(result.is_cancelled());
+    assert_eq!(result.events(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 1);
+    poller.delete(&socket).unwrap();
+}
+
+#[test]
+fn not_cancelled() {
+    let poller = Poller::new().unwrap();
+    let token = CancelToken::new();
+    let mut events = Events::new();
+
+    let result = poller
+        .wait_cancelable(&mut events, Some(Duration::from_millis(10)), &token)
+        .unwrap();
+    assert!(result.is_timed_out());
+    assert!(!result.is_cancelled());
+
+    poller.notify().unwrap();
+    let result = poller.wait_cancelable(&mut events, None, &token).unwrap();
+    assert!(result.is_notified());
+    assert!(!result.is_cancelled());
+
+    // Cancelling the token after the waits doesn't wake up later plain waits.
+    token.cancel();
+    let result = poller
+        .wait_result(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(result.is_timed_out());
+}
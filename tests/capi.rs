@@ -0,0 +1,105 @@
+#![cfg(all(feature = "capi", unix))]
+
+use polling::capi::*;
+use std::io::Write;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::ptr;
+
+#[test]
+fn add_wait_delete() {
+    unsafe {
+        let mut poller = ptr::null_mut();
+        assert_eq!(polling_new(&mut poller), 0);
+        assert!(!poller.is_null());
+
+        let (read, mut write) = UnixStream::pair().unwrap();
+        assert_eq!(
+            polling_add(
+                poller,
+                read.as_raw_fd(),
+                3,
+                POLLING_READABLE,
+                POLLING_MODE_ONESHOT
+            ),
+            0
+        );
+
+        write.write_all(&[1]).unwrap();
+        let mut events = [polling_event_t { key: 0, flags: 0 }; 4];
+        let mut count = 0;
+        assert_eq!(
+            polling_wait(poller, events.as_mut_ptr(), events.len(), 1000, &mut count),
+            0
+        );
+        assert_eq!(count, 1);
+        assert_eq!(events[0].key, 3);
+        assert_ne!(events[0].flags & POLLING_READABLE, 0);
+
+        assert_eq!(polling_delete(poller, read.as_raw_fd()), 0);
+        assert_ne!(polling_delete(poller, read.as_raw_fd()), 0);
+        polling_free(poller);
+    }
+}
+
+#[test]
+fn notify() {
+    unsafe {
+        let mut poller = ptr::null_mut();
+        assert_eq!(polling_new(&mut poller), 0);
+
+        assert_eq!(polling_notify(poller), 0);
+        let mut events = [polling_event_t { key: 0, flags: 0 }; 1];
+        let mut count = 1;
+        assert_eq!(
+            polling_wait(poller, events.as_mut_ptr(), events.len(), -1, &mut count),
+            0
+        );
+        assert_eq!(count, 0);
+
+        assert_eq!(polling_add(poller, 0, 0, POLLING_READABLE, 42), -1);
+        polling_free(poller);
+    }
+}
+
+#[test]
+fn more_events_than_capacity() {
+    unsafe {
+        let mut poller = ptr::null_mut();
+        assert_eq!(polling_new(&mut poller), 0);
+
+        let (a, _a) = UnixStream::pair().unwrap();
+        let (b, _b) = UnixStream::pair().unwrap();
+        for (key, source) in [(1, &a), (2, &b)] {
+            assert_eq!(
+                polling_add(
+                    poller,
+                    source.as_raw_fd(),
+                    key,
+                    POLLING_WRITABLE,
+                    POLLING_MODE_ONESHOT
+                ),
+                0
+            );
+        }
+
+        // Only one event fits, the other one is returned by the next call.
+        let mut keys = Vec::new();
+        for _ in 0..2 {
+            let mut events = [polling_event_t { key: 0, flags: 0 }; 1];
+            let mut count = 0;
+            assert_eq!(
+                polling_wait(poller, events.as_mut_ptr(), events.len(), 1000, &mut count),
+                0
+            );
+            assert_eq!(count, 1);
+            keys.push(events[0].key);
+        }
+        keys.sort_unstable();
+        assert_eq!(keys, [1, 2]);
+
+        assert_eq!(polling_delete(poller, a.as_raw_fd()), 0);
+        assert_eq!(polling_delete(poller, b.as_raw_fd()), 0);
+        polling_free(poller);
+    }
+}
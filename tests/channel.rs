@@ -0,0 +1,52 @@
+use polling::{channel, Event, Events, Poller};
+use std::sync::mpsc::TryRecvError;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn messages_wake_up() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (sender, receiver) = channel::channel(&poller, 9).unwrap();
+    assert_eq!(receiver.key(), 9);
+
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    let other = sender.clone();
+    thread::spawn(move || {
+        other.send(1).unwrap();
+        other.send(2).unwrap();
+    })
+    .join()
+    .unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events.iter().next().unwrap().with_no_extra(),
+        Event::readable(9)
+    );
+    assert_eq!(receiver.try_iter().collect::<Vec<_>>(), [1, 2]);
+
+    // Dropping the last sender reports the disconnection.
+    drop(sender);
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.iter().next().unwrap().key, 9);
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn receiver_dropped() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (sender, receiver) = channel::channel(&poller, 1).unwrap();
+    drop(receiver);
+    assert_eq!(sender.send(5).unwrap_err().0, 5);
+}
@@ -0,0 +1,41 @@
+use polling::{Clock, Events, Poller};
+use std::io;
+use std::time::{Duration, Instant};
+
+#[test]
+fn boottime() {
+    for concurrent in [false, true] {
+        let res = Poller::builder()
+            .clock(Clock::Boottime)
+            .concurrent_waits(concurrent)
+            .build();
+        let poller = match res {
+            Ok(poller) => poller,
+            Err(err) => {
+                assert!(!Clock::Boottime.is_supported());
+                assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+                return;
+            }
+        };
+        assert!(Clock::Boottime.is_supported());
+
+        let mut events = Events::new();
+        let start = Instant::now();
+        let res = poller
+            .wait_result(&mut events, Some(Duration::from_millis(50)))
+            .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert!(res.is_timed_out());
+
+        poller.notify().unwrap();
+        let res = poller.wait_result(&mut events, None).unwrap();
+        assert!(res.is_notified());
+    }
+}
+
+#[test]
+fn monotonic() {
+    assert!(Clock::Monotonic.is_supported());
+    assert_eq!(Clock::default(), Clock::Monotonic);
+    Poller::builder().clock(Clock::Monotonic).build().unwrap();
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "completion")]
+
+use polling::completion::{Completion, CompletionPoller};
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+#[test]
+fn read_after_write() {
+    let poller = CompletionPoller::new().unwrap();
+    let (reader, mut writer) = tcp_pair().unwrap();
+    unsafe {
+        poller.submit_read(&reader, vec![0; 16], 1).unwrap();
+    }
+    assert_eq!(poller.len(), 1);
+
+    // Nothing completes before data arrives.
+    let mut completions = Vec::new();
+    poller
+        .wait(&mut completions, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(completions.is_empty());
+
+    writer.write_all(b"hello").unwrap();
+    wait_for(&poller, &mut completions, 1);
+    let completion = &completions[0];
+    assert_eq!(completion.user_data, 1);
+    let len = *completion.result.as_ref().unwrap();
+    assert_eq!(&completion.buf[..len], b"hello");
+    assert!(poller.is_empty());
+}
+
+#[test]
+fn ordered_operations() {
+    let poller = CompletionPoller::new().unwrap();
+    let (reader, writer) = tcp_pair().unwrap();
+    unsafe {
+        poller.submit_write(&writer, b"ab".to_vec(), 1).unwrap();
+        poller.submit_write(&writer, b"cd".to_vec(), 2).unwrap();
+        poller.submit_read(&reader, vec![0; 4], 3).unwrap();
+    }
+
+    let mut completions = Vec::new();
+    wait_for(&poller, &mut completions, 3);
+    let writes: Vec<_> = completions
+        .iter()
+        .filter(|c| c.user_data != 3)
+        .map(|c| c.user_data)
+        .collect();
+    assert_eq!(writes, [1, 2]);
+
+    // The read may complete before the second write.
+    let read = completions.iter().find(|c| c.user_data == 3).unwrap();
+    let len = *read.result.as_ref().unwrap();
+    assert!(b"abcd".starts_with(&read.buf[..len]));
+    assert!(len > 0);
+}
+
+#[test]
+fn end_of_stream() {
+    let poller = CompletionPoller::new().unwrap();
+    let (reader, writer) = tcp_pair().unwrap();
+    unsafe {
+        poller.submit_read(&reader, vec![0; 4], 1).unwrap();
+    }
+    drop(writer);
+
+    let mut completions = Vec::new();
+    wait_for(&poller, &mut completions, 1);
+    assert_eq!(*completions[0].result.as_ref().unwrap(), 0);
+}
+
+fn wait_for(poller: &CompletionPoller, completions: &mut Vec<Completion>, count: usize) {
+    while completions.len() < count {
+        poller
+            .wait(completions, Some(Duration::from_secs(10)))
+            .unwrap();
+    }
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    a.set_nonblocking(true)?;
+    b.set_nonblocking(true)?;
+    Ok((a, b))
+}
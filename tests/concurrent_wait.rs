@@ -0,0 +1,120 @@
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use polling::{Event, Events, PollError, Poller};
+
+const THREADS: usize = 4;
+
+#[test]
+fn events_are_delivered_once() -> io::Result<()> {
+    let poller = Poller::builder().concurrent_waits(true).build()?;
+    assert!(poller.concurrent_waits());
+
+    let pairs = (0..THREADS)
+        .map(|_| tcp_pair())
+        .collect::<io::Result<Vec<_>>>()?;
+    for (key, (reader, _)) in pairs.iter().enumerate() {
+        unsafe { poller.add(reader, Event::readable(key))? };
+    }
+
+    let keys = Mutex::new(Vec::new());
+    let done = AtomicUsize::new(0);
+
+    thread::scope(|s| {
+        for _ in 0..THREADS {
+            s.spawn(|| {
+                let mut events = Events::new();
+                poller
+                    .wait(&mut events, Some(Duration::from_secs(10)))
+                    .unwrap();
+                keys.lock().unwrap().extend(events.iter().map(|ev| ev.key));
+                done.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+
+        for (_, writer) in &pairs {
+            (&*writer).write_all(&[1]).unwrap();
+        }
+
+        // Wake up the threads that didn't get an event.
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while keys.lock().unwrap().len() < THREADS && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        while done.load(Ordering::SeqCst) < THREADS {
+            poller.notify().unwrap();
+            thread::sleep(Duration::from_millis(10));
+        }
+    });
+
+    // The sources are oneshot, so every event is delivered to exactly one thread.
+    let mut keys = keys.into_inner().unwrap();
+    keys.sort();
+    assert_eq!(keys, (0..THREADS).collect::<Vec<_>>());
+
+    for (reader, _) in &pairs {
+        poller.delete(reader)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn waits_block_concurrently() -> io::Result<()> {
+    let poller = Poller::builder().concurrent_waits(true).build()?;
+    let timeout = Duration::from_millis(200);
+
+    // Without concurrent waits, all but one of the threads would return immediately.
+    thread::scope(|s| {
+        for _ in 0..THREADS {
+            s.spawn(|| {
+                let start = Instant::now();
+                let mut events = Events::new();
+                poller.wait(&mut events, Some(timeout)).unwrap();
+                assert!(events.is_empty());
+                assert!(start.elapsed() >= timeout);
+            });
+        }
+    });
+
+    Ok(())
+}
+
+#[test]
+fn close_wakes_all_waiters() -> io::Result<()> {
+    let poller = Poller::builder().concurrent_waits(true).build()?;
+    let (reader, _writer) = tcp_pair()?;
+    unsafe { poller.add(&reader, Event::readable(0))? };
+
+    thread::scope(|s| {
+        let waiters = (0..THREADS)
+            .map(|_| s.spawn(|| poller.wait(&mut Events::new(), None)))
+            .collect::<Vec<_>>();
+
+        thread::sleep(Duration::from_millis(50));
+        poller.close().unwrap();
+        for waiter in waiters {
+            let err = waiter.join().unwrap().unwrap_err();
+            assert!(matches!(PollError::from(err), PollError::Closed));
+        }
+    });
+
+    // Later waits and registrations fail, but sources can still be deleted.
+    let err = poller.wait(&mut Events::new(), None).unwrap_err();
+    assert!(matches!(PollError::from(err), PollError::Closed));
+    let (other, _) = tcp_pair()?;
+    let err = unsafe { poller.add(&other, Event::readable(1)) }.unwrap_err();
+    assert!(matches!(PollError::from(err), PollError::Closed));
+    poller.delete(&reader)?;
+    poller.close()
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
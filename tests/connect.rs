@@ -0,0 +1,58 @@
+use polling::{Events, Poller};
+use socket2::{Domain, Socket, Type};
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+
+#[test]
+fn connected() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let socket = connecting(listener.local_addr().unwrap()).unwrap();
+    let poller = Poller::new().unwrap();
+    unsafe {
+        poller.add_connecting(&socket, 1).unwrap();
+    }
+
+    wait_for_connect(&poller, &socket).unwrap();
+    assert!(socket.peer_addr().is_ok());
+    poller.delete(&socket).unwrap();
+}
+
+#[test]
+fn refused() {
+    // Nothing listens on the port once the listener is dropped.
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let socket = connecting(addr).unwrap();
+    let poller = Poller::new().unwrap();
+    unsafe {
+        poller.add_connecting(&socket, 1).unwrap();
+    }
+
+    let err = wait_for_connect(&poller, &socket).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+    poller.delete(&socket).unwrap();
+}
+
+fn connecting(addr: SocketAddr) -> io::Result<Socket> {
+    let socket = Socket::new(Domain::IPV4, Type::STREAM, None)?;
+    socket.set_nonblocking(true)?;
+    // The attempt is in progress, or its error is reported by `finish_connect()`.
+    let _ = socket.connect(&addr.into());
+    Ok(socket)
+}
+
+fn wait_for_connect(poller: &Poller, socket: &Socket) -> io::Result<()> {
+    let mut events = Events::new();
+    loop {
+        events.clear();
+        poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+        assert_eq!(events.len(), 1, "the connection attempt timed out");
+        match poller.finish_connect(socket) {
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+            res => return res,
+        }
+    }
+}
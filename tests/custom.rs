@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::UdpSocket;
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use polling::{Backend, Event, PollError, PollMode, Poller, PollerBackend, RawSource};
+
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
+#[cfg(windows)]
+use std::os::windows::io::AsRawSocket;
+
+/// A backend whose sources become ready when the test says so.
+#[derive(Default)]
+struct Simulated {
+    state: Mutex<State>,
+    cond: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    sources: HashMap<RawSource, (Event, PollMode)>,
+    ready: Vec<RawSource>,
+    notified: bool,
+    /// A poller to close when the next wait starts.
+    close: Option<Weak<Poller>>,
+}
+
+impl Simulated {
+    fn fire(&self, source: RawSource) {
+        self.state.lock().unwrap().ready.push(source);
+        self.cond.notify_all();
+    }
+}
+
+struct Handle(Arc<Simulated>);
+
+impl PollerBackend for Handle {
+    fn add(&self, source: RawSource, interest: Event, mode: PollMode) -> io::Result<()> {
+        self.modify(source, interest, mode)
+    }
+
+    fn modify(&self, source: RawSource, interest: Event, mode: PollMode) -> io::Result<()> {
+        let mut state = self.0.state.lock().unwrap();
+        state.sources.insert(source, (interest, mode));
+        Ok(())
+    }
+
+    fn delete(&self, source: RawSource) -> io::Result<()> {
+        let mut state = self.0.state.lock().unwrap();
+        state.sources.remove(&source);
+        state.ready.retain(|&ready| ready != source);
+        Ok(())
+    }
+
+    fn wait(&self, events: &mut Vec<Event>, deadline: Option<Instant>) -> io::Result<()> {
+        let close = self.0.state.lock().unwrap().close.take();
+        if let Some(poller) = close.and_then(|poller| poller.upgrade()) {
+            poller.close()?;
+        }
+
+        let mut state = self.0.state.lock().unwrap();
+        loop {
+            let ready = std::mem::take(&mut state.ready);
+            for source in ready {
+                if let Some((interest, mode)) = state.sources.get_mut(&source) {
+                    if interest.readable {
+                        events.push(Event::readable(interest.key));
+                        if *mode == PollMode::Oneshot {
+                            interest.readable = false;
+                        }
+                    }
+                }
+            }
+            if !events.is_empty() || std::mem::take(&mut state.notified) {
+                return Ok(());
+            }
+
+            let now = Instant::now();
+            state = match deadline {
+                Some(deadline) if deadline <= now => return Ok(()),
+                Some(deadline) => self.0.cond.wait_timeout(state, deadline - now).unwrap().0,
+                None => self.0.cond.wait(state).unwrap(),
+            };
+        }
+    }
+
+    fn notify(&self) -> io::Result<()> {
+        self.0.state.lock().unwrap().notified = true;
+        self.0.cond.notify_all();
+        Ok(())
+    }
+}
+
+fn poller() -> (Poller, Arc<Simulated>) {
+    let sim = Arc::new(Simulated::default());
+    let poller = Poller::builder().build_custom(Handle(sim.clone())).unwrap();
+    (poller, sim)
+}
+
+#[test]
+fn events() {
+    let (poller, sim) = poller();
+    assert_eq!(poller.backend(), Backend::Custom);
+    assert!(!poller.supports_level());
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    #[cfg(unix)]
+    let raw = socket.as_raw_fd();
+    #[cfg(windows)]
+    let raw = socket.as_raw_socket();
+    unsafe {
+        poller.add(&socket, Event::readable(3)).unwrap();
+    }
+
+    let mut events = poller.new_events();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(Duration::from_millis(20));
+            sim.fire(raw);
+        });
+        poller.wait(&mut events, None).unwrap();
+    });
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 3);
+
+    // The oneshot registration is disabled until it is modified.
+    sim.fire(raw);
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    poller.modify(&socket, Event::readable(3)).unwrap();
+    sim.fire(raw);
+    poller.wait(&mut events, None).unwrap();
+    assert_eq!(events.len(), 1);
+
+    poller.delete(&socket).unwrap();
+    assert!(sim.state.lock().unwrap().sources.is_empty());
+}
+
+#[test]
+fn close_during_wait() {
+    let (poller, sim) = poller();
+    let poller = Arc::new(poller);
+
+    let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+    unsafe {
+        poller.add(&a, Event::readable(1)).unwrap();
+        poller.add(&b, Event::readable(2)).unwrap();
+    }
+    #[cfg(unix)]
+    let (a, b) = (a.as_raw_fd(), b.as_raw_fd());
+    #[cfg(windows)]
+    let (a, b) = (a.as_raw_socket(), b.as_raw_socket());
+
+    // The event of the first source is set aside for the next wait.
+    sim.fire(a);
+    sim.fire(b);
+    let event = poller.wait_for_key(2, None).unwrap().unwrap();
+    assert_eq!(event.key, 2);
+
+    // The poller is closed while the next wait is in the backend.
+    sim.state.lock().unwrap().close = Some(Arc::downgrade(&poller));
+    let mut events = poller.new_events();
+    let err = poller.wait(&mut events, None).unwrap_err();
+    assert!(matches!(PollError::from(err), PollError::Closed));
+    assert!(events.is_empty());
+
+    // The event that was set aside is not lost.
+    assert_eq!(poller.drain(&mut events).unwrap(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 1);
+    let err = poller.drain(&mut events).unwrap_err();
+    assert!(matches!(PollError::from(err), PollError::Closed));
+}
+
+#[test]
+fn notify_and_timers() {
+    let (poller, _sim) = poller();
+    let mut events = poller.new_events();
+
+    poller.notify().unwrap();
+    let start = Instant::now();
+    poller.wait(&mut events, None).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(events.is_empty());
+
+    // Timers are kept by the poller on top of the backend.
+    poller
+        .set_timer(5, Instant::now() + Duration::from_millis(20))
+        .unwrap();
+    while events.is_empty() {
+        poller.wait(&mut events, None).unwrap();
+    }
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 5);
+}
+
+#[test]
+fn builder() {
+    let sim = Arc::new(Simulated::default());
+    let poller = Poller::builder()
+        .require_backend(Backend::Custom)
+        .build_custom(Handle(sim.clone()))
+        .unwrap();
+    assert_eq!(poller.backend(), Backend::Custom);
+
+    let err = Poller::builder()
+        .require_backend(Backend::current())
+        .build_custom(Handle(sim.clone()))
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+
+    // The backend doesn't support level-triggered mode.
+    let err = Poller::builder()
+        .mode(PollMode::Level)
+        .build_custom(Handle(sim))
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+
+    let err = Poller::builder()
+        .require_backend(Backend::Custom)
+        .build()
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+}
+
+#[cfg(any(
+    all(
+        any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "redox",
+            target_os = "illumos",
+            target_os = "solaris",
+            target_vendor = "apple",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd",
+            target_os = "dragonfly",
+        ),
+        not(polling_test_poll_backend)
+    ),
+    windows
+))]
+#[test]
+fn no_system_handle() {
+    let (poller, _sim) = poller();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::OwnedFd;
+
+        assert!(poller.try_as_fd().is_none());
+        let err = OwnedFd::try_from(poller).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+    #[cfg(windows)]
+    assert!(poller.try_as_handle().is_none());
+}
@@ -0,0 +1,38 @@
+//! Tests for draining a poller on a dispatch queue on Apple platforms.
+
+#![cfg(all(target_vendor = "apple", not(polling_test_poll_backend)))]
+
+use polling::os::kqueue::{DispatchSource, QosClass};
+use polling::{Event, Poller};
+
+use std::net::UdpSocket;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn global_queue() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+    let (sender, receiver) = mpsc::channel();
+    let source = DispatchSource::global_queue(poller.clone(), QosClass::Utility, move |events| {
+        for ev in events.unwrap().iter() {
+            sender.send(ev.key).ok();
+        }
+    })
+    .unwrap();
+
+    // A registered source runs the handler on the queue.
+    unsafe {
+        poller.add(&socket, Event::writable(1)).unwrap();
+    }
+    assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), 1);
+
+    // Once dropped, the source stops draining the poller.
+    drop(source);
+    poller.modify(&socket, Event::writable(2)).unwrap();
+    assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+
+    poller.delete(&socket).unwrap();
+}
@@ -0,0 +1,100 @@
+use polling::{Event, PollError, Poller};
+use std::io;
+use std::net::TcpListener;
+
+#[test]
+fn registration_errors() {
+    let poller = Poller::new().unwrap();
+    let socket = TcpListener::bind("127.0.0.1:0").unwrap();
+
+    let err = poller.modify(&socket, Event::readable(1)).unwrap_err();
+    assert!(matches!(PollError::from(err), PollError::NotRegistered));
+
+    unsafe {
+        let err = poller
+            .add(&socket, Event::readable(poller.notify_key()))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(matches!(PollError::from(err), PollError::ReservedKey));
+
+        poller.add(&socket, Event::readable(1)).unwrap();
+        let err = poller.add(&socket, Event::readable(2)).unwrap_err();
+        assert!(matches!(PollError::from(err), PollError::AlreadyRegistered));
+    }
+
+    poller.delete(&socket).unwrap();
+}
+
+#[test]
+fn io_error_conversion() {
+    let err = io::Error::from(PollError::NotRegistered);
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    assert!(matches!(PollError::from(err), PollError::NotRegistered));
+
+    // Errors of the system are classified by their kind.
+    let err = io::Error::from(io::ErrorKind::AlreadyExists);
+    assert!(matches!(PollError::from(err), PollError::AlreadyRegistered));
+
+    let err = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+    match PollError::from(err) {
+        PollError::Backend(err) => assert_eq!(err.kind(), io::ErrorKind::PermissionDenied),
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn strict_errors() {
+    use polling::{Events, PollerBuilder};
+    use std::net::UdpSocket;
+    use std::time::Duration;
+
+    let poller = PollerBuilder::new().strict_errors(true).build().unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    unsafe {
+        poller.add(&socket, Event::all(1)).unwrap();
+    }
+
+    // The oneshot filter that fired is gone, which isn't an error.
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    poller.modify(&socket, Event::readable(1)).unwrap();
+    poller.modify(&socket, Event::none(1)).unwrap();
+    poller.delete(&socket).unwrap();
+
+    let err = poller.delete(&socket).unwrap_err();
+    assert!(matches!(PollError::from(err), PollError::NotRegistered));
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "misuse of the poller")]
+fn misuse_delete_twice() {
+    let poller = polling::PollerBuilder::new()
+        .misuse_checks(true)
+        .build()
+        .unwrap();
+    let socket = TcpListener::bind("127.0.0.1:0").unwrap();
+    unsafe {
+        poller.add(&socket, Event::readable(1)).unwrap();
+    }
+    poller.delete(&socket).unwrap();
+    let _ = poller.delete(&socket);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "dropped with 1 registered sources")]
+fn misuse_drop_registered() {
+    let poller = polling::PollerBuilder::new()
+        .misuse_checks(true)
+        .build()
+        .unwrap();
+    let socket = TcpListener::bind("127.0.0.1:0").unwrap();
+    unsafe {
+        poller.add(&socket, Event::readable(1)).unwrap();
+    }
+    drop(poller);
+}
@@ -0,0 +1,35 @@
+//! Tests for the `EVFILT_EMPTY` filter on FreeBSD.
+
+#![cfg(all(target_os = "freebsd", not(polling_test_poll_backend)))]
+
+use polling::os::kqueue::{Empty, PollerKqueueExt};
+use polling::{Events, PollMode, Poller};
+
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::io::AsFd;
+use std::time::Duration;
+
+#[test]
+fn send_buffer_drained() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut write = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let _read = listener.accept().unwrap().0;
+
+    let poller = Poller::new().unwrap();
+    write.write_all(b"hello").unwrap();
+    poller
+        .add_filter(Empty(write.as_fd()), 5, PollMode::Oneshot)
+        .unwrap();
+
+    // Once the peer has acknowledged the data, the event is delivered as writable.
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    let event = events.iter().next().unwrap();
+    assert_eq!(event.key, 5);
+    assert!(event.writable);
+
+    poller.delete_filter(Empty(write.as_fd())).unwrap();
+}
@@ -0,0 +1,64 @@
+#![cfg(unix)]
+
+use polling::os::unix::PollerUnixExt;
+use polling::{Event, Events, Poller};
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+#[test]
+fn reinit_after_fork() {
+    let mut poller = Poller::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+    write.write_all(&[1]).unwrap();
+    unsafe {
+        poller.add(&read, Event::readable(1)).unwrap();
+    }
+
+    match unsafe { libc::fork() } {
+        -1 => panic!("fork failed: {}", io::Error::last_os_error()),
+        0 => {
+            // Exit successfully only if the child gets the event and notifications.
+            let mut child = || -> io::Result<bool> {
+                poller.reinit_after_fork()?;
+                let mut events = Events::new();
+                poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+                let delivered = events.iter().any(|ev| ev.key == 1);
+
+                poller.notify()?;
+                events.clear();
+                poller.wait(&mut events, Some(Duration::from_secs(5)))?;
+                Ok(delivered && poller.registered_len() == 1)
+            };
+            let code = if matches!(child(), Ok(true)) { 0 } else { 1 };
+            unsafe { libc::_exit(code) }
+        }
+        pid => {
+            let mut status = 0;
+            assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+            assert!(libc::WIFEXITED(status));
+            assert_eq!(libc::WEXITSTATUS(status), 0);
+        }
+    }
+
+    // The parent still gets its own events and notifications.
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.iter().next().unwrap().key, 1);
+
+    poller.notify().unwrap();
+    events.clear();
+    poller.wait(&mut events, None).unwrap();
+    assert!(events.is_empty());
+
+    poller.delete(&read).unwrap();
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
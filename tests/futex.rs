@@ -0,0 +1,47 @@
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use polling::os::futex::FutexPoller;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn timeout() {
+    let poller = FutexPoller::new();
+    let start = Instant::now();
+    let res = poller.wait(Some(Duration::from_millis(20))).unwrap();
+    assert!(res.is_timed_out());
+    assert!(!res.is_notified());
+    assert!(start.elapsed() >= Duration::from_millis(20));
+
+    assert!(poller.wait(Some(Duration::ZERO)).unwrap().is_timed_out());
+}
+
+#[test]
+fn notify_before_wait() {
+    let poller = FutexPoller::new();
+    poller.notify();
+    poller.notify();
+
+    // Notifications don't pile up.
+    assert!(poller.wait(None).unwrap().is_notified());
+    assert!(poller
+        .wait(Some(Duration::from_millis(10)))
+        .unwrap()
+        .is_timed_out());
+}
+
+#[test]
+fn notify_from_thread() {
+    let poller = Arc::new(FutexPoller::new());
+    let notifier = poller.clone();
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        notifier.notify();
+    });
+
+    let res = poller.wait(Some(Duration::from_secs(10))).unwrap();
+    assert!(res.is_notified());
+    assert_eq!(res.events(), 0);
+    handle.join().unwrap();
+}
@@ -0,0 +1,107 @@
+use std::io;
+use std::net::UdpSocket;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use polling::{Event, Events, Hooks, PollMode, PollerBuilder, RawSource, WaitResult};
+
+#[derive(Default)]
+struct Recorder(Mutex<Vec<String>>);
+
+impl Recorder {
+    fn push(&self, record: String) {
+        self.0.lock().unwrap().push(record);
+    }
+
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl Hooks for Recorder {
+    fn on_add(&self, _: RawSource, interest: Event, _: PollMode, result: Result<(), &io::Error>) {
+        self.push(format!("add {} {}", interest.key, result.is_ok()));
+    }
+
+    fn on_modify(
+        &self,
+        _: RawSource,
+        interest: Event,
+        _: PollMode,
+        result: Result<(), &io::Error>,
+    ) {
+        self.push(format!("modify {} {}", interest.key, result.is_ok()));
+    }
+
+    fn on_delete(&self, _: RawSource, result: Result<(), &io::Error>) {
+        self.push(format!("delete {}", result.is_ok()));
+    }
+
+    fn on_notify(&self, result: Result<(), &io::Error>) {
+        self.push(format!("notify {}", result.is_ok()));
+    }
+
+    fn on_wait(&self, result: Result<WaitResult, &io::Error>) {
+        let result = result.unwrap();
+        self.push(format!("wait {} {}", result.events(), result.is_notified()));
+    }
+}
+
+#[test]
+fn operations() {
+    let recorder = Arc::new(Recorder::default());
+    let poller = PollerBuilder::new()
+        .hooks(recorder.clone())
+        .build()
+        .unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+    unsafe {
+        poller.add(&socket, Event::writable(1)).unwrap();
+        // Failed operations are reported as well.
+        poller.add(&socket, Event::writable(1)).unwrap_err();
+    }
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    poller.modify(&socket, Event::readable(2)).unwrap();
+    poller.notify().unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    poller.delete(&socket).unwrap();
+
+    assert_eq!(
+        recorder.take(),
+        [
+            "add 1 true",
+            "add 1 false",
+            "wait 1 false",
+            "modify 2 true",
+            "notify true",
+            "wait 0 true",
+            "delete true",
+        ]
+    );
+}
+
+#[test]
+fn split() {
+    let recorder = Arc::new(Recorder::default());
+    let poller = PollerBuilder::new()
+        .hooks(recorder.clone())
+        .build()
+        .unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+    // Each half of a split source is reported.
+    unsafe {
+        poller
+            .add_split(&socket, Event::readable(1), Event::writable(2))
+            .unwrap();
+    }
+    poller.delete(&socket).unwrap();
+    assert_eq!(recorder.take(), ["add 1 true", "add 2 true", "delete true"]);
+}
@@ -0,0 +1,68 @@
+use polling::{Event, Events, PollMode, Poller};
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+#[test]
+fn once_per_mode() {
+    for mode in [
+        PollMode::Oneshot,
+        PollMode::Level,
+        PollMode::Edge,
+        PollMode::EdgeOneshot,
+    ] {
+        let poller = match Poller::builder().mode(mode).initial_readiness(true).build() {
+            Ok(poller) => poller,
+            Err(err) if err.kind() == io::ErrorKind::Unsupported => continue,
+            Err(err) => panic!("{}", err),
+        };
+
+        let (read, mut write) = tcp_pair().unwrap();
+        write.write_all(&[1]).unwrap();
+        // Give the byte time to arrive.
+        std::thread::sleep(Duration::from_millis(50));
+        unsafe {
+            poller.add(&read, Event::readable(1)).unwrap();
+        }
+
+        let mut events = Events::new();
+        poller.wait(&mut events, Some(Duration::ZERO)).unwrap();
+        let ev = events.iter().next().unwrap();
+        assert_eq!(events.len(), 1, "{:?}", mode);
+        assert!(ev.readable && !ev.writable);
+
+        // Oneshot registrations are disabled after the event.
+        if matches!(mode, PollMode::Oneshot | PollMode::EdgeOneshot) {
+            events.clear();
+            poller
+                .wait(&mut events, Some(Duration::from_millis(50)))
+                .unwrap();
+            assert!(events.is_empty(), "{:?}", mode);
+        }
+
+        poller.delete(&read).unwrap();
+    }
+}
+
+#[test]
+fn not_ready() {
+    let poller = Poller::builder().initial_readiness(true).build().unwrap();
+    let (read, _write) = tcp_pair().unwrap();
+    unsafe {
+        poller.add(&read, Event::readable(1)).unwrap();
+    }
+
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+    poller.delete(&read).unwrap();
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
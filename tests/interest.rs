@@ -0,0 +1,279 @@
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use polling::{Event, Events, Interest, Poller};
+
+#[test]
+fn flags() {
+    let interest = Interest::READABLE | Interest::WRITABLE;
+    assert!(interest.contains(Interest::READABLE));
+    assert!(interest.intersects(Interest::WRITABLE | Interest::HANGUP));
+    assert!(!interest.contains(Interest::WRITABLE | Interest::HANGUP));
+    assert_eq!(interest & Interest::WRITABLE, Interest::WRITABLE);
+    assert_eq!(interest.difference(Interest::READABLE), Interest::WRITABLE);
+    assert!((!Interest::NONE).contains(interest | Interest::EXCLUSIVE));
+    assert!(Interest::default().is_empty());
+
+    assert_eq!(format!("{:?}", interest), "READABLE | WRITABLE");
+    assert_eq!(format!("{:?}", Interest::NONE), "NONE");
+}
+
+#[test]
+fn event_round_trip() {
+    let event = Event::from_interest(1, Interest::READABLE | Interest::WRITABLE);
+    assert_eq!(event, Event::all(1));
+    assert_eq!(event.interest(), Interest::READABLE | Interest::WRITABLE);
+
+    let mut event = Event::all(2);
+    event.set_interest(Interest::WRITABLE);
+    assert_eq!(event, Event::writable(2));
+}
+
+#[test]
+fn delivered_events() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    let (reader, mut writer) = tcp_pair()?;
+
+    unsafe { poller.add(&reader, Event::from_interest(1, Interest::READABLE))? };
+    writer.write_all(&[1])?;
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+
+    let interest = events.iter().next().unwrap().interest();
+    assert!(interest.is_readable());
+    assert!(!interest.is_writable());
+
+    poller.delete(&reader)?;
+    Ok(())
+}
+
+#[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    not(polling_test_poll_backend)
+))]
+#[test]
+fn exclusive() -> io::Result<()> {
+    use polling::PollMode;
+
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    let (reader, mut writer) = tcp_pair()?;
+
+    let interest = Event::from_interest(1, Interest::READABLE | Interest::EXCLUSIVE);
+    unsafe { poller.add_with_mode(&reader, interest, PollMode::Level)? };
+    writer.write_all(&[1])?;
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    assert_eq!(events.len(), 1);
+
+    // Exclusive sources can't be modified.
+    let changed = Event::from_interest(
+        1,
+        Interest::READABLE | Interest::WRITABLE | Interest::EXCLUSIVE,
+    );
+    assert!(poller
+        .modify_with_mode(&reader, changed, PollMode::Level)
+        .is_err());
+
+    poller.delete(&reader)?;
+    Ok(())
+}
+
+#[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    not(polling_test_poll_backend)
+))]
+#[test]
+fn wakeup() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    let (reader, mut writer) = tcp_pair()?;
+
+    // Without `CAP_BLOCK_SUSPEND`, the kernel ignores the flag instead of failing.
+    let interest = Event::from_interest(1, Interest::READABLE | Interest::WAKEUP);
+    assert_eq!(interest.interest(), Interest::READABLE | Interest::WAKEUP);
+    unsafe { poller.add(&reader, interest)? };
+    writer.write_all(&[1])?;
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    assert_eq!(events.len(), 1);
+    assert!(events.iter().next().unwrap().readable);
+
+    poller.delete(&reader)?;
+    Ok(())
+}
+
+#[cfg(all(
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_vendor = "apple",
+        target_os = "freebsd",
+    ),
+    not(polling_test_poll_backend)
+))]
+#[test]
+fn hangup_only() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    let (reader, mut writer) = tcp_pair()?;
+    unsafe { poller.add(&reader, Event::from_interest(1, Interest::HANGUP))? };
+
+    // Data doesn't wake up the poller.
+    writer.write_all(&[1])?;
+    poller.wait(&mut events, Some(Duration::from_millis(100)))?;
+    assert!(events.is_empty());
+
+    drop(writer);
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    assert_eq!(events.len(), 1);
+    let event = events.iter().next().unwrap();
+    assert_eq!(event.key, 1);
+    assert!(event.is_interrupt());
+
+    poller.delete(&reader)?;
+    Ok(())
+}
+
+#[cfg(all(
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_vendor = "apple",
+        target_os = "freebsd",
+    ),
+    not(polling_test_poll_backend)
+))]
+#[test]
+fn delete_on_hangup() -> io::Result<()> {
+    use polling::PollMode;
+
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    let (reader, writer) = tcp_pair()?;
+    let interest = Event::readable(1).with_interrupt().with_delete_on_hangup();
+    let mode = if poller.supports_level() {
+        PollMode::Level
+    } else {
+        PollMode::Oneshot
+    };
+    unsafe { poller.add_with_mode(&reader, interest, mode)? };
+    assert!(poller
+        .registered(&reader)
+        .unwrap()
+        .interest()
+        .deletes_on_hangup());
+
+    // The hangup is delivered once, and the source is deleted along with it.
+    drop(writer);
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    assert_eq!(events.len(), 1);
+    assert!(events.iter().next().unwrap().is_interrupt());
+    assert!(poller.registered(&reader).is_none());
+
+    events.clear();
+    poller.wait(&mut events, Some(Duration::from_millis(100)))?;
+    assert!(events.is_empty());
+    Ok(())
+}
+
+#[cfg(all(
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_vendor = "apple",
+        target_os = "freebsd",
+    ),
+    not(polling_test_poll_backend)
+))]
+#[test]
+fn delete_on_hangup_drain() -> io::Result<()> {
+    use polling::PollMode;
+    use std::time::Instant;
+
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    let (reader, writer) = tcp_pair()?;
+    let interest = Event::readable(1).with_interrupt().with_delete_on_hangup();
+    let mode = if poller.supports_level() {
+        PollMode::Level
+    } else {
+        PollMode::Oneshot
+    };
+    unsafe { poller.add_with_mode(&reader, interest, mode)? };
+    assert_eq!(poller.registered_len(), 1);
+
+    // A hangup collected by `drain()` deletes the source as well.
+    drop(writer);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while events.is_empty() && Instant::now() < deadline {
+        poller.drain(&mut events)?;
+    }
+    assert_eq!(events.len(), 1);
+    assert!(events.iter().next().unwrap().is_interrupt());
+    assert_eq!(poller.registered_len(), 0);
+
+    events.clear();
+    poller.drain(&mut events)?;
+    assert!(events.is_empty());
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn priority_only() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    let (reader, mut writer) = tcp_pair()?;
+    unsafe { poller.add(&reader, Event::from_interest(1, Interest::PRIORITY))? };
+
+    // Regular data doesn't wake up the poller.
+    writer.write_all(&[1])?;
+    poller.wait(&mut events, Some(Duration::from_millis(100)))?;
+    assert!(events.is_empty());
+
+    socket2::SockRef::from(&writer).send_out_of_band(&[2])?;
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    assert_eq!(events.len(), 1);
+    let event = events.iter().next().unwrap();
+    assert_eq!(event.key, 1);
+    assert!(event.is_priority());
+
+    poller.delete(&reader)?;
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn error_only() -> io::Result<()> {
+    use std::net::UdpSocket;
+
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    let socket = UdpSocket::bind("127.0.0.1:0")?;
+    unsafe { poller.add(&socket, Event::from_interest(1, Interest::ERROR))? };
+
+    // Datagrams don't wake up the poller.
+    let peer = UdpSocket::bind("127.0.0.1:0")?;
+    peer.send_to(&[1], socket.local_addr()?)?;
+    poller.wait(&mut events, Some(Duration::from_millis(100)))?;
+    assert!(events.is_empty());
+
+    // Sending to a closed port is answered with an ICMP error.
+    let closed = peer.local_addr()?;
+    drop(peer);
+    socket.connect(closed)?;
+    socket.send(&[1])?;
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().is_err(), Some(true));
+
+    poller.delete(&socket)?;
+    Ok(())
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
@@ -36,6 +36,11 @@ fn basic_io() {
         events.iter().next().unwrap().with_no_extra(),
         Event::readable(1)
     );
+
+    // The delivered events are shown in debug output.
+    let debug = format!("{:?}", events);
+    assert!(debug.starts_with("[Event { key: 1, readable: true, writable: false"));
+
     poller.delete(&read).unwrap();
 }
 
@@ -81,6 +86,30 @@ fn insert_twice() {
     poller.delete(&read).unwrap();
 }
 
+#[test]
+fn duplicate_registration() {
+    let (read, _write) = tcp_pair().unwrap();
+    let poller = Poller::new().unwrap();
+
+    // Sources that were never added can't be modified.
+    assert_eq!(
+        poller.modify(&read, Event::readable(1)).unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+
+    // Adding a source again fails, even with a different key.
+    unsafe {
+        poller.add(&read, Event::readable(1)).unwrap();
+        assert_eq!(
+            poller.add(&read, Event::writable(2)).unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+    }
+    assert_eq!(poller.registered(&read).unwrap().interest().key, 1);
+
+    poller.delete(&read).unwrap();
+}
+
 /// Test that calling `wait` appends events, as [documented], rather than
 /// overwriting them.
 ///
@@ -136,6 +165,602 @@ fn append_events() {
     }
 }
 
+#[test]
+fn append_from_pollers() {
+    use std::net::UdpSocket;
+
+    let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let first = Poller::new().unwrap();
+    let second = Poller::new().unwrap();
+    unsafe {
+        first.add(&a, Event::writable(1)).unwrap();
+        second.add(&b, Event::writable(2)).unwrap();
+    }
+
+    // Events from several pollers accumulate until the list is cleared.
+    let mut events = Events::new();
+    first
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    second
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    let mut keys = events.iter().map(|ev| ev.key).collect::<Vec<_>>();
+    keys.sort_unstable();
+    assert_eq!(keys, [1, 2]);
+
+    events.clear();
+    assert!(events.is_empty());
+    first.delete(&a).unwrap();
+    second.delete(&b).unwrap();
+}
+
+#[test]
+fn wait_with() {
+    let poller = Poller::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+    unsafe {
+        poller.add(&read, Event::readable(1)).unwrap();
+    }
+
+    let mut keys = Vec::new();
+    let n = poller
+        .wait_with(Some(Duration::from_secs(0)), |ev| keys.push(ev.key))
+        .unwrap();
+    assert_eq!(n, 0);
+    assert!(keys.is_empty());
+
+    write.write_all(&[1]).unwrap();
+    let n = poller
+        .wait_with(Some(Duration::from_secs(1)), |ev| {
+            // Waiting again from within the callback uses a separate buffer.
+            poller
+                .wait_with(Some(Duration::from_secs(0)), |_| panic!("no more events"))
+                .unwrap();
+            keys.push(ev.key);
+        })
+        .unwrap();
+    assert_eq!(n, 1);
+    assert_eq!(keys, [1]);
+
+    poller.delete(&read).unwrap();
+}
+
+#[test]
+fn wait_into() {
+    let poller = Poller::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+    unsafe {
+        poller.add(&read, Event::readable(1)).unwrap();
+    }
+
+    let mut events = vec![Event::none(0)];
+    write.write_all(&[1]).unwrap();
+    let n = poller
+        .wait_into(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(n, 1);
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[1].key, 1);
+
+    poller.delete(&read).unwrap();
+}
+
+#[test]
+fn iterate_events() {
+    let poller = Poller::new().unwrap();
+    let (read1, mut write1) = tcp_pair().unwrap();
+    let (read2, mut write2) = tcp_pair().unwrap();
+    unsafe {
+        poller.add(&read1, Event::readable(1)).unwrap();
+        poller.add(&read2, Event::readable(2)).unwrap();
+    }
+
+    write1.write_all(&[1]).unwrap();
+    write2.write_all(&[1]).unwrap();
+    let mut events = Events::new();
+    while events.len() < 2 {
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+    }
+
+    let mut keys = Vec::new();
+    for ev in &events {
+        keys.push(ev.key);
+    }
+    keys.sort_unstable();
+    assert_eq!(keys, [1, 2]);
+
+    let iter = events.iter();
+    assert_eq!(iter.len(), 2);
+    assert_eq!(iter.size_hint(), (2, Some(2)));
+    let forward: Vec<_> = events.iter().map(|ev| ev.key).collect();
+    let mut backward: Vec<_> = events.iter().rev().map(|ev| ev.key).collect();
+    backward.reverse();
+    assert_eq!(forward, backward);
+
+    poller.delete(&read1).unwrap();
+    poller.delete(&read2).unwrap();
+}
+
+#[test]
+fn retain_events() {
+    let poller = Poller::new().unwrap();
+    let (read1, mut write1) = tcp_pair().unwrap();
+    let (read2, mut write2) = tcp_pair().unwrap();
+    let (read3, mut write3) = tcp_pair().unwrap();
+    unsafe {
+        poller.add(&read1, Event::readable(1)).unwrap();
+        poller.add(&read2, Event::readable(2)).unwrap();
+        poller.add(&read3, Event::readable(3)).unwrap();
+    }
+
+    write1.write_all(&[1]).unwrap();
+    write2.write_all(&[1]).unwrap();
+    let mut events = Events::new();
+    while events.len() < 2 {
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+    }
+
+    // The kept events stay in the buffer, and new ones are appended after them.
+    events.retain(|ev| ev.key == 2);
+    assert_eq!(events.len(), 1);
+    write3.write_all(&[1]).unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    let keys: Vec<_> = events.iter().map(|ev| ev.key).collect();
+    assert_eq!(keys, [2, 3]);
+
+    events.retain(|_| false);
+    assert!(events.is_empty());
+
+    poller.delete(&read1).unwrap();
+    poller.delete(&read2).unwrap();
+    poller.delete(&read3).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn read_low_watermark() {
+    use polling::PollMode;
+    use std::num::NonZeroUsize;
+
+    let poller = Poller::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+    let interest = Event::readable(1).with_read_low_watermark(NonZeroUsize::new(4).unwrap());
+    unsafe {
+        poller
+            .add_with_mode(&read, interest, PollMode::Level)
+            .unwrap();
+    }
+
+    // A short write doesn't make the source readable.
+    let mut events = Events::new();
+    write.write_all(&[1, 2]).unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    // Reaching the watermark does.
+    write.write_all(&[3, 4]).unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert!(events.iter().next().unwrap().readable);
+
+    // The registration remembers the watermark.
+    assert_eq!(
+        poller
+            .registered(&read)
+            .unwrap()
+            .interest()
+            .read_low_watermark(),
+        NonZeroUsize::new(4)
+    );
+
+    poller.delete(&read).unwrap();
+}
+
+#[test]
+fn write_low_watermark() {
+    use std::num::NonZeroUsize;
+
+    let poller = Poller::new().unwrap();
+    let (read, _write) = tcp_pair().unwrap();
+    let chunk = NonZeroUsize::new(4096).unwrap();
+    let interest = Event::writable(1).with_write_low_watermark(chunk);
+    let res = unsafe { poller.add(&read, interest) };
+
+    // The send low watermark can't be changed everywhere.
+    if cfg!(any(target_os = "linux", target_os = "android", windows)) {
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::Unsupported);
+        assert!(poller.registered(&read).is_none());
+        return;
+    }
+
+    res.unwrap();
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert!(events.iter().next().unwrap().writable);
+    assert_eq!(
+        poller
+            .registered(&read)
+            .unwrap()
+            .interest()
+            .write_low_watermark(),
+        Some(chunk)
+    );
+
+    poller.delete(&read).unwrap();
+}
+
+#[test]
+fn take_error() {
+    use socket2::{Domain, Socket, Type};
+
+    // Find a port that nothing listens on.
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let socket = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+    socket.set_nonblocking(true).unwrap();
+    let poller = Poller::new().unwrap();
+    unsafe {
+        poller.add(&socket, Event::writable(1)).unwrap();
+    }
+    let _ = socket.connect(&addr.into());
+
+    // The failed connection is reported, and the error is stored in the socket.
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(10)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    let err = poller.take_error(&socket).unwrap().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+
+    // Taking the error clears it.
+    assert!(poller.take_error(&socket).unwrap().is_none());
+
+    poller.delete(&socket).unwrap();
+}
+
+#[test]
+fn delete_all() {
+    let poller = Poller::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+    write.write_all(&[1]).unwrap();
+    unsafe {
+        poller.add(&read, Event::readable(1)).unwrap();
+        poller.add(&write, Event::writable(2)).unwrap();
+    }
+
+    poller.delete_all().unwrap();
+    assert_eq!(poller.registered_len(), 0);
+
+    // No events are delivered, but notifications still work.
+    let mut events = Events::new();
+    poller.notify().unwrap();
+    poller.wait(&mut events, None).unwrap();
+    assert!(events.is_empty());
+
+    // The sources can be added again.
+    unsafe {
+        poller.add(&read, Event::readable(3)).unwrap();
+    }
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.iter().next().unwrap().key, 3);
+    poller.delete(&read).unwrap();
+}
+
+#[test]
+fn wait_limit() {
+    use std::net::UdpSocket;
+    use std::num::NonZeroUsize;
+
+    let poller = Poller::new().unwrap();
+    let sockets = (0..3)
+        .map(|_| UdpSocket::bind("127.0.0.1:0").unwrap())
+        .collect::<Vec<_>>();
+    for (key, socket) in sockets.iter().enumerate() {
+        unsafe {
+            poller.add(socket, Event::writable(key)).unwrap();
+        }
+    }
+
+    // The events beyond the limit are delivered by the next wait.
+    let mut events = Events::new();
+    let limit = NonZeroUsize::new(2).unwrap();
+    poller
+        .wait_limit(&mut events, Some(Duration::from_secs(1)), limit)
+        .unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events.capacity(), Events::new().capacity());
+
+    poller
+        .wait_limit(&mut events, Some(Duration::from_secs(1)), limit)
+        .unwrap();
+    let mut keys = events.iter().map(|ev| ev.key).collect::<Vec<_>>();
+    keys.sort_unstable();
+    assert_eq!(keys, [0, 1, 2]);
+
+    for socket in &sockets {
+        poller.delete(socket).unwrap();
+    }
+}
+
+#[test]
+fn wait_limit_fair() {
+    use polling::PollMode;
+    use std::collections::HashSet;
+    use std::net::UdpSocket;
+    use std::num::NonZeroUsize;
+
+    let poller = Poller::new().unwrap();
+    let sockets = (0..4)
+        .map(|_| UdpSocket::bind("127.0.0.1:0").unwrap())
+        .collect::<Vec<_>>();
+    for (key, socket) in sockets.iter().enumerate() {
+        unsafe {
+            poller
+                .add_with_mode(socket, Event::writable(key), PollMode::Level)
+                .unwrap();
+        }
+    }
+
+    // All sockets stay writable, but none of them is starved by the others.
+    let mut seen = HashSet::new();
+    let mut events = Events::new();
+    let limit = NonZeroUsize::new(1).unwrap();
+    for _ in 0..sockets.len() {
+        events.clear();
+        poller
+            .wait_limit(&mut events, Some(Duration::from_secs(1)), limit)
+            .unwrap();
+        seen.extend(events.iter().map(|ev| ev.key));
+    }
+    assert_eq!(seen.len(), sockets.len());
+
+    for socket in &sockets {
+        poller.delete(socket).unwrap();
+    }
+}
+
+#[test]
+fn events_grow() {
+    use std::net::UdpSocket;
+
+    let poller = Poller::new().unwrap();
+    let sockets = (0..100)
+        .map(|_| UdpSocket::bind("127.0.0.1:0").unwrap())
+        .collect::<Vec<_>>();
+    for (key, socket) in sockets.iter().enumerate() {
+        unsafe {
+            poller.add(socket, Event::writable(key)).unwrap();
+        }
+    }
+
+    // The buffer starts out small and grows when a wait fills it.
+    let mut events = Events::new();
+    let mut batches = Vec::new();
+    let mut keys = Vec::new();
+    while keys.len() < sockets.len() {
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        batches.push(events.len());
+        keys.extend(events.iter().map(|ev| ev.key));
+    }
+    assert!(batches[0] < sockets.len());
+    assert!(batches.len() > 1 && batches[1] > batches[0]);
+    keys.sort_unstable();
+    keys.dedup();
+    assert_eq!(keys.len(), sockets.len());
+    assert_eq!(events.capacity(), Events::new().capacity());
+
+    for socket in &sockets {
+        poller.delete(socket).unwrap();
+    }
+}
+
+#[test]
+fn read_write_merged() {
+    let poller = Poller::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+    write.write_all(&[1]).unwrap();
+    polling::poll_once(
+        &read,
+        polling::Interest::READABLE,
+        Some(Duration::from_secs(1)),
+    )
+    .unwrap()
+    .unwrap();
+
+    // A source that is readable and writable at once is reported in a single event.
+    unsafe {
+        poller.add(&read, Event::all(1)).unwrap();
+    }
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    let ev = events.iter().next().unwrap();
+    assert!(ev.readable && ev.writable);
+
+    poller.delete(&read).unwrap();
+}
+
+#[test]
+fn may_have_more() {
+    use std::net::UdpSocket;
+    use std::num::NonZeroUsize;
+
+    let poller = Poller::new().unwrap();
+    let sockets = (0..4)
+        .map(|_| UdpSocket::bind("127.0.0.1:0").unwrap())
+        .collect::<Vec<_>>();
+    for (key, socket) in sockets.iter().enumerate() {
+        unsafe {
+            poller.add(socket, Event::writable(key)).unwrap();
+        }
+    }
+
+    // A wait that fills the buffer may have left events behind.
+    let mut events = Events::with_capacity(NonZeroUsize::new(2).unwrap());
+    let result = poller
+        .wait_result(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(result.events(), 2);
+    assert!(result.may_have_more());
+
+    // Once they are all delivered, a wait doesn't fill it anymore.
+    let mut delivered = 2;
+    loop {
+        events.clear();
+        let result = poller
+            .wait_result(&mut events, Some(Duration::ZERO))
+            .unwrap();
+        delivered += result.events();
+        if !result.may_have_more() {
+            break;
+        }
+    }
+    assert_eq!(delivered, sockets.len());
+
+    for socket in &sockets {
+        poller.delete(socket).unwrap();
+    }
+}
+
+#[test]
+fn drain() {
+    use polling::{PollMode, PollerBuilder};
+    use std::net::UdpSocket;
+    use std::num::NonZeroUsize;
+
+    let sockets = (0..10)
+        .map(|_| UdpSocket::bind("127.0.0.1:0").unwrap())
+        .collect::<Vec<_>>();
+    for mode in [PollMode::Oneshot, PollMode::Level] {
+        let poller = PollerBuilder::new().drain(true).mode(mode).build().unwrap();
+        for (key, socket) in sockets.iter().enumerate() {
+            unsafe {
+                poller.add(socket, Event::writable(key)).unwrap();
+            }
+        }
+
+        // One wait delivers every source, even in level-triggered mode where they keep being
+        // reported.
+        let mut events = Events::with_capacity(NonZeroUsize::new(3).unwrap());
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(events.key_count(), sockets.len());
+
+        for socket in &sockets {
+            poller.delete(socket).unwrap();
+        }
+    }
+}
+
+#[test]
+fn modify_one_direction() {
+    use polling::PollMode;
+
+    let (reader, mut writer) = tcp_pair().unwrap();
+    writer.write_all(&[1]).unwrap();
+    let poller = Poller::new().unwrap();
+    unsafe {
+        poller
+            .add_with_mode(&reader, Event::all(1), PollMode::Level)
+            .unwrap();
+    }
+
+    // Only the direction that is still of interest is reported, including after setting the
+    // same interest again.
+    let mut events = Events::new();
+    for interest in [
+        Event::writable(1),
+        Event::readable(1),
+        Event::readable(1),
+        Event::all(1),
+    ] {
+        poller
+            .modify_with_mode(&reader, interest, PollMode::Level)
+            .unwrap();
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(10)))
+            .unwrap();
+        let ev = events.iter().next().unwrap();
+        assert_eq!(
+            (ev.readable, ev.writable),
+            (interest.readable, interest.writable)
+        );
+    }
+
+    poller.delete(&reader).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn keeps_blocking_mode() {
+    use std::os::unix::io::AsRawFd;
+
+    let poller = Poller::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+    let flags = || unsafe { libc::fcntl(read.as_raw_fd(), libc::F_GETFL) };
+    let before = flags();
+    assert_eq!(before & libc::O_NONBLOCK, 0);
+
+    unsafe {
+        poller.add(&read, Event::readable(1)).unwrap();
+    }
+    assert_eq!(flags(), before);
+
+    // Events are still delivered for the blocking socket.
+    write.write_all(&[1]).unwrap();
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.iter().next().unwrap().key, 1);
+
+    poller.delete(&read).unwrap();
+    assert_eq!(flags(), before);
+}
+
+#[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    not(polling_test_poll_backend)
+))]
+#[test]
+fn unpollable_file() {
+    let poller = Poller::new().unwrap();
+    let file = std::fs::File::open(file!()).unwrap();
+
+    // epoll can't poll regular files.
+    let err = unsafe { poller.add(&file, Event::readable(1)) }.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    assert!(err.to_string().contains("epoll can't poll a regular file"));
+}
+
 fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
     let listener = TcpListener::bind("127.0.0.1:0")?;
     let a = TcpStream::connect(listener.local_addr()?)?;
@@ -0,0 +1,49 @@
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use polling::os::io_uring::RingEventfd;
+use polling::{Events, Poller};
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+#[test]
+fn completions_deliver_events() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let eventfd = RingEventfd::new(&poller, 5)?;
+    let mut events = Events::new();
+
+    poller.wait(&mut events, Some(Duration::ZERO))?;
+    assert!(events.is_empty());
+
+    // Signal the eventfd like a ring posting a completion.
+    signal(&eventfd)?;
+    poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 5);
+
+    // No more events until the eventfd is reset and signaled again.
+    events.clear();
+    eventfd.reset()?;
+    poller.wait(&mut events, Some(Duration::ZERO))?;
+    assert!(events.is_empty());
+
+    signal(&eventfd)?;
+    poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    assert_eq!(events.len(), 1);
+
+    // Resetting without a pending completion doesn't block.
+    eventfd.reset()?;
+    eventfd.reset()?;
+    drop(eventfd);
+    assert_eq!(poller.registered_len(), 0);
+    Ok(())
+}
+
+fn signal(eventfd: &RingEventfd<'_>) -> io::Result<()> {
+    let value = 1u64.to_ne_bytes();
+    let res = unsafe { libc::write(eventfd.as_raw_fd(), value.as_ptr().cast(), value.len()) };
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
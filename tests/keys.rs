@@ -0,0 +1,87 @@
+use std::io;
+use std::net::UdpSocket;
+use std::time::Duration;
+
+use polling::{Events, Interest, KeyAllocator, Poller};
+
+#[test]
+fn reuses_slots() {
+    let keys = KeyAllocator::new();
+    let a = keys.allocate();
+    let b = keys.allocate();
+    assert_ne!(a, b);
+    assert_eq!(keys.index(a), Some(0));
+    assert_eq!(keys.index(b), Some(1));
+    assert_eq!(keys.len(), 2);
+
+    // A freed slot is reused under a new generation, so the old key is stale.
+    assert!(keys.free(a));
+    assert!(!keys.free(a));
+    let c = keys.allocate();
+    assert_ne!(a, c);
+    assert_eq!(keys.index(c), Some(0));
+    assert_eq!(keys.index(a), None);
+    assert!(!keys.contains(a));
+    assert_eq!(keys.len(), 2);
+}
+
+#[test]
+fn skips_notify_key() {
+    // The first key of the first slot is 0.
+    let poller = Poller::builder().notify_key(0).build().unwrap();
+    let keys = KeyAllocator::for_poller(&poller);
+    let key = keys.allocate();
+    assert_ne!(key, 0);
+    assert_eq!(keys.index(key), Some(0));
+}
+
+#[test]
+fn registration_frees_key() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let keys = KeyAllocator::for_poller(&poller);
+
+    let socket = keys.register(&poller, UdpSocket::bind("127.0.0.1:0")?, Interest::READABLE)?;
+    let key = socket.key().unwrap();
+    assert_eq!(
+        poller.registered(socket.get_ref()).unwrap().interest().key,
+        key
+    );
+    drop(socket);
+    assert!(!keys.contains(key));
+
+    let socket = keys.register(&poller, UdpSocket::bind("127.0.0.1:0")?, Interest::READABLE)?;
+    let key = socket.key().unwrap();
+    socket.into_inner()?;
+    assert!(keys.is_empty());
+    assert!(!keys.contains(key));
+    Ok(())
+}
+
+#[test]
+fn add_auto() -> io::Result<()> {
+    let poller = Poller::builder().notify_key(0).build()?;
+    let a = UdpSocket::bind("127.0.0.1:0")?;
+    let b = UdpSocket::bind("127.0.0.1:0")?;
+    let key_a = unsafe { poller.add_auto(&a, Interest::WRITABLE)? };
+    let key_b = unsafe { poller.add_auto(&b, Interest::WRITABLE)? };
+    assert_ne!(key_a, 0);
+    assert_ne!(key_a, key_b);
+
+    let mut events = Events::new();
+    poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    let mut keys = events.iter().map(|ev| ev.key).collect::<Vec<_>>();
+    keys.sort_unstable();
+    let mut expected = [key_a, key_b];
+    expected.sort_unstable();
+    assert_eq!(keys, expected);
+
+    // The key is freed on delete, and reusing its slot gives a different key.
+    poller.delete(&a)?;
+    let key_c = unsafe { poller.add_auto(&a, Interest::READABLE)? };
+    assert_ne!(key_c, key_a);
+    assert_ne!(key_c, key_b);
+
+    poller.delete(&a)?;
+    poller.delete(&b)?;
+    Ok(())
+}
@@ -0,0 +1,66 @@
+//! Tests for running the event loop on worker threads.
+
+use polling::{Event, Poller, PollerBuilder};
+
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+#[test]
+fn handles_events() {
+    for concurrent in [false, true] {
+        let poller = Arc::new(
+            PollerBuilder::new()
+                .concurrent_waits(concurrent)
+                .build()
+                .unwrap(),
+        );
+        let (reader, mut writer) = tcp_pair().unwrap();
+        let reader = Arc::new(reader);
+        unsafe {
+            poller.add(&*reader, Event::readable(1)).unwrap();
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let source = reader.clone();
+        let threads = poller
+            .run_on_threads(4, move |poller, event| {
+                sender.lock().unwrap().send(event.key).unwrap();
+                poller.modify(&*source, Event::readable(1)).unwrap();
+            })
+            .unwrap();
+
+        writer.write_all(&[1]).unwrap();
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(10)).unwrap(), 1);
+
+        assert!(!threads.is_stopped());
+        threads.stop().unwrap();
+        poller.delete(&*reader).unwrap();
+    }
+}
+
+#[test]
+fn no_threads() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let err = poller.run_on_threads(0, |_, _| {}).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn stop_on_drop() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let threads = poller.run_on_threads(3, |_, _| {}).unwrap();
+    drop(threads);
+
+    // The threads no longer hold the poller.
+    assert_eq!(Arc::strong_count(&poller), 1);
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
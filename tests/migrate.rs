@@ -0,0 +1,93 @@
+//! Test moving the registrations of one poller to another.
+
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use polling::{Event, Events, Poller};
+
+#[test]
+fn migrate() {
+    let old = Poller::new().unwrap();
+    let new = Poller::new().unwrap();
+    let mut events = Events::new();
+
+    let (reader1, mut writer1) = tcp_pair().unwrap();
+    let (reader2, mut writer2) = tcp_pair().unwrap();
+    unsafe {
+        old.add(&reader1, Event::readable(1)).unwrap();
+        old.add(&reader2, Event::readable(2)).unwrap();
+    }
+    old.set_group(&reader1, 5).unwrap();
+
+    // Deliver the oneshot event of the second source, which disables it.
+    writer2.write_all(&[1]).unwrap();
+    old.wait(&mut events, Some(Duration::from_secs(1))).unwrap();
+    assert_eq!(events.iter().next().unwrap().key, 2);
+
+    old.migrate_to(&new).unwrap();
+    assert_eq!(old.registered_len(), 0);
+    assert_eq!(new.registered_len(), 2);
+    assert_eq!(
+        new.registered(&reader1).unwrap().interest(),
+        Event::readable(1)
+    );
+
+    // The events are reported by the new poller, in the group of the source.
+    new.mute_group(5).unwrap();
+    writer1.write_all(&[1]).unwrap();
+    events.clear();
+    old.wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    new.wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+    new.unmute_group(5).unwrap();
+    new.wait(&mut events, Some(Duration::from_secs(1))).unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 1);
+
+    // The source whose event was delivered stays disabled until it is modified.
+    events.clear();
+    new.wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+    new.modify(&reader2, Event::readable(2)).unwrap();
+    events.clear();
+    new.wait(&mut events, Some(Duration::from_secs(1))).unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 2);
+
+    new.delete_all().unwrap();
+}
+
+#[test]
+fn errors() {
+    let old = Poller::new().unwrap();
+    let new = Poller::new().unwrap();
+    let err = old.migrate_to(&old).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    // A source that is already registered in the other poller stays where it is.
+    let (reader1, _writer1) = tcp_pair().unwrap();
+    let (reader2, _writer2) = tcp_pair().unwrap();
+    unsafe {
+        old.add(&reader1, Event::readable(1)).unwrap();
+        old.add(&reader2, Event::readable(2)).unwrap();
+        new.add(&reader1, Event::readable(3)).unwrap();
+    }
+    assert!(old.migrate_to(&new).is_err());
+    assert_eq!(old.registered(&reader1).unwrap().interest().key, 1);
+    assert!(old.registered(&reader2).is_none());
+    assert_eq!(new.registered(&reader2).unwrap().interest().key, 2);
+
+    old.delete_all().unwrap();
+    new.delete_all().unwrap();
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
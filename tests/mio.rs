@@ -0,0 +1,61 @@
+#![cfg(feature = "mio")]
+
+use mio::{Interest, Token};
+use polling::mio::Registry;
+use polling::{Events, Poller};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+#[test]
+fn translate() {
+    let ev = polling::mio::event(Token(3), Interest::READABLE | Interest::WRITABLE);
+    assert_eq!(ev.key, 3);
+    assert!(ev.readable);
+    assert!(ev.writable);
+    assert_eq!(polling::mio::token(&ev), Token(3));
+
+    let ev = polling::mio::event(Token(4), Interest::WRITABLE);
+    assert!(!ev.readable);
+    assert!(ev.writable);
+}
+
+#[test]
+fn register_and_wait() {
+    let poller = Poller::new().unwrap();
+    let registry = Registry::new(&poller);
+    let (mut read, mut write) = tcp_pair().unwrap();
+    unsafe {
+        registry
+            .register(&read, Token(1), Interest::READABLE)
+            .unwrap();
+    }
+
+    let mut events = Events::new();
+    for _ in 0..2 {
+        write.write_all(&[1]).unwrap();
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        let ev = events.iter().next().unwrap();
+        assert_eq!(polling::mio::token(&ev), Token(1));
+        assert!(ev.readable);
+
+        // Drain the source and re-arm it in case edge and level modes are unsupported.
+        read.read_exact(&mut [0]).unwrap();
+        registry
+            .reregister(&read, Token(1), Interest::READABLE)
+            .unwrap();
+    }
+
+    registry.deregister(&read).unwrap();
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "mock")]
+
+use std::net::UdpSocket;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use polling::mock::{Call, MockBackend};
+use polling::{Backend, Event, PollMode, Poller};
+
+fn keys(events: &polling::Events) -> Vec<usize> {
+    events.iter().map(|ev| ev.key).collect()
+}
+
+#[test]
+fn replay() {
+    let mock = MockBackend::new();
+    mock.push(Duration::ZERO, [Event::readable(1), Event::writable(2)]);
+    mock.push(Duration::ZERO, [Event::all(3)]);
+    mock.push(Duration::from_millis(50), [Event::readable(4)]);
+
+    let poller = Poller::builder().build_custom(mock.clone()).unwrap();
+    assert_eq!(poller.backend(), Backend::Custom);
+    let mut events = poller.new_events();
+
+    // Each wait delivers one step.
+    poller.wait(&mut events, None).unwrap();
+    assert_eq!(keys(&events), [1, 2]);
+    events.clear();
+    poller.wait(&mut events, None).unwrap();
+    assert_eq!(keys(&events), [3]);
+    assert_eq!(events.iter().next().unwrap(), Event::all(3));
+
+    // The last step isn't due before the timeout.
+    let start = Instant::now();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    poller.wait(&mut events, None).unwrap();
+    assert_eq!(keys(&events), [4]);
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert!(mock.is_done());
+
+    // The script is over.
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+#[test]
+fn notify() {
+    let mock = MockBackend::new();
+    let poller = Poller::builder().build_custom(mock.clone()).unwrap();
+    let mut events = poller.new_events();
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(Duration::from_millis(20));
+            poller.notify().unwrap();
+        });
+        poller.wait(&mut events, None).unwrap();
+    });
+    assert!(events.is_empty());
+
+    // A step pushed during a wait is delivered by it.
+    thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(Duration::from_millis(20));
+            mock.push(Duration::ZERO, [Event::readable(5)]);
+        });
+        poller.wait(&mut events, None).unwrap();
+    });
+    assert_eq!(keys(&events), [5]);
+}
+
+#[test]
+fn calls() {
+    let mock = MockBackend::new();
+    let poller = Poller::builder().build_custom(mock.clone()).unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    #[cfg(unix)]
+    let source = std::os::unix::io::AsRawFd::as_raw_fd(&socket);
+    #[cfg(windows)]
+    let source = std::os::windows::io::AsRawSocket::as_raw_socket(&socket);
+
+    unsafe {
+        poller.add(&socket, Event::readable(1)).unwrap();
+    }
+    poller
+        .modify_with_mode(&socket, Event::writable(1), PollMode::Level)
+        .unwrap();
+    poller.delete(&socket).unwrap();
+
+    assert_eq!(
+        mock.calls(),
+        [
+            Call::Add {
+                source,
+                interest: Event::readable(1),
+                mode: PollMode::Oneshot,
+            },
+            Call::Modify {
+                source,
+                interest: Event::writable(1),
+                mode: PollMode::Level,
+            },
+            Call::Delete { source },
+        ]
+    );
+
+    mock.clear_calls();
+    assert!(mock.calls().is_empty());
+}
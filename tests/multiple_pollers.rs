@@ -1,6 +1,6 @@
 //! Test registering one source into multiple pollers.
 
-use polling::{Event, Events, PollMode, Poller};
+use polling::{Backend, Event, Events, PollMode, Poller};
 
 use std::io::{self, prelude::*};
 use std::net::{TcpListener, TcpStream};
@@ -284,31 +284,31 @@ fn oneshot_triggered() {
     // Write to the source.
     writer.write_all(&[1]).unwrap();
 
-    // Sources should have either one or no events.
-    match poller1.wait(&mut events, Some(Duration::from_secs(1))) {
-        Ok(1) => {
-            assert_eq!(events.len(), 1);
-            assert_eq!(
-                events.iter().next().unwrap().with_no_extra(),
-                Event::readable(1)
-            );
-        }
-        Ok(0) => assert!(events.is_empty()),
-        _ => panic!("unexpected error"),
-    }
+    // Both pollers should have an event.
+    assert_eq!(
+        poller1
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap(),
+        1
+    );
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events.iter().next().unwrap().with_no_extra(),
+        Event::readable(1)
+    );
     events.clear();
 
-    match poller2.wait(&mut events, Some(Duration::from_secs(1))) {
-        Ok(1) => {
-            assert_eq!(events.len(), 1);
-            assert_eq!(
-                events.iter().next().unwrap().with_no_extra(),
-                Event::readable(2)
-            );
-        }
-        Ok(0) => assert!(events.is_empty()),
-        _ => panic!("unexpected error"),
-    }
+    assert_eq!(
+        poller2
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap(),
+        1
+    );
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events.iter().next().unwrap().with_no_extra(),
+        Event::readable(2)
+    );
     events.clear();
 
     // Writing more data should not cause an event.
@@ -348,6 +348,74 @@ fn oneshot_triggered() {
         0
     );
     assert!(events.is_empty());
+
+    // Dereference the pollers.
+    poller1.delete(&reader).unwrap();
+    poller2.delete(&reader).unwrap();
+}
+
+#[test]
+fn independent_registrations() {
+    // The second poller runs on every backend that is available, next to the default one.
+    for &backend in Backend::available() {
+        independent_registrations_with(backend);
+    }
+}
+
+fn independent_registrations_with(backend: Backend) {
+    // With strict errors, deleting a oneshot registration that fired must still succeed, even
+    // though kqueue already deleted its filter.
+    let poller1 = Poller::builder().strict_errors(true).build().unwrap();
+    let poller2 = Poller::builder().require_backend(backend).build().unwrap();
+    let mut events = Events::new();
+
+    // Register the source with different interests.
+    let (reader, mut writer) = tcp_pair().unwrap();
+    unsafe {
+        poller1.add(&reader, Event::readable(1)).unwrap();
+        poller2.add(&reader, Event::all(2)).unwrap();
+    }
+
+    // The second poller reports the writability it is interested in.
+    assert_eq!(
+        poller1
+            .wait(&mut events, Some(Duration::from_millis(100)))
+            .unwrap(),
+        0
+    );
+    poller2
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 2);
+    assert!(events.iter().next().unwrap().writable);
+    events.clear();
+
+    // Re-enabling the oneshot registration of one poller leaves the other one disabled.
+    poller1.modify(&reader, Event::readable(1)).unwrap();
+    writer.write_all(&[1]).unwrap();
+    poller1
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 1);
+    events.clear();
+    assert_eq!(
+        poller2
+            .wait(&mut events, Some(Duration::from_millis(100)))
+            .unwrap(),
+        0
+    );
+
+    // Deleting the source from one poller leaves it in the other.
+    poller1.delete(&reader).unwrap();
+    poller2.modify(&reader, Event::readable(2)).unwrap();
+    poller2
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 2);
+    poller2.delete(&reader).unwrap();
 }
 
 fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
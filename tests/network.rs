@@ -0,0 +1,30 @@
+//! Tests for the source of network interface changes.
+
+#![cfg(any(
+    target_os = "linux",
+    target_vendor = "apple",
+    target_os = "freebsd",
+    windows
+))]
+
+use polling::{Event, Events, NetworkChanges, Poller};
+use std::time::Duration;
+
+#[test]
+fn register() {
+    let poller = Poller::new().unwrap();
+    let changes = NetworkChanges::new().unwrap();
+    unsafe {
+        poller.add(&changes, Event::readable(1)).unwrap();
+    }
+
+    // The interfaces may change while the test runs, so only the key of an event is checked.
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.iter().all(|ev| ev.key == 1));
+
+    changes.reset().unwrap();
+    poller.delete(&changes).unwrap();
+}
@@ -1,10 +1,11 @@
-use std::io;
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
 use std::thread;
 use std::time::Duration;
 
 use easy_parallel::Parallel;
-use polling::Events;
 use polling::Poller;
+use polling::{Event, Events};
 
 #[test]
 fn simple() -> io::Result<()> {
@@ -20,6 +21,23 @@ fn simple() -> io::Result<()> {
     Ok(())
 }
 
+#[test]
+fn reports_pending() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+
+    for _ in 0..10 {
+        assert!(!poller.is_notified());
+        assert!(poller.try_notify()?);
+        assert!(poller.is_notified());
+        assert!(!poller.try_notify()?);
+        poller.wait(&mut events, None)?;
+        assert!(events.is_empty());
+    }
+
+    Ok(())
+}
+
 #[test]
 fn concurrent() -> io::Result<()> {
     let poller = Poller::new()?;
@@ -36,3 +54,226 @@ fn concurrent() -> io::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn custom_key() -> io::Result<()> {
+    let poller = Poller::with_notify_key(0)?;
+    let mut events = Events::new();
+
+    // The reserved key is rejected, but `usize::MAX` is now a regular key.
+    let (reader, mut writer) = tcp_pair()?;
+    let err = unsafe { poller.add(&reader, Event::readable(0)) }.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    unsafe { poller.add(&reader, Event::readable(usize::MAX))? };
+
+    // Notifications don't show up as events.
+    poller.notify()?;
+    poller.wait(&mut events, None)?;
+    assert!(events.is_empty());
+
+    writer.write_all(&[1])?;
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, usize::MAX);
+
+    poller.delete(&reader)?;
+    Ok(())
+}
+
+#[test]
+fn wait_result() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+
+    let result = poller.wait_result(&mut events, Some(Duration::from_millis(10)))?;
+    assert!(result.is_timed_out());
+    assert!(!result.is_notified());
+
+    poller.notify()?;
+    let result = poller.wait_result(&mut events, Some(Duration::from_secs(1)))?;
+    assert!(result.is_notified());
+    assert!(!result.is_timed_out());
+    assert_eq!(result.events(), 0);
+
+    let (read, mut write) = tcp_pair()?;
+    write.write_all(&[1])?;
+    unsafe {
+        poller.add(&read, Event::readable(1))?;
+    }
+    let result = poller.wait_result(&mut events, Some(Duration::from_secs(1)))?;
+    assert_eq!(result.events(), 1);
+    assert!(!result.is_notified());
+    assert!(!result.is_timed_out());
+    poller.delete(&read)?;
+
+    Ok(())
+}
+
+#[test]
+fn sticky() -> io::Result<()> {
+    let poller = Poller::builder().sticky_notify(true).build()?;
+    let mut events = Events::new();
+
+    poller.notify()?;
+    for _ in 0..3 {
+        assert!(poller.wait_result(&mut events, None)?.is_notified());
+    }
+
+    // Once cleared, waits block until the next notification.
+    assert!(poller.clear_notification()?);
+    let res = poller.wait_result(&mut events, Some(Duration::from_millis(10)))?;
+    assert!(!res.is_notified());
+
+    poller.notify()?;
+    assert!(poller.wait_result(&mut events, None)?.is_notified());
+    assert!(poller.wait_result(&mut events, None)?.is_notified());
+    Ok(())
+}
+
+#[test]
+fn clear_pending() -> io::Result<()> {
+    let poller = Poller::new()?;
+    assert!(!poller.clear_notification()?);
+    poller.notify()?;
+    assert!(poller.clear_notification()?);
+    assert!(!poller.clear_notification()?);
+
+    // The wakeup is consumed, and events that were ready are kept for the next wait.
+    let (reader, mut writer) = tcp_pair()?;
+    unsafe { poller.add(&reader, Event::readable(1))? };
+    writer.write_all(&[1])?;
+    thread::sleep(Duration::from_millis(50));
+    poller.notify()?;
+    assert!(poller.clear_notification()?);
+
+    let mut events = Events::new();
+    let res = poller.wait_result(&mut events, Some(Duration::from_secs(1)))?;
+    assert!(!res.is_notified());
+    assert_eq!(events.len(), 1);
+
+    events.clear();
+    let res = poller.wait_result(&mut events, Some(Duration::from_millis(10)))?;
+    assert!(res.is_timed_out());
+    poller.delete(&reader)?;
+    Ok(())
+}
+
+#[test]
+fn drain_keeps_notification() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let (reader, mut writer) = tcp_pair()?;
+    unsafe { poller.add(&reader, Event::readable(1))? };
+    writer.write_all(&[1])?;
+    thread::sleep(Duration::from_millis(50));
+
+    // Draining collects the ready events, but the notification stays for the next wait.
+    poller.notify()?;
+    let mut events = Events::new();
+    assert_eq!(poller.drain(&mut events)?, 1);
+
+    events.clear();
+    let res = poller.wait_result(&mut events, Some(Duration::from_secs(1)))?;
+    assert!(res.is_notified());
+    assert_eq!(events.len(), 0);
+    poller.delete(&reader)?;
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "vita")))]
+#[test]
+fn from_signal() -> io::Result<()> {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static POLLER: OnceLock<Poller> = OnceLock::new();
+    let poller = POLLER.get_or_init(|| Poller::new().unwrap());
+
+    let id = unsafe {
+        signal_hook::low_level::register(signal_hook::consts::SIGUSR2, || {
+            POLLER.get().unwrap().notify_from_signal();
+        })?
+    };
+
+    let handle = thread::spawn(|| {
+        thread::sleep(Duration::from_millis(100));
+        signal_hook::low_level::raise(signal_hook::consts::SIGUSR2).unwrap();
+    });
+
+    let start = Instant::now();
+    let mut events = Events::new();
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    assert!(events.is_empty());
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    handle.join().unwrap();
+    signal_hook::low_level::unregister(id);
+    Ok(())
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
+
+#[test]
+fn retry_spurious() {
+    use polling::PollerBuilder;
+    use std::net::UdpSocket;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    let poller = Arc::new(PollerBuilder::new().retry_spurious(true).build().unwrap());
+    let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").unwrap());
+    unsafe {
+        poller.add(&*socket, Event::none(1)).unwrap();
+    }
+
+    // Changing the interest wakes up the wait on some backends, which then waits again.
+    let modifier = thread::spawn({
+        let poller = poller.clone();
+        let socket = socket.clone();
+        move || {
+            thread::sleep(Duration::from_millis(50));
+            poller.modify(&*socket, Event::none(1)).unwrap();
+        }
+    });
+    let start = Instant::now();
+    let mut events = Events::new();
+    let result = poller
+        .wait_result(&mut events, Some(Duration::from_millis(200)))
+        .unwrap();
+    assert!(result.is_timed_out());
+    assert!(start.elapsed() >= Duration::from_millis(200));
+
+    modifier.join().unwrap();
+    poller.delete(&*socket).unwrap();
+}
+
+#[test]
+fn disabled() -> io::Result<()> {
+    use polling::PollerBuilder;
+    use std::time::Instant;
+
+    let poller = PollerBuilder::new().notifications(false).build()?;
+    let err = poller.notify().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    poller.notify_from_signal();
+    assert!(!poller.is_notified());
+
+    // Waits still time out and deliver events.
+    let mut events = Events::new();
+    let start = Instant::now();
+    poller.wait(&mut events, Some(Duration::from_millis(50)))?;
+    assert!(events.is_empty());
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    let (mut a, b) = tcp_pair()?;
+    unsafe { poller.add(&b, Event::readable(1))? };
+    a.write_all(&[1])?;
+    poller.wait(&mut events, Some(Duration::from_secs(1)))?;
+    assert_eq!(events.len(), 1);
+    poller.delete(&b)?;
+    Ok(())
+}
@@ -16,17 +16,11 @@ fn level_triggered() {
 
     // Create our poller and register our streams.
     let poller = Poller::new().unwrap();
-    if unsafe { poller.add_with_mode(&reader, Event::readable(reader_token), PollMode::Level) }
-        .is_err()
-    {
-        // Only panic if we're on a platform that should support level mode.
-        cfg_if::cfg_if! {
-            if #[cfg(any(target_os = "solaris", target_os = "illumos"))] {
-                return;
-            } else {
-                panic!("Level mode should be supported on this platform");
-            }
-        }
+    assert!(poller.supports_level());
+    unsafe {
+        poller
+            .add_with_mode(&reader, Event::readable(reader_token), PollMode::Level)
+            .unwrap();
     }
 
     // Write some data to the writer.
@@ -0,0 +1,65 @@
+use polling::{Event, Events, Parker};
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn unpark_before_park() {
+    let parker = Parker::new().unwrap();
+    let unparker = parker.unparker();
+    unparker.unpark().unwrap();
+    unparker.unpark().unwrap();
+
+    parker.park().unwrap();
+    assert!(!parker.park_timeout(Duration::from_millis(10)).unwrap());
+}
+
+#[test]
+fn unpark_from_thread() {
+    let parker = Parker::new().unwrap();
+    let unparker = parker.unparker();
+
+    let start = Instant::now();
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        unparker.unpark().unwrap();
+    });
+
+    assert!(parker.park_timeout(Duration::from_secs(5)).unwrap());
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    handle.join().unwrap();
+}
+
+#[test]
+fn park_events() {
+    let parker = Parker::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+    unsafe {
+        parker.poller().add(&read, Event::readable(1)).unwrap();
+    }
+
+    write.write_all(&[1]).unwrap();
+    let mut events = Events::new();
+    while events.is_empty() {
+        let unparked = parker
+            .park_events(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert!(!unparked);
+    }
+    assert_eq!(events.iter().next().unwrap().key, 1);
+
+    events.clear();
+    parker.unparker().unpark().unwrap();
+    assert!(parker.park_events(&mut events, None).unwrap());
+    assert!(events.is_empty());
+
+    parker.poller().delete(&read).unwrap();
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
@@ -0,0 +1,180 @@
+use polling::{Event, Events, PollError, Poller};
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn pause_and_resume() {
+    let poller = Poller::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+    write.write_all(&[1]).unwrap();
+    unsafe {
+        poller.add(&read, Event::readable(1)).unwrap();
+    }
+
+    poller.pause().unwrap();
+    assert!(poller.is_paused());
+
+    // Nothing is delivered while paused, but the registration is kept.
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.is_empty());
+    assert_eq!(poller.registered(&read).unwrap().interest().key, 1);
+
+    // Notifications still work.
+    poller.notify().unwrap();
+    poller.wait(&mut events, None).unwrap();
+    assert!(events.is_empty());
+
+    poller.resume().unwrap();
+    assert!(!poller.is_paused());
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 1);
+
+    poller.delete(&read).unwrap();
+}
+
+#[test]
+fn register_while_paused() {
+    let poller = Poller::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+    let (split, _other) = tcp_pair().unwrap();
+    write.write_all(&[1]).unwrap();
+
+    poller.pause().unwrap();
+    unsafe {
+        poller.add(&read, Event::none(1)).unwrap();
+        poller
+            .add_split(&split, Event::readable(2), Event::writable(3))
+            .unwrap();
+    }
+    poller.modify(&read, Event::readable(1)).unwrap();
+
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    poller.resume().unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+
+    // Give the other source a moment to report its event.
+    if events.len() < 2 {
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+    }
+    let mut keys = events.iter().map(|ev| ev.key).collect::<Vec<_>>();
+    keys.sort_unstable();
+    assert_eq!(keys, [1, 3]);
+
+    poller.delete(&read).unwrap();
+    poller.delete(&split).unwrap();
+}
+
+#[test]
+fn mute_group() {
+    let poller = Poller::new().unwrap();
+    let (control, mut control_writer) = tcp_pair().unwrap();
+    let (data, mut data_writer) = tcp_pair().unwrap();
+    control_writer.write_all(&[1]).unwrap();
+    data_writer.write_all(&[1]).unwrap();
+    unsafe {
+        poller.add(&control, Event::readable(1)).unwrap();
+        poller.add(&data, Event::readable(2)).unwrap();
+    }
+
+    // Only the sources outside of the muted group are delivered.
+    poller.set_group(&data, 7).unwrap();
+    poller.mute_group(7).unwrap();
+    assert!(poller.is_group_muted(7));
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 1);
+
+    // Sources that are modified or moved into the group stay muted.
+    poller.modify(&data, Event::readable(2)).unwrap();
+    poller.modify(&control, Event::readable(1)).unwrap();
+    poller.set_group(&control, 7).unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    // Moving a source out of the group enables it again, and so does unmuting the group.
+    poller.set_group(&control, 0).unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.iter().next().unwrap().key, 1);
+
+    poller.unmute_group(7).unwrap();
+    assert!(!poller.is_group_muted(7));
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 2);
+
+    poller.delete(&control).unwrap();
+    poller.delete(&data).unwrap();
+}
+
+#[test]
+fn seal_and_drain() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (reader, mut writer) = tcp_pair().unwrap();
+    unsafe { poller.add(&reader, Event::readable(1)).unwrap() };
+
+    poller.seal();
+    assert!(poller.is_sealed());
+    assert!(!poller.is_drained());
+    let (other, _) = tcp_pair().unwrap();
+    let err = unsafe { poller.add(&other, Event::readable(2)) }.unwrap_err();
+    assert!(matches!(PollError::from(err), PollError::Sealed));
+
+    // Registered sources still deliver events.
+    let mut events = Events::new();
+    writer.write_all(&[1]).unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(10)))
+        .unwrap();
+    assert_eq!(events.iter().next().map(|ev| ev.key), Some(1));
+
+    // Deleting the last source wakes up the wait.
+    let waiter = thread::spawn({
+        let poller = poller.clone();
+        move || {
+            let mut events = Events::new();
+            while !poller.is_drained() {
+                events.clear();
+                poller.wait(&mut events, None).unwrap();
+            }
+        }
+    });
+    thread::sleep(Duration::from_millis(50));
+    poller.delete(&reader).unwrap();
+    waiter.join().unwrap();
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
@@ -0,0 +1,55 @@
+use polling::{poll_once, Interest};
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn readable_after_write() {
+    let (reader, mut writer) = tcp_pair().unwrap();
+    let event = poll_once(&reader, Interest::READABLE, Some(Duration::ZERO)).unwrap();
+    assert!(event.is_none());
+
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        writer.write_all(&[1]).unwrap();
+        writer
+    });
+    let event = poll_once(&reader, Interest::READABLE, None)
+        .unwrap()
+        .unwrap();
+    assert!(event.readable);
+    assert!(!event.writable);
+    handle.join().unwrap();
+}
+
+#[test]
+fn timeout() {
+    let (reader, _writer) = tcp_pair().unwrap();
+    let start = Instant::now();
+    let event = poll_once(
+        &reader,
+        Interest::READABLE,
+        Some(Duration::from_millis(100)),
+    )
+    .unwrap();
+    assert!(event.is_none());
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+#[test]
+fn hangup() {
+    let (reader, writer) = tcp_pair().unwrap();
+    drop(writer);
+    let event = poll_once(&reader, Interest::READABLE, Some(Duration::from_secs(5)))
+        .unwrap()
+        .unwrap();
+    assert!(event.readable);
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
@@ -0,0 +1,35 @@
+#![cfg(any(target_os = "linux", target_vendor = "apple"))]
+
+use std::time::Duration;
+
+use polling::{Events, MemoryPressure, Poller};
+
+#[test]
+fn register() {
+    // Kernels without PSI, and sandboxes, don't allow creating a trigger.
+    let pressure = match MemoryPressure::new() {
+        Ok(pressure) => pressure,
+        Err(_) => return,
+    };
+    assert!(format!("{:?}", pressure).contains("MemoryPressure"));
+
+    let poller = Poller::new().unwrap();
+    unsafe {
+        poller.add(&pressure, pressure.interest(1)).unwrap();
+    }
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.iter().all(|ev| ev.key == 1));
+    pressure.reset().unwrap();
+    poller.delete(&pressure).unwrap();
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn invalid_threshold() {
+    // The stall must be shorter than the window.
+    let err = MemoryPressure::with_threshold(Duration::from_secs(3), Duration::from_secs(2));
+    assert!(err.is_err());
+}
@@ -0,0 +1,42 @@
+//! Tests for sources that are closed while they are registered.
+//!
+//! This is a separate test binary, so that no other test reuses the file descriptors that are
+//! closed here.
+
+use std::io;
+use std::net::{TcpListener, TcpStream};
+
+use polling::{Event, Poller};
+
+#[test]
+fn purge_closed() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let (a, _b) = tcp_pair()?;
+    let (c, _d) = tcp_pair()?;
+    unsafe {
+        poller.add(&a, Event::readable(1))?;
+        poller.add(&c, Event::readable(2))?;
+    }
+
+    // Close a registered source without deleting it.
+    drop(a);
+
+    let purged = poller.purge_closed();
+    assert_eq!(purged.len(), 1);
+    assert_eq!(purged[0].interest().key, 1);
+    assert_eq!(poller.registered_len(), 1);
+    assert_eq!(poller.registrations()[0].interest().key, 2);
+
+    // Nothing is left to purge.
+    assert!(poller.purge_closed().is_empty());
+
+    poller.delete(&c)?;
+    Ok(())
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
@@ -0,0 +1,39 @@
+//! Tests for waiting with a QoS class on Apple platforms.
+
+#![cfg(all(target_vendor = "apple", not(polling_test_poll_backend)))]
+
+use polling::os::kqueue::{PollerQosExt, QosClass};
+use polling::{Events, Poller};
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn wait_with_qos() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut events = Events::new();
+
+    // The wait times out normally.
+    poller
+        .wait_with_qos(
+            &mut events,
+            Some(Duration::from_millis(10)),
+            QosClass::Background,
+        )
+        .unwrap();
+    assert!(events.is_empty());
+
+    // And is woken up by a notification.
+    let notifier = thread::spawn({
+        let poller = poller.clone();
+        move || {
+            thread::sleep(Duration::from_millis(50));
+            poller.notify().unwrap();
+        }
+    });
+    poller
+        .wait_with_qos(&mut events, None, QosClass::Utility)
+        .unwrap();
+    notifier.join().unwrap();
+}
@@ -0,0 +1,77 @@
+//! Tests for raw `kqueue` filters.
+
+#![cfg(all(
+    any(
+        target_vendor = "apple",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+    ),
+    not(polling_test_poll_backend)
+))]
+
+use polling::os::kqueue::{EventKqueueExt, PollerKqueueExt, RawFilter};
+use polling::{Event, Events, PollMode, Poller};
+
+use std::io::Write;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+#[test]
+fn raw_read_filter() {
+    let (read, mut write) = UnixStream::pair().unwrap();
+    let poller = Poller::new().unwrap();
+
+    let filter = RawFilter {
+        ident: read.as_raw_fd() as usize,
+        filter: libc::EVFILT_READ as _,
+        fflags: 0,
+        data: 0,
+    };
+    poller.add_filter(filter, 4, PollMode::Oneshot).unwrap();
+    write.write_all(b"abc").unwrap();
+
+    // The delivered event carries the raw fields, including the number of readable bytes.
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    let event = events.iter().next().unwrap();
+    assert_eq!(event.key, 4);
+    let raw = event.raw_filter().unwrap();
+    assert_eq!(raw.ident, read.as_raw_fd() as usize);
+    assert_eq!(raw.filter, libc::EVFILT_READ as i32);
+    assert_eq!(raw.data, 3);
+
+    // Events created by the user don't.
+    assert_eq!(Event::readable(4).raw_filter(), None);
+
+    poller.delete_filter(filter).unwrap();
+}
+
+// `EV_EOF` is a `u32` on NetBSD.
+#[allow(clippy::unnecessary_cast)]
+#[test]
+fn raw_flags() {
+    let (read, mut write) = UnixStream::pair().unwrap();
+    let poller = Poller::new().unwrap();
+    unsafe {
+        poller.add(&read, Event::readable(5)).unwrap();
+    }
+
+    // The flags of the delivered event show the end of the stream.
+    write.write_all(b"x").unwrap();
+    drop(write);
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    let event = events.iter().next().unwrap();
+    assert_eq!(event.key, 5);
+    assert_ne!(event.raw_flags().unwrap() & libc::EV_EOF as u32, 0);
+    assert_eq!(Event::readable(5).raw_flags(), None);
+
+    poller.delete(&read).unwrap();
+}
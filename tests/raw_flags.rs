@@ -0,0 +1,44 @@
+//! Tests for raw `epoll` flags.
+
+#![cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    not(polling_test_poll_backend)
+))]
+
+use polling::os::epoll::{EventEpollExt, PollerEpollExt};
+use polling::{Events, PollMode, Poller};
+
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+#[test]
+fn raw_registration() {
+    let (read, mut write) = UnixStream::pair().unwrap();
+    let poller = Poller::new().unwrap();
+
+    let flags = (libc::EPOLLIN | libc::EPOLLRDHUP | libc::EPOLLET) as u32;
+    unsafe {
+        poller.add_raw(&read, 3, flags).unwrap();
+    }
+    assert_eq!(poller.registered(&read).unwrap().mode(), PollMode::Edge);
+
+    // The raw mask of the delivered event shows the hangup.
+    write.write_all(b"x").unwrap();
+    drop(write);
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    let event = events.iter().next().unwrap();
+    assert_eq!(event.key, 3);
+    assert!(event.readable);
+    assert_ne!(event.raw_flags() & libc::EPOLLRDHUP as u32, 0);
+
+    poller
+        .modify_raw(&read, 3, (libc::EPOLLIN | libc::EPOLLONESHOT) as u32)
+        .unwrap();
+    assert_eq!(poller.registered(&read).unwrap().mode(), PollMode::Oneshot);
+
+    poller.delete(&read).unwrap();
+}
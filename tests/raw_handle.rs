@@ -0,0 +1,99 @@
+//! Uses the raw epoll file descriptor directly.
+
+#![cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    not(polling_test_poll_backend)
+))]
+
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::io::{AsRawFd, OwnedFd};
+use std::time::Duration;
+
+use polling::{Event, Events, Poller};
+
+#[test]
+fn into_and_from_fd() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    let (reader, mut writer) = tcp_pair()?;
+    unsafe { poller.add(&reader, Event::readable(1))? };
+
+    // The source stays registered in the instance.
+    let fd = OwnedFd::try_from(poller)?;
+    let poller = Poller::from_fd(fd)?;
+
+    writer.write_all(&[1])?;
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 1);
+
+    // Notifications still work.
+    events.clear();
+    poller.notify()?;
+    poller.wait(&mut events, None)?;
+    assert!(events.is_empty());
+
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "illumos", target_os = "solaris")))]
+#[test]
+fn from_other_fd() -> io::Result<()> {
+    let (reader, _writer) = tcp_pair()?;
+    assert!(Poller::from_fd(OwnedFd::from(reader)).is_err());
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn epoll_ctl() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    let (reader, mut writer) = tcp_pair()?;
+
+    // Register the source with flags the portable API doesn't offer.
+    let mut ev = libc::epoll_event {
+        events: (libc::EPOLLIN | libc::EPOLLEXCLUSIVE) as u32,
+        u64: 7,
+    };
+    let res = unsafe {
+        libc::epoll_ctl(
+            poller.as_raw_fd(),
+            libc::EPOLL_CTL_ADD,
+            reader.as_raw_fd(),
+            &mut ev,
+        )
+    };
+    if res == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    writer.write_all(&[1])?;
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 7);
+
+    poller.delete(&reader)?;
+    Ok(())
+}
+
+#[test]
+fn cloexec() -> io::Result<()> {
+    let is_cloexec = |poller: &Poller| {
+        let flags = unsafe { libc::fcntl(poller.as_raw_fd(), libc::F_GETFD) };
+        assert!(flags >= 0);
+        flags & libc::FD_CLOEXEC != 0
+    };
+
+    assert!(is_cloexec(&Poller::new()?));
+    assert!(!is_cloexec(&Poller::builder().cloexec(false).build()?));
+    Ok(())
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
@@ -0,0 +1,71 @@
+#![cfg(feature = "reactor")]
+
+use polling::reactor::Reactor;
+use polling::Interest;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn dispatch_and_deregister() {
+    let reactor = Reactor::new().unwrap();
+    let (mut read, mut write) = tcp_pair().unwrap();
+    read.set_nonblocking(true).unwrap();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let key = unsafe {
+        let calls = calls.clone();
+        let mut read2 = read.try_clone().unwrap();
+        reactor
+            .register(&read, Interest::READABLE, move |_, ev| {
+                assert!(ev.readable);
+                while read2.read(&mut [0; 16]).is_ok() {}
+                calls.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap()
+    };
+    assert_eq!(reactor.len(), 1);
+
+    for i in 1..=2 {
+        write.write_all(&[1]).unwrap();
+        while calls.load(Ordering::SeqCst) < i {
+            reactor.turn(Some(Duration::from_secs(1))).unwrap();
+        }
+    }
+
+    reactor.deregister(key).unwrap();
+    assert!(reactor.deregister(key).is_err());
+    assert!(reactor.is_empty());
+
+    write.write_all(&[1]).unwrap();
+    assert_eq!(reactor.turn(Some(Duration::from_millis(50))).unwrap(), 0);
+    assert!(read.read(&mut [0; 16]).is_ok());
+}
+
+#[test]
+fn deregister_from_callback() {
+    let reactor = Reactor::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+
+    unsafe {
+        reactor
+            .register(&read, Interest::READABLE, |reactor, ev| {
+                reactor.deregister(ev.key).unwrap();
+                reactor.stop().unwrap();
+            })
+            .unwrap();
+    }
+
+    write.write_all(&[1]).unwrap();
+    reactor.run().unwrap();
+    assert!(reactor.is_empty());
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
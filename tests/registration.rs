@@ -0,0 +1,156 @@
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use polling::{Event, Events, PollMode, Poller};
+
+#[test]
+fn delete_on_drop() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    let (reader, mut writer) = tcp_pair()?;
+
+    let reader = poller.register(reader, Event::readable(1))?;
+    writer.write_all(&[1])?;
+
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 1);
+
+    // Once the guard is dropped, the source is no longer in the poller.
+    drop(reader);
+    events.clear();
+    poller.wait(&mut events, Some(Duration::from_secs(0)))?;
+    assert!(events.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn modify_and_into_inner() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    let (reader, mut writer) = tcp_pair()?;
+
+    let reader = poller.register(reader, Event::none(1))?;
+    writer.write_all(&[1])?;
+
+    poller.wait(&mut events, Some(Duration::from_secs(0)))?;
+    assert!(events.is_empty());
+
+    reader.modify(Event::readable(2))?;
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().key, 2);
+
+    // The source can be registered again after it has been taken out.
+    let reader = reader.into_inner()?;
+    let reader = poller.register(reader, Event::readable(3))?;
+    drop(reader);
+
+    Ok(())
+}
+
+#[test]
+fn registrations() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let (a, b) = tcp_pair()?;
+    assert!(poller.registrations().is_empty());
+
+    let a = poller.register(a, Event::readable(1))?;
+    let b = poller.register(b, Event::writable(2))?;
+    assert_eq!(poller.registered_len(), 2);
+
+    let mut keys = poller
+        .registrations()
+        .iter()
+        .map(|r| r.interest().key)
+        .collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(keys, [1, 2]);
+
+    // Modifying the source updates its registration.
+    a.modify(Event::all(3))?;
+    let registered = poller.registered(a.get_ref()).unwrap();
+    assert_eq!(registered.interest().key, 3);
+    assert!(registered.interest().readable && registered.interest().writable);
+    assert_eq!(registered.mode(), PollMode::Oneshot);
+
+    // Deleting the source removes its registration.
+    drop(a);
+    assert_eq!(poller.registered_len(), 1);
+    assert_eq!(poller.registrations()[0].interest().key, 2);
+    drop(b);
+    assert!(poller.registrations().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn with_registered() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    let (reader, mut writer) = tcp_pair()?;
+    writer.write_all(&[1])?;
+
+    let key = poller.with_registered(&reader, Event::readable(1), |poller| {
+        poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+        io::Result::Ok(events.iter().next().map(|ev| ev.key))
+    })??;
+    assert_eq!(key, Some(1));
+
+    // The source was deleted, so it can be added again.
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        poller.with_registered(&reader, Event::readable(1), |_| panic!("oops"))
+    }));
+    assert!(res.is_err());
+
+    // And it was deleted on the panic as well.
+    unsafe {
+        poller.add(&reader, Event::readable(1))?;
+    }
+    poller.delete(&reader)?;
+
+    Ok(())
+}
+
+#[test]
+fn read_with() -> io::Result<()> {
+    use std::io::Read;
+
+    for mode in [PollMode::Oneshot, PollMode::Level] {
+        let poller = Poller::new()?;
+        let (reader, mut writer) = tcp_pair()?;
+        reader.set_nonblocking(true)?;
+        let reader = poller.register_with_mode(reader, Event::writable(1), mode)?;
+
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            writer.write_all(&[1, 2, 3]).unwrap();
+            writer
+        });
+
+        // The read waits for the data instead of failing with `WouldBlock`.
+        let mut buf = [0; 3];
+        let len = reader.read_with(|mut reader| reader.read(&mut buf))?;
+        assert_eq!(&buf[..len], &[1, 2, 3][..len]);
+        let _writer = writer.join().unwrap();
+
+        // The interest in writability is kept.
+        let registered = poller.registered(reader.get_ref()).unwrap();
+        assert!(registered.interest().readable && registered.interest().writable);
+        assert_eq!(registered.mode(), mode);
+
+        let len = reader.write_with(|mut reader| reader.write(&[4]))?;
+        assert_eq!(len, 1);
+    }
+
+    Ok(())
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
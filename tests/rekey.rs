@@ -0,0 +1,72 @@
+use polling::{Event, Events, PollMode, Poller};
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+#[test]
+fn keeps_interest() {
+    let poller = Poller::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+    unsafe {
+        poller.add(&read, Event::readable(1)).unwrap();
+    }
+    write.write_all(&[1]).unwrap();
+
+    poller.rekey(&read, 2).unwrap();
+    let registered = poller.registered(&read).unwrap();
+    assert_eq!(registered.interest().key, 2);
+    assert!(registered.interest().readable);
+    assert_eq!(registered.mode(), PollMode::Oneshot);
+
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(keys(&events), [2]);
+
+    // The oneshot registration fired, and stays disabled under its new key.
+    poller.rekey(&read, 3).unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    poller.modify(&read, Event::readable(3)).unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(keys(&events), [3]);
+
+    poller.delete(&read).unwrap();
+}
+
+#[test]
+fn errors() {
+    let poller = Poller::new().unwrap();
+    let (read, _write) = tcp_pair().unwrap();
+    assert_eq!(
+        poller.rekey(&read, 1).unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+
+    unsafe {
+        poller.add(&read, Event::readable(1)).unwrap();
+    }
+    assert_eq!(
+        poller.rekey(&read, poller.notify_key()).unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+    poller.delete(&read).unwrap();
+}
+
+fn keys(events: &Events) -> Vec<usize> {
+    events.iter().map(|ev| ev.key).collect()
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "serde")]
+
+use polling::{Event, Interest, PollMode};
+use std::num::NonZeroUsize;
+
+#[test]
+fn event_round_trip() {
+    let event = Event::from_interest(7, Interest::READABLE | Interest::WRITABLE)
+        .with_write_low_watermark(NonZeroUsize::new(64).unwrap());
+    let json = serde_json::to_string(&event).unwrap();
+    assert_eq!(
+        json,
+        r#"{"key":7,"interest":["READABLE","WRITABLE"],"read_low_watermark":null,"write_low_watermark":64}"#
+    );
+    assert_eq!(serde_json::from_str::<Event>(&json).unwrap(), event);
+
+    let event = Event::readable(7).with_delete_on_hangup();
+    let json = serde_json::to_string(&event).unwrap();
+    assert!(json.ends_with(r#""delete_on_hangup":true}"#));
+    assert_eq!(serde_json::from_str::<Event>(&json).unwrap(), event);
+}
+
+#[test]
+fn interest_names() {
+    let interest = Interest::READABLE | Interest::HANGUP;
+    assert_eq!(
+        serde_json::to_string(&interest).unwrap(),
+        r#"["READABLE","HANGUP"]"#
+    );
+    assert_eq!(
+        serde_json::from_str::<Interest>(r#"["HANGUP","READABLE"]"#).unwrap(),
+        interest
+    );
+    assert!(serde_json::from_str::<Interest>(r#"["READABLE","NOPE"]"#).is_err());
+}
+
+#[test]
+fn poll_mode() {
+    let json = serde_json::to_string(&PollMode::EdgeOneshot).unwrap();
+    assert_eq!(json, r#""EdgeOneshot""#);
+    assert_eq!(
+        serde_json::from_str::<PollMode>(&json).unwrap(),
+        PollMode::EdgeOneshot
+    );
+}
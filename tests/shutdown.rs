@@ -0,0 +1,41 @@
+//! Tests for the source of termination signals.
+
+#![cfg(all(unix, not(target_os = "vita")))]
+
+use polling::{Event, Events, Poller, ShutdownSignal};
+use std::time::Duration;
+
+#[test]
+fn delivers_signals() {
+    let poller = Poller::new().unwrap();
+    let signal = ShutdownSignal::new().unwrap();
+    unsafe {
+        poller.add(&signal, Event::readable(1)).unwrap();
+    }
+
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    for signal_number in [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM] {
+        signal_hook::low_level::raise(signal_number).unwrap();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events.iter().next().unwrap().key, 1);
+
+        // Resetting the source consumes the signal.
+        signal.reset().unwrap();
+        poller.modify(signal, Event::readable(1)).unwrap();
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_millis(10)))
+            .unwrap();
+        assert!(events.is_empty());
+    }
+
+    poller.delete(signal).unwrap();
+}
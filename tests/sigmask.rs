@@ -0,0 +1,50 @@
+#![cfg(all(unix, not(target_os = "vita")))]
+
+use polling::os::unix::PollerUnixExt;
+use polling::{Events, Poller};
+use std::io;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn pending_signal_interrupts_wait() {
+    let flag = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(libc::SIGUSR2, flag.clone()).unwrap();
+
+    std::thread::spawn(move || unsafe {
+        // Block the signal in this thread, and make it pending.
+        let mut blocked = mem::zeroed();
+        libc::sigemptyset(&mut blocked);
+        libc::sigaddset(&mut blocked, libc::SIGUSR2);
+        assert_eq!(
+            libc::pthread_sigmask(libc::SIG_BLOCK, &blocked, ptr::null_mut()),
+            0
+        );
+        assert_eq!(libc::pthread_kill(libc::pthread_self(), libc::SIGUSR2), 0);
+        assert!(!flag.load(Ordering::SeqCst));
+
+        // Unblock it only while waiting.
+        let mut unblocked = mem::zeroed();
+        libc::sigemptyset(&mut unblocked);
+        let poller = Poller::new().unwrap();
+        let mut events = Events::new();
+        let res =
+            poller.wait_with_sigmask(&mut events, Some(Duration::from_millis(100)), &unblocked);
+
+        assert!(flag.load(Ordering::SeqCst));
+        if cfg!(any(target_os = "linux", target_os = "android")) && !cfg!(polling_test_poll_backend)
+        {
+            assert_eq!(res.unwrap_err().kind(), io::ErrorKind::Interrupted);
+        }
+
+        // The signal is blocked again after the wait.
+        let mut current = mem::zeroed();
+        libc::pthread_sigmask(libc::SIG_BLOCK, ptr::null(), &mut current);
+        assert_eq!(libc::sigismember(&current, libc::SIGUSR2), 1);
+    })
+    .join()
+    .unwrap();
+}
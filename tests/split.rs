@@ -0,0 +1,147 @@
+use polling::{Event, Events, PollMode, Poller};
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+#[test]
+fn oneshot_halves() {
+    let poller = Poller::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+    unsafe {
+        poller
+            .add_split(&read, Event::readable(1), Event::writable(2))
+            .unwrap();
+    }
+
+    let registered = poller.registered(&read).unwrap();
+    assert_eq!(registered.interest().key, 1);
+    assert_eq!(registered.write_key(), Some(2));
+
+    // Only the write half is ready.
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(keys(&events), [2]);
+    assert!(events.iter().next().unwrap().writable);
+
+    // The write half fired, but the read half is still enabled.
+    write.write_all(&[1]).unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(keys(&events), [1]);
+    assert!(events.iter().next().unwrap().readable);
+
+    // Both halves have fired now.
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(0)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    // Re-enable both halves.
+    poller
+        .modify_split(&read, Event::readable(1), Event::writable(2))
+        .unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    let mut keys = keys(&events);
+    keys.sort_unstable();
+    assert_eq!(keys, [1, 2]);
+
+    poller.delete(&read).unwrap();
+}
+
+#[test]
+fn level_halves() {
+    let poller = Poller::new().unwrap();
+    if !poller.supports_level() {
+        return;
+    }
+
+    let (read, mut write) = tcp_pair().unwrap();
+    write.write_all(&[1]).unwrap();
+    unsafe {
+        poller
+            .add_split_with_mode(
+                &read,
+                Event::readable(1),
+                Event::writable(2),
+                PollMode::Level,
+            )
+            .unwrap();
+    }
+
+    for _ in 0..2 {
+        let mut events = Events::new();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        let mut keys = keys(&events);
+        keys.sort_unstable();
+        assert_eq!(keys, [1, 2]);
+    }
+
+    poller.delete(&read).unwrap();
+}
+
+#[test]
+fn disabled_half() {
+    let poller = Poller::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+    write.write_all(&[1]).unwrap();
+    unsafe {
+        poller
+            .add_split(&read, Event::none(1), Event::writable(2))
+            .unwrap();
+    }
+
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(keys(&events), [2]);
+
+    // A plain modification goes back to a single key.
+    poller.modify(&read, Event::readable(3)).unwrap();
+    assert_eq!(poller.registered(&read).unwrap().write_key(), None);
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(keys(&events), [3]);
+
+    poller.delete(&read).unwrap();
+}
+
+#[test]
+fn reserved_key() {
+    let poller = Poller::new().unwrap();
+    let (read, _write) = tcp_pair().unwrap();
+    unsafe {
+        let err = poller
+            .add_split(
+                &read,
+                Event::readable(1),
+                Event::writable(poller.notify_key()),
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+    assert!(poller.registered(&read).is_none());
+}
+
+fn keys(events: &Events) -> Vec<usize> {
+    events.iter().map(|ev| ev.key).collect()
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "stall-diagnostics")]
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use polling::{Events, Hooks, Poller, Stall};
+
+#[derive(Default)]
+struct Stalls(Mutex<Vec<Stall>>);
+
+impl Hooks for Stalls {
+    fn on_stall(&self, stall: Stall) {
+        self.0.lock().unwrap().push(stall);
+    }
+}
+
+fn poller(threshold: Duration) -> (Arc<Poller>, Arc<Stalls>) {
+    let stalls = Arc::new(Stalls::default());
+    let poller = Poller::builder()
+        .stall_threshold(threshold)
+        .hooks(stalls.clone())
+        .build()
+        .unwrap();
+    (Arc::new(poller), stalls)
+}
+
+#[test]
+fn no_stalls() {
+    let (poller, stalls) = poller(Duration::from_secs(10));
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    poller.notify().unwrap();
+    poller.wait(&mut events, None).unwrap();
+    assert!(stalls.0.lock().unwrap().is_empty());
+}
+
+#[test]
+fn overslept() {
+    // Every wait returns a little after its deadline, which exceeds a zero threshold.
+    let (poller, stalls) = poller(Duration::ZERO);
+    poller
+        .wait(&mut Events::new(), Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(matches!(
+        stalls.0.lock().unwrap()[..],
+        [Stall::Overslept { .. }]
+    ));
+}
+
+#[test]
+fn slow_wakeup() {
+    let (poller, stalls) = poller(Duration::ZERO);
+    let waiter = thread::spawn({
+        let poller = poller.clone();
+        move || poller.wait(&mut Events::new(), None)
+    });
+
+    thread::sleep(Duration::from_millis(50));
+    poller.notify().unwrap();
+    waiter.join().unwrap().unwrap();
+    assert!(matches!(
+        stalls.0.lock().unwrap()[..],
+        [Stall::LostWakeup { .. }]
+    ));
+}
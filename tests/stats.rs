@@ -0,0 +1,91 @@
+#![cfg(feature = "metrics")]
+
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use polling::{Event, Events, PollMode, Poller};
+
+#[test]
+fn counters() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    assert_eq!(poller.stats(), Default::default());
+
+    let (reader, mut writer) = tcp_pair()?;
+    let reader = poller.register(reader, Event::readable(1))?;
+    writer.write_all(&[1])?;
+
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    let stats = poller.stats();
+    assert_eq!(stats.waits, 1);
+    assert_eq!(stats.events, 1);
+    assert_eq!(stats.spurious_wakeups, 0);
+
+    reader.modify(Event::readable(1))?;
+    assert_eq!(poller.stats().modifications, 1);
+
+    // A second notification while one is pending doesn't reach the operating system.
+    poller.notify()?;
+    poller.notify()?;
+    assert_eq!(poller.stats().notifications, 1);
+
+    // Depending on the backend, the notification and the readiness may be reported by separate
+    // waits, so wait until the event arrives.
+    events.clear();
+    while events.is_empty() {
+        poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    }
+    let stats = poller.stats();
+    assert!(stats.waits == 2 || stats.waits == 3);
+    assert_eq!(stats.events, 2);
+    assert_eq!(stats.spurious_wakeups, 0);
+
+    // Timeouts are not spurious wakeups.
+    drop(reader);
+    events.clear();
+    poller.wait(&mut events, Some(Duration::from_millis(10)))?;
+    assert_eq!(poller.stats().spurious_wakeups, 0);
+
+    Ok(())
+}
+
+#[test]
+fn unchanged_interest() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    let (reader, mut writer) = tcp_pair()?;
+    let reader = poller.register(reader, Event::readable(1))?;
+
+    // The source is still armed, so setting the same interest is skipped.
+    reader.modify(Event::readable(1))?;
+    assert_eq!(poller.stats().modifications, 0);
+
+    // Once an event is delivered, the oneshot source has to be armed again.
+    writer.write_all(&[1])?;
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    assert_eq!(events.len(), 1);
+    reader.modify(Event::readable(1))?;
+    reader.modify(Event::readable(1))?;
+    assert_eq!(poller.stats().modifications, 1);
+
+    events.clear();
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    assert_eq!(events.len(), 1);
+
+    // A level-triggered source stays armed after an event.
+    reader.modify_with_mode(Event::readable(1), PollMode::Level)?;
+    events.clear();
+    poller.wait(&mut events, Some(Duration::from_secs(10)))?;
+    reader.modify_with_mode(Event::readable(1), PollMode::Level)?;
+    assert_eq!(poller.stats().modifications, 2);
+
+    Ok(())
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
@@ -3,6 +3,11 @@ use std::time::{Duration, Instant};
 
 use polling::{Events, Poller};
 
+#[cfg(polling_test_max_wait)]
+use polling::{Event, PollMode, PollerBackend, RawSource};
+#[cfg(polling_test_max_wait)]
+use std::sync::{Arc, Mutex};
+
 #[test]
 fn twice() -> io::Result<()> {
     let poller = Poller::new()?;
@@ -30,3 +35,153 @@ fn non_blocking() -> io::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn timestamp() -> io::Result<()> {
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+    assert_eq!(events.timestamp(), None);
+
+    let start = Instant::now();
+    poller.wait(&mut events, Some(Duration::from_millis(100)))?;
+    let end = Instant::now();
+    let returned = events.timestamp().unwrap();
+    assert!(returned >= start + Duration::from_millis(100));
+    assert!(returned <= end);
+
+    events.clear();
+    assert_eq!(events.timestamp(), Some(returned));
+
+    Ok(())
+}
+
+#[test]
+fn longer_than_backend_limit() -> io::Result<()> {
+    use std::sync::Arc;
+    use std::thread;
+
+    // Far more milliseconds than fit into an `int`, which some backends take.
+    let poller = Arc::new(Poller::new()?);
+    let waiter = thread::spawn({
+        let poller = poller.clone();
+        move || {
+            let start = Instant::now();
+            let result = poller.wait_result(
+                &mut Events::new(),
+                Some(Duration::from_secs(30 * 24 * 60 * 60)),
+            );
+            (result, start.elapsed())
+        }
+    });
+
+    thread::sleep(Duration::from_millis(100));
+    poller.notify()?;
+    let (result, elapsed) = waiter.join().unwrap();
+    assert!(result?.is_notified());
+    assert!(elapsed >= Duration::from_millis(100));
+
+    Ok(())
+}
+
+/// The longest time a single wait on the backend blocks for with `polling_test_max_wait`.
+#[cfg(polling_test_max_wait)]
+const MAX_WAIT: Duration = Duration::from_millis(50);
+
+/// A backend without sources that records how long each wait may block for.
+#[cfg(polling_test_max_wait)]
+struct Recorder(Arc<Mutex<Vec<Option<Duration>>>>);
+
+#[cfg(polling_test_max_wait)]
+impl PollerBackend for Recorder {
+    fn add(&self, _: RawSource, _: Event, _: PollMode) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn modify(&self, _: RawSource, _: Event, _: PollMode) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn delete(&self, _: RawSource) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn wait(&self, _: &mut Vec<Event>, deadline: Option<Instant>) -> io::Result<()> {
+        let timeout = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        self.0.lock().unwrap().push(timeout);
+        if let Some(timeout) = timeout {
+            std::thread::sleep(timeout);
+        }
+        Ok(())
+    }
+
+    fn notify(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(polling_test_max_wait)]
+#[test]
+fn split_at_backend_limit() -> io::Result<()> {
+    let waits = Arc::default();
+    let poller = Poller::builder().build_custom(Recorder(Arc::clone(&waits)))?;
+
+    // The wait is split into several waits on the backend, which together last as long as the
+    // timeout.
+    let start = Instant::now();
+    let result = poller.wait_result(&mut Events::new(), Some(6 * MAX_WAIT))?;
+    assert!(result.is_timed_out());
+    assert!(start.elapsed() >= 6 * MAX_WAIT);
+
+    let waits = waits.lock().unwrap();
+    assert!(waits.len() >= 6);
+    for timeout in waits.iter() {
+        assert!(timeout.unwrap() <= MAX_WAIT);
+    }
+
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "vita")))]
+#[test]
+fn interrupted_by_signal() -> io::Result<()> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    let poller = Poller::new()?;
+    let mut events = Events::new();
+
+    // Install a handler so the signal interrupts the syscall instead of killing us.
+    let caught = Arc::new(AtomicBool::new(false));
+    let id = signal_hook::flag::register(signal_hook::consts::SIGUSR1, caught.clone())?;
+
+    // Keep interrupting the waiting thread while it sleeps.
+    let waiter = unsafe { libc::pthread_self() };
+    let done = Arc::new(AtomicBool::new(false));
+    let interrupter = thread::spawn({
+        let done = done.clone();
+        move || {
+            while !done.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(50));
+                unsafe {
+                    libc::pthread_kill(waiter, libc::SIGUSR1);
+                }
+            }
+        }
+    });
+
+    let dur = Duration::from_millis(500);
+    let start = Instant::now();
+    let result = poller.wait(&mut events, Some(dur));
+    let elapsed = start.elapsed();
+
+    done.store(true, Ordering::SeqCst);
+    interrupter.join().unwrap();
+    signal_hook::low_level::unregister(id);
+
+    assert_eq!(result?, 0);
+    assert!(caught.load(Ordering::SeqCst));
+    assert!(elapsed >= dur, "{elapsed:?} < {dur:?}");
+
+    Ok(())
+}
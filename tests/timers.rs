@@ -0,0 +1,225 @@
+use polling::{Clock, Events, Poller};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn many_timers() {
+    let poller = Poller::new().unwrap();
+    let start = Instant::now();
+    for key in 0..1000 {
+        let deadline = start + Duration::from_micros(50 * (1000 - key as u64));
+        poller.set_timer(key, deadline).unwrap();
+    }
+    assert_eq!(poller.pending_timers(), 1000);
+
+    let mut events = Events::new();
+    let mut fired = Vec::new();
+    while fired.len() < 1000 {
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(5)))
+            .unwrap();
+        fired.extend(events.iter().map(|ev| ev.key));
+    }
+
+    // The latest deadline is 50ms away, so most of the timers fire in later waits.
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    let mut sorted = fired.clone();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+    assert_eq!(fired, sorted);
+    assert_eq!(poller.pending_timers(), 0);
+}
+
+#[test]
+fn move_and_cancel() {
+    let poller = Poller::new().unwrap();
+    let soon = Instant::now() + Duration::from_millis(20);
+    poller.set_timer(1, soon).unwrap();
+    poller.set_timer(2, soon).unwrap();
+
+    // Moving a timer replaces its deadline.
+    poller
+        .set_timer(1, Instant::now() + Duration::from_secs(60))
+        .unwrap();
+    assert!(poller.cancel_timer(2));
+    assert!(!poller.cancel_timer(2));
+
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+    assert_eq!(poller.pending_timers(), 1);
+    assert!(poller.cancel_timer(1));
+}
+
+#[test]
+fn earlier_timer_wakes_up_wait() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let handle = {
+        let poller = poller.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            poller
+                .set_timer(3, Instant::now() + Duration::from_millis(10))
+                .unwrap();
+        })
+    };
+
+    let start = Instant::now();
+    let mut events = Events::new();
+    while events.is_empty() {
+        poller
+            .wait(&mut events, Some(Duration::from_secs(10)))
+            .unwrap();
+    }
+    handle.join().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(events.iter().next().unwrap().key, 3);
+}
+
+#[test]
+fn reserved_key() {
+    let poller = Poller::new().unwrap();
+    assert!(poller
+        .set_timer(poller.notify_key(), Instant::now())
+        .is_err());
+}
+
+#[test]
+fn boottime() {
+    if !Clock::Boottime.is_supported() {
+        return;
+    }
+
+    let poller = Poller::builder().clock(Clock::Boottime).build().unwrap();
+    let start = Instant::now();
+    poller
+        .set_timer(1, start + Duration::from_millis(30))
+        .unwrap();
+    poller.set_timer(2, start).unwrap();
+
+    let mut events = Events::new();
+    let mut fired = Vec::new();
+    while fired.len() < 2 {
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(5)))
+            .unwrap();
+        fired.extend(events.iter().map(|ev| ev.key));
+    }
+    assert_eq!(fired, [2, 1]);
+    assert!(start.elapsed() >= Duration::from_millis(30));
+}
+
+#[test]
+fn slack() {
+    let slack = Duration::from_millis(30);
+    let poller = Poller::builder().timer_slack(slack).build().unwrap();
+    let mut events = Events::new();
+
+    // Waits that don't block are not delayed.
+    let start = Instant::now();
+    poller.wait(&mut events, Some(Duration::ZERO)).unwrap();
+    assert!(start.elapsed() < slack);
+
+    let start = Instant::now();
+    poller
+        .set_timer(1, start + Duration::from_millis(1))
+        .unwrap();
+    while events.is_empty() {
+        poller
+            .wait(&mut events, Some(Duration::from_secs(5)))
+            .unwrap();
+    }
+    assert!(start.elapsed() >= Duration::from_millis(1));
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn deadlines() {
+    use polling::Event;
+    use std::net::UdpSocket;
+
+    let poller = Poller::new().unwrap();
+    let idle = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let ready = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let deleted = UdpSocket::bind("127.0.0.1:0").unwrap();
+    unsafe {
+        poller.add(&idle, Event::readable(1)).unwrap();
+        poller.add(&ready, Event::writable(2)).unwrap();
+        poller.add(&deleted, Event::readable(3)).unwrap();
+    }
+    let deadline = Instant::now() + Duration::from_millis(50);
+    for socket in [&idle, &ready, &deleted] {
+        poller.set_deadline(socket, Some(deadline)).unwrap();
+    }
+    poller.delete(&deleted).unwrap();
+
+    // The writable socket cancels its deadline, and only the idle one times out.
+    let mut events = Events::new();
+    let mut delivered = Vec::new();
+    while Instant::now() < deadline + Duration::from_millis(50) {
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_millis(10)))
+            .unwrap();
+        delivered.extend(events.iter().map(|ev| (ev.key, ev.is_timeout())));
+    }
+    assert_eq!(delivered, [(2, false), (1, true)]);
+
+    let err = poller.set_deadline(&deleted, Some(deadline)).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    poller.delete(&idle).unwrap();
+    poller.delete(&ready).unwrap();
+}
+
+#[test]
+fn key_priority() {
+    let poller = Poller::new().unwrap();
+    let deadline = Instant::now() + Duration::from_millis(10);
+    for key in 1..=4 {
+        poller.set_timer(key, deadline).unwrap();
+    }
+    poller.set_key_priority(3, 10);
+    poller.set_key_priority(4, -1);
+    poller.set_key_priority(2, 5);
+    poller.set_key_priority(2, 0);
+
+    let mut events = Events::new();
+    while events.len() < 4 {
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+    }
+    let keys: Vec<_> = events.iter().map(|ev| ev.key).collect();
+    assert_eq!(keys, [3, 1, 2, 4]);
+}
+
+#[test]
+fn tick() {
+    let interval = Duration::from_millis(20);
+    let poller = Poller::builder().tick(3, Some(interval)).build().unwrap();
+    poller.set_timer(4, Instant::now() + interval * 2).unwrap();
+
+    let start = Instant::now();
+    let mut events = Events::new();
+    let mut ticks = 0;
+    while ticks < 3 {
+        events.clear();
+        poller.wait(&mut events, None).unwrap();
+        ticks += events.iter().filter(|ev| ev.key == 3).count();
+    }
+    assert!(start.elapsed() >= interval * 3);
+
+    // The notification key is reserved, and a zero interval disables the tick.
+    assert!(Poller::builder()
+        .tick(usize::MAX, Some(interval))
+        .build()
+        .is_err());
+    assert!(Poller::builder()
+        .tick(1, Some(Duration::ZERO))
+        .build()
+        .is_ok());
+}
@@ -0,0 +1,97 @@
+//! Tests for pointers attached to `kqueue` registrations.
+
+#![cfg(all(
+    any(
+        target_vendor = "apple",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+    ),
+    not(polling_test_poll_backend)
+))]
+
+use polling::os::kqueue::{EventKqueueExt, PollerUdataExt};
+use polling::{Event, Events, PollMode, Poller};
+
+use std::io;
+use std::os::unix::net::UnixStream;
+use std::ptr::NonNull;
+use std::time::Duration;
+
+#[test]
+fn udata_delivered() {
+    let (a, _b) = UnixStream::pair().unwrap();
+    let poller = Poller::new().unwrap();
+    let mut handler = 0u64;
+    let udata = NonNull::from(&mut handler).cast();
+
+    unsafe {
+        poller.add(&a, Event::none(1)).unwrap();
+        poller
+            .modify_with_udata(&a, Event::writable(2), PollMode::Oneshot, Some(udata))
+            .unwrap();
+    }
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    let event = events.iter().next().unwrap();
+    assert_eq!(event.key, 2);
+    assert_eq!(event.udata(), Some(udata));
+
+    // The pointer is kept by later modifications, with the new key.
+    poller.modify(&a, Event::writable(3)).unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    let event = events.iter().next().unwrap();
+    assert_eq!(event.key, 3);
+    assert_eq!(event.udata(), Some(udata));
+
+    // Detach the pointer.
+    unsafe {
+        poller
+            .modify_with_udata(&a, Event::writable(4), PollMode::Oneshot, None)
+            .unwrap();
+    }
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    let event = events.iter().next().unwrap();
+    assert_eq!(event.key, 4);
+    assert_eq!(event.udata(), None);
+
+    poller.delete(&a).unwrap();
+}
+
+#[test]
+fn udata_in_use() {
+    let (a, b) = UnixStream::pair().unwrap();
+    let poller = Poller::new().unwrap();
+    let mut handler = 0u64;
+    let udata = NonNull::from(&mut handler).cast();
+
+    unsafe {
+        poller.add(&a, Event::none(1)).unwrap();
+        poller.add(&b, Event::none(2)).unwrap();
+        poller
+            .modify_with_udata(&a, Event::none(1), PollMode::Oneshot, Some(udata))
+            .unwrap();
+        let err = poller
+            .modify_with_udata(&b, Event::none(2), PollMode::Oneshot, Some(udata))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    }
+
+    // Deleting the source detaches the pointer.
+    poller.delete(&a).unwrap();
+    unsafe {
+        poller
+            .modify_with_udata(&b, Event::none(2), PollMode::Oneshot, Some(udata))
+            .unwrap();
+    }
+    poller.delete(&b).unwrap();
+}
@@ -0,0 +1,146 @@
+//! Tests for waiting on several pollers at once.
+
+#![cfg(all(
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "redox",
+        target_os = "illumos",
+        target_os = "solaris",
+        target_vendor = "apple",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+    ),
+    not(polling_test_poll_backend)
+))]
+
+use polling::{Event, Events, Poller};
+
+use std::io::{self, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn ready_poller() {
+    let first = Poller::new().unwrap();
+    let second = Poller::new().unwrap();
+    let (reader, mut writer) = UnixStream::pair().unwrap();
+    unsafe {
+        second.add(&reader, Event::readable(1)).unwrap();
+    }
+
+    let (mut first_events, mut second_events) = (Events::new(), Events::new());
+    let n = polling::wait_any(
+        &mut [(&first, &mut first_events), (&second, &mut second_events)],
+        Some(Duration::ZERO),
+    )
+    .unwrap();
+    assert_eq!(n, 0);
+
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        writer.write_all(&[1]).unwrap();
+        writer
+    });
+    let n = polling::wait_any(
+        &mut [(&first, &mut first_events), (&second, &mut second_events)],
+        Some(Duration::from_secs(10)),
+    )
+    .unwrap();
+    assert_eq!(n, 1);
+    assert!(first_events.is_empty());
+    assert_eq!(
+        second_events.iter().next().unwrap().with_no_extra(),
+        Event::readable(1)
+    );
+
+    handle.join().unwrap();
+    second.delete(&reader).unwrap();
+}
+
+#[test]
+fn notify_and_timers() {
+    let first = Arc::new(Poller::new().unwrap());
+    let second = Poller::new().unwrap();
+    let (mut first_events, mut second_events) = (Events::new(), Events::new());
+
+    // A notification wakes up the wait.
+    let notifier = first.clone();
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        notifier.notify().unwrap();
+    });
+    let start = Instant::now();
+    polling::wait_any(
+        &mut [(&first, &mut first_events), (&second, &mut second_events)],
+        Some(Duration::from_secs(10)),
+    )
+    .unwrap();
+    assert!(start.elapsed() < Duration::from_secs(10));
+    handle.join().unwrap();
+
+    // So does a timer, which doesn't involve the backend.
+    second
+        .set_timer(5, Instant::now() + Duration::from_millis(50))
+        .unwrap();
+    // Setting the earliest timer notifies the poller, which may end the first wait.
+    let start = Instant::now();
+    while second_events.is_empty() {
+        polling::wait_any(
+            &mut [(&first, &mut first_events), (&second, &mut second_events)],
+            Some(Duration::from_secs(10)),
+        )
+        .unwrap();
+    }
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert!(first_events.is_empty());
+    assert_eq!(second_events.iter().next().unwrap().key, 5);
+
+    // Without activity, the timeout elapses.
+    let start = Instant::now();
+    let n = polling::wait_any(
+        &mut [(&first, &mut first_events), (&second, &mut second_events)],
+        Some(Duration::from_millis(50)),
+    )
+    .unwrap();
+    assert_eq!(n, 0);
+    assert!(start.elapsed() >= Duration::from_millis(50));
+}
+
+#[test]
+fn custom_backend() {
+    struct Inert;
+
+    impl polling::PollerBackend for Inert {
+        fn add(&self, _: polling::RawSource, _: Event, _: polling::PollMode) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn modify(&self, _: polling::RawSource, _: Event, _: polling::PollMode) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn delete(&self, _: polling::RawSource) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn wait(&self, _: &mut Vec<Event>, _: Option<Instant>) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn notify(&self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // A custom backend has no file descriptor to poll.
+    let custom = Poller::builder().build_custom(Inert).unwrap();
+    let native = Poller::new().unwrap();
+    let (mut a, mut b) = (Events::new(), Events::new());
+    let err = polling::wait_any(&mut [(&native, &mut a), (&custom, &mut b)], None).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+}
@@ -0,0 +1,77 @@
+use polling::{Event, Events, Poller};
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn other_events_are_deferred() {
+    let poller = Poller::new().unwrap();
+    let (a_read, mut a_write) = tcp_pair().unwrap();
+    let (b_read, mut b_write) = tcp_pair().unwrap();
+    unsafe {
+        poller.add(&a_read, Event::readable(1)).unwrap();
+        poller.add(&b_read, Event::readable(2)).unwrap();
+    }
+
+    a_write.write_all(&[1]).unwrap();
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        b_write.write_all(&[1]).unwrap();
+        b_write
+    });
+
+    let event = poller
+        .wait_for_key(2, Some(Duration::from_secs(10)))
+        .unwrap()
+        .unwrap();
+    assert_eq!(event.key, 2);
+    assert!(event.readable);
+    handle.join().unwrap();
+
+    // The event of the first source is delivered by the next wait, which doesn't block.
+    let mut events = Events::new();
+    let start = Instant::now();
+    poller.wait(&mut events, None).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    let keys: Vec<_> = events.iter().map(|ev| ev.key).collect();
+    assert_eq!(keys, [1]);
+
+    poller.delete(&a_read).unwrap();
+    poller.delete(&b_read).unwrap();
+}
+
+#[test]
+fn deferred_event_is_found() {
+    let poller = Poller::new().unwrap();
+    let (read, mut write) = tcp_pair().unwrap();
+    unsafe {
+        poller.add(&read, Event::readable(1)).unwrap();
+    }
+    write.write_all(&[1]).unwrap();
+
+    // The event is set aside while waiting for another key.
+    let event = poller
+        .wait_for_key(2, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(event.is_none());
+
+    let event = poller.wait_for_key(1, Some(Duration::ZERO)).unwrap();
+    assert_eq!(event.unwrap().key, 1);
+
+    poller.delete(&read).unwrap();
+}
+
+#[test]
+fn notified() {
+    let poller = Poller::new().unwrap();
+    poller.notify().unwrap();
+    assert!(poller.wait_for_key(1, None).unwrap().is_none());
+}
+
+fn tcp_pair() -> io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let a = TcpStream::connect(listener.local_addr()?)?;
+    let (b, _) = listener.accept()?;
+    Ok((a, b))
+}
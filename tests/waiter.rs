@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use polling::{Events, Poller};
+
+#[test]
+fn notify_before_wait() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let waiter = poller.waiter();
+    waiter.notifier().notify().unwrap();
+    waiter.notifier().notify().unwrap();
+
+    // The first wait consumes the notification, the second one times out.
+    let mut events = Events::new();
+    let start = Instant::now();
+    waiter.wait(&mut events, None).unwrap();
+    waiter
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+#[test]
+fn wakes_the_target() {
+    let poller = Arc::new(Poller::builder().concurrent_waits(true).build().unwrap());
+    let stop = Arc::new(AtomicBool::new(false));
+    let woken = Arc::new(AtomicUsize::new(0));
+
+    // Threads that only stop once they are told to.
+    let others = (0..3)
+        .map(|_| {
+            let waiter = poller.waiter();
+            let notifier = waiter.notifier();
+            let stop = stop.clone();
+            let handle = thread::spawn(move || {
+                let mut events = Events::new();
+                while !stop.load(Ordering::SeqCst) {
+                    waiter.wait(&mut events, None).unwrap();
+                }
+            });
+            (notifier, handle)
+        })
+        .collect::<Vec<_>>();
+
+    let target = poller.waiter();
+    let notifier = target.notifier();
+    let handle = {
+        let woken = woken.clone();
+        thread::spawn(move || {
+            let mut events = Events::new();
+            target.wait(&mut events, None).unwrap();
+            woken.fetch_add(1, Ordering::SeqCst);
+        })
+    };
+
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(woken.load(Ordering::SeqCst), 0);
+    notifier.notify().unwrap();
+    handle.join().unwrap();
+    assert_eq!(woken.load(Ordering::SeqCst), 1);
+
+    stop.store(true, Ordering::SeqCst);
+    for (notifier, handle) in others {
+        notifier.notify().unwrap();
+        handle.join().unwrap();
+    }
+}
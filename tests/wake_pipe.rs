@@ -0,0 +1,72 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use polling::{Event, Events, PollMode, Poller, WakePipe};
+
+#[test]
+fn wake_and_reset() {
+    let poller = Poller::new().unwrap();
+    let pipe = Arc::new(WakePipe::new().unwrap());
+    unsafe {
+        poller
+            .add_with_mode(&*pipe, Event::readable(1), PollMode::Level)
+            .unwrap();
+    }
+
+    // Nothing is readable at first.
+    let mut events = Events::new();
+    poller.wait(&mut events, Some(Duration::ZERO)).unwrap();
+    assert!(events.is_empty());
+
+    // A wakeup from another thread makes the pipe readable.
+    let waker = pipe.clone();
+    thread::spawn(move || {
+        waker.wake().unwrap();
+        waker.wake().unwrap();
+    })
+    .join()
+    .unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(events.iter().next().unwrap().key, 1);
+
+    // It stays readable until it is reset.
+    events.clear();
+    poller.wait(&mut events, Some(Duration::ZERO)).unwrap();
+    assert_eq!(events.len(), 1);
+
+    pipe.reset().unwrap();
+    events.clear();
+    poller.wait(&mut events, Some(Duration::ZERO)).unwrap();
+    assert!(events.is_empty());
+
+    // It can be woken up again.
+    pipe.wake().unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+
+    poller.delete(&*pipe).unwrap();
+}
+
+#[test]
+fn independent_of_notify() {
+    let poller = Poller::new().unwrap();
+    let pipe = WakePipe::new().unwrap();
+    unsafe {
+        poller.add(&pipe, Event::readable(1)).unwrap();
+    }
+
+    // A notification of the poller doesn't wake up the pipe.
+    poller.notify().unwrap();
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    poller.delete(&pipe).unwrap();
+}
@@ -0,0 +1,102 @@
+use polling::{Event, Events, Interest, Poller};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn wake_from_thread() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let waker = poller.waker(3).unwrap();
+
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        waker.wake();
+    });
+
+    let mut events = Events::new();
+    while events.is_empty() {
+        poller
+            .wait(&mut events, Some(Duration::from_secs(5)))
+            .unwrap();
+    }
+    handle.join().unwrap();
+
+    assert_eq!(events.len(), 1);
+    let ev = events.iter().next().unwrap();
+    assert_eq!(ev.key, 3);
+    assert!(ev.readable);
+}
+
+#[test]
+fn wake_twice_delivers_once() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let waker = poller.waker(1).unwrap();
+    waker.wake_by_ref();
+    waker.wake();
+    poller.waker(2).unwrap().wake();
+
+    let mut events = Events::new();
+    poller.wait(&mut events, Some(Duration::ZERO)).unwrap();
+    let mut keys: Vec<_> = events.iter().map(|ev| ev.key).collect();
+    keys.sort_unstable();
+    assert_eq!(keys, [1, 2]);
+
+    events.clear();
+    poller.wait(&mut events, Some(Duration::ZERO)).unwrap();
+    assert!(events.is_empty());
+}
+
+#[test]
+fn outlives_poller() {
+    let poller = Arc::new(Poller::new().unwrap());
+    assert!(poller.waker(poller.notify_key()).is_err());
+
+    let waker = poller.waker(1).unwrap();
+    drop(poller);
+    waker.wake();
+}
+
+#[test]
+fn virtual_source() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let source = poller.virtual_source(Event::all(4)).unwrap();
+    assert_eq!(source.key(), 4);
+
+    // Readiness marked several times is delivered once, merged.
+    let handle = source.clone();
+    thread::spawn(move || {
+        handle.set_ready(Interest::READABLE).unwrap();
+        handle.set_ready(Interest::WRITABLE).unwrap();
+    })
+    .join()
+    .unwrap();
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.iter().next().unwrap().with_no_extra(), Event::all(4));
+
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    // Readiness outside of the interest is not delivered.
+    let readable = poller.virtual_source(Event::readable(5)).unwrap();
+    readable.set_ready(Interest::WRITABLE).unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(10)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    let err = poller
+        .virtual_source(Event::readable(poller.notify_key()))
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    // The handle doesn't keep the poller alive.
+    drop(poller);
+    source.set_ready(Interest::READABLE).unwrap();
+}
@@ -0,0 +1,34 @@
+//! Tests for raw AFD flags on Windows.
+
+#![cfg(windows)]
+
+use polling::os::iocp::{EventWindowsExt, PollerWindowsExt, AFD_POLL_ACCEPT};
+use polling::{Events, PollMode, Poller};
+
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+#[test]
+fn raw_accept() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+
+    let poller = Poller::new().unwrap();
+    unsafe {
+        poller
+            .add_raw(&listener, 1, AFD_POLL_ACCEPT, PollMode::Oneshot)
+            .unwrap();
+    }
+
+    let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+
+    let mut events = Events::new();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    let event = events.iter().next().unwrap();
+    assert_eq!(event.key, 1);
+    assert_ne!(event.raw_flags() & AFD_POLL_ACCEPT, 0);
+
+    poller.delete(&listener).unwrap();
+}